@@ -1,6 +1,9 @@
 //! Alert data object as defined in the USB PD specification 6.4.6
-use bitfield::{bitfield, Bit};
+use core::ops::BitOr;
 
+use bitfield::{bitfield, Bit, BitMut};
+
+use crate::battery::BatteryRef;
 use crate::PdError;
 
 /// Error type for ADO conversion, contains the complete undecoded ADO
@@ -16,7 +19,7 @@ impl From<InvalidType> for PdError {
 
 bitfield! {
     /// Battery status change flags
-    #[derive(Copy, Clone, PartialEq, Eq)]
+    #[derive(Copy, Clone, Default, PartialEq, Eq)]
     struct BatteryStatusChangeRaw(u8);
     impl Debug;
 
@@ -40,7 +43,7 @@ impl defmt::Format for BatteryStatusChangeRaw {
 }
 
 /// Battery status change event
-#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub struct BatteryStatusChange(BatteryStatusChangeRaw);
 
@@ -48,6 +51,28 @@ pub struct BatteryStatusChange(BatteryStatusChangeRaw);
 pub const MAX_BATTERY_INDEX: usize = 3;
 
 impl BatteryStatusChange {
+    /// Create a battery status change event with no batteries changed
+    pub const fn new() -> Self {
+        Self(BatteryStatusChangeRaw(0))
+    }
+
+    /// Returns the event with the given battery marked as changed
+    pub fn with_changed(mut self, battery: BatteryRef) -> Self {
+        match battery {
+            BatteryRef::Fixed(index) => {
+                let mut fixed = self.0.fixed_battery_status_change();
+                fixed.set_bit(index as usize, true);
+                self.0.set_fixed_battery_status_change(fixed);
+            }
+            BatteryRef::HotSwappable(index) => {
+                let mut hot_swappable = self.0.hot_swappable_battery_status();
+                hot_swappable.set_bit(index as usize, true);
+                self.0.set_hot_swappable_battery_status(hot_swappable);
+            }
+        }
+        self
+    }
+
     /// Returns the fixed battery status change at the given index
     pub fn fixed_battery_status_change(&self, index: usize) -> Result<bool, PdError> {
         if index > MAX_BATTERY_INDEX {
@@ -63,6 +88,22 @@ impl BatteryStatusChange {
         }
         Ok(self.0.hot_swappable_battery_status().bit(index))
     }
+
+    /// Returns the batteries whose status changed, fixed batteries first
+    ///
+    /// Each of these should be queried with a Get_Battery_Status message.
+    pub fn changed_batteries(&self) -> impl Iterator<Item = BatteryRef> {
+        let fixed = self.0.fixed_battery_status_change();
+        let hot_swappable = self.0.hot_swappable_battery_status();
+        (0..=MAX_BATTERY_INDEX)
+            .filter(move |index| fixed.bit(*index))
+            .map(|index| BatteryRef::Fixed(index as u8))
+            .chain(
+                (0..=MAX_BATTERY_INDEX)
+                    .filter(move |index| hot_swappable.bit(*index))
+                    .map(|index| BatteryRef::HotSwappable(index as u8)),
+            )
+    }
 }
 
 bitfield! {
@@ -80,13 +121,16 @@ bitfield! {
     pub u8, extended_alert_type, set_extended_alert_type: 3, 0;
 }
 
-/// ADO types
+/// Alert type bit for battery status change events
+const BATTERY_STATUS_CHANGE_BIT: usize = 1;
+/// Alert type bit for extended alert events
+const EXTENDED_ALERT_BIT: usize = 7;
+
+/// Alerts that don't carry any additional information
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
-pub enum Ado {
-    /// Battery status change event
-    BatteryStatusChange(BatteryStatusChange),
-    /// Over-current event,
+pub enum Alert {
+    /// Over-current event
     Ocp,
     /// Over-temperature event
     Otp,
@@ -96,6 +140,34 @@ pub enum Ado {
     SourceInputChange,
     /// Over-voltage event
     Ovp,
+}
+
+impl Alert {
+    /// All alerts in bit order
+    const ALL: [Alert; 5] = [
+        Alert::Ocp,
+        Alert::Otp,
+        Alert::OperatingConditionChange,
+        Alert::SourceInputChange,
+        Alert::Ovp,
+    ];
+
+    /// Returns the bit of the alert in the alert type field
+    const fn bit(self) -> usize {
+        match self {
+            Alert::Ocp => 2,
+            Alert::Otp => 3,
+            Alert::OperatingConditionChange => 4,
+            Alert::SourceInputChange => 5,
+            Alert::Ovp => 6,
+        }
+    }
+}
+
+/// Extended alert types
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ExtendedAlert {
     /// Power state change
     PowerStateChange,
     /// Power button press
@@ -106,30 +178,147 @@ pub enum Ado {
     ControllerInitiatedWake,
 }
 
+impl TryFrom<u8> for ExtendedAlert {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x01 => Ok(ExtendedAlert::PowerStateChange),
+            0x02 => Ok(ExtendedAlert::PowerButtonPress),
+            0x03 => Ok(ExtendedAlert::PowerButtonRelease),
+            0x04 => Ok(ExtendedAlert::ControllerInitiatedWake),
+            _ => Err(value),
+        }
+    }
+}
+
+impl From<ExtendedAlert> for u8 {
+    fn from(value: ExtendedAlert) -> Self {
+        match value {
+            ExtendedAlert::PowerStateChange => 0x01,
+            ExtendedAlert::PowerButtonPress => 0x02,
+            ExtendedAlert::PowerButtonRelease => 0x03,
+            ExtendedAlert::ControllerInitiatedWake => 0x04,
+        }
+    }
+}
+
+/// Alert data object, a set of alerts that can be reported at the same time
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Ado {
+    /// Alert type bits for [`Alert`] values
+    alerts: u8,
+    /// Battery status change event
+    battery_status_change: Option<BatteryStatusChange>,
+    /// Extended alert event
+    extended_alert: Option<ExtendedAlert>,
+}
+
+impl Ado {
+    /// Create an empty ADO
+    pub const fn new() -> Self {
+        Self {
+            alerts: 0,
+            battery_status_change: None,
+            extended_alert: None,
+        }
+    }
+
+    /// Returns the ADO with the given alert added
+    pub const fn with_alert(mut self, alert: Alert) -> Self {
+        self.alerts |= 1 << alert.bit();
+        self
+    }
+
+    /// Returns the ADO with the given battery status change event
+    pub const fn with_battery_status_change(mut self, battery_status_change: BatteryStatusChange) -> Self {
+        self.battery_status_change = Some(battery_status_change);
+        self
+    }
+
+    /// Returns the ADO with the given extended alert event
+    pub const fn with_extended_alert(mut self, extended_alert: ExtendedAlert) -> Self {
+        self.extended_alert = Some(extended_alert);
+        self
+    }
+
+    /// Returns true if the given alert is set
+    pub const fn contains(&self, alert: Alert) -> bool {
+        self.alerts & (1 << alert.bit()) != 0
+    }
+
+    /// Returns the alerts that are set
+    pub fn alerts(&self) -> impl Iterator<Item = Alert> {
+        let alerts = *self;
+        Alert::ALL.into_iter().filter(move |alert| alerts.contains(*alert))
+    }
+
+    /// Returns the battery status change event, if any
+    pub const fn battery_status_change(&self) -> Option<BatteryStatusChange> {
+        self.battery_status_change
+    }
+
+    /// Returns the extended alert event, if any
+    pub const fn extended_alert(&self) -> Option<ExtendedAlert> {
+        self.extended_alert
+    }
+
+    /// Returns true if no alerts are set
+    pub const fn is_empty(&self) -> bool {
+        self.alerts == 0 && self.battery_status_change.is_none() && self.extended_alert.is_none()
+    }
+}
+
+impl From<Alert> for Ado {
+    fn from(alert: Alert) -> Self {
+        Ado::new().with_alert(alert)
+    }
+}
+
+impl BitOr for Alert {
+    type Output = Ado;
+
+    fn bitor(self, rhs: Alert) -> Self::Output {
+        Ado::from(self).with_alert(rhs)
+    }
+}
+
+impl BitOr<Alert> for Ado {
+    type Output = Ado;
+
+    fn bitor(self, rhs: Alert) -> Self::Output {
+        self.with_alert(rhs)
+    }
+}
+
 impl TryFrom<AdoRaw> for Ado {
     type Error = InvalidType;
 
     fn try_from(raw: AdoRaw) -> Result<Self, Self::Error> {
-        match raw.alert_type() {
-            // Standard alert types
-            0x02 => Ok(Ado::BatteryStatusChange(BatteryStatusChange(BatteryStatusChangeRaw(
-                raw.battery_status_change(),
-            )))),
-            0x04 => Ok(Ado::Ocp),
-            0x08 => Ok(Ado::Otp),
-            0x10 => Ok(Ado::OperatingConditionChange),
-            0x20 => Ok(Ado::SourceInputChange),
-            0x40 => Ok(Ado::Ovp),
-            // Extended alert types
-            0x80 => match raw.extended_alert_type() {
-                0x01 => Ok(Ado::PowerStateChange),
-                0x02 => Ok(Ado::PowerButtonPress),
-                0x03 => Ok(Ado::PowerButtonRelease),
-                0x04 => Ok(Ado::ControllerInitiatedWake),
-                _ => Err(InvalidType(raw.0)),
-            },
-            _ => Err(InvalidType(raw.0)),
+        const RESERVED_BIT: usize = 0;
+
+        let alert_type = raw.alert_type();
+        if alert_type.bit(RESERVED_BIT) {
+            return Err(InvalidType(raw.0));
         }
+
+        let mut ado = Alert::ALL
+            .into_iter()
+            .filter(|alert| alert_type.bit(alert.bit()))
+            .fold(Ado::new(), Ado::with_alert);
+
+        if alert_type.bit(BATTERY_STATUS_CHANGE_BIT) {
+            ado = ado
+                .with_battery_status_change(BatteryStatusChange(BatteryStatusChangeRaw(raw.battery_status_change())));
+        }
+
+        if alert_type.bit(EXTENDED_ALERT_BIT) {
+            let extended_alert = raw.extended_alert_type().try_into().map_err(|_| InvalidType(raw.0))?;
+            ado = ado.with_extended_alert(extended_alert);
+        }
+
+        Ok(ado)
     }
 }
 
@@ -144,49 +333,80 @@ impl TryFrom<u32> for Ado {
 impl From<Ado> for u32 {
     fn from(ado: Ado) -> Self {
         let mut raw = AdoRaw(0);
+        let mut alert_type = ado.alerts;
 
-        match ado {
-            Ado::BatteryStatusChange(bsc) => {
-                raw.set_alert_type(0x02);
-                raw.set_battery_status_change(bsc.0 .0);
-            }
-            Ado::Ocp => {
-                raw.set_alert_type(0x04);
-            }
-            Ado::Otp => {
-                raw.set_alert_type(0x08);
-            }
-            Ado::OperatingConditionChange => {
-                raw.set_alert_type(0x10);
-            }
-            Ado::SourceInputChange => {
-                raw.set_alert_type(0x20);
-            }
-            Ado::Ovp => {
-                raw.set_alert_type(0x40);
-            }
-            Ado::PowerStateChange => {
-                raw.set_alert_type(0x80);
-                raw.set_extended_alert_type(0x01);
-            }
-            Ado::PowerButtonPress => {
-                raw.set_alert_type(0x80);
-                raw.set_extended_alert_type(0x02);
-            }
-            Ado::PowerButtonRelease => {
-                raw.set_alert_type(0x80);
-                raw.set_extended_alert_type(0x03);
-            }
-            Ado::ControllerInitiatedWake => {
-                raw.set_alert_type(0x80);
-                raw.set_extended_alert_type(0x04);
-            }
+        if let Some(bsc) = ado.battery_status_change {
+            alert_type.set_bit(BATTERY_STATUS_CHANGE_BIT, true);
+            raw.set_battery_status_change(bsc.0 .0);
+        }
+
+        if let Some(extended_alert) = ado.extended_alert {
+            alert_type.set_bit(EXTENDED_ALERT_BIT, true);
+            raw.set_extended_alert_type(extended_alert.into());
         }
 
+        raw.set_alert_type(alert_type);
         raw.0
     }
 }
 
+/// Requests a sink should send to get the details of an alert
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum FollowUpRequest {
+    /// Get_Status message
+    GetStatus,
+    /// Get_Battery_Status message for the given battery
+    GetBatteryStatus(BatteryRef),
+    /// Get_PPS_Status message
+    GetPpsStatus,
+}
+
+/// Alert message
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct AlertMessage(pub Ado);
+
+impl AlertMessage {
+    /// Returns the requests a sink should send in response to this alert, in the order they should be sent
+    ///
+    /// `pps_contract` should be set if the sink is operating under a PPS contract, in which case the PPS status is
+    /// requested when the operating condition changes.
+    pub fn follow_up_requests(&self, pps_contract: bool) -> impl Iterator<Item = FollowUpRequest> {
+        let ado = self.0;
+        let get_status = ado.alerts != 0 || ado.extended_alert == Some(ExtendedAlert::PowerStateChange);
+        let get_pps_status = pps_contract && ado.contains(Alert::OperatingConditionChange);
+
+        get_status
+            .then_some(FollowUpRequest::GetStatus)
+            .into_iter()
+            .chain(
+                ado.battery_status_change
+                    .into_iter()
+                    .flat_map(|bsc| bsc.changed_batteries())
+                    .map(FollowUpRequest::GetBatteryStatus),
+            )
+            .chain(get_pps_status.then_some(FollowUpRequest::GetPpsStatus))
+    }
+}
+
+impl TryFrom<&[u32]> for AlertMessage {
+    type Error = PdError;
+
+    fn try_from(value: &[u32]) -> Result<Self, Self::Error> {
+        match value {
+            [ado] => Ok(AlertMessage(Ado::try_from(*ado)?)),
+            _ => Err(PdError::InvalidParams),
+        }
+    }
+}
+
+impl From<AlertMessage> for [u32; 1] {
+    fn from(value: AlertMessage) -> Self {
+        [value.0.into()]
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -217,6 +437,19 @@ mod tests {
         assert_eq!(bsc.hot_swappable_battery_status(4), Err(PdError::InvalidParams));
     }
 
+    #[test]
+    fn test_battery_status_change_changed_batteries() {
+        let bsc = BatteryStatusChange(BatteryStatusChangeRaw(0b1001_0100));
+        let mut batteries = bsc.changed_batteries();
+        assert_eq!(batteries.next(), Some(BatteryRef::Fixed(0)));
+        assert_eq!(batteries.next(), Some(BatteryRef::Fixed(3)));
+        assert_eq!(batteries.next(), Some(BatteryRef::HotSwappable(2)));
+        assert_eq!(batteries.next(), None);
+
+        let bsc = BatteryStatusChange(BatteryStatusChangeRaw(0));
+        assert_eq!(bsc.changed_batteries().next(), None);
+    }
+
     #[test]
     fn test_ado_try_from_standard_alerts() {
         let mut raw = AdoRaw(0);
@@ -224,49 +457,59 @@ mod tests {
         // BatteryStatusChange
         raw.set_alert_type(0x02);
         raw.set_battery_status_change(0b0010_0001);
-        let ado = Ado::try_from(raw).unwrap();
-        match ado {
-            Ado::BatteryStatusChange(bsc) => {
-                assert_eq!(bsc.fixed_battery_status_change(1).unwrap(), true);
-                assert_eq!(bsc.hot_swappable_battery_status(0).unwrap(), true);
-            }
-            _ => panic!("Expected BatteryStatusChange"),
-        }
+        let expected = BatteryStatusChange::new()
+            .with_changed(BatteryRef::Fixed(1))
+            .with_changed(BatteryRef::HotSwappable(0));
+        assert_eq!(Ado::try_from(raw), Ok(Ado::new().with_battery_status_change(expected)));
+
+        raw.set_battery_status_change(0);
 
         // Ocp
         raw.set_alert_type(0x04);
-        assert_eq!(Ado::try_from(raw).unwrap(), Ado::Ocp);
+        assert_eq!(Ado::try_from(raw), Ok(Alert::Ocp.into()));
 
         // Otp
         raw.set_alert_type(0x08);
-        assert_eq!(Ado::try_from(raw).unwrap(), Ado::Otp);
+        assert_eq!(Ado::try_from(raw), Ok(Alert::Otp.into()));
 
         // OperatingConditionChange
         raw.set_alert_type(0x10);
-        assert_eq!(Ado::try_from(raw).unwrap(), Ado::OperatingConditionChange);
+        assert_eq!(Ado::try_from(raw), Ok(Alert::OperatingConditionChange.into()));
 
         // SourceInputChange
         raw.set_alert_type(0x20);
-        assert_eq!(Ado::try_from(raw).unwrap(), Ado::SourceInputChange);
+        assert_eq!(Ado::try_from(raw), Ok(Alert::SourceInputChange.into()));
 
         // Ovp
         raw.set_alert_type(0x40);
-        assert_eq!(Ado::try_from(raw).unwrap(), Ado::Ovp);
+        assert_eq!(Ado::try_from(raw), Ok(Alert::Ovp.into()));
 
         // Extended alert types
         raw.set_alert_type(0x80);
 
         raw.set_extended_alert_type(0x01);
-        assert_eq!(Ado::try_from(raw).unwrap(), Ado::PowerStateChange);
+        assert_eq!(
+            Ado::try_from(raw),
+            Ok(Ado::new().with_extended_alert(ExtendedAlert::PowerStateChange))
+        );
 
         raw.set_extended_alert_type(0x02);
-        assert_eq!(Ado::try_from(raw).unwrap(), Ado::PowerButtonPress);
+        assert_eq!(
+            Ado::try_from(raw),
+            Ok(Ado::new().with_extended_alert(ExtendedAlert::PowerButtonPress))
+        );
 
         raw.set_extended_alert_type(0x03);
-        assert_eq!(Ado::try_from(raw).unwrap(), Ado::PowerButtonRelease);
+        assert_eq!(
+            Ado::try_from(raw),
+            Ok(Ado::new().with_extended_alert(ExtendedAlert::PowerButtonRelease))
+        );
 
         raw.set_extended_alert_type(0x04);
-        assert_eq!(Ado::try_from(raw).unwrap(), Ado::ControllerInitiatedWake);
+        assert_eq!(
+            Ado::try_from(raw),
+            Ok(Ado::new().with_extended_alert(ExtendedAlert::ControllerInitiatedWake))
+        );
 
         raw.set_extended_alert_type(0x05);
         assert_eq!(Ado::try_from(raw), Err(InvalidType(raw.0)));
@@ -277,15 +520,97 @@ mod tests {
         let mut raw = AdoRaw(0);
         raw.set_alert_type(0xFF);
         assert!(Ado::try_from(raw).is_err());
+
+        // Reserved bit
+        raw.set_alert_type(0x01);
+        assert_eq!(Ado::try_from(raw), Err(InvalidType(raw.0)));
     }
 
     #[test]
     fn test_ado_roundtrip() {
         const RAW_ADO: u32 = 0x02010000;
-        let ado = Ado::try_from(RAW_ADO).expect("Failed to parse raw ADO");
-        let expected = Ado::BatteryStatusChange(BatteryStatusChange(BatteryStatusChangeRaw(0x01)));
-        assert_eq!(ado, expected);
-        let raw_converted: u32 = ado.into();
-        assert_eq!(RAW_ADO, raw_converted);
+        let expected = Ado::new().with_battery_status_change(BatteryStatusChange(BatteryStatusChangeRaw(0x01)));
+        assert_eq!(Ado::try_from(RAW_ADO), Ok(expected));
+        assert_eq!(u32::from(expected), RAW_ADO);
+    }
+
+    #[test]
+    fn test_ado_combined_alerts() {
+        // OCP and OTP in the same ADO
+        const RAW_ADO: u32 = 0x0C000000;
+        let expected = Alert::Ocp | Alert::Otp;
+        assert_eq!(Ado::try_from(RAW_ADO), Ok(expected));
+        assert_eq!(u32::from(expected), RAW_ADO);
+        assert!(expected.contains(Alert::Ocp));
+        assert!(expected.contains(Alert::Otp));
+        assert!(!expected.contains(Alert::Ovp));
+
+        let mut alerts = expected.alerts();
+        assert_eq!(alerts.next(), Some(Alert::Ocp));
+        assert_eq!(alerts.next(), Some(Alert::Otp));
+        assert_eq!(alerts.next(), None);
+
+        // Every alert type at once
+        const RAW_ALL: u32 = 0xFE80_0002;
+        let expected =
+            (Alert::Ocp | Alert::Otp | Alert::OperatingConditionChange | Alert::SourceInputChange | Alert::Ovp)
+                .with_battery_status_change(BatteryStatusChange::new().with_changed(BatteryRef::Fixed(3)))
+                .with_extended_alert(ExtendedAlert::PowerButtonPress);
+        assert_eq!(Ado::try_from(RAW_ALL), Ok(expected));
+        assert_eq!(u32::from(expected), RAW_ALL);
+        assert!(!expected.is_empty());
+        assert!(Ado::new().is_empty());
+    }
+
+    #[test]
+    fn test_alert_message_follow_up_requests() {
+        // Battery only
+        let message = AlertMessage(
+            Ado::new().with_battery_status_change(
+                BatteryStatusChange::new()
+                    .with_changed(BatteryRef::Fixed(0))
+                    .with_changed(BatteryRef::HotSwappable(1)),
+            ),
+        );
+        let mut requests = message.follow_up_requests(false);
+        assert_eq!(
+            requests.next(),
+            Some(FollowUpRequest::GetBatteryStatus(BatteryRef::Fixed(0)))
+        );
+        assert_eq!(
+            requests.next(),
+            Some(FollowUpRequest::GetBatteryStatus(BatteryRef::HotSwappable(1)))
+        );
+        assert_eq!(requests.next(), None);
+
+        // Operating condition change under a PPS contract
+        let message = AlertMessage(Alert::OperatingConditionChange.into());
+        let mut requests = message.follow_up_requests(true);
+        assert_eq!(requests.next(), Some(FollowUpRequest::GetStatus));
+        assert_eq!(requests.next(), Some(FollowUpRequest::GetPpsStatus));
+        assert_eq!(requests.next(), None);
+
+        // No PPS status outside of a PPS contract
+        let mut requests = message.follow_up_requests(false);
+        assert_eq!(requests.next(), Some(FollowUpRequest::GetStatus));
+        assert_eq!(requests.next(), None);
+
+        // Power button events are fully described by the ADO
+        let message = AlertMessage(Ado::new().with_extended_alert(ExtendedAlert::PowerButtonPress));
+        assert_eq!(message.follow_up_requests(false).next(), None);
+    }
+
+    #[test]
+    fn test_alert_message_data_objects() {
+        assert_eq!(
+            AlertMessage::try_from([0x0400_0000].as_slice()),
+            Ok(AlertMessage(Alert::Ocp.into()))
+        );
+        assert_eq!(AlertMessage::try_from([].as_slice()), Err(PdError::InvalidParams));
+        assert_eq!(
+            AlertMessage::try_from([0x0400_0000, 0].as_slice()),
+            Err(PdError::InvalidParams)
+        );
+        assert_eq!(<[u32; 1]>::from(AlertMessage(Alert::Ovp.into())), [0x4000_0000]);
     }
 }
@@ -0,0 +1,463 @@
+//! Battery related messages as defined in the USB PD specification
+//!
+//! This covers the Get_Battery_Cap (6.5.3) and Get_Battery_Status (6.5.4) request data blocks, the
+//! Battery_Capabilities data block (6.5.5) and the Battery_Status data object (6.4.5).
+use bitfield::bitfield;
+
+use crate::ado::MAX_BATTERY_INDEX;
+use crate::usb::ProductId;
+use crate::PdError;
+
+/// Length of the Get_Battery_Cap and Get_Battery_Status data blocks in bytes
+pub const GET_BATTERY_LEN: usize = 1;
+/// Length of the Battery_Capabilities data block in bytes
+pub const BATTERY_CAPABILITIES_LEN: usize = 9;
+
+/// 0.1 Wh unit in mWh
+pub const MWH100_UNIT: u32 = 100;
+
+/// Battery reference value reserved for hot swappable batteries
+const HOT_SWAPPABLE_BASE: u8 = MAX_BATTERY_INDEX as u8 + 1;
+
+/// Error type for battery reference conversion, contains the raw value
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidBatteryRef(pub u8);
+
+impl From<InvalidBatteryRef> for PdError {
+    fn from(_: InvalidBatteryRef) -> Self {
+        PdError::InvalidParams
+    }
+}
+
+/// Reference to a battery, indexed by the same slots as [`crate::ado::BatteryStatusChange`]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum BatteryRef {
+    /// Fixed battery
+    Fixed(u8),
+    /// Hot swappable battery
+    HotSwappable(u8),
+}
+
+impl BatteryRef {
+    /// Create a reference to the fixed battery at the given index
+    pub fn fixed(index: usize) -> Result<Self, PdError> {
+        if index > MAX_BATTERY_INDEX {
+            return Err(PdError::InvalidParams);
+        }
+        Ok(BatteryRef::Fixed(index as u8))
+    }
+
+    /// Create a reference to the hot swappable battery at the given index
+    pub fn hot_swappable(index: usize) -> Result<Self, PdError> {
+        if index > MAX_BATTERY_INDEX {
+            return Err(PdError::InvalidParams);
+        }
+        Ok(BatteryRef::HotSwappable(index as u8))
+    }
+
+    /// Returns the index of the battery within its kind
+    pub fn index(&self) -> usize {
+        match self {
+            BatteryRef::Fixed(index) | BatteryRef::HotSwappable(index) => *index as usize,
+        }
+    }
+}
+
+impl TryFrom<u8> for BatteryRef {
+    type Error = InvalidBatteryRef;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0..HOT_SWAPPABLE_BASE => Ok(BatteryRef::Fixed(value)),
+            HOT_SWAPPABLE_BASE..=7 => Ok(BatteryRef::HotSwappable(value - HOT_SWAPPABLE_BASE)),
+            _ => Err(InvalidBatteryRef(value)),
+        }
+    }
+}
+
+impl From<BatteryRef> for u8 {
+    fn from(value: BatteryRef) -> Self {
+        match value {
+            BatteryRef::Fixed(index) => index,
+            BatteryRef::HotSwappable(index) => index + HOT_SWAPPABLE_BASE,
+        }
+    }
+}
+
+/// Get_Battery_Cap data block
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GetBatteryCap(pub BatteryRef);
+
+impl TryFrom<[u8; GET_BATTERY_LEN]> for GetBatteryCap {
+    type Error = InvalidBatteryRef;
+
+    fn try_from(value: [u8; GET_BATTERY_LEN]) -> Result<Self, Self::Error> {
+        let [battery_ref] = value;
+        Ok(GetBatteryCap(battery_ref.try_into()?))
+    }
+}
+
+impl From<GetBatteryCap> for [u8; GET_BATTERY_LEN] {
+    fn from(value: GetBatteryCap) -> Self {
+        [value.0.into()]
+    }
+}
+
+/// Get_Battery_Status data block
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GetBatteryStatus(pub BatteryRef);
+
+impl TryFrom<[u8; GET_BATTERY_LEN]> for GetBatteryStatus {
+    type Error = InvalidBatteryRef;
+
+    fn try_from(value: [u8; GET_BATTERY_LEN]) -> Result<Self, Self::Error> {
+        let [battery_ref] = value;
+        Ok(GetBatteryStatus(battery_ref.try_into()?))
+    }
+}
+
+impl From<GetBatteryStatus> for [u8; GET_BATTERY_LEN] {
+    fn from(value: GetBatteryStatus) -> Self {
+        [value.0.into()]
+    }
+}
+
+/// Battery capacity
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Capacity {
+    /// Battery not present
+    #[default]
+    NotPresent,
+    /// Capacity unknown
+    Unknown,
+    /// Capacity in mWh, saturated to 100 mWh to 6553.4 Wh when encoded
+    Known(u32),
+}
+
+/// Raw capacity indicating that the battery is not present
+const CAPACITY_NOT_PRESENT: u16 = 0x0000;
+/// Raw capacity indicating that the capacity is unknown
+const CAPACITY_UNKNOWN: u16 = 0xFFFF;
+
+impl From<u16> for Capacity {
+    fn from(value: u16) -> Self {
+        match value {
+            CAPACITY_NOT_PRESENT => Capacity::NotPresent,
+            CAPACITY_UNKNOWN => Capacity::Unknown,
+            capacity => Capacity::Known(capacity as u32 * MWH100_UNIT),
+        }
+    }
+}
+
+impl From<Capacity> for u16 {
+    fn from(value: Capacity) -> Self {
+        match value {
+            Capacity::NotPresent => CAPACITY_NOT_PRESENT,
+            Capacity::Unknown => CAPACITY_UNKNOWN,
+            // Known capacities must not encode as one of the reserved values
+            Capacity::Known(capacity_mwh) => {
+                (capacity_mwh / MWH100_UNIT).clamp(CAPACITY_NOT_PRESENT as u32 + 1, CAPACITY_UNKNOWN as u32 - 1) as u16
+            }
+        }
+    }
+}
+
+bitfield! {
+    /// Raw Battery_Capabilities data block
+    #[derive(Copy, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    struct BatteryCapabilitiesRaw([u8]);
+    impl Debug;
+
+    /// USB vendor ID
+    pub u16, usb_vendor_id, set_usb_vendor_id: 15, 0;
+    /// USB product ID
+    pub u16, usb_product_id, set_usb_product_id: 31, 16;
+    /// Design capacity in 0.1 Wh units
+    pub u16, design_capacity, set_design_capacity: 47, 32;
+    /// Last full charge capacity in 0.1 Wh units
+    pub u16, last_full_charge_capacity, set_last_full_charge_capacity: 63, 48;
+    /// Invalid battery reference
+    pub bool, invalid_battery_ref, set_invalid_battery_ref: 64;
+}
+
+/// Battery_Capabilities data block
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BatteryCapabilities {
+    /// USB vendor ID
+    pub usb_vendor_id: u16,
+    /// USB product ID
+    pub usb_product_id: ProductId,
+    /// Design capacity
+    pub design_capacity: Capacity,
+    /// Last full charge capacity
+    pub last_full_charge_capacity: Capacity,
+    /// The requested battery reference does not exist
+    pub invalid_battery_ref: bool,
+}
+
+impl From<[u8; BATTERY_CAPABILITIES_LEN]> for BatteryCapabilities {
+    fn from(value: [u8; BATTERY_CAPABILITIES_LEN]) -> Self {
+        let raw = BatteryCapabilitiesRaw(value);
+        BatteryCapabilities {
+            usb_vendor_id: raw.usb_vendor_id(),
+            usb_product_id: ProductId(raw.usb_product_id()),
+            design_capacity: raw.design_capacity().into(),
+            last_full_charge_capacity: raw.last_full_charge_capacity().into(),
+            invalid_battery_ref: raw.invalid_battery_ref(),
+        }
+    }
+}
+
+impl From<BatteryCapabilities> for [u8; BATTERY_CAPABILITIES_LEN] {
+    fn from(value: BatteryCapabilities) -> Self {
+        let mut raw = BatteryCapabilitiesRaw([0u8; BATTERY_CAPABILITIES_LEN]);
+        raw.set_usb_vendor_id(value.usb_vendor_id);
+        raw.set_usb_product_id(value.usb_product_id.0);
+        raw.set_design_capacity(value.design_capacity.into());
+        raw.set_last_full_charge_capacity(value.last_full_charge_capacity.into());
+        raw.set_invalid_battery_ref(value.invalid_battery_ref);
+        raw.0
+    }
+}
+
+/// Battery charging status
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ChargingStatus {
+    /// Battery is charging
+    Charging,
+    /// Battery is discharging
+    Discharging,
+    /// Battery is idle
+    #[default]
+    Idle,
+}
+
+impl TryFrom<u8> for ChargingStatus {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x0 => Ok(ChargingStatus::Charging),
+            0x1 => Ok(ChargingStatus::Discharging),
+            0x2 => Ok(ChargingStatus::Idle),
+            _ => Err(value),
+        }
+    }
+}
+
+impl From<ChargingStatus> for u8 {
+    fn from(value: ChargingStatus) -> Self {
+        match value {
+            ChargingStatus::Charging => 0x0,
+            ChargingStatus::Discharging => 0x1,
+            ChargingStatus::Idle => 0x2,
+        }
+    }
+}
+
+/// Error type for BSDO conversion, contains the complete undecoded BSDO
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidBsdo(pub u32);
+
+impl From<InvalidBsdo> for PdError {
+    fn from(_: InvalidBsdo) -> Self {
+        PdError::InvalidParams
+    }
+}
+
+bitfield! {
+    /// Raw Battery_Status data object
+    #[derive(Copy, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    struct BatteryStatusRaw(u32);
+    impl Debug;
+
+    /// Present state of charge in 0.1 Wh units
+    pub u16, present_charge, set_present_charge: 31, 16;
+    /// Invalid battery reference
+    pub bool, invalid_battery_ref, set_invalid_battery_ref: 8;
+    /// Battery is present
+    pub bool, present, set_present: 9;
+    /// Charging status
+    pub u8, charging_status, set_charging_status: 11, 10;
+}
+
+/// Battery_Status data object
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BatteryStatus {
+    /// Present state of charge in mWh, `None` if unknown. Saturated to 6553.4 Wh when encoded.
+    pub present_charge_mwh: Option<u32>,
+    /// The requested battery reference does not exist
+    pub invalid_battery_ref: bool,
+    /// Battery is present
+    pub present: bool,
+    /// Charging status, only valid if the battery is present
+    pub charging_status: ChargingStatus,
+}
+
+/// Raw present state of charge indicating that it is unknown
+const CHARGE_UNKNOWN: u16 = 0xFFFF;
+
+impl TryFrom<u32> for BatteryStatus {
+    type Error = InvalidBsdo;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        let raw = BatteryStatusRaw(value);
+        Ok(BatteryStatus {
+            present_charge_mwh: match raw.present_charge() {
+                CHARGE_UNKNOWN => None,
+                charge => Some(charge as u32 * MWH100_UNIT),
+            },
+            invalid_battery_ref: raw.invalid_battery_ref(),
+            present: raw.present(),
+            charging_status: raw.charging_status().try_into().map_err(|_| InvalidBsdo(value))?,
+        })
+    }
+}
+
+impl From<BatteryStatus> for u32 {
+    fn from(value: BatteryStatus) -> Self {
+        let mut raw = BatteryStatusRaw(0);
+        raw.set_present_charge(value.present_charge_mwh.map_or(CHARGE_UNKNOWN, |charge| {
+            (charge / MWH100_UNIT).min(CHARGE_UNKNOWN as u32 - 1) as u16
+        }));
+        raw.set_invalid_battery_ref(value.invalid_battery_ref);
+        raw.set_present(value.present);
+        raw.set_charging_status(value.charging_status.into());
+        raw.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_battery_ref() {
+        for (raw, expected) in [
+            (0, BatteryRef::Fixed(0)),
+            (3, BatteryRef::Fixed(3)),
+            (4, BatteryRef::HotSwappable(0)),
+            (7, BatteryRef::HotSwappable(3)),
+        ] {
+            assert_eq!(BatteryRef::try_from(raw), Ok(expected));
+            assert_eq!(u8::from(expected), raw);
+        }
+
+        assert_eq!(BatteryRef::try_from(8), Err(InvalidBatteryRef(8)));
+        assert_eq!(BatteryRef::fixed(3), Ok(BatteryRef::Fixed(3)));
+        assert_eq!(BatteryRef::fixed(4), Err(PdError::InvalidParams));
+        assert_eq!(BatteryRef::hot_swappable(2), Ok(BatteryRef::HotSwappable(2)));
+        assert_eq!(BatteryRef::hot_swappable(4), Err(PdError::InvalidParams));
+    }
+
+    #[test]
+    fn test_get_battery_roundtrip() {
+        assert_eq!(
+            GetBatteryCap::try_from([0x05]),
+            Ok(GetBatteryCap(BatteryRef::HotSwappable(1)))
+        );
+        assert_eq!(
+            <[u8; GET_BATTERY_LEN]>::from(GetBatteryCap(BatteryRef::Fixed(2))),
+            [0x02]
+        );
+        assert_eq!(
+            GetBatteryStatus::try_from([0x01]),
+            Ok(GetBatteryStatus(BatteryRef::Fixed(1)))
+        );
+        assert_eq!(
+            <[u8; GET_BATTERY_LEN]>::from(GetBatteryStatus(BatteryRef::HotSwappable(3))),
+            [0x07]
+        );
+        assert_eq!(GetBatteryStatus::try_from([0x08]), Err(InvalidBatteryRef(0x08)));
+    }
+
+    #[test]
+    fn test_battery_capabilities_roundtrip() {
+        // 50 Wh design capacity, 45.6 Wh last full charge capacity
+        const RAW: [u8; BATTERY_CAPABILITIES_LEN] = [0x5E, 0x04, 0x34, 0x12, 0xF4, 0x01, 0xC8, 0x01, 0x00];
+        let expected = BatteryCapabilities {
+            usb_vendor_id: 0x045E,
+            usb_product_id: ProductId(0x1234),
+            design_capacity: Capacity::Known(50000),
+            last_full_charge_capacity: Capacity::Known(45600),
+            invalid_battery_ref: false,
+        };
+        assert_eq!(BatteryCapabilities::from(RAW), expected);
+        assert_eq!(<[u8; BATTERY_CAPABILITIES_LEN]>::from(expected), RAW);
+    }
+
+    #[test]
+    fn test_battery_capabilities_invalid_ref() {
+        const RAW: [u8; BATTERY_CAPABILITIES_LEN] = [0x5E, 0x04, 0x34, 0x12, 0x00, 0x00, 0xFF, 0xFF, 0x01];
+        let expected = BatteryCapabilities {
+            usb_vendor_id: 0x045E,
+            usb_product_id: ProductId(0x1234),
+            design_capacity: Capacity::NotPresent,
+            last_full_charge_capacity: Capacity::Unknown,
+            invalid_battery_ref: true,
+        };
+        assert_eq!(BatteryCapabilities::from(RAW), expected);
+        assert_eq!(<[u8; BATTERY_CAPABILITIES_LEN]>::from(expected), RAW);
+    }
+
+    #[test]
+    fn test_capacity_saturate() {
+        assert_eq!(u16::from(Capacity::Known(50)), 0x0001);
+        assert_eq!(u16::from(Capacity::Known(6_553_400)), 0xFFFE);
+        assert_eq!(u16::from(Capacity::Known(6_553_500)), 0xFFFE);
+        assert_eq!(u16::from(Capacity::Known(u32::MAX)), 0xFFFE);
+    }
+
+    #[test]
+    fn test_battery_status_saturate() {
+        for (charge_mwh, expected) in [(6_553_400, 0xFFFE), (6_553_500, 0xFFFE), (u32::MAX, 0xFFFE)] {
+            let status = BatteryStatus {
+                present_charge_mwh: Some(charge_mwh),
+                ..Default::default()
+            };
+            assert_eq!(u32::from(status) >> 16, expected);
+        }
+    }
+
+    #[test]
+    fn test_battery_status_roundtrip() {
+        // 32.1 Wh, present and discharging
+        const RAW: u32 = 0x01410600;
+        let expected = BatteryStatus {
+            present_charge_mwh: Some(32100),
+            invalid_battery_ref: false,
+            present: true,
+            charging_status: ChargingStatus::Discharging,
+        };
+        assert_eq!(BatteryStatus::try_from(RAW), Ok(expected));
+        assert_eq!(u32::from(expected), RAW);
+
+        // Unknown charge
+        const RAW_UNKNOWN: u32 = 0xFFFF0A00;
+        let expected = BatteryStatus {
+            present_charge_mwh: None,
+            invalid_battery_ref: false,
+            present: true,
+            charging_status: ChargingStatus::Idle,
+        };
+        assert_eq!(BatteryStatus::try_from(RAW_UNKNOWN), Ok(expected));
+        assert_eq!(u32::from(expected), RAW_UNKNOWN);
+    }
+
+    #[test]
+    fn test_battery_status_invalid() {
+        // Reserved charging status
+        assert_eq!(BatteryStatus::try_from(0x00000E00), Err(InvalidBsdo(0x00000E00)));
+    }
+}
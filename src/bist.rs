@@ -0,0 +1,155 @@
+//! BIST data object as defined in the USB PD specification 6.4.3
+//!
+//! Built-In Self Test (BIST) messages are used during compliance testing to put the UUT into a test mode.
+use core::ops::RangeInclusive;
+
+use bitfield::bitfield;
+
+use crate::pdo::{Common, Contract};
+use crate::{PdError, PowerRole};
+
+/// vSafe5V voltage range in mV
+pub const VSAFE5V_MV: RangeInclusive<u16> = 4750..=5500;
+
+/// Error type for BIST mode conversion, contains the raw value
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidBistMode(pub u8);
+
+impl From<InvalidBistMode> for PdError {
+    fn from(_: InvalidBistMode) -> Self {
+        PdError::InvalidParams
+    }
+}
+
+bitfield! {
+    /// Raw BIST data object
+    #[derive(Copy, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    struct BistRaw(u32);
+    impl Debug;
+
+    /// BIST mode
+    pub u8, mode, set_mode: 31, 28;
+}
+
+/// BIST data object
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Bist {
+    /// Transmit a continuous carrier signal
+    CarrierMode,
+    /// Receive and discard test data messages
+    TestData,
+    /// Enter shared capacity test mode
+    SharedTestModeEntry,
+    /// Exit shared capacity test mode
+    SharedTestModeExit,
+}
+
+impl Bist {
+    /// Returns true if the UUT should act on this BIST request
+    ///
+    /// BIST requests are only valid while the contract is at vSafe5V. Shared capacity test mode only applies to
+    /// sources.
+    pub fn is_allowed(&self, contract: &Contract, power_role: PowerRole) -> bool {
+        let at_vsafe5v =
+            VSAFE5V_MV.contains(&contract.pdo.min_voltage_mv()) && VSAFE5V_MV.contains(&contract.pdo.max_voltage_mv());
+
+        match self {
+            Bist::CarrierMode | Bist::TestData => at_vsafe5v,
+            Bist::SharedTestModeEntry | Bist::SharedTestModeExit => at_vsafe5v && power_role == PowerRole::Source,
+        }
+    }
+}
+
+impl TryFrom<u32> for Bist {
+    type Error = InvalidBistMode;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        let raw = BistRaw(value);
+        match raw.mode() {
+            0x5 => Ok(Bist::CarrierMode),
+            0x8 => Ok(Bist::TestData),
+            0x9 => Ok(Bist::SharedTestModeEntry),
+            0xA => Ok(Bist::SharedTestModeExit),
+            mode => Err(InvalidBistMode(mode)),
+        }
+    }
+}
+
+impl From<Bist> for u32 {
+    fn from(value: Bist) -> Self {
+        let mut raw = BistRaw(0);
+        raw.set_mode(match value {
+            Bist::CarrierMode => 0x5,
+            Bist::TestData => 0x8,
+            Bist::SharedTestModeEntry => 0x9,
+            Bist::SharedTestModeExit => 0xA,
+        });
+        raw.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdo::rdo::tests::fixed_rdo;
+    use crate::pdo::{sink, source};
+
+    fn source_contract(voltage_mv: u16) -> Contract {
+        let pdo = source::Pdo::Fixed(source::FixedData {
+            voltage_mv,
+            current_ma: 3000,
+            ..Default::default()
+        });
+        Contract::from_source(pdo, fixed_rdo(1))
+    }
+
+    fn is_allowed(bist: Bist, contract: Contract, power_role: PowerRole) -> bool {
+        bist.is_allowed(&contract, power_role)
+    }
+
+    #[test]
+    fn test_bist_roundtrip() {
+        assert_eq!(Bist::try_from(0x5000_0000), Ok(Bist::CarrierMode));
+        assert_eq!(Bist::try_from(0x8000_0000), Ok(Bist::TestData));
+        assert_eq!(Bist::try_from(0x9000_0000), Ok(Bist::SharedTestModeEntry));
+        assert_eq!(Bist::try_from(0xA000_0000), Ok(Bist::SharedTestModeExit));
+        assert_eq!(u32::from(Bist::CarrierMode), 0x5000_0000);
+        assert_eq!(u32::from(Bist::SharedTestModeExit), 0xA000_0000);
+        assert_eq!(Bist::try_from(0x0000_0000), Err(InvalidBistMode(0x0)));
+        assert_eq!(Bist::try_from(0xF000_0000), Err(InvalidBistMode(0xF)));
+    }
+
+    #[test]
+    fn test_bist_is_allowed() {
+        let vsafe5v = source_contract(5000);
+        let high_voltage = source_contract(20000);
+
+        assert!(is_allowed(Bist::CarrierMode, vsafe5v, PowerRole::Sink));
+        assert!(is_allowed(Bist::TestData, vsafe5v, PowerRole::Source));
+        assert!(!is_allowed(Bist::CarrierMode, high_voltage, PowerRole::Sink));
+        assert!(!is_allowed(Bist::TestData, high_voltage, PowerRole::Source));
+
+        assert!(is_allowed(Bist::SharedTestModeEntry, vsafe5v, PowerRole::Source));
+        assert!(!is_allowed(Bist::SharedTestModeEntry, vsafe5v, PowerRole::Sink));
+        assert!(!is_allowed(Bist::SharedTestModeExit, high_voltage, PowerRole::Source));
+    }
+
+    #[test]
+    fn test_bist_is_allowed_sink_contract() {
+        let pdo = sink::Pdo::Fixed(sink::FixedData {
+            operational_current_ma: 3000,
+            voltage_mv: 5000,
+            dual_role_power: false,
+            higher_capability: false,
+            unconstrained_power: false,
+            usb_comms_capable: false,
+            dual_role_data: false,
+            frs_required_current: sink::FrsRequiredCurrent::None,
+        });
+        let contract = Contract::from_sink(pdo, fixed_rdo(1));
+        assert!(is_allowed(Bist::CarrierMode, contract, PowerRole::Sink));
+    }
+}
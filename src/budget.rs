@@ -0,0 +1,690 @@
+//! Source power budget shared between multiple ports
+//!
+//! [`PowerBudget`] distributes the power of a single supply across the ports it feeds. Every port is given one of a
+//! list of allowed power levels and advertises the fixed supplies allowed at that level by the power rules in the
+//! USB PD specification 10.2.3. The split is decided by a [`Policy`], which runs again whenever a sink attaches or
+//! detaches, a priority changes or the total power changes.
+//!
+//! The lowest power level is reserved for every port, so a sink that attaches is always offered at least that level.
+//! Attached ports whose allocation changed must be sent new `Source_Capabilities`, these are returned by
+//! [`PowerBudget::pending_updates`] and [`PowerBudget::take_update`].
+//!
+//! More than 3 A is only offered on ports where a 5 A cable has been discovered, see [`PowerBudget::set_cable_5a`].
+use crate::pdo::{source, MA10_UNIT};
+use crate::ucsi::lpm::get_connector_status::ProviderCapsLimitedReason;
+use crate::{GlobalPortId, PdError};
+
+/// Common power levels in mW
+pub const DEFAULT_LEVELS_MW: [u32; 5] = [15000, 27000, 45000, 60000, 100000];
+
+/// Maximum number of PDOs generated for a power level
+pub const MAX_PDOS: usize = 4;
+
+/// Fixed supplies offered by the power rules, voltage in mV, maximum current in mA and the power in mW above which
+/// the supply is offered
+const FIXED_SUPPLIES: [(u16, u16, u32); MAX_PDOS] = [
+    (5000, 3000, 0),
+    (9000, 3000, 15000),
+    (15000, 3000, 27000),
+    (20000, 3000, 45000),
+];
+/// Power in mW above which 20 V is offered at 5 A, this requires a 5 A cable
+const FIXED_20V_5A_MW: u32 = 60000;
+/// Highest power in mW with a 3 A cable
+const MAX_3A_CABLE_MW: u32 = FIXED_20V_5A_MW;
+/// Maximum 20 V current with a 5 A cable
+const MAX_CURRENT_5A_MA: u16 = 5000;
+
+/// Source capabilities for a power level
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Capabilities {
+    /// Power level in mW
+    power_mw: u32,
+    /// PDOs
+    pdos: [source::Pdo; MAX_PDOS],
+    /// Number of valid PDOs
+    num_pdos: usize,
+}
+
+impl Capabilities {
+    /// Create the fixed supply capabilities for a power level
+    ///
+    /// The flags of the vSafe5V PDO are taken from `vsafe5v`, its voltage and current are ignored. The current is
+    /// limited to 3 A unless `cable_5a` is set.
+    pub fn new(power_mw: u32, vsafe5v: source::FixedData, cable_5a: bool) -> Self {
+        let mut pdos = [source::Pdo::default(); MAX_PDOS];
+        let mut num_pdos = 0;
+        for ((voltage_mv, max_current_ma, above_mw), pdo) in FIXED_SUPPLIES.into_iter().zip(pdos.iter_mut()) {
+            if voltage_mv != 5000 && power_mw <= above_mw {
+                break;
+            }
+
+            let max_current_ma = if cable_5a && voltage_mv == 20000 && power_mw > FIXED_20V_5A_MW {
+                MAX_CURRENT_5A_MA
+            } else {
+                max_current_ma
+            };
+            let current_ma = (power_mw.saturating_mul(1000) / voltage_mv as u32).min(max_current_ma as u32) as u16;
+            let data = if num_pdos == 0 {
+                vsafe5v
+            } else {
+                source::FixedData::default()
+            };
+            *pdo = source::Pdo::Fixed(source::FixedData {
+                voltage_mv,
+                current_ma: current_ma / MA10_UNIT * MA10_UNIT,
+                ..data
+            });
+            num_pdos += 1;
+        }
+
+        Self {
+            power_mw,
+            pdos,
+            num_pdos,
+        }
+    }
+
+    /// Returns the power level in mW
+    pub fn power_mw(&self) -> u32 {
+        self.power_mw
+    }
+
+    /// Returns the PDOs, the first is always vSafe5V
+    pub fn pdos(&self) -> &[source::Pdo] {
+        self.pdos.get(..self.num_pdos).unwrap_or_default()
+    }
+}
+
+/// Budget state of a single port
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Port {
+    /// A sink is attached
+    pub attached: bool,
+    /// Priority, higher values are served first by [`Priority`]
+    pub priority: u8,
+    /// Attach sequence number, lower values attached earlier
+    pub attach_order: u32,
+    /// Allocated power in mW, zero if detached
+    pub allocated_mw: u32,
+    /// A 5 A cable has been discovered
+    pub cable_5a: bool,
+    /// Power in mW of the last capabilities returned by [`PowerBudget::take_update`]
+    pub advertised_mw: u32,
+    /// Value of `cable_5a` for the last capabilities returned by [`PowerBudget::take_update`]
+    pub advertised_cable_5a: bool,
+}
+
+impl Port {
+    /// Returns the highest power in mW the port can offer with its cable
+    pub fn max_mw(&self) -> u32 {
+        if self.cable_5a {
+            u32::MAX
+        } else {
+            MAX_3A_CABLE_MW
+        }
+    }
+
+    /// Returns true if the port is attached and its capabilities changed since they were last advertised
+    fn is_pending(&self) -> bool {
+        self.attached && (self.allocated_mw, self.cable_5a) != (self.advertised_mw, self.advertised_cable_5a)
+    }
+}
+
+/// Power allocation policy
+pub trait Policy {
+    /// Allocate power to the attached ports in `ports`
+    ///
+    /// `allocation` has the same length as `ports` and is zero on entry. Every attached port must be given one of
+    /// `levels`, which is sorted in ascending order, and the sum must not exceed `total_mw`. The total is always
+    /// enough to give every port the lowest level. Power above [`Port::max_mw`] cannot be used by a port.
+    fn allocate(&self, total_mw: u32, levels: &[u32], ports: &[Port], allocation: &mut [u32]);
+}
+
+/// Returns the highest level that does not exceed `max_mw`, the lowest level if none does
+fn highest_level(levels: &[u32], max_mw: u32) -> u32 {
+    levels
+        .iter()
+        .rev()
+        .find(|level| **level <= max_mw)
+        .or(levels.first())
+        .copied()
+        .unwrap_or(0)
+}
+
+/// Allocate the highest possible level to each attached port in turn, reserving the lowest level for the rest
+///
+/// `before` returns true if the first port is served before the second, ties are served in port order.
+fn allocate_in_order(
+    total_mw: u32,
+    levels: &[u32],
+    ports: &[Port],
+    allocation: &mut [u32],
+    before: impl Fn(&Port, &Port) -> bool,
+) {
+    let min_mw = levels.first().copied().unwrap_or(0);
+    let mut remaining_mw = total_mw;
+    let mut waiting = ports.iter().filter(|port| port.attached).count() as u32;
+    while waiting > 0 {
+        waiting -= 1;
+        let next = ports
+            .iter()
+            .zip(allocation.iter_mut())
+            .filter(|(port, allocated_mw)| port.attached && **allocated_mw == 0)
+            .reduce(|first, other| if before(other.0, first.0) { other } else { first });
+        let Some((port, allocated_mw)) = next else {
+            break;
+        };
+
+        let reserved_mw = min_mw.saturating_mul(waiting);
+        *allocated_mw = highest_level(levels, remaining_mw.saturating_sub(reserved_mw).min(port.max_mw()));
+        remaining_mw = remaining_mw.saturating_sub(*allocated_mw);
+    }
+}
+
+/// Ports are served in the order they attached, a port keeps its power until the budget is needed to give a later
+/// port the lowest level
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct FirstCome;
+
+impl Policy for FirstCome {
+    fn allocate(&self, total_mw: u32, levels: &[u32], ports: &[Port], allocation: &mut [u32]) {
+        allocate_in_order(total_mw, levels, ports, allocation, |port, other| {
+            port.attach_order < other.attach_order
+        });
+    }
+}
+
+/// Every attached port is given the highest level that fits in an equal share of the total, any remainder is unused
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EqualShare;
+
+impl Policy for EqualShare {
+    fn allocate(&self, total_mw: u32, levels: &[u32], ports: &[Port], allocation: &mut [u32]) {
+        let attached = ports.iter().filter(|port| port.attached).count() as u32;
+        let share_mw = total_mw.checked_div(attached).unwrap_or(0);
+        for (port, allocated_mw) in ports.iter().zip(allocation.iter_mut()) {
+            if port.attached {
+                *allocated_mw = highest_level(levels, share_mw.min(port.max_mw()));
+            }
+        }
+    }
+}
+
+/// Ports are served from the highest priority down, ports with the same priority in the order they attached
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Priority;
+
+impl Policy for Priority {
+    fn allocate(&self, total_mw: u32, levels: &[u32], ports: &[Port], allocation: &mut [u32]) {
+        allocate_in_order(total_mw, levels, ports, allocation, |port, other| {
+            (port.priority, other.attach_order) > (other.priority, port.attach_order)
+        });
+    }
+}
+
+/// Power budget shared by `N` ports
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PowerBudget<'a, P: Policy, const N: usize> {
+    /// Allocation policy
+    policy: P,
+    /// Total power in mW
+    total_mw: u32,
+    /// Allowed power levels in mW, in ascending order
+    levels: &'a [u32],
+    /// Flags for the vSafe5V PDO
+    vsafe5v: source::FixedData,
+    /// Port states
+    ports: [Port; N],
+    /// Attach sequence number of the next sink
+    next_attach_order: u32,
+}
+
+impl<'a, P: Policy, const N: usize> PowerBudget<'a, P, N> {
+    /// Create a new power budget with all ports detached
+    ///
+    /// Returns [`PdError::InvalidParams`] if `levels` is empty, not in strictly ascending order, starts at zero or
+    /// `total_mw` cannot give every port the lowest level.
+    pub fn new(policy: P, total_mw: u32, levels: &'a [u32]) -> Result<Self, PdError> {
+        let ascending = levels.windows(2).all(|pair| pair.first() < pair.get(1));
+        if !ascending || levels.first().is_none_or(|min_mw| *min_mw == 0) {
+            return Err(PdError::InvalidParams);
+        }
+
+        let mut budget = Self {
+            policy,
+            total_mw: 0,
+            levels,
+            vsafe5v: source::FixedData::default(),
+            ports: [Port::default(); N],
+            next_attach_order: 0,
+        };
+        budget.set_total_mw(total_mw)?;
+        Ok(budget)
+    }
+
+    /// Returns the total power in mW
+    pub fn total_mw(&self) -> u32 {
+        self.total_mw
+    }
+
+    /// Returns the power in mW that is not allocated to any port
+    pub fn unallocated_mw(&self) -> u32 {
+        let allocated_mw = self
+            .ports
+            .iter()
+            .fold(0u32, |total, port| total.saturating_add(port.allocated_mw));
+        self.total_mw.saturating_sub(allocated_mw)
+    }
+
+    /// Returns the state of all ports
+    pub fn ports(&self) -> &[Port; N] {
+        &self.ports
+    }
+
+    /// Set the flags used for the vSafe5V PDO of all ports, the voltage and current are ignored
+    ///
+    /// This does not make any port pending, the new flags are included in the next update.
+    pub fn set_vsafe5v_flags(&mut self, vsafe5v: source::FixedData) {
+        self.vsafe5v = vsafe5v;
+    }
+
+    /// Set the total power in mW, for example when the supply is derated
+    ///
+    /// Returns [`PdError::InvalidParams`] if the total cannot give every port the lowest level.
+    pub fn set_total_mw(&mut self, total_mw: u32) -> Result<(), PdError> {
+        let min_mw = self.levels.first().copied().unwrap_or(0);
+        if min_mw
+            .checked_mul(N as u32)
+            .is_none_or(|reserved_mw| reserved_mw > total_mw)
+        {
+            return Err(PdError::InvalidParams);
+        }
+
+        self.total_mw = total_mw;
+        self.rebalance();
+        Ok(())
+    }
+
+    /// Returns the state of a port
+    fn port_mut(&mut self, port: GlobalPortId) -> Result<&mut Port, PdError> {
+        self.ports.get_mut(port.0 as usize).ok_or(PdError::InvalidPort)
+    }
+
+    /// Returns the state of a port
+    pub fn port(&self, port: GlobalPortId) -> Result<&Port, PdError> {
+        self.ports.get(port.0 as usize).ok_or(PdError::InvalidPort)
+    }
+
+    /// A sink has attached to a port
+    ///
+    /// The port is pending with its initial capabilities, along with any other port whose allocation changed.
+    pub fn attach(&mut self, port: GlobalPortId) -> Result<(), PdError> {
+        let attach_order = self.next_attach_order;
+        let state = self.port_mut(port)?;
+        if state.attached {
+            return Ok(());
+        }
+
+        *state = Port {
+            attached: true,
+            attach_order,
+            ..*state
+        };
+        self.next_attach_order = self.next_attach_order.wrapping_add(1);
+        self.rebalance();
+        Ok(())
+    }
+
+    /// The sink has detached from a port
+    pub fn detach(&mut self, port: GlobalPortId) -> Result<(), PdError> {
+        let state = self.port_mut(port)?;
+        if !state.attached {
+            return Ok(());
+        }
+
+        state.attached = false;
+        state.cable_5a = false;
+        state.advertised_mw = 0;
+        state.advertised_cable_5a = false;
+        self.rebalance();
+        Ok(())
+    }
+
+    /// Set whether a 5 A cable has been discovered on a port, this is cleared when the sink detaches
+    ///
+    /// Without a 5 A cable the port is limited to 3 A and to [`Port::max_mw`].
+    pub fn set_cable_5a(&mut self, port: GlobalPortId, cable_5a: bool) -> Result<(), PdError> {
+        self.port_mut(port)?.cable_5a = cable_5a;
+        self.rebalance();
+        Ok(())
+    }
+
+    /// Set the priority of a port, used by the [`Priority`] policy
+    pub fn set_priority(&mut self, port: GlobalPortId, priority: u8) -> Result<(), PdError> {
+        self.port_mut(port)?.priority = priority;
+        self.rebalance();
+        Ok(())
+    }
+
+    /// Run the policy and update the allocation of every port
+    fn rebalance(&mut self) {
+        let mut allocation = [0; N];
+        self.policy
+            .allocate(self.total_mw, self.levels, &self.ports, &mut allocation);
+        for (port, allocated_mw) in self.ports.iter_mut().zip(allocation) {
+            port.allocated_mw = if port.attached { allocated_mw } else { 0 };
+        }
+    }
+
+    /// Returns the capabilities for the present allocation of a port
+    ///
+    /// A detached port is given the capabilities of the lowest level.
+    pub fn capabilities(&self, port: GlobalPortId) -> Result<Capabilities, PdError> {
+        let state = self.port(port)?;
+        let power_mw = if state.attached {
+            state.allocated_mw
+        } else {
+            self.levels.first().copied().unwrap_or(0)
+        };
+        Ok(Capabilities::new(power_mw, self.vsafe5v, state.cable_5a))
+    }
+
+    /// Returns the attached ports that must be sent new `Source_Capabilities`
+    pub fn pending_updates(&self) -> impl Iterator<Item = GlobalPortId> + '_ {
+        self.ports
+            .iter()
+            .zip(0..)
+            .filter(|(port, _)| port.is_pending())
+            .map(|(_, index)| GlobalPortId(index))
+    }
+
+    /// Returns the new capabilities if the port must be sent new `Source_Capabilities`, `None` if not
+    ///
+    /// The port is no longer pending after this call.
+    pub fn take_update(&mut self, port: GlobalPortId) -> Result<Option<Capabilities>, PdError> {
+        let vsafe5v = self.vsafe5v;
+        let state = self.port_mut(port)?;
+        if !state.is_pending() {
+            return Ok(None);
+        }
+
+        state.advertised_mw = state.allocated_mw;
+        state.advertised_cable_5a = state.cable_5a;
+        Ok(Some(Capabilities::new(state.allocated_mw, vsafe5v, state.cable_5a)))
+    }
+
+    /// Returns the reason reported to UCSI for the capabilities of a port being limited, `None` if not limited
+    ///
+    /// The power budget is lowered if the port is given less than the highest level its cable allows and the budget
+    /// limit is reached if there is not enough unallocated power for another port at the lowest level.
+    pub fn limited_reason(&self, port: GlobalPortId) -> Result<Option<ProviderCapsLimitedReason>, PdError> {
+        let state = self.port(port)?;
+        if !state.attached {
+            return Ok(None);
+        }
+
+        let max_mw = highest_level(self.levels, state.max_mw());
+        let min_mw = self.levels.first().copied().unwrap_or(0);
+        let lowered = state.allocated_mw < max_mw;
+        let limit = self.unallocated_mw() < min_mw;
+        if !lowered && !limit {
+            return Ok(None);
+        }
+
+        let mut reason = ProviderCapsLimitedReason::default();
+        reason
+            .set_power_budget_lowered(lowered)
+            .set_reaching_power_budget_limit(limit);
+        Ok(Some(reason))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOTAL_MW: u32 = 150000;
+
+    fn budget<P: Policy, const N: usize>(policy: P, total_mw: u32) -> PowerBudget<'static, P, N> {
+        PowerBudget {
+            policy,
+            total_mw,
+            levels: &DEFAULT_LEVELS_MW,
+            vsafe5v: source::FixedData::default(),
+            ports: [Port {
+                cable_5a: true,
+                ..Port::default()
+            }; N],
+            next_attach_order: 0,
+        }
+    }
+
+    fn allocations<P: Policy, const N: usize>(budget: &PowerBudget<'_, P, N>) -> [u32; N] {
+        budget.ports().map(|port| port.allocated_mw)
+    }
+
+    fn pending<P: Policy, const N: usize>(budget: &PowerBudget<'_, P, N>) -> [bool; N] {
+        let mut pending = [false; N];
+        for port in budget.pending_updates() {
+            if let Some(pending) = pending.get_mut(port.0 as usize) {
+                *pending = true;
+            }
+        }
+        pending
+    }
+
+    fn fixed(voltage_mv: u16, current_ma: u16) -> source::Pdo {
+        source::Pdo::Fixed(source::FixedData {
+            voltage_mv,
+            current_ma,
+            ..Default::default()
+        })
+    }
+
+    #[test]
+    fn test_capabilities() {
+        let flags = source::FixedData {
+            usb_comms_capable: true,
+            ..Default::default()
+        };
+        let vsafe5v = source::Pdo::Fixed(source::FixedData {
+            voltage_mv: 5000,
+            current_ma: 3000,
+            ..flags
+        });
+
+        assert_eq!(Capabilities::new(15000, flags, false).pdos(), &[vsafe5v]);
+        assert_eq!(
+            Capabilities::new(27000, flags, false).pdos(),
+            &[vsafe5v, fixed(9000, 3000)]
+        );
+        assert_eq!(
+            Capabilities::new(45000, flags, false).pdos(),
+            &[vsafe5v, fixed(9000, 3000), fixed(15000, 3000)]
+        );
+        assert_eq!(
+            Capabilities::new(60000, flags, false).pdos(),
+            &[vsafe5v, fixed(9000, 3000), fixed(15000, 3000), fixed(20000, 3000)]
+        );
+        assert_eq!(
+            Capabilities::new(100000, flags, true).pdos(),
+            &[vsafe5v, fixed(9000, 3000), fixed(15000, 3000), fixed(20000, 5000)]
+        );
+        // Limited to 3 A without a 5 A cable
+        assert_eq!(
+            Capabilities::new(100000, flags, false).pdos(),
+            &[vsafe5v, fixed(9000, 3000), fixed(15000, 3000), fixed(20000, 3000)]
+        );
+        // Power between levels limits the current of the highest voltage
+        assert_eq!(
+            Capabilities::new(35000, flags, false).pdos(),
+            &[vsafe5v, fixed(9000, 3000), fixed(15000, 2330)]
+        );
+        assert_eq!(
+            Capabilities::new(7500, flags, false).pdos(),
+            &[source::Pdo::Fixed(source::FixedData {
+                voltage_mv: 5000,
+                current_ma: 1500,
+                ..flags
+            })]
+        );
+    }
+
+    #[test]
+    fn test_new() {
+        assert!(PowerBudget::<_, 4>::new(FirstCome, TOTAL_MW, &DEFAULT_LEVELS_MW).is_ok());
+        assert_eq!(
+            PowerBudget::<_, 4>::new(FirstCome, 59999, &DEFAULT_LEVELS_MW),
+            Err(PdError::InvalidParams)
+        );
+        assert_eq!(
+            PowerBudget::<_, 4>::new(FirstCome, TOTAL_MW, &[]),
+            Err(PdError::InvalidParams)
+        );
+        assert_eq!(
+            PowerBudget::<_, 4>::new(FirstCome, TOTAL_MW, &[27000, 15000]),
+            Err(PdError::InvalidParams)
+        );
+        assert_eq!(
+            PowerBudget::<_, 4>::new(FirstCome, TOTAL_MW, &[0, 15000]),
+            Err(PdError::InvalidParams)
+        );
+    }
+
+    #[test]
+    fn test_first_come() {
+        let mut budget = budget::<_, 4>(FirstCome, TOTAL_MW);
+
+        assert_eq!(budget.attach(GlobalPortId(2)), Ok(()));
+        assert_eq!(allocations(&budget), [0, 0, 100000, 0]);
+        assert_eq!(pending(&budget), [false, false, true, false]);
+        assert_eq!(
+            budget
+                .take_update(GlobalPortId(2))
+                .map(|caps| caps.map(|caps| caps.power_mw())),
+            Ok(Some(100000))
+        );
+        assert_eq!(budget.take_update(GlobalPortId(2)), Ok(None));
+
+        // The first port keeps its power
+        budget.attach(GlobalPortId(0)).ok();
+        budget.attach(GlobalPortId(1)).ok();
+        assert_eq!(allocations(&budget), [27000, 15000, 100000, 0]);
+        assert_eq!(pending(&budget), [true, true, false, false]);
+
+        // The lowest level must be reserved for the last port
+        budget.attach(GlobalPortId(3)).ok();
+        assert_eq!(allocations(&budget), [15000, 15000, 100000, 15000]);
+
+        // Power is returned in attach order
+        budget.detach(GlobalPortId(2)).ok();
+        assert_eq!(allocations(&budget), [100000, 27000, 0, 15000]);
+        assert_eq!(budget.take_update(GlobalPortId(2)), Ok(None));
+
+        assert_eq!(budget.attach(GlobalPortId(4)), Err(PdError::InvalidPort));
+    }
+
+    #[test]
+    fn test_equal_share() {
+        let mut budget = budget::<_, 4>(EqualShare, TOTAL_MW);
+
+        budget.attach(GlobalPortId(0)).ok();
+        assert_eq!(allocations(&budget), [100000, 0, 0, 0]);
+        budget.attach(GlobalPortId(1)).ok();
+        assert_eq!(allocations(&budget), [60000, 60000, 0, 0]);
+        budget.attach(GlobalPortId(2)).ok();
+        assert_eq!(allocations(&budget), [45000, 45000, 45000, 0]);
+        budget.attach(GlobalPortId(3)).ok();
+        assert_eq!(allocations(&budget), [27000, 27000, 27000, 27000]);
+        assert_eq!(budget.unallocated_mw(), 42000);
+    }
+
+    #[test]
+    fn test_priority() {
+        let mut budget = budget::<_, 4>(Priority, TOTAL_MW);
+
+        budget.attach(GlobalPortId(0)).ok();
+        budget.attach(GlobalPortId(1)).ok();
+        assert_eq!(allocations(&budget), [100000, 45000, 0, 0]);
+
+        budget.set_priority(GlobalPortId(1), 1).ok();
+        assert_eq!(allocations(&budget), [45000, 100000, 0, 0]);
+
+        // A higher priority port is served first even if it attached last
+        budget.set_priority(GlobalPortId(1), 0).ok();
+        budget.set_priority(GlobalPortId(3), 1).ok();
+        budget.attach(GlobalPortId(3)).ok();
+        assert_eq!(allocations(&budget), [27000, 15000, 0, 100000]);
+    }
+
+    #[test]
+    fn test_cable_5a() {
+        let mut budget = budget::<_, 2>(FirstCome, TOTAL_MW);
+        budget.attach(GlobalPortId(0)).ok();
+        budget.take_update(GlobalPortId(0)).ok();
+
+        // Without a 5 A cable the port is limited to 60 W and the rest goes to the next port
+        assert_eq!(budget.set_cable_5a(GlobalPortId(0), false), Ok(()));
+        budget.attach(GlobalPortId(1)).ok();
+        assert_eq!(allocations(&budget), [60000, 60000]);
+        assert_eq!(pending(&budget), [true, true]);
+        assert_eq!(budget.limited_reason(GlobalPortId(0)), Ok(None));
+        assert_eq!(
+            budget
+                .take_update(GlobalPortId(0))
+                .map(|caps| caps.map(|caps| caps.pdos().last().copied())),
+            Ok(Some(Some(fixed(20000, 3000))))
+        );
+
+        // Discovering a 5 A cable is advertised even if the allocation does not change
+        budget.detach(GlobalPortId(1)).ok();
+        budget.set_total_mw(60000).ok();
+        budget.take_update(GlobalPortId(0)).ok();
+        assert_eq!(budget.set_cable_5a(GlobalPortId(0), true), Ok(()));
+        assert_eq!(allocations(&budget), [60000, 0]);
+        assert_eq!(pending(&budget), [true, false]);
+        budget.set_total_mw(TOTAL_MW).ok();
+        assert_eq!(
+            budget
+                .take_update(GlobalPortId(0))
+                .map(|caps| caps.map(|caps| caps.pdos().last().copied())),
+            Ok(Some(Some(fixed(20000, 5000))))
+        );
+
+        // The cable is forgotten when the sink detaches
+        budget.detach(GlobalPortId(0)).ok();
+        assert_eq!(budget.ports().map(|port| port.cable_5a), [false, false]);
+        assert_eq!(budget.set_cable_5a(GlobalPortId(2), true), Err(PdError::InvalidPort));
+    }
+
+    #[test]
+    fn test_limited_reason() {
+        let mut budget = budget::<_, 2>(FirstCome, 115000);
+
+        budget.attach(GlobalPortId(0)).ok();
+        assert_eq!(budget.limited_reason(GlobalPortId(0)), Ok(None));
+        assert_eq!(budget.limited_reason(GlobalPortId(1)), Ok(None));
+
+        budget.attach(GlobalPortId(1)).ok();
+        let reason = budget.limited_reason(GlobalPortId(1));
+        assert_eq!(
+            reason.map(
+                |reason| reason.map(|reason| (reason.power_budget_lowered(), reason.reaching_power_budget_limit()))
+            ),
+            Ok(Some((true, true)))
+        );
+
+        // Derating the supply lowers the first port
+        assert_eq!(budget.set_total_mw(30000), Ok(()));
+        assert_eq!(allocations(&budget), [15000, 15000]);
+        assert_eq!(pending(&budget), [true, true]);
+        assert_eq!(budget.set_total_mw(29999), Err(PdError::InvalidParams));
+    }
+}
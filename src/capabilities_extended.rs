@@ -0,0 +1,804 @@
+//! Source_Capabilities_Extended and Sink_Capabilities_Extended data blocks as defined in the USB PD specification
+//! 6.5.1 and 6.5.13
+//!
+//! These data blocks provide information that doesn't fit in the PDOs such as product identification, power supply
+//! characteristics and the PDP ratings of the port.
+use bitfield::bitfield;
+
+use crate::pdo::source::PeakCurrent;
+use crate::pdo::MW1000_UNIT;
+use crate::usb::ProductId;
+use crate::vdm::structured::command::discover_identity::CertStatVdo;
+
+/// Length of the Source_Capabilities_Extended data block in bytes
+pub const SOURCE_CAPABILITIES_EXTENDED_LEN: usize = 25;
+/// Length of the Sink_Capabilities_Extended data block in bytes
+pub const SINK_CAPABILITIES_EXTENDED_LEN: usize = 24;
+
+/// Errors that can occur when parsing an extended capabilities data block
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ParseError {
+    /// The load step slew rate contains an invalid value
+    InvalidLoadStepSlewRate,
+    /// The touch temperature contains an invalid value
+    InvalidTouchTemp,
+}
+
+impl From<ParseError> for crate::PdError {
+    fn from(_: ParseError) -> Self {
+        crate::PdError::InvalidParams
+    }
+}
+
+/// Load step slew rate
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LoadStepSlewRate {
+    /// 150 mA/us
+    #[default]
+    Ma150PerUs,
+    /// 500 mA/us
+    Ma500PerUs,
+}
+
+impl TryFrom<u8> for LoadStepSlewRate {
+    type Error = ParseError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x0 => Ok(LoadStepSlewRate::Ma150PerUs),
+            0x1 => Ok(LoadStepSlewRate::Ma500PerUs),
+            _ => Err(ParseError::InvalidLoadStepSlewRate),
+        }
+    }
+}
+
+impl From<LoadStepSlewRate> for u8 {
+    fn from(value: LoadStepSlewRate) -> Self {
+        match value {
+            LoadStepSlewRate::Ma150PerUs => 0x0,
+            LoadStepSlewRate::Ma500PerUs => 0x1,
+        }
+    }
+}
+
+/// Load step magnitude, as a percentage of the operating current
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum LoadStepMagnitude {
+    /// 25% of the operating current
+    #[default]
+    Pct25,
+    /// 90% of the operating current
+    Pct90,
+}
+
+/// Safety standards the power supply is compliant with
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Compliance {
+    /// LPS compliant
+    pub lps: bool,
+    /// PS1 compliant
+    pub ps1: bool,
+    /// PS2 compliant
+    pub ps2: bool,
+}
+
+/// Touch current characteristics of the source
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TouchCurrent {
+    /// Low touch current EPS
+    pub low_touch_current: bool,
+    /// Ground pin supported
+    pub ground_pin_supported: bool,
+    /// Ground pin intended for protective earth
+    pub ground_pin_protective_earth: bool,
+}
+
+/// Safety standard the touch temperature is compliant with
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TouchTemp {
+    /// Not applicable, only valid for sinks
+    #[default]
+    NotApplicable,
+    /// IEC 60950-1
+    Iec60950_1,
+    /// IEC 62368-1 TS1
+    Iec62368_1Ts1,
+    /// IEC 62368-1 TS2
+    Iec62368_1Ts2,
+}
+
+/// Overload capability used to describe the peak current of a source and the load characteristics of a sink
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct OverloadCapability {
+    /// Overload as a percentage of the operating current, in 10% increments up to 250%
+    pub overload_pct: u16,
+    /// Overload period in ms, in 20 ms increments
+    pub period_ms: u16,
+    /// Duty cycle as a percentage, in 5% increments
+    pub duty_cycle_pct: u8,
+    /// For sources, VBUS voltage droop. For sinks, able to tolerate VBUS voltage droop.
+    pub vbus_droop: bool,
+}
+
+bitfield! {
+    /// Raw overload capability
+    #[derive(Copy, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    struct OverloadCapabilityRaw(u16);
+    impl Debug;
+
+    /// Percent overload in 10% units
+    pub u16, overload, set_overload: 4, 0;
+    /// Overload period in 20 ms units
+    pub u16, period, set_period: 10, 5;
+    /// Duty cycle in 5% units
+    pub u8, duty_cycle, set_duty_cycle: 14, 11;
+    /// VBUS voltage droop
+    pub bool, vbus_droop, set_vbus_droop: 15;
+}
+
+/// Overload percentage unit
+const OVERLOAD_UNIT: u16 = 10;
+/// Maximum overload percentage, larger values are reserved
+const MAX_OVERLOAD_PCT: u16 = 250;
+/// Overload period unit in ms
+const PERIOD_UNIT: u16 = 20;
+/// Overload duty cycle unit in percent
+const DUTY_CYCLE_UNIT: u8 = 5;
+
+impl From<u16> for OverloadCapability {
+    fn from(value: u16) -> Self {
+        let raw = OverloadCapabilityRaw(value);
+        OverloadCapability {
+            overload_pct: (raw.overload() * OVERLOAD_UNIT).min(MAX_OVERLOAD_PCT),
+            period_ms: raw.period() * PERIOD_UNIT,
+            duty_cycle_pct: raw.duty_cycle() * DUTY_CYCLE_UNIT,
+            vbus_droop: raw.vbus_droop(),
+        }
+    }
+}
+
+impl From<OverloadCapability> for u16 {
+    fn from(value: OverloadCapability) -> Self {
+        let mut raw = OverloadCapabilityRaw(0);
+        raw.set_overload(value.overload_pct.min(MAX_OVERLOAD_PCT) / OVERLOAD_UNIT);
+        raw.set_period(value.period_ms / PERIOD_UNIT);
+        raw.set_duty_cycle(value.duty_cycle_pct / DUTY_CYCLE_UNIT);
+        raw.set_vbus_droop(value.vbus_droop);
+        raw.0
+    }
+}
+
+bitfield! {
+    /// Raw Source_Capabilities_Extended data block
+    #[derive(Copy, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    struct SourceCapabilitiesExtendedRaw([u8]);
+    impl Debug;
+
+    /// USB vendor ID
+    pub u16, usb_vendor_id, set_usb_vendor_id: 15, 0;
+    /// USB product ID
+    pub u16, usb_product_id, set_usb_product_id: 31, 16;
+    /// XID
+    pub u32, xid, set_xid: 63, 32;
+    /// Firmware version
+    pub u8, fw_version, set_fw_version: 71, 64;
+    /// Hardware version
+    pub u8, hw_version, set_hw_version: 79, 72;
+    /// Load step slew rate
+    pub u8, load_step_slew_rate, set_load_step_slew_rate: 81, 80;
+    /// Load step magnitude
+    pub bool, load_step_magnitude, set_load_step_magnitude: 82;
+    /// Holdup time in ms
+    pub u8, holdup_time, set_holdup_time: 95, 88;
+    /// LPS compliant
+    pub bool, lps, set_lps: 96;
+    /// PS1 compliant
+    pub bool, ps1, set_ps1: 97;
+    /// PS2 compliant
+    pub bool, ps2, set_ps2: 98;
+    /// Low touch current EPS
+    pub bool, low_touch_current, set_low_touch_current: 104;
+    /// Ground pin supported
+    pub bool, ground_pin_supported, set_ground_pin_supported: 105;
+    /// Ground pin intended for protective earth
+    pub bool, ground_pin_protective_earth, set_ground_pin_protective_earth: 106;
+    /// Peak current 1
+    pub u16, peak_current1, set_peak_current1: 127, 112;
+    /// Peak current 2
+    pub u16, peak_current2, set_peak_current2: 143, 128;
+    /// Peak current 3
+    pub u16, peak_current3, set_peak_current3: 159, 144;
+    /// Touch temperature
+    pub u8, touch_temp, set_touch_temp: 167, 160;
+    /// External supply present
+    pub bool, external_supply, set_external_supply: 168;
+    /// External supply is unconstrained
+    pub bool, external_supply_unconstrained, set_external_supply_unconstrained: 169;
+    /// Internal battery present
+    pub bool, internal_battery, set_internal_battery: 170;
+    /// Number of fixed batteries
+    pub u8, fixed_batteries, set_fixed_batteries: 179, 176;
+    /// Number of hot swappable battery slots
+    pub u8, hot_swappable_battery_slots, set_hot_swappable_battery_slots: 183, 180;
+    /// SPR source PDP rating in W
+    pub u32, spr_pdp, set_spr_pdp: 190, 184;
+    /// EPR source PDP rating in W
+    pub u32, epr_pdp, set_epr_pdp: 199, 192;
+}
+
+/// Source inputs
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SourceInputs {
+    /// External supply present
+    pub external_supply: bool,
+    /// External supply is unconstrained, only valid if `external_supply` is set
+    pub external_supply_unconstrained: bool,
+    /// Internal battery present
+    pub internal_battery: bool,
+}
+
+/// Source_Capabilities_Extended data block
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SourceCapabilitiesExtended {
+    /// USB vendor ID
+    pub usb_vendor_id: u16,
+    /// USB product ID
+    pub usb_product_id: ProductId,
+    /// XID assigned by USB-IF
+    pub xid: CertStatVdo,
+    /// Firmware version
+    pub fw_version: u8,
+    /// Hardware version
+    pub hw_version: u8,
+    /// Load step slew rate
+    pub load_step_slew_rate: LoadStepSlewRate,
+    /// Load step magnitude
+    pub load_step_magnitude: LoadStepMagnitude,
+    /// Holdup time in ms
+    pub holdup_time_ms: u8,
+    /// Compliance
+    pub compliance: Compliance,
+    /// Touch current
+    pub touch_current: TouchCurrent,
+    /// Peak current capabilities
+    pub peak_current: [OverloadCapability; 3],
+    /// Touch temperature
+    pub touch_temp: TouchTemp,
+    /// Source inputs
+    pub source_inputs: SourceInputs,
+    /// Number of fixed batteries
+    pub fixed_batteries: u8,
+    /// Number of hot swappable battery slots
+    pub hot_swappable_battery_slots: u8,
+    /// SPR source PDP rating in mW
+    pub spr_pdp_mw: u32,
+    /// EPR source PDP rating in mW
+    pub epr_pdp_mw: u32,
+}
+
+impl SourceCapabilitiesExtended {
+    /// Returns the highest fixed PDO peak current whose 10 ms @ 50% duty cycle overload is covered by
+    /// [`Self::peak_current`]
+    pub fn pdo_peak_current(&self) -> PeakCurrent {
+        const MIN_PERIOD_MS: u16 = 10;
+        const MIN_DUTY_CYCLE_PCT: u8 = 50;
+
+        let overload_pct = self
+            .peak_current
+            .iter()
+            .filter(|peak| peak.period_ms >= MIN_PERIOD_MS && peak.duty_cycle_pct >= MIN_DUTY_CYCLE_PCT)
+            .map(|peak| peak.overload_pct)
+            .max()
+            .unwrap_or(0);
+
+        [PeakCurrent::Pct150, PeakCurrent::Pct125, PeakCurrent::Pct110]
+            .into_iter()
+            .find(|peak| peak.overload_pct() <= overload_pct)
+            .unwrap_or(PeakCurrent::Pct100)
+    }
+}
+
+impl TryFrom<[u8; SOURCE_CAPABILITIES_EXTENDED_LEN]> for SourceCapabilitiesExtended {
+    type Error = ParseError;
+
+    fn try_from(value: [u8; SOURCE_CAPABILITIES_EXTENDED_LEN]) -> Result<Self, Self::Error> {
+        let raw = SourceCapabilitiesExtendedRaw(value);
+        Ok(SourceCapabilitiesExtended {
+            usb_vendor_id: raw.usb_vendor_id(),
+            usb_product_id: ProductId(raw.usb_product_id()),
+            xid: CertStatVdo(raw.xid()),
+            fw_version: raw.fw_version(),
+            hw_version: raw.hw_version(),
+            load_step_slew_rate: raw.load_step_slew_rate().try_into()?,
+            load_step_magnitude: if raw.load_step_magnitude() {
+                LoadStepMagnitude::Pct90
+            } else {
+                LoadStepMagnitude::Pct25
+            },
+            holdup_time_ms: raw.holdup_time(),
+            compliance: Compliance {
+                lps: raw.lps(),
+                ps1: raw.ps1(),
+                ps2: raw.ps2(),
+            },
+            touch_current: TouchCurrent {
+                low_touch_current: raw.low_touch_current(),
+                ground_pin_supported: raw.ground_pin_supported(),
+                ground_pin_protective_earth: raw.ground_pin_protective_earth(),
+            },
+            peak_current: [
+                raw.peak_current1().into(),
+                raw.peak_current2().into(),
+                raw.peak_current3().into(),
+            ],
+            touch_temp: match raw.touch_temp() {
+                0x0 => TouchTemp::Iec60950_1,
+                0x1 => TouchTemp::Iec62368_1Ts1,
+                0x2 => TouchTemp::Iec62368_1Ts2,
+                _ => return Err(ParseError::InvalidTouchTemp),
+            },
+            source_inputs: SourceInputs {
+                external_supply: raw.external_supply(),
+                external_supply_unconstrained: raw.external_supply_unconstrained(),
+                internal_battery: raw.internal_battery(),
+            },
+            fixed_batteries: raw.fixed_batteries(),
+            hot_swappable_battery_slots: raw.hot_swappable_battery_slots(),
+            spr_pdp_mw: raw.spr_pdp() * MW1000_UNIT,
+            epr_pdp_mw: raw.epr_pdp() * MW1000_UNIT,
+        })
+    }
+}
+
+impl TryFrom<SourceCapabilitiesExtended> for [u8; SOURCE_CAPABILITIES_EXTENDED_LEN] {
+    type Error = ParseError;
+
+    fn try_from(value: SourceCapabilitiesExtended) -> Result<Self, Self::Error> {
+        let mut raw = SourceCapabilitiesExtendedRaw([0u8; SOURCE_CAPABILITIES_EXTENDED_LEN]);
+        raw.set_usb_vendor_id(value.usb_vendor_id);
+        raw.set_usb_product_id(value.usb_product_id.0);
+        raw.set_xid(value.xid.0);
+        raw.set_fw_version(value.fw_version);
+        raw.set_hw_version(value.hw_version);
+        raw.set_load_step_slew_rate(value.load_step_slew_rate.into());
+        raw.set_load_step_magnitude(value.load_step_magnitude == LoadStepMagnitude::Pct90);
+        raw.set_holdup_time(value.holdup_time_ms);
+        raw.set_lps(value.compliance.lps);
+        raw.set_ps1(value.compliance.ps1);
+        raw.set_ps2(value.compliance.ps2);
+        raw.set_low_touch_current(value.touch_current.low_touch_current);
+        raw.set_ground_pin_supported(value.touch_current.ground_pin_supported);
+        raw.set_ground_pin_protective_earth(value.touch_current.ground_pin_protective_earth);
+        let [peak_current1, peak_current2, peak_current3] = value.peak_current;
+        raw.set_peak_current1(peak_current1.into());
+        raw.set_peak_current2(peak_current2.into());
+        raw.set_peak_current3(peak_current3.into());
+        raw.set_touch_temp(match value.touch_temp {
+            TouchTemp::Iec60950_1 => 0x0,
+            TouchTemp::Iec62368_1Ts1 => 0x1,
+            TouchTemp::Iec62368_1Ts2 => 0x2,
+            // Sources must report a touch temperature standard
+            TouchTemp::NotApplicable => return Err(ParseError::InvalidTouchTemp),
+        });
+        raw.set_external_supply(value.source_inputs.external_supply);
+        raw.set_external_supply_unconstrained(value.source_inputs.external_supply_unconstrained);
+        raw.set_internal_battery(value.source_inputs.internal_battery);
+        raw.set_fixed_batteries(value.fixed_batteries);
+        raw.set_hot_swappable_battery_slots(value.hot_swappable_battery_slots);
+        raw.set_spr_pdp(value.spr_pdp_mw / MW1000_UNIT);
+        raw.set_epr_pdp(value.epr_pdp_mw / MW1000_UNIT);
+        Ok(raw.0)
+    }
+}
+
+bitfield! {
+    /// Raw Sink_Capabilities_Extended data block
+    #[derive(Copy, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    struct SinkCapabilitiesExtendedRaw([u8]);
+    impl Debug;
+
+    /// USB vendor ID
+    pub u16, usb_vendor_id, set_usb_vendor_id: 15, 0;
+    /// USB product ID
+    pub u16, usb_product_id, set_usb_product_id: 31, 16;
+    /// XID
+    pub u32, xid, set_xid: 63, 32;
+    /// Firmware version
+    pub u8, fw_version, set_fw_version: 71, 64;
+    /// Hardware version
+    pub u8, hw_version, set_hw_version: 79, 72;
+    /// SKEDB version
+    pub u8, version, set_version: 87, 80;
+    /// Load step slew rate
+    pub u8, load_step_slew_rate, set_load_step_slew_rate: 89, 88;
+    /// Sink load characteristics
+    pub u16, load_characteristics, set_load_characteristics: 111, 96;
+    /// LPS compliant
+    pub bool, lps, set_lps: 112;
+    /// PS1 compliant
+    pub bool, ps1, set_ps1: 113;
+    /// PS2 compliant
+    pub bool, ps2, set_ps2: 114;
+    /// Touch temperature
+    pub u8, touch_temp, set_touch_temp: 127, 120;
+    /// Number of fixed batteries
+    pub u8, fixed_batteries, set_fixed_batteries: 131, 128;
+    /// Number of hot swappable battery slots
+    pub u8, hot_swappable_battery_slots, set_hot_swappable_battery_slots: 135, 132;
+    /// PPS charging supported
+    pub bool, pps_charging, set_pps_charging: 136;
+    /// VBUS powered
+    pub bool, vbus_powered, set_vbus_powered: 137;
+    /// Mains powered
+    pub bool, mains_powered, set_mains_powered: 138;
+    /// Battery powered
+    pub bool, battery_powered, set_battery_powered: 139;
+    /// Battery essentially unlimited
+    pub bool, battery_unlimited, set_battery_unlimited: 140;
+    /// AVS supported
+    pub bool, avs, set_avs: 141;
+    /// SPR sink minimum PDP in W
+    pub u32, spr_min_pdp, set_spr_min_pdp: 150, 144;
+    /// SPR sink operational PDP in W
+    pub u32, spr_operational_pdp, set_spr_operational_pdp: 158, 152;
+    /// SPR sink maximum PDP in W
+    pub u32, spr_max_pdp, set_spr_max_pdp: 166, 160;
+    /// EPR sink minimum PDP in W
+    pub u32, epr_min_pdp, set_epr_min_pdp: 175, 168;
+    /// EPR sink operational PDP in W
+    pub u32, epr_operational_pdp, set_epr_operational_pdp: 183, 176;
+    /// EPR sink maximum PDP in W
+    pub u32, epr_max_pdp, set_epr_max_pdp: 191, 184;
+}
+
+/// Sink modes
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SinkModes {
+    /// PPS charging supported
+    pub pps_charging: bool,
+    /// VBUS powered
+    pub vbus_powered: bool,
+    /// Mains powered
+    pub mains_powered: bool,
+    /// Battery powered
+    pub battery_powered: bool,
+    /// Battery essentially unlimited
+    pub battery_unlimited: bool,
+    /// AVS supported
+    pub avs: bool,
+}
+
+/// Sink PDP ratings in mW
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SinkPdp {
+    /// Minimum PDP in mW
+    pub min_mw: u32,
+    /// Operational PDP in mW
+    pub operational_mw: u32,
+    /// Maximum PDP in mW
+    pub max_mw: u32,
+}
+
+/// Sink_Capabilities_Extended data block
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SinkCapabilitiesExtended {
+    /// USB vendor ID
+    pub usb_vendor_id: u16,
+    /// USB product ID
+    pub usb_product_id: ProductId,
+    /// XID assigned by USB-IF
+    pub xid: CertStatVdo,
+    /// Firmware version
+    pub fw_version: u8,
+    /// Hardware version
+    pub hw_version: u8,
+    /// SKEDB version
+    pub version: u8,
+    /// Load step slew rate
+    pub load_step_slew_rate: LoadStepSlewRate,
+    /// Sink load characteristics
+    pub load_characteristics: OverloadCapability,
+    /// Compliance
+    pub compliance: Compliance,
+    /// Touch temperature
+    pub touch_temp: TouchTemp,
+    /// Number of fixed batteries
+    pub fixed_batteries: u8,
+    /// Number of hot swappable battery slots
+    pub hot_swappable_battery_slots: u8,
+    /// Sink modes
+    pub sink_modes: SinkModes,
+    /// SPR PDP ratings
+    pub spr_pdp: SinkPdp,
+    /// EPR PDP ratings
+    pub epr_pdp: SinkPdp,
+}
+
+impl TryFrom<[u8; SINK_CAPABILITIES_EXTENDED_LEN]> for SinkCapabilitiesExtended {
+    type Error = ParseError;
+
+    fn try_from(value: [u8; SINK_CAPABILITIES_EXTENDED_LEN]) -> Result<Self, Self::Error> {
+        let raw = SinkCapabilitiesExtendedRaw(value);
+        Ok(SinkCapabilitiesExtended {
+            usb_vendor_id: raw.usb_vendor_id(),
+            usb_product_id: ProductId(raw.usb_product_id()),
+            xid: CertStatVdo(raw.xid()),
+            fw_version: raw.fw_version(),
+            hw_version: raw.hw_version(),
+            version: raw.version(),
+            load_step_slew_rate: raw.load_step_slew_rate().try_into()?,
+            load_characteristics: raw.load_characteristics().into(),
+            compliance: Compliance {
+                lps: raw.lps(),
+                ps1: raw.ps1(),
+                ps2: raw.ps2(),
+            },
+            touch_temp: match raw.touch_temp() {
+                0x0 => TouchTemp::NotApplicable,
+                0x1 => TouchTemp::Iec60950_1,
+                0x2 => TouchTemp::Iec62368_1Ts1,
+                0x3 => TouchTemp::Iec62368_1Ts2,
+                _ => return Err(ParseError::InvalidTouchTemp),
+            },
+            fixed_batteries: raw.fixed_batteries(),
+            hot_swappable_battery_slots: raw.hot_swappable_battery_slots(),
+            sink_modes: SinkModes {
+                pps_charging: raw.pps_charging(),
+                vbus_powered: raw.vbus_powered(),
+                mains_powered: raw.mains_powered(),
+                battery_powered: raw.battery_powered(),
+                battery_unlimited: raw.battery_unlimited(),
+                avs: raw.avs(),
+            },
+            spr_pdp: SinkPdp {
+                min_mw: raw.spr_min_pdp() * MW1000_UNIT,
+                operational_mw: raw.spr_operational_pdp() * MW1000_UNIT,
+                max_mw: raw.spr_max_pdp() * MW1000_UNIT,
+            },
+            epr_pdp: SinkPdp {
+                min_mw: raw.epr_min_pdp() * MW1000_UNIT,
+                operational_mw: raw.epr_operational_pdp() * MW1000_UNIT,
+                max_mw: raw.epr_max_pdp() * MW1000_UNIT,
+            },
+        })
+    }
+}
+
+impl From<SinkCapabilitiesExtended> for [u8; SINK_CAPABILITIES_EXTENDED_LEN] {
+    fn from(value: SinkCapabilitiesExtended) -> Self {
+        let mut raw = SinkCapabilitiesExtendedRaw([0u8; SINK_CAPABILITIES_EXTENDED_LEN]);
+        raw.set_usb_vendor_id(value.usb_vendor_id);
+        raw.set_usb_product_id(value.usb_product_id.0);
+        raw.set_xid(value.xid.0);
+        raw.set_fw_version(value.fw_version);
+        raw.set_hw_version(value.hw_version);
+        raw.set_version(value.version);
+        raw.set_load_step_slew_rate(value.load_step_slew_rate.into());
+        raw.set_load_characteristics(value.load_characteristics.into());
+        raw.set_lps(value.compliance.lps);
+        raw.set_ps1(value.compliance.ps1);
+        raw.set_ps2(value.compliance.ps2);
+        raw.set_touch_temp(match value.touch_temp {
+            TouchTemp::NotApplicable => 0x0,
+            TouchTemp::Iec60950_1 => 0x1,
+            TouchTemp::Iec62368_1Ts1 => 0x2,
+            TouchTemp::Iec62368_1Ts2 => 0x3,
+        });
+        raw.set_fixed_batteries(value.fixed_batteries);
+        raw.set_hot_swappable_battery_slots(value.hot_swappable_battery_slots);
+        raw.set_pps_charging(value.sink_modes.pps_charging);
+        raw.set_vbus_powered(value.sink_modes.vbus_powered);
+        raw.set_mains_powered(value.sink_modes.mains_powered);
+        raw.set_battery_powered(value.sink_modes.battery_powered);
+        raw.set_battery_unlimited(value.sink_modes.battery_unlimited);
+        raw.set_avs(value.sink_modes.avs);
+        raw.set_spr_min_pdp(value.spr_pdp.min_mw / MW1000_UNIT);
+        raw.set_spr_operational_pdp(value.spr_pdp.operational_mw / MW1000_UNIT);
+        raw.set_spr_max_pdp(value.spr_pdp.max_mw / MW1000_UNIT);
+        raw.set_epr_min_pdp(value.epr_pdp.min_mw / MW1000_UNIT);
+        raw.set_epr_operational_pdp(value.epr_pdp.operational_mw / MW1000_UNIT);
+        raw.set_epr_max_pdp(value.epr_pdp.max_mw / MW1000_UNIT);
+        raw.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RAW_SOURCE: [u8; SOURCE_CAPABILITIES_EXTENDED_LEN] = [
+        0x5E, 0x04, // VID
+        0x34, 0x12, // PID
+        0x78, 0x56, 0x34, 0x12, // XID
+        0x02, // FW version
+        0x01, // HW version
+        0x05, // 500 mA/us, 90% load step
+        0x03, // 3 ms holdup time
+        0x01, // LPS compliant
+        0x03, // Low touch current, ground pin supported
+        0x2F, 0x50, // 150%, 20 ms, 50% duty cycle
+        0x00, 0x00, // Not supported
+        0x00, 0x00, // Not supported
+        0x02, // IEC 62368-1 TS2
+        0x03, // Unconstrained external supply
+        0x00, // No batteries
+        0x64, // 100 W SPR PDP
+        0x8C, // 140 W EPR PDP
+    ];
+
+    fn source() -> SourceCapabilitiesExtended {
+        SourceCapabilitiesExtended {
+            usb_vendor_id: 0x045E,
+            usb_product_id: ProductId(0x1234),
+            xid: CertStatVdo(0x12345678),
+            fw_version: 2,
+            hw_version: 1,
+            load_step_slew_rate: LoadStepSlewRate::Ma500PerUs,
+            load_step_magnitude: LoadStepMagnitude::Pct90,
+            holdup_time_ms: 3,
+            compliance: Compliance {
+                lps: true,
+                ps1: false,
+                ps2: false,
+            },
+            touch_current: TouchCurrent {
+                low_touch_current: true,
+                ground_pin_supported: true,
+                ground_pin_protective_earth: false,
+            },
+            peak_current: [
+                OverloadCapability {
+                    overload_pct: 150,
+                    period_ms: 20,
+                    duty_cycle_pct: 50,
+                    vbus_droop: false,
+                },
+                OverloadCapability::default(),
+                OverloadCapability::default(),
+            ],
+            touch_temp: TouchTemp::Iec62368_1Ts2,
+            source_inputs: SourceInputs {
+                external_supply: true,
+                external_supply_unconstrained: true,
+                internal_battery: false,
+            },
+            fixed_batteries: 0,
+            hot_swappable_battery_slots: 0,
+            spr_pdp_mw: 100000,
+            epr_pdp_mw: 140000,
+        }
+    }
+
+    #[test]
+    fn test_source_roundtrip() {
+        assert_eq!(SourceCapabilitiesExtended::try_from(RAW_SOURCE), Ok(source()));
+        assert_eq!(
+            <[u8; SOURCE_CAPABILITIES_EXTENDED_LEN]>::try_from(source()),
+            Ok(RAW_SOURCE)
+        );
+    }
+
+    #[test]
+    fn test_source_invalid() {
+        let mut raw = RAW_SOURCE;
+        raw[10] = 0x02;
+        assert_eq!(
+            SourceCapabilitiesExtended::try_from(raw),
+            Err(ParseError::InvalidLoadStepSlewRate)
+        );
+
+        let mut raw = RAW_SOURCE;
+        raw[20] = 0x03;
+        assert_eq!(
+            SourceCapabilitiesExtended::try_from(raw),
+            Err(ParseError::InvalidTouchTemp)
+        );
+
+        let mut source = source();
+        source.touch_temp = TouchTemp::NotApplicable;
+        assert_eq!(
+            <[u8; SOURCE_CAPABILITIES_EXTENDED_LEN]>::try_from(source),
+            Err(ParseError::InvalidTouchTemp)
+        );
+    }
+
+    #[test]
+    fn test_source_pdo_peak_current() {
+        let mut source = source();
+        assert_eq!(source.pdo_peak_current(), PeakCurrent::Pct150);
+
+        source.peak_current[0].overload_pct = 130;
+        assert_eq!(source.pdo_peak_current(), PeakCurrent::Pct125);
+
+        // Short overload periods don't count
+        source.peak_current[0].period_ms = 0;
+        assert_eq!(source.pdo_peak_current(), PeakCurrent::Pct100);
+    }
+
+    #[test]
+    fn test_overload_capability_clamped() {
+        // Values above 25 are interpreted as 250%
+        let overload = OverloadCapability::from(0x001F);
+        assert_eq!(overload.overload_pct, 250);
+
+        // Encoding saturates instead of wrapping into the 5-bit field
+        let overload = OverloadCapability {
+            overload_pct: 320,
+            ..overload
+        };
+        assert_eq!(u16::from(overload), 0x0019);
+    }
+
+    #[test]
+    fn test_sink_roundtrip() {
+        const RAW_SINK: [u8; SINK_CAPABILITIES_EXTENDED_LEN] = [
+            0x5E, 0x04, // VID
+            0x34, 0x12, // PID
+            0x78, 0x56, 0x34, 0x12, // XID
+            0x02, // FW version
+            0x01, // HW version
+            0x01, // SKEDB version
+            0x01, // 500 mA/us
+            0x00, 0x00, // No overload
+            0x02, // PS1 compliant
+            0x03, // IEC 62368-1 TS2
+            0x01, // One fixed battery
+            0x2D, // PPS charging, mains powered, battery powered and AVS supported
+            0x0F, 0x3C, 0x64, // SPR 15/60/100 W
+            0x3C, 0x64, 0x8C, // EPR 60/100/140 W
+        ];
+        let expected = SinkCapabilitiesExtended {
+            usb_vendor_id: 0x045E,
+            usb_product_id: ProductId(0x1234),
+            xid: CertStatVdo(0x12345678),
+            fw_version: 2,
+            hw_version: 1,
+            version: 1,
+            load_step_slew_rate: LoadStepSlewRate::Ma500PerUs,
+            load_characteristics: OverloadCapability::default(),
+            compliance: Compliance {
+                lps: false,
+                ps1: true,
+                ps2: false,
+            },
+            touch_temp: TouchTemp::Iec62368_1Ts2,
+            fixed_batteries: 1,
+            hot_swappable_battery_slots: 0,
+            sink_modes: SinkModes {
+                pps_charging: true,
+                vbus_powered: false,
+                mains_powered: true,
+                battery_powered: true,
+                battery_unlimited: false,
+                avs: true,
+            },
+            spr_pdp: SinkPdp {
+                min_mw: 15000,
+                operational_mw: 60000,
+                max_mw: 100000,
+            },
+            epr_pdp: SinkPdp {
+                min_mw: 60000,
+                operational_mw: 100000,
+                max_mw: 140000,
+            },
+        };
+        assert_eq!(SinkCapabilitiesExtended::try_from(RAW_SINK), Ok(expected));
+        assert_eq!(<[u8; SINK_CAPABILITIES_EXTENDED_LEN]>::from(expected), RAW_SINK);
+    }
+}
@@ -1,10 +1,96 @@
 //! Constants for USB Power Delivery (USB PD) protocol
+//!
+//! Timing values are taken from the time values table in section 6.6 of the PD spec, counter values from the counter
+//! values table in section 6.7 of the PD spec and Type-C timing values from section 4.11 of the Type-C spec. Values
+//! specified with both bounds are provided as a [`Range`], values with only a single bound or a single value are
+//! provided as a [`Minimum`], [`Maximum`] or [`Nominal`].
 
 use wrappers::{Maximum, Minimum, Nominal, Range};
 
+/// Time in microseconds to wait for a GoodCRC after transmitting a message.
+///
+/// This is `tReceive` in the PD spec, see 6.6.1 CRCReceiveTimer.
+pub const T_RECEIVE_US: Range<u16> = Range {
+    minimum: Minimum(900),
+    nominal: Nominal(1000),
+    maximum: Maximum(1100),
+};
+
+/// Time in microseconds from a missing GoodCRC to the start of the retransmission.
+///
+/// This is `tRetry` in the PD spec, see 6.6.1 CRCReceiveTimer.
+pub const T_RETRY_US: Maximum<u16> = Maximum(195);
+
+/// Time in microseconds from receiving a message to transmitting the GoodCRC response.
+///
+/// This is `tTransmit` in the PD spec, see 6.6.1 CRCReceiveTimer.
+pub const T_TRANSMIT_US: Maximum<u16> = Maximum(195);
+
+/// Sender response time in milliseconds.
+///
+/// This is `tSenderResponse` in the PD spec, see 6.6.2 SenderResponseTimer.
+pub const T_SENDER_RESPONSE_MS: Range<u16> = Range {
+    minimum: Minimum(27),
+    nominal: Nominal(30),
+    maximum: Maximum(33),
+};
+
+/// Time in milliseconds for a receiver to respond to a message that requires a response.
+///
+/// This is `tReceiverResponse` in the PD spec, see 6.6.2 SenderResponseTimer.
+pub const T_RECEIVER_RESPONSE_MS: Maximum<u16> = Maximum(15);
+
+/// Time in milliseconds from vSafe5V to the first `Source_Capabilities` message.
+///
+/// This is `tFirstSourceCap` in the PD spec, see 6.6.3 Capability Timers.
+pub const T_FIRST_SOURCE_CAP_MS: Maximum<u16> = Maximum(250);
+
+/// Interval in milliseconds between `Source_Capabilities` messages while a source is not connected.
+///
+/// This is `tTypeCSendSourceCap` in the PD spec, see 6.6.3 Capability Timers.
+pub const T_TYPE_C_SEND_SOURCE_CAP_MS: Range<u16> = Range {
+    minimum: Minimum(100),
+    nominal: Nominal(150),
+    maximum: Maximum(200),
+};
+
+/// Time in milliseconds a sink waits for `Source_Capabilities` before issuing a hard reset.
+///
+/// This is `tTypeCSinkWaitCap` in the PD spec, see 6.6.3 Capability Timers.
+pub const T_TYPE_C_SINK_WAIT_CAP_MS: Range<u16> = Range {
+    minimum: Minimum(310),
+    nominal: Nominal(465),
+    maximum: Maximum(620),
+};
+
+/// Time in milliseconds a sink waits after a `Wait` response before re-sending a `Request`.
+///
+/// This is `tSinkRequest` in the PD spec, see 6.6.4 Wait Timers and Times.
+pub const T_SINK_REQUEST_MS: Minimum<u16> = Minimum(100);
+
+/// Time in milliseconds to wait after a `Wait` response before re-sending `PR_Swap`.
+///
+/// This is `tPRSwapWait` in the PD spec, see 6.6.4 Wait Timers and Times.
+pub const T_PR_SWAP_WAIT_MS: Minimum<u16> = Minimum(100);
+
+/// Time in milliseconds to wait after a `Wait` response before re-sending `DR_Swap`.
+///
+/// This is `tDRSwapWait` in the PD spec, see 6.6.4 Wait Timers and Times.
+pub const T_DR_SWAP_WAIT_MS: Minimum<u16> = Minimum(100);
+
+/// Time in milliseconds to wait after a `Wait` response before re-sending `VCONN_Swap`.
+///
+/// This is `tVCONNSwapWait` in the PD spec, see 6.6.4 Wait Timers and Times.
+pub const T_VCONN_SWAP_WAIT_MS: Minimum<u16> = Minimum(100);
+
+/// Time in milliseconds to wait after a `Wait` response before re-sending `Enter_USB`.
+///
+/// This is `tEnterUSBWait` in the PD spec, see 6.6.4 Wait Timers and Times.
+pub const T_ENTER_USB_WAIT_MS: Minimum<u16> = Minimum(100);
+
 /// Source transition request time in milliseconds for SPR mode.
 ///
-/// This is `tPSTransition` for SPR mode in the PD spec.
+/// This is `tPSTransition` for SPR mode in the PD spec, see 6.6.5.1 PSTransitionTimer.
 pub const T_PS_TRANSITION_SPR_MS: Range<u16> = Range {
     minimum: Minimum(450),
     nominal: Nominal(500),
@@ -13,13 +99,453 @@ pub const T_PS_TRANSITION_SPR_MS: Range<u16> = Range {
 
 /// Source transition request time in milliseconds for EPR mode.
 ///
-/// This is `tPSTransition` for EPR mode in the PD spec.
+/// This is `tPSTransition` for EPR mode in the PD spec, see 6.6.5.1 PSTransitionTimer.
 pub const T_PS_TRANSITION_EPR_MS: Range<u16> = Range {
     minimum: Minimum(830),
     nominal: Nominal(925),
     maximum: Maximum(1020),
 };
 
+/// Time in milliseconds the new sink waits for the old source to turn off during a power role swap.
+///
+/// This is `tPSSourceOff` in the PD spec, see 6.6.5.2 PSSourceOffTimer.
+pub const T_PS_SOURCE_OFF_MS: Range<u16> = Range {
+    minimum: Minimum(750),
+    nominal: Nominal(835),
+    maximum: Maximum(920),
+};
+
+/// Time in milliseconds the new sink waits for the new source to turn on during a power role swap.
+///
+/// This is `tPSSourceOn` in the PD spec, see 6.6.5.3 PSSourceOnTimer.
+pub const T_PS_SOURCE_ON_MS: Range<u16> = Range {
+    minimum: Minimum(390),
+    nominal: Nominal(435),
+    maximum: Maximum(480),
+};
+
+/// Time in milliseconds after which a non-responsive port partner is treated as not PD capable.
+///
+/// This is `tNoResponse` in the PD spec, see 6.6.6 NoResponseTimer.
+pub const T_NO_RESPONSE_MS: Range<u16> = Range {
+    minimum: Minimum(4500),
+    nominal: Nominal(5000),
+    maximum: Maximum(5500),
+};
+
+/// Time in milliseconds a port stays in BIST Carrier Mode.
+///
+/// This is `tBISTContMode` in the PD spec, see 6.6.7.1 BISTContModeTimer.
+pub const T_BIST_CONT_MODE_MS: Range<u16> = Range {
+    minimum: Minimum(30),
+    nominal: Nominal(45),
+    maximum: Maximum(60),
+};
+
+/// Time in milliseconds for a source to enter BIST Shared Capacity Test Mode.
+///
+/// This is `tBISTSharedTestMode` in the PD spec, see 6.6.7.2 BISTSharedTestModeTimer.
+pub const T_BIST_SHARED_TEST_MODE_MS: Maximum<u16> = Maximum(1000);
+
+/// Time in milliseconds the new source waits before sending `Source_Capabilities` after a power role swap.
+///
+/// This is `tSwapSourceStart` in the PD spec, see 6.6.8.1 SwapSourceStartTimer.
+pub const T_SWAP_SOURCE_START_MS: Minimum<u16> = Minimum(20);
+
+/// Time in milliseconds for the new sink to be ready after a power role swap.
+///
+/// This is `tSwapSinkReady` in the PD spec, see 6.6.8 Power Role Swap Timers.
+pub const T_SWAP_SINK_READY_MS: Maximum<u16> = Maximum(15);
+
+/// Time in milliseconds to respond to a `Soft_Reset` message.
+///
+/// This is `tSoftReset` in the PD spec, see 6.6.9 Soft Reset Timers.
+pub const T_SOFT_RESET_MS: Maximum<u16> = Maximum(15);
+
+/// Time in milliseconds from a protocol error to sending `Soft_Reset`.
+///
+/// This is `tProtErrSoftReset` in the PD spec, see 6.6.9 Soft Reset Timers.
+pub const T_PROT_ERR_SOFT_RESET_MS: Maximum<u16> = Maximum(15);
+
+/// Time in milliseconds for a DFP to complete a data reset.
+///
+/// This is `tDataReset` in the PD spec, see 6.6.10 Data Reset Timers.
+pub const T_DATA_RESET_MS: Range<u16> = Range {
+    minimum: Minimum(200),
+    nominal: Nominal(225),
+    maximum: Maximum(250),
+};
+
+/// Time in milliseconds after which a DFP considers a data reset to have failed.
+///
+/// This is `tDataResetFail` in the PD spec, see 6.6.10 Data Reset Timers.
+pub const T_DATA_RESET_FAIL_MS: Nominal<u16> = Nominal(300);
+
+/// Time in milliseconds from a hard reset request to the hard reset signaling.
+///
+/// This is `tHardReset` in the PD spec, see 6.6.11 Hard Reset Timers.
+pub const T_HARD_RESET_MS: Maximum<u16> = Maximum(5);
+
+/// Time in microseconds to wait for hard reset signaling to complete.
+///
+/// This is `tHardResetComplete` in the PD spec, see 6.6.11.1 HardResetCompleteTimer.
+pub const T_HARD_RESET_COMPLETE_US: Range<u16> = Range {
+    minimum: Minimum(4000),
+    nominal: Nominal(4500),
+    maximum: Maximum(5000),
+};
+
+/// Time in milliseconds a source waits after hard reset signaling before transitioning VBUS to vSafe0V.
+///
+/// This is `tPSHardReset` in the PD spec, see 6.6.11.2 PSHardResetTimer.
+pub const T_PS_HARD_RESET_MS: Range<u16> = Range {
+    minimum: Minimum(25),
+    nominal: Nominal(30),
+    maximum: Maximum(35),
+};
+
+/// Time in milliseconds from a protocol error to sending hard reset signaling.
+///
+/// This is `tProtErrHardReset` in the PD spec, see 6.6.11 Hard Reset Timers.
+pub const T_PROT_ERR_HARD_RESET_MS: Maximum<u16> = Maximum(15);
+
+/// Time in milliseconds to wait for a response to a structured VDM.
+///
+/// This is `tVDMSenderResponse` in the PD spec, see 6.6.12.1 VDMResponseTimer.
+pub const T_VDM_SENDER_RESPONSE_MS: Range<u16> = Range {
+    minimum: Minimum(24),
+    nominal: Nominal(27),
+    maximum: Maximum(30),
+};
+
+/// Time in milliseconds to respond to a structured VDM.
+///
+/// This is `tVDMReceiverResponse` in the PD spec, see 6.6.12.1 VDMResponseTimer.
+pub const T_VDM_RECEIVER_RESPONSE_MS: Maximum<u16> = Maximum(15);
+
+/// Time in milliseconds to wait for a response to `Enter Mode`.
+///
+/// This is `tVDMWaitModeEntry` in the PD spec, see 6.6.12.2 VDMModeEntryTimer.
+pub const T_VDM_WAIT_MODE_ENTRY_MS: Range<u16> = Range {
+    minimum: Minimum(40),
+    nominal: Nominal(45),
+    maximum: Maximum(50),
+};
+
+/// Time in milliseconds to wait for a response to `Exit Mode`.
+///
+/// This is `tVDMWaitModeExit` in the PD spec, see 6.6.12.3 VDMModeExitTimer.
+pub const T_VDM_WAIT_MODE_EXIT_MS: Range<u16> = Range {
+    minimum: Minimum(40),
+    nominal: Nominal(45),
+    maximum: Maximum(50),
+};
+
+/// Time in milliseconds to wait before retrying a structured VDM after a `BUSY` response.
+///
+/// This is `tVDMBusy` in the PD spec, see 6.6.12.4 VDMBusyTimer.
+pub const T_VDM_BUSY_MS: Minimum<u16> = Minimum(50);
+
+/// Time in milliseconds for the new VCONN source to apply VCONN during a VCONN swap.
+///
+/// This is `tVCONNSourceOn` in the PD spec, see 6.6.13 VCONN Timers.
+pub const T_VCONN_SOURCE_ON_MS: Maximum<u16> = Maximum(50);
+
+/// Time in milliseconds for the old VCONN source to remove VCONN during a VCONN swap.
+///
+/// This is `tVCONNSourceOff` in the PD spec, see 6.6.13 VCONN Timers.
+pub const T_VCONN_SOURCE_OFF_MS: Maximum<u16> = Maximum(25);
+
+/// Time in milliseconds the old VCONN source waits for `PS_RDY` from the new VCONN source.
+///
+/// This is `tVCONNSourceTimeout` in the PD spec, see 6.6.13.1 VCONNOnTimer.
+pub const T_VCONN_SOURCE_TIMEOUT_MS: Range<u16> = Range {
+    minimum: Minimum(100),
+    nominal: Nominal(150),
+    maximum: Maximum(200),
+};
+
+/// Time in milliseconds for VCONN to discharge after being removed.
+///
+/// This is `tVCONNSourceDischarge` in the PD spec, see 6.6.13.2 VCONNDischargeTimer.
+pub const T_VCONN_SOURCE_DISCHARGE_MS: Range<u16> = Range {
+    minimum: Minimum(160),
+    nominal: Nominal(200),
+    maximum: Maximum(240),
+};
+
+/// Time in microseconds from receiving a message to transmitting a message to a cable plug.
+///
+/// This is `tCableMessage` in the PD spec, see 6.6.14 tCableMessage.
+pub const T_CABLE_MESSAGE_US: Minimum<u16> = Minimum(750);
+
+/// Interval in milliseconds between `Discover Identity` requests to a cable plug.
+///
+/// This is `tDiscoverIdentity` in the PD spec, see 6.6.15 DiscoverIdentityTimer.
+pub const T_DISCOVER_IDENTITY_MS: Range<u16> = Range {
+    minimum: Minimum(40),
+    nominal: Nominal(45),
+    maximum: Maximum(50),
+};
+
+/// Time in milliseconds a source waits after setting Rp to SinkTxNG before initiating an AMS.
+///
+/// This is `tSinkTx` in the PD spec, see 6.6.16.1 SinkTxTimer.
+pub const T_SINK_TX_MS: Range<u16> = Range {
+    minimum: Minimum(16),
+    nominal: Nominal(18),
+    maximum: Maximum(20),
+};
+
+/// Time in milliseconds a source may hold the bus before sending its first message of an AMS.
+///
+/// This is `tSrcHoldsBus` in the PD spec, see 6.6.16 Collision Avoidance Timers.
+pub const T_SRC_HOLDS_BUS_MS: Maximum<u16> = Maximum(50);
+
+/// Time in milliseconds from the fast role swap signal to VBUS dropping below vSafe5V.
+///
+/// This is `tFRSwap5V` in the PD spec, see 6.6.17.1 tFRSwap5V.
+pub const T_FR_SWAP_5V_MS: Maximum<u16> = Maximum(15);
+
+/// Time in milliseconds for the new sink to respond to `PS_RDY` during a fast role swap.
+///
+/// This is `tFRSwapComplete` in the PD spec, see 6.6.17.2 tFRSwapComplete.
+pub const T_FR_SWAP_COMPLETE_MS: Maximum<u16> = Maximum(15);
+
+/// Time in milliseconds from detecting the fast role swap signal to sending `FR_Swap`.
+///
+/// This is `tFRSwapInit` in the PD spec, see 6.6.17.3 tFRSwapInit.
+pub const T_FR_SWAP_INIT_MS: Maximum<u16> = Maximum(15);
+
+/// Time in milliseconds for a chunk receiver to send a chunk request.
+///
+/// This is `tChunkReceiverRequest` in the PD spec, see 6.6.18 Chunking Timers.
+pub const T_CHUNK_RECEIVER_REQUEST_MS: Maximum<u16> = Maximum(15);
+
+/// Time in milliseconds for a chunk receiver to respond to a chunk.
+///
+/// This is `tChunkReceiverResponse` in the PD spec, see 6.6.18 Chunking Timers.
+pub const T_CHUNK_RECEIVER_RESPONSE_MS: Maximum<u16> = Maximum(15);
+
+/// Time in milliseconds a chunk sender waits for the next chunk request.
+///
+/// This is `tChunkSenderRequest` in the PD spec, see 6.6.18.2 ChunkSenderRequestTimer.
+pub const T_CHUNK_SENDER_REQUEST_MS: Range<u16> = Range {
+    minimum: Minimum(24),
+    nominal: Nominal(27),
+    maximum: Maximum(30),
+};
+
+/// Time in milliseconds a chunk requester waits for the requested chunk.
+///
+/// This is `tChunkSenderResponse` in the PD spec, see 6.6.18.3 ChunkSenderResponseTimer.
+pub const T_CHUNK_SENDER_RESPONSE_MS: Range<u16> = Range {
+    minimum: Minimum(24),
+    nominal: Nominal(27),
+    maximum: Maximum(30),
+};
+
+/// Time in milliseconds to wait before sending `Not_Supported` in response to a chunked extended message.
+///
+/// This is `tChunkingNotSupported` in the PD spec, see 6.6.18.1 ChunkingNotSupportedTimer.
+pub const T_CHUNKING_NOT_SUPPORTED_MS: Range<u16> = Range {
+    minimum: Minimum(40),
+    nominal: Nominal(45),
+    maximum: Maximum(50),
+};
+
+/// Maximum interval in milliseconds between `Request` messages from a sink in a PPS contract.
+///
+/// This is `tPPSRequest` in the PD spec, see 6.6.19.1 SinkPPSPeriodicTimer.
+pub const T_PPS_REQUEST_MS: Maximum<u16> = Maximum(10000);
+
+/// Time in milliseconds after which a source in a PPS contract issues a hard reset if no `Request` is received.
+///
+/// This is `tPPSTimeout` in the PD spec, see 6.6.19.2 SourcePPSCommTimer.
+pub const T_PPS_TIMEOUT_MS: Range<u16> = Range {
+    minimum: Minimum(12000),
+    nominal: Nominal(13500),
+    maximum: Maximum(15000),
+};
+
+/// Time in milliseconds for the source to respond to an EPR mode entry request.
+///
+/// This is `tEnterEPR` in the PD spec, see 6.6.21.1 SinkEPREnterTimer.
+pub const T_ENTER_EPR_MS: Range<u16> = Range {
+    minimum: Minimum(450),
+    nominal: Nominal(500),
+    maximum: Maximum(550),
+};
+
+/// Time in milliseconds for the source to complete cable discovery during EPR mode entry.
+///
+/// This is `tEPRSourceCableDiscovery` in the PD spec, see 6.6.21.2 tEPRSourceCableDiscovery.
+pub const T_EPR_SOURCE_CABLE_DISCOVERY_MS: Maximum<u16> = Maximum(2000);
+
+/// Interval in milliseconds at which a sink in EPR mode sends `EPR_KeepAlive`.
+///
+/// This is `tSinkEPRKeepAlive` in the PD spec, see 6.6.21.3 SinkEPRKeepAliveTimer.
+pub const T_SINK_EPR_KEEP_ALIVE_MS: Range<u16> = Range {
+    minimum: Minimum(250),
+    nominal: Nominal(375),
+    maximum: Maximum(500),
+};
+
+/// Time in milliseconds a source in EPR mode waits for `EPR_KeepAlive` before exiting EPR mode.
+///
+/// This is `tSourceEPRKeepAlive` in the PD spec, see 6.6.21.4 SourceEPRKeepAliveTimer.
+pub const T_SOURCE_EPR_KEEP_ALIVE_MS: Range<u16> = Range {
+    minimum: Minimum(750),
+    nominal: Nominal(875),
+    maximum: Maximum(1000),
+};
+
+/// Time in milliseconds from `Accept` to the start of a source voltage transition.
+///
+/// This is `tSrcTransition` in the PD spec, see 7.3 Transitions.
+pub const T_SRC_TRANSITION_MS: Range<u16> = Range {
+    minimum: Minimum(25),
+    nominal: Nominal(30),
+    maximum: Maximum(35),
+};
+
+/// Time in milliseconds for a source to recover after hard reset before restoring VBUS to vSafe5V.
+///
+/// This is `tSrcRecover` in the PD spec, see 7.3 Transitions.
+pub const T_SRC_RECOVER_MS: Range<u16> = Range {
+    minimum: Minimum(660),
+    nominal: Nominal(830),
+    maximum: Maximum(1000),
+};
+
+/// Time in milliseconds for a source to turn VBUS on to vSafe5V.
+///
+/// This is `tSrcTurnOn` in the PD spec, see 7.3 Transitions.
+pub const T_SRC_TURN_ON_MS: Maximum<u16> = Maximum(275);
+
+/// Time in milliseconds for VBUS to reach vSafe0V after a hard reset.
+///
+/// This is `tSafe0V` in the PD spec, see 7.3 Transitions.
+pub const T_SAFE_0V_MS: Maximum<u16> = Maximum(650);
+
+/// Maximum value of the MessageID counter before it rolls over to zero.
+///
+/// This is `nMessageIDCount` in the PD spec, see 6.7.1 MessageID Counter.
+pub const N_MESSAGE_ID_COUNT: u8 = 7;
+
+/// Number of times a message is retransmitted when no GoodCRC is received.
+///
+/// This is `nRetryCount` in the PD spec, see 6.7.2 Retry Counter.
+pub const N_RETRY_COUNT: u8 = 2;
+
+/// Number of hard resets a port attempts before concluding the partner is not responsive.
+///
+/// This is `nHardResetCount` in the PD spec, see 6.7.3 Hard Reset Counter.
+pub const N_HARD_RESET_COUNT: u8 = 2;
+
+/// Number of `Source_Capabilities` messages a source sends before concluding the sink is not PD capable.
+///
+/// This is `nCapsCount` in the PD spec, see 6.7.4 Capabilities Counter.
+pub const N_CAPS_COUNT: u8 = 50;
+
+/// Number of `Discover Identity` requests sent to a cable plug before giving up.
+///
+/// This is `nDiscoverIdentityCount` in the PD spec, see 6.7.5 Discover Identity Counter.
+pub const N_DISCOVER_IDENTITY_COUNT: u8 = 20;
+
+/// Number of `BUSY` responses to a structured VDM before giving up.
+///
+/// This is `nBusyCount` in the PD spec, see 6.7.6 VDMBusyCounter.
+pub const N_BUSY_COUNT: u8 = 5;
+
+/// Time in milliseconds a CC termination must be stable before a port transitions to an attached state.
+///
+/// This is `tCCDebounce` in the Type-C spec, see 4.11.2 CC Timing.
+pub const T_CC_DEBOUNCE_MS: Range<u16> = Range {
+    minimum: Minimum(100),
+    nominal: Nominal(150),
+    maximum: Maximum(200),
+};
+
+/// Time in milliseconds a CC termination must be stable before a detach or Rp change is recognized.
+///
+/// This is `tPDDebounce` in the Type-C spec, see 4.11.2 CC Timing.
+pub const T_PD_DEBOUNCE_MS: Range<u16> = Range {
+    minimum: Minimum(10),
+    nominal: Nominal(15),
+    maximum: Maximum(20),
+};
+
+/// Time in milliseconds a CC termination must be stable in the Try.SRC and Try.SNK states.
+///
+/// This is `tTryCCDebounce` in the Type-C spec, see 4.11.2 CC Timing.
+pub const T_TRY_CC_DEBOUNCE_MS: Range<u16> = Range {
+    minimum: Minimum(10),
+    nominal: Nominal(15),
+    maximum: Maximum(20),
+};
+
+/// Period in milliseconds of a DRP toggling between the source and sink terminations.
+///
+/// This is `tDRP` in the Type-C spec, see 4.11.2 CC Timing.
+pub const T_DRP_MS: Range<u16> = Range {
+    minimum: Minimum(50),
+    nominal: Nominal(75),
+    maximum: Maximum(100),
+};
+
+/// Time in milliseconds a DRP waits in Try.SRC or Try.SNK before checking the partner termination.
+///
+/// This is `tDRPTry` in the Type-C spec, see 4.11.2 CC Timing.
+pub const T_DRP_TRY_MS: Range<u16> = Range {
+    minimum: Minimum(75),
+    nominal: Nominal(112),
+    maximum: Maximum(150),
+};
+
+/// Time in milliseconds a DRP waits in TryWait.SNK for VBUS before transitioning to Unattached.
+///
+/// This is `tDRPTryWait` in the Type-C spec, see 4.11.2 CC Timing.
+pub const T_DRP_TRY_WAIT_MS: Range<u16> = Range {
+    minimum: Minimum(400),
+    nominal: Nominal(600),
+    maximum: Maximum(800),
+};
+
+/// Time in milliseconds after which a DRP exits Try.SRC when no sink is detected.
+///
+/// This is `tTryTimeout` in the Type-C spec, see 4.11.2 CC Timing.
+pub const T_TRY_TIMEOUT_MS: Range<u16> = Range {
+    minimum: Minimum(550),
+    nominal: Nominal(825),
+    maximum: Maximum(1100),
+};
+
+/// Time in milliseconds a port remains in ErrorRecovery with terminations removed.
+///
+/// This is `tErrorRecovery` in the Type-C spec, see 4.11.2 CC Timing.
+pub const T_ERROR_RECOVERY_MS: Minimum<u16> = Minimum(25);
+
+/// Time in milliseconds for a sink to reduce its current draw after the advertised Rp current decreases.
+///
+/// This is `tSinkAdj` in the Type-C spec, see 4.11.2 CC Timing.
+pub const T_SINK_ADJ_MS: Maximum<u16> = Maximum(60);
+
+/// Time in milliseconds from entering Attached.SRC until VBUS reaches vSafe5V.
+///
+/// This is `tVBUSON` in the Type-C spec, see 4.11.3 VBUS Timing.
+pub const T_VBUS_ON_MS: Maximum<u16> = Maximum(275);
+
+/// Time in milliseconds from the sink detaching until the source removes VBUS.
+///
+/// This is `tVBUSOFF` in the Type-C spec, see 4.11.3 VBUS Timing.
+pub const T_VBUS_OFF_MS: Maximum<u16> = Maximum(650);
+
+/// Time in milliseconds from entering Attached.SRC until VCONN is applied.
+///
+/// This is `tVCONNON` in the Type-C spec, see 4.11.3 VBUS Timing.
+pub const T_VCONN_ON_MS: Maximum<u16> = Maximum(2);
+
 pub mod wrappers {
     /// A minimum value.
     ///
@@ -0,0 +1,340 @@
+//! Mock PD controller
+//!
+//! Each port is plain data that tests can set up and inspect directly. Commands are recorded and applied to the port
+//! state as a real controller would, a successful swap changes the corresponding role.
+use core::convert::Infallible;
+
+use super::{PdController, PdoController, PdoOwner, PortStatus, SwapController};
+use crate::pdinfo::{AltMode, PowerPathStatus};
+use crate::pdo::{sink, source, Contract};
+use crate::policy::swap::SwapType;
+use crate::{DataRole, Error, PdError, PortId, PowerRole};
+
+/// Maximum number of PDOs stored per list, 7 SPR PDOs followed by 4 EPR PDOs
+pub const MAX_PDOS: usize = 11;
+
+/// Fixed-capacity list of PDOs
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Pdos<T> {
+    /// PDO storage
+    pdos: [T; MAX_PDOS],
+    /// Number of valid PDOs
+    len: usize,
+}
+
+impl<T: Copy + Default> Pdos<T> {
+    /// Create a list from `pdos`, returns [`PdError::InvalidParams`] if there are more than [`MAX_PDOS`]
+    pub fn new(pdos: &[T]) -> Result<Self, PdError> {
+        let mut list = Self::default();
+        list.pdos
+            .get_mut(..pdos.len())
+            .ok_or(PdError::InvalidParams)?
+            .copy_from_slice(pdos);
+        list.len = pdos.len();
+        Ok(list)
+    }
+
+    /// Returns the PDOs
+    pub fn as_slice(&self) -> &[T] {
+        self.pdos.get(..self.len).unwrap_or_default()
+    }
+
+    /// Copy as many PDOs as fit into `pdos`, returns the number copied
+    fn copy_to(&self, pdos: &mut [T]) -> usize {
+        let len = self.len.min(pdos.len());
+        for (dest, pdo) in pdos.iter_mut().zip(self.as_slice()) {
+            *dest = *pdo;
+        }
+        len
+    }
+}
+
+impl<T: Copy + Default> Default for Pdos<T> {
+    fn default() -> Self {
+        Self {
+            pdos: [T::default(); MAX_PDOS],
+            len: 0,
+        }
+    }
+}
+
+/// State of a mock port
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MockPort {
+    /// Port status
+    pub status: PortStatus,
+    /// Active contract
+    pub contract: Option<Contract>,
+    /// Active alternate modes
+    pub alt_mode: AltMode,
+    /// Power path switch status
+    pub power_path: PowerPathStatus,
+    /// Source PDOs advertised by the port
+    pub source_pdos: Pdos<source::Pdo>,
+    /// Sink PDOs advertised by the port
+    pub sink_pdos: Pdos<sink::Pdo>,
+    /// Source PDOs advertised by the port partner
+    pub partner_source_pdos: Pdos<source::Pdo>,
+    /// Sink PDOs advertised by the port partner
+    pub partner_sink_pdos: Pdos<sink::Pdo>,
+    /// Result of the next swap request
+    pub swap_result: Result<(), PdError>,
+    /// Swaps requested
+    pub swaps: usize,
+    /// Hard resets performed
+    pub hard_resets: usize,
+}
+
+impl Default for MockPort {
+    fn default() -> Self {
+        Self {
+            status: PortStatus::detached(PowerRole::Sink, DataRole::Ufp),
+            contract: None,
+            alt_mode: AltMode::none(),
+            power_path: PowerPathStatus::none(),
+            source_pdos: Pdos::default(),
+            sink_pdos: Pdos::default(),
+            partner_source_pdos: Pdos::default(),
+            partner_sink_pdos: Pdos::default(),
+            swap_result: Ok(()),
+            swaps: 0,
+            hard_resets: 0,
+        }
+    }
+}
+
+/// Mock controller with `N` ports
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MockController<const N: usize> {
+    /// Port state, indexed by port ID
+    pub ports: [MockPort; N],
+}
+
+impl<const N: usize> MockController<N> {
+    /// Create a controller with all ports detached
+    pub fn new() -> Self {
+        Self {
+            ports: [MockPort::default(); N],
+        }
+    }
+
+    /// Returns the given port, returns [`PdError::InvalidPort`] if it does not exist
+    pub fn port<P: PortId>(&mut self, port: P) -> Result<&mut MockPort, PdError> {
+        self.ports.get_mut(port.into() as usize).ok_or(PdError::InvalidPort)
+    }
+}
+
+impl<const N: usize> Default for MockController<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<P: PortId, const N: usize> PdController<P> for MockController<N> {
+    type BusError = Infallible;
+
+    fn num_ports(&self) -> u8 {
+        N as u8
+    }
+
+    async fn port_status(&mut self, port: P) -> Result<PortStatus, Error<Self::BusError>> {
+        Ok(self.port(port)?.status)
+    }
+
+    async fn active_contract(&mut self, port: P) -> Result<Option<Contract>, Error<Self::BusError>> {
+        Ok(self.port(port)?.contract)
+    }
+
+    async fn alt_mode(&mut self, port: P) -> Result<AltMode, Error<Self::BusError>> {
+        Ok(self.port(port)?.alt_mode)
+    }
+
+    async fn power_path_status(&mut self, port: P) -> Result<PowerPathStatus, Error<Self::BusError>> {
+        Ok(self.port(port)?.power_path)
+    }
+
+    async fn hard_reset(&mut self, port: P) -> Result<(), Error<Self::BusError>> {
+        let port = self.port(port)?;
+        port.hard_resets += 1;
+        port.contract = None;
+        port.alt_mode = AltMode::none();
+        Ok(())
+    }
+}
+
+impl<P: PortId, const N: usize> PdoController<P> for MockController<N> {
+    async fn source_pdos(
+        &mut self,
+        port: P,
+        owner: PdoOwner,
+        pdos: &mut [source::Pdo],
+    ) -> Result<usize, Error<Self::BusError>> {
+        let port = self.port(port)?;
+        Ok(match owner {
+            PdoOwner::Port => port.source_pdos.copy_to(pdos),
+            PdoOwner::Partner => port.partner_source_pdos.copy_to(pdos),
+        })
+    }
+
+    async fn sink_pdos(
+        &mut self,
+        port: P,
+        owner: PdoOwner,
+        pdos: &mut [sink::Pdo],
+    ) -> Result<usize, Error<Self::BusError>> {
+        let port = self.port(port)?;
+        Ok(match owner {
+            PdoOwner::Port => port.sink_pdos.copy_to(pdos),
+            PdoOwner::Partner => port.partner_sink_pdos.copy_to(pdos),
+        })
+    }
+
+    async fn set_sink_pdos(&mut self, port: P, pdos: &[sink::Pdo]) -> Result<(), Error<Self::BusError>> {
+        self.port(port)?.sink_pdos = Pdos::new(pdos)?;
+        Ok(())
+    }
+}
+
+impl<P: PortId, const N: usize> SwapController<P> for MockController<N> {
+    async fn request_swap(&mut self, port: P, swap: SwapType) -> Result<(), Error<Self::BusError>> {
+        let port = self.port(port)?;
+        port.swaps += 1;
+        if !port.status.is_attached() {
+            return PdError::InvalidMode.into();
+        }
+        port.swap_result?;
+
+        let status = &mut port.status;
+        match swap {
+            SwapType::PowerRole => {
+                status.power_role = match status.power_role {
+                    PowerRole::Sink => PowerRole::Source,
+                    PowerRole::Source => PowerRole::Sink,
+                };
+                // The contract is renegotiated after a power role swap
+                port.contract = None;
+            }
+            SwapType::DataRole => {
+                status.data_role = match status.data_role {
+                    DataRole::Ufp => DataRole::Dfp,
+                    DataRole::Dfp => DataRole::Ufp,
+                };
+            }
+            SwapType::Vconn => status.vconn_source = !status.vconn_source,
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pdo::rdo::tests::fixed_rdo;
+    use crate::pdo::source::FixedData;
+    use crate::test::block_on;
+    use crate::type_c::ConnectionState;
+    use crate::LocalPortId;
+
+    const PORT0: LocalPortId = LocalPortId(0);
+    const PORT1: LocalPortId = LocalPortId(1);
+
+    fn source_pdo(voltage_mv: u16) -> source::Pdo {
+        source::Pdo::Fixed(FixedData {
+            voltage_mv,
+            current_ma: 3000,
+            ..Default::default()
+        })
+    }
+
+    fn attached() -> MockController<2> {
+        let mut controller = MockController::new();
+        if let Ok(port) = controller.port(PORT0) {
+            port.status.connection = Some(ConnectionState::Attached);
+        }
+        controller
+    }
+
+    #[test]
+    fn test_status() {
+        let mut controller = attached();
+        let contract = Contract::from_source(source_pdo(5000), fixed_rdo(1));
+        if let Ok(port) = controller.port(PORT0) {
+            port.contract = Some(contract);
+            port.power_path = PowerPathStatus::new(false, true);
+        }
+
+        assert_eq!(PdController::<LocalPortId>::num_ports(&controller), 2);
+        assert!(block_on(controller.port_status(PORT0)).is_ok_and(|status| status.is_attached()));
+        assert_eq!(block_on(controller.active_contract(PORT0)), Ok(Some(contract)));
+        assert_eq!(
+            block_on(controller.power_path_status(PORT0)),
+            Ok(PowerPathStatus::new(false, true))
+        );
+        assert_eq!(block_on(controller.alt_mode(PORT1)), Ok(AltMode::none()));
+        assert_eq!(
+            block_on(controller.port_status(LocalPortId(2))),
+            Err(Error::Pd(PdError::InvalidPort))
+        );
+
+        assert_eq!(block_on(controller.hard_reset(PORT0)), Ok(()));
+        assert_eq!(block_on(controller.active_contract(PORT0)), Ok(None));
+        assert_eq!(controller.ports.first().map(|port| port.hard_resets), Some(1));
+    }
+
+    #[test]
+    fn test_pdos() {
+        let mut controller = attached();
+        let partner_pdos = [source_pdo(5000), source_pdo(9000), source_pdo(15000)];
+        if let Ok(port) = controller.port(PORT0) {
+            port.partner_source_pdos = Pdos::new(&partner_pdos).unwrap_or_default();
+        }
+
+        let mut pdos = [source::Pdo::default(); 2];
+        assert_eq!(
+            block_on(controller.source_pdos(PORT0, PdoOwner::Partner, &mut pdos)),
+            Ok(2)
+        );
+        assert_eq!(pdos.as_slice(), partner_pdos.get(..2).unwrap_or_default());
+        assert_eq!(
+            block_on(controller.source_pdos(PORT0, PdoOwner::Port, &mut pdos)),
+            Ok(0)
+        );
+
+        let sink_pdos = [sink::Pdo::default(); 3];
+        assert_eq!(block_on(controller.set_sink_pdos(PORT0, &sink_pdos)), Ok(()));
+        let mut pdos = [sink::Pdo::default(); MAX_PDOS];
+        assert_eq!(block_on(controller.sink_pdos(PORT0, PdoOwner::Port, &mut pdos)), Ok(3));
+        assert_eq!(
+            block_on(controller.set_sink_pdos(PORT0, &[sink::Pdo::default(); MAX_PDOS + 1])),
+            Err(Error::Pd(PdError::InvalidParams))
+        );
+    }
+
+    #[test]
+    fn test_swap() {
+        let mut controller = attached();
+        assert_eq!(block_on(controller.request_swap(PORT0, SwapType::PowerRole)), Ok(()));
+        assert_eq!(block_on(controller.request_swap(PORT0, SwapType::DataRole)), Ok(()));
+        assert_eq!(block_on(controller.request_swap(PORT0, SwapType::Vconn)), Ok(()));
+
+        let status = block_on(controller.port_status(PORT0)).ok();
+        assert_eq!(status.map(|status| status.power_role), Some(PowerRole::Source));
+        assert_eq!(status.map(|status| status.data_role), Some(DataRole::Dfp));
+        assert_eq!(status.map(|status| status.vconn_source), Some(true));
+
+        if let Ok(port) = controller.port(PORT0) {
+            port.swap_result = Err(PdError::SwapRejectedPartner);
+        }
+        assert_eq!(
+            block_on(controller.request_swap(PORT0, SwapType::PowerRole)),
+            Err(Error::Pd(PdError::SwapRejectedPartner))
+        );
+        assert_eq!(
+            block_on(controller.request_swap(PORT1, SwapType::DataRole)),
+            Err(Error::Pd(PdError::InvalidMode))
+        );
+    }
+}
@@ -0,0 +1,121 @@
+//! Vendor-neutral interface to PD controllers
+//!
+//! Most systems use an integrated PD controller that runs the policy engine itself and is managed by the host over a
+//! vendor-specific bus protocol. These traits give drivers for such controllers a common async interface, the
+//! functionality is split so a driver only implements what its controller supports. With the `std` feature,
+//! `mock::MockController` can stand in for a controller in tests.
+use crate::pdinfo::{AltMode, PowerPathStatus};
+use crate::pdo::{sink, source, Contract};
+use crate::policy::swap::SwapType;
+use crate::type_c::{ConnectionState, Current};
+use crate::{DataRole, Error, PlugOrientation, PortId, PowerRole};
+
+#[cfg(any(test, feature = "std"))]
+pub mod mock;
+
+/// Port status
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PortStatus {
+    /// Connection state, `None` if nothing is attached
+    pub connection: Option<ConnectionState>,
+    /// Current power role
+    pub power_role: PowerRole,
+    /// Current data role
+    pub data_role: DataRole,
+    /// Plug orientation
+    pub orientation: PlugOrientation,
+    /// True if the port is sourcing VCONN
+    pub vconn_source: bool,
+    /// Current advertised through Rp, `None` if the port is not an attached sink
+    pub type_c_current: Option<Current>,
+}
+
+impl PortStatus {
+    /// Returns the status of a port with nothing attached
+    pub const fn detached(power_role: PowerRole, data_role: DataRole) -> Self {
+        Self {
+            connection: None,
+            power_role,
+            data_role,
+            orientation: PlugOrientation::CC1,
+            vconn_source: false,
+            type_c_current: None,
+        }
+    }
+
+    /// Returns true if the port is attached to another device or accessory
+    pub fn is_attached(&self) -> bool {
+        self.connection.is_some()
+    }
+}
+
+/// Selects whether PDOs are read from the port itself or from its partner
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PdoOwner {
+    /// PDOs advertised by the port
+    Port,
+    /// PDOs advertised by the port partner
+    Partner,
+}
+
+/// Base PD controller trait, provides port status and hard reset
+#[allow(async_fn_in_trait)]
+pub trait PdController<P: PortId> {
+    /// Bus error type
+    type BusError;
+
+    /// Returns the number of ports on the controller
+    fn num_ports(&self) -> u8;
+
+    /// Returns the port status
+    async fn port_status(&mut self, port: P) -> Result<PortStatus, Error<Self::BusError>>;
+
+    /// Returns the active contract, `None` if no explicit contract has been negotiated
+    async fn active_contract(&mut self, port: P) -> Result<Option<Contract>, Error<Self::BusError>>;
+
+    /// Returns the active alternate modes
+    async fn alt_mode(&mut self, port: P) -> Result<AltMode, Error<Self::BusError>>;
+
+    /// Returns the status of the power path switches
+    async fn power_path_status(&mut self, port: P) -> Result<PowerPathStatus, Error<Self::BusError>>;
+
+    /// Perform a hard reset
+    async fn hard_reset(&mut self, port: P) -> Result<(), Error<Self::BusError>>;
+}
+
+/// PDO access
+#[allow(async_fn_in_trait)]
+pub trait PdoController<P: PortId>: PdController<P> {
+    /// Read source PDOs into `pdos`, returns the number of PDOs read
+    async fn source_pdos(
+        &mut self,
+        port: P,
+        owner: PdoOwner,
+        pdos: &mut [source::Pdo],
+    ) -> Result<usize, Error<Self::BusError>>;
+
+    /// Read sink PDOs into `pdos`, returns the number of PDOs read
+    async fn sink_pdos(
+        &mut self,
+        port: P,
+        owner: PdoOwner,
+        pdos: &mut [sink::Pdo],
+    ) -> Result<usize, Error<Self::BusError>>;
+
+    /// Set the sink PDOs advertised by the port
+    ///
+    /// Returns [`crate::PdError::InvalidParams`] if the controller cannot store this many PDOs.
+    async fn set_sink_pdos(&mut self, port: P, pdos: &[sink::Pdo]) -> Result<(), Error<Self::BusError>>;
+}
+
+/// Role swap requests
+#[allow(async_fn_in_trait)]
+pub trait SwapController<P: PortId>: PdController<P> {
+    /// Request a swap with the port partner and wait for it to complete
+    ///
+    /// Errors are reported as by [`crate::policy::swap::StateMachine`], in particular
+    /// [`crate::PdError::SwapRejectedPartner`] if the partner rejected the swap.
+    async fn request_swap(&mut self, port: P, swap: SwapType) -> Result<(), Error<Self::BusError>>;
+}
@@ -0,0 +1,280 @@
+//! Country related messages as defined in the USB PD specification
+//!
+//! This covers the Country_Codes (6.5.11) and Country_Info (6.5.12) data blocks and the Get_Country_Info data object
+//! (6.4.7).
+use crate::PdError;
+
+/// Maximum length of an extended message data block in bytes
+pub const MAX_EXTENDED_MESSAGE_LEN: usize = 260;
+/// Length of the fixed portion of the Country_Codes and Country_Info data blocks in bytes
+pub const COUNTRY_HEADER_LEN: usize = 4;
+/// Length of a country code in bytes
+pub const COUNTRY_CODE_LEN: usize = 2;
+/// Maximum number of country codes in a Country_Codes data block
+pub const MAX_COUNTRY_CODES: usize = (MAX_EXTENDED_MESSAGE_LEN - COUNTRY_HEADER_LEN) / COUNTRY_CODE_LEN;
+/// Maximum length of the country specific data in a Country_Info data block
+pub const MAX_COUNTRY_DATA_LEN: usize = MAX_EXTENDED_MESSAGE_LEN - COUNTRY_HEADER_LEN;
+
+/// ISO 3166 alpha-2 country code
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CountryCode(pub [u8; COUNTRY_CODE_LEN]);
+
+impl CountryCode {
+    /// Returns the country code as a `str` if it is valid ASCII
+    pub fn as_str(&self) -> Option<&str> {
+        if self.0.is_ascii() {
+            core::str::from_utf8(&self.0).ok()
+        } else {
+            None
+        }
+    }
+}
+
+/// The first character is stored in the most significant byte
+impl From<u16> for CountryCode {
+    fn from(value: u16) -> Self {
+        CountryCode(value.to_be_bytes())
+    }
+}
+
+impl From<CountryCode> for u16 {
+    fn from(value: CountryCode) -> Self {
+        u16::from_be_bytes(value.0)
+    }
+}
+
+/// Get_Country_Info data object
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct GetCountryInfo(pub CountryCode);
+
+impl From<u32> for GetCountryInfo {
+    fn from(value: u32) -> Self {
+        GetCountryInfo(CountryCode::from((value >> 16) as u16))
+    }
+}
+
+impl From<GetCountryInfo> for u32 {
+    fn from(value: GetCountryInfo) -> Self {
+        (u16::from(value.0) as u32) << 16
+    }
+}
+
+/// Decode the country code and length fields common to both data blocks
+fn decode_header(value: &[u8]) -> Result<(u16, &[u8]), PdError> {
+    let ([field0, field1, _, _], rest) = value
+        .split_first_chunk::<COUNTRY_HEADER_LEN>()
+        .map(|(header, rest)| (*header, rest))
+        .ok_or(PdError::InvalidParams)?;
+    Ok((u16::from_le_bytes([field0, field1]), rest))
+}
+
+/// Encode the country code and length fields common to both data blocks
+fn encode_header(field: u16, buf: &mut [u8]) -> Result<&mut [u8], PdError> {
+    let (header, rest) = buf
+        .split_first_chunk_mut::<COUNTRY_HEADER_LEN>()
+        .ok_or(PdError::InvalidParams)?;
+    let [field0, field1] = field.to_le_bytes();
+    *header = [field0, field1, 0, 0];
+    Ok(rest)
+}
+
+/// Country_Codes data block
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CountryCodes {
+    /// Country code storage, only the first `len` entries are valid
+    codes: [CountryCode; MAX_COUNTRY_CODES],
+    /// Number of country codes
+    len: usize,
+}
+
+impl CountryCodes {
+    /// Create a new country codes data block, fails if there are more than [`MAX_COUNTRY_CODES`] codes
+    pub fn new(codes: &[CountryCode]) -> Result<Self, PdError> {
+        let mut storage = [CountryCode::default(); MAX_COUNTRY_CODES];
+        storage
+            .get_mut(..codes.len())
+            .ok_or(PdError::InvalidParams)?
+            .copy_from_slice(codes);
+
+        Ok(CountryCodes {
+            codes: storage,
+            len: codes.len(),
+        })
+    }
+
+    /// Returns the country codes
+    pub fn codes(&self) -> &[CountryCode] {
+        self.codes.get(..self.len).unwrap_or_default()
+    }
+
+    /// Returns the number of country codes
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if there are no country codes
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns the length of the encoded data block in bytes
+    pub fn encoded_len(&self) -> usize {
+        COUNTRY_HEADER_LEN + self.len * COUNTRY_CODE_LEN
+    }
+
+    /// Encode the data block into the given buffer, returns the number of bytes written
+    pub fn encode_into_slice(&self, buf: &mut [u8]) -> Result<usize, PdError> {
+        let buf = buf.get_mut(..self.encoded_len()).ok_or(PdError::InvalidParams)?;
+        let rest = encode_header(self.len as u16, buf)?;
+        for (chunk, code) in rest.chunks_exact_mut(COUNTRY_CODE_LEN).zip(self.codes()) {
+            chunk.copy_from_slice(&u16::from(*code).to_le_bytes());
+        }
+        Ok(self.encoded_len())
+    }
+}
+
+impl TryFrom<&[u8]> for CountryCodes {
+    type Error = PdError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let (len, rest) = decode_header(value)?;
+        let len = len as usize;
+        if len > MAX_COUNTRY_CODES || rest.len() < len * COUNTRY_CODE_LEN {
+            return Err(PdError::InvalidParams);
+        }
+
+        let mut codes = [CountryCode::default(); MAX_COUNTRY_CODES];
+        for (code, chunk) in codes.iter_mut().zip(rest.chunks_exact(COUNTRY_CODE_LEN)).take(len) {
+            if let [byte0, byte1] = *chunk {
+                *code = CountryCode::from(u16::from_le_bytes([byte0, byte1]));
+            }
+        }
+        Ok(CountryCodes { codes, len })
+    }
+}
+
+/// Country_Info data block
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CountryInfo {
+    /// Country code
+    pub country_code: CountryCode,
+    /// Country specific data storage, only the first `data_len` bytes are valid
+    data: [u8; MAX_COUNTRY_DATA_LEN],
+    /// Length of the country specific data
+    data_len: usize,
+}
+
+impl CountryInfo {
+    /// Create a new country info data block, fails if the data is longer than [`MAX_COUNTRY_DATA_LEN`]
+    pub fn new(country_code: CountryCode, data: &[u8]) -> Result<Self, PdError> {
+        let mut storage = [0u8; MAX_COUNTRY_DATA_LEN];
+        storage
+            .get_mut(..data.len())
+            .ok_or(PdError::InvalidParams)?
+            .copy_from_slice(data);
+
+        Ok(CountryInfo {
+            country_code,
+            data: storage,
+            data_len: data.len(),
+        })
+    }
+
+    /// Returns the country specific data
+    pub fn data(&self) -> &[u8] {
+        self.data.get(..self.data_len).unwrap_or_default()
+    }
+
+    /// Returns the length of the encoded data block in bytes
+    pub fn encoded_len(&self) -> usize {
+        COUNTRY_HEADER_LEN + self.data_len
+    }
+
+    /// Encode the data block into the given buffer, returns the number of bytes written
+    pub fn encode_into_slice(&self, buf: &mut [u8]) -> Result<usize, PdError> {
+        let buf = buf.get_mut(..self.encoded_len()).ok_or(PdError::InvalidParams)?;
+        let rest = encode_header(self.country_code.into(), buf)?;
+        rest.copy_from_slice(self.data());
+        Ok(self.encoded_len())
+    }
+}
+
+impl TryFrom<&[u8]> for CountryInfo {
+    type Error = PdError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let (country_code, data) = decode_header(value)?;
+        CountryInfo::new(country_code.into(), data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const US: CountryCode = CountryCode(*b"US");
+    const DE: CountryCode = CountryCode(*b"DE");
+
+    #[test]
+    fn test_get_country_info() {
+        assert_eq!(GetCountryInfo::from(0x5553_0000), GetCountryInfo(US));
+        assert_eq!(u32::from(GetCountryInfo(DE)), 0x4445_0000);
+        assert_eq!(US.as_str(), Some("US"));
+    }
+
+    #[test]
+    fn test_country_codes_roundtrip() {
+        const RAW: [u8; 8] = [0x02, 0x00, 0x00, 0x00, b'S', b'U', b'E', b'D'];
+
+        let codes = CountryCodes::try_from(RAW.as_slice());
+        assert_eq!(codes, CountryCodes::new(&[US, DE]));
+        assert_eq!(codes.as_ref().map(|codes| codes.codes()), Ok([US, DE].as_slice()));
+
+        let mut buf = [0u8; MAX_EXTENDED_MESSAGE_LEN];
+        let len = codes.and_then(|codes| codes.encode_into_slice(&mut buf));
+        assert_eq!(len, Ok(RAW.len()));
+        assert_eq!(buf.get(..RAW.len()), Some(RAW.as_slice()));
+    }
+
+    #[test]
+    fn test_country_codes_invalid() {
+        // Length field larger than the data
+        assert_eq!(
+            CountryCodes::try_from([0x02, 0x00, 0x00, 0x00, b'S', b'U'].as_slice()),
+            Err(PdError::InvalidParams)
+        );
+        assert_eq!(
+            CountryCodes::new(&[US; MAX_COUNTRY_CODES + 1]),
+            Err(PdError::InvalidParams)
+        );
+    }
+
+    #[test]
+    fn test_country_info_roundtrip() {
+        const RAW: [u8; 7] = [b'S', b'U', 0x00, 0x00, 0x01, 0x02, 0x03];
+
+        let info = CountryInfo::try_from(RAW.as_slice());
+        assert_eq!(info, CountryInfo::new(US, &[0x01, 0x02, 0x03]));
+
+        let mut buf = [0u8; MAX_EXTENDED_MESSAGE_LEN];
+        let len = info.and_then(|info| info.encode_into_slice(&mut buf));
+        assert_eq!(len, Ok(RAW.len()));
+        assert_eq!(buf.get(..RAW.len()), Some(RAW.as_slice()));
+    }
+
+    #[test]
+    fn test_country_info_invalid() {
+        assert_eq!(
+            CountryInfo::try_from([b'S', b'U'].as_slice()),
+            Err(PdError::InvalidParams)
+        );
+        assert_eq!(
+            CountryInfo::try_from([0u8; MAX_EXTENDED_MESSAGE_LEN + 1].as_slice()),
+            Err(PdError::InvalidParams)
+        );
+    }
+}
@@ -0,0 +1,487 @@
+//! Enter_USB data object as defined in the USB PD specification 6.4.8
+//!
+//! The Enter_USB Data Object (EUDO) is sent by the DFP to enter USB4, USB 3.2 or USB 2.0 operation. The cable fields
+//! are derived from the cable plug's Discover Identity response, see [`CableInfo`].
+use bitfield::bitfield;
+
+use crate::vdm::structured::command::discover_identity::{
+    active_cable_vdo, passive_cable_vdo, ActiveCableVdo1, ActiveCableVdo2, PassiveCableVdo,
+};
+use crate::PdError;
+
+/// Errors that can occur when parsing an [`EnterUsb`] from a raw value
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ParseEnterUsbError {
+    /// [`EnterUsb::usb_mode`] contains an invalid value
+    InvalidUsbMode,
+    /// [`CableInfo::speed`] contains an invalid value
+    InvalidCableSpeed,
+    /// [`CableInfo::current`] contains an invalid value
+    InvalidCableCurrent,
+}
+
+impl From<ParseEnterUsbError> for PdError {
+    fn from(_: ParseEnterUsbError) -> Self {
+        PdError::InvalidParams
+    }
+}
+
+/// USB mode to enter
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum UsbMode {
+    /// USB 2.0
+    #[default]
+    Usb2,
+    /// USB 3.2
+    Usb3,
+    /// USB4
+    Usb4,
+}
+
+impl TryFrom<u8> for UsbMode {
+    type Error = ParseEnterUsbError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0b000 => Ok(UsbMode::Usb2),
+            0b001 => Ok(UsbMode::Usb3),
+            0b010 => Ok(UsbMode::Usb4),
+            _ => Err(ParseEnterUsbError::InvalidUsbMode),
+        }
+    }
+}
+
+impl From<UsbMode> for u8 {
+    fn from(value: UsbMode) -> Self {
+        match value {
+            UsbMode::Usb2 => 0b000,
+            UsbMode::Usb3 => 0b001,
+            UsbMode::Usb4 => 0b010,
+        }
+    }
+}
+
+/// Highest speed supported by the cable
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CableSpeed {
+    /// USB 2.0 only
+    #[default]
+    Usb2,
+    /// USB 3.2 Gen1
+    Usb3Gen1,
+    /// USB 3.2 Gen2 and USB4 Gen2
+    Gen2,
+    /// USB4 Gen3
+    Usb4Gen3,
+    /// USB4 Gen4
+    Usb4Gen4,
+}
+
+impl TryFrom<u8> for CableSpeed {
+    type Error = ParseEnterUsbError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0b000 => Ok(CableSpeed::Usb2),
+            0b001 => Ok(CableSpeed::Usb3Gen1),
+            0b010 => Ok(CableSpeed::Gen2),
+            0b011 => Ok(CableSpeed::Usb4Gen3),
+            0b100 => Ok(CableSpeed::Usb4Gen4),
+            _ => Err(ParseEnterUsbError::InvalidCableSpeed),
+        }
+    }
+}
+
+impl From<CableSpeed> for u8 {
+    fn from(value: CableSpeed) -> Self {
+        match value {
+            CableSpeed::Usb2 => 0b000,
+            CableSpeed::Usb3Gen1 => 0b001,
+            CableSpeed::Gen2 => 0b010,
+            CableSpeed::Usb4Gen3 => 0b011,
+            CableSpeed::Usb4Gen4 => 0b100,
+        }
+    }
+}
+
+impl From<passive_cable_vdo::UsbHighestSpeed> for CableSpeed {
+    fn from(value: passive_cable_vdo::UsbHighestSpeed) -> Self {
+        use passive_cable_vdo::UsbHighestSpeed::*;
+        match value {
+            Usb2p0 => CableSpeed::Usb2,
+            Usb3p2Gen1 => CableSpeed::Usb3Gen1,
+            Usb3p2 => CableSpeed::Gen2,
+            Usb4Gen3 => CableSpeed::Usb4Gen3,
+            Usb4Gen4 => CableSpeed::Usb4Gen4,
+        }
+    }
+}
+
+impl From<active_cable_vdo::UsbHighestSpeed> for CableSpeed {
+    fn from(value: active_cable_vdo::UsbHighestSpeed) -> Self {
+        use active_cable_vdo::UsbHighestSpeed::*;
+        match value {
+            Usb2p0 => CableSpeed::Usb2,
+            Usb3p2Gen1 => CableSpeed::Usb3Gen1,
+            Usb3p2 => CableSpeed::Gen2,
+            Usb4Gen3 => CableSpeed::Usb4Gen3,
+            Usb4Gen4 => CableSpeed::Usb4Gen4,
+        }
+    }
+}
+
+/// Cable type
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CableType {
+    /// Passive cable
+    #[default]
+    Passive,
+    /// Active re-timer cable
+    ActiveRetimer,
+    /// Active re-driver cable
+    ActiveRedriver,
+    /// Optically isolated active cable
+    OpticallyIsolated,
+}
+
+impl From<u8> for CableType {
+    fn from(value: u8) -> Self {
+        // NOTE: If this mask changes, the panic safety comment below must be reevaluated
+        const CABLE_TYPE_MASK: u8 = 0x3;
+        match value & CABLE_TYPE_MASK {
+            0b00 => CableType::Passive,
+            0b01 => CableType::ActiveRetimer,
+            0b10 => CableType::ActiveRedriver,
+            0b11 => CableType::OpticallyIsolated,
+            // Panic safety: This will never panic if the mask above does not change
+            #[allow(clippy::unreachable)]
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl From<CableType> for u8 {
+    fn from(value: CableType) -> Self {
+        match value {
+            CableType::Passive => 0b00,
+            CableType::ActiveRetimer => 0b01,
+            CableType::ActiveRedriver => 0b10,
+            CableType::OpticallyIsolated => 0b11,
+        }
+    }
+}
+
+/// VBUS current the cable is able to carry
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CableCurrent {
+    /// VBUS is not carried through the cable
+    #[default]
+    VbusNotSupported,
+    /// 3A
+    Current3A,
+    /// 5A
+    Current5A,
+}
+
+impl TryFrom<u8> for CableCurrent {
+    type Error = ParseEnterUsbError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0b00 => Ok(CableCurrent::VbusNotSupported),
+            0b10 => Ok(CableCurrent::Current3A),
+            0b11 => Ok(CableCurrent::Current5A),
+            _ => Err(ParseEnterUsbError::InvalidCableCurrent),
+        }
+    }
+}
+
+impl From<CableCurrent> for u8 {
+    fn from(value: CableCurrent) -> Self {
+        match value {
+            CableCurrent::VbusNotSupported => 0b00,
+            CableCurrent::Current3A => 0b10,
+            CableCurrent::Current5A => 0b11,
+        }
+    }
+}
+
+impl From<passive_cable_vdo::VbusCurrentHandlingCapability> for CableCurrent {
+    fn from(value: passive_cable_vdo::VbusCurrentHandlingCapability) -> Self {
+        match value {
+            passive_cable_vdo::VbusCurrentHandlingCapability::ThreeAmps => CableCurrent::Current3A,
+            passive_cable_vdo::VbusCurrentHandlingCapability::FiveAmps => CableCurrent::Current5A,
+        }
+    }
+}
+
+impl From<active_cable_vdo::VbusCurrentHandlingCapability> for CableCurrent {
+    fn from(value: active_cable_vdo::VbusCurrentHandlingCapability) -> Self {
+        match value {
+            active_cable_vdo::VbusCurrentHandlingCapability::ThreeAmps => CableCurrent::Current3A,
+            active_cable_vdo::VbusCurrentHandlingCapability::FiveAmps => CableCurrent::Current5A,
+        }
+    }
+}
+
+/// Cable fields of the EUDO
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CableInfo {
+    /// Highest speed supported by the cable
+    pub speed: CableSpeed,
+    /// Cable type
+    pub cable_type: CableType,
+    /// VBUS current the cable is able to carry
+    pub current: CableCurrent,
+}
+
+impl From<&PassiveCableVdo> for CableInfo {
+    fn from(value: &PassiveCableVdo) -> Self {
+        CableInfo {
+            speed: value.usb_highest_speed.into(),
+            cable_type: CableType::Passive,
+            current: value.vbus_current_handling_capability.into(),
+        }
+    }
+}
+
+impl From<(&ActiveCableVdo1, &ActiveCableVdo2)> for CableInfo {
+    fn from((vdo1, vdo2): (&ActiveCableVdo1, &ActiveCableVdo2)) -> Self {
+        let cable_type = if vdo2.optically_isolated_active_cable {
+            CableType::OpticallyIsolated
+        } else {
+            match vdo2.active_element {
+                active_cable_vdo::ActiveElement::Redriver => CableType::ActiveRedriver,
+                active_cable_vdo::ActiveElement::Retimer => CableType::ActiveRetimer,
+            }
+        };
+
+        CableInfo {
+            speed: vdo1.usb_highest_speed.into(),
+            cable_type,
+            current: if vdo1.vbus_through_cable {
+                vdo1.vbus_current_handling_capability.into()
+            } else {
+                CableCurrent::VbusNotSupported
+            },
+        }
+    }
+}
+
+bitfield! {
+    /// Raw Enter_USB data object
+    #[derive(Copy, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    struct EnterUsbRaw(u32);
+    impl Debug;
+
+    /// USB mode
+    pub u8, usb_mode, set_usb_mode: 30, 28;
+    /// USB4 DRD
+    pub bool, usb4_drd, set_usb4_drd: 26;
+    /// USB3 DRD
+    pub bool, usb3_drd, set_usb3_drd: 25;
+    /// Cable speed
+    pub u8, cable_speed, set_cable_speed: 23, 21;
+    /// Cable type
+    pub u8, cable_type, set_cable_type: 20, 19;
+    /// Cable current
+    pub u8, cable_current, set_cable_current: 18, 17;
+    /// PCIe tunneling supported
+    pub bool, pcie_support, set_pcie_support: 16;
+    /// DisplayPort tunneling supported
+    pub bool, dp_support, set_dp_support: 15;
+    /// Thunderbolt 3 tunneling supported
+    pub bool, tbt_support, set_tbt_support: 14;
+    /// Host present
+    pub bool, host_present, set_host_present: 13;
+}
+
+/// Enter_USB data object
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EnterUsb {
+    /// USB mode to enter
+    pub usb_mode: UsbMode,
+    /// USB4 dual role data capable
+    pub usb4_drd: bool,
+    /// USB3 dual role data capable
+    pub usb3_drd: bool,
+    /// Cable information
+    pub cable: CableInfo,
+    /// PCIe tunneling supported
+    pub pcie_support: bool,
+    /// DisplayPort tunneling supported
+    pub dp_support: bool,
+    /// Thunderbolt 3 tunneling supported
+    pub tbt_support: bool,
+    /// Host present
+    pub host_present: bool,
+}
+
+impl TryFrom<u32> for EnterUsb {
+    type Error = ParseEnterUsbError;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        let raw = EnterUsbRaw(value);
+        Ok(EnterUsb {
+            usb_mode: raw.usb_mode().try_into()?,
+            usb4_drd: raw.usb4_drd(),
+            usb3_drd: raw.usb3_drd(),
+            cable: CableInfo {
+                speed: raw.cable_speed().try_into()?,
+                cable_type: raw.cable_type().into(),
+                current: raw.cable_current().try_into()?,
+            },
+            pcie_support: raw.pcie_support(),
+            dp_support: raw.dp_support(),
+            tbt_support: raw.tbt_support(),
+            host_present: raw.host_present(),
+        })
+    }
+}
+
+impl From<EnterUsb> for u32 {
+    fn from(value: EnterUsb) -> Self {
+        let mut raw = EnterUsbRaw(0);
+        raw.set_usb_mode(value.usb_mode.into());
+        raw.set_usb4_drd(value.usb4_drd);
+        raw.set_usb3_drd(value.usb3_drd);
+        raw.set_cable_speed(value.cable.speed.into());
+        raw.set_cable_type(value.cable.cable_type.into());
+        raw.set_cable_current(value.cable.current.into());
+        raw.set_pcie_support(value.pcie_support);
+        raw.set_dp_support(value.dp_support);
+        raw.set_tbt_support(value.tbt_support);
+        raw.set_host_present(value.host_present);
+        raw.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enter_usb_roundtrip() {
+        // USB4, USB4 DRD, Gen3 passive 5A cable, PCIe, DP and host present
+        const RAW: u32 = 0x2467_A000;
+        let expected = EnterUsb {
+            usb_mode: UsbMode::Usb4,
+            usb4_drd: true,
+            usb3_drd: false,
+            cable: CableInfo {
+                speed: CableSpeed::Usb4Gen3,
+                cable_type: CableType::Passive,
+                current: CableCurrent::Current5A,
+            },
+            pcie_support: true,
+            dp_support: true,
+            tbt_support: false,
+            host_present: true,
+        };
+        assert_eq!(EnterUsb::try_from(RAW), Ok(expected));
+        assert_eq!(u32::from(expected), RAW);
+    }
+
+    #[test]
+    fn test_enter_usb_invalid() {
+        assert_eq!(EnterUsb::try_from(0x3000_0000), Err(ParseEnterUsbError::InvalidUsbMode));
+        assert_eq!(
+            EnterUsb::try_from(0x20A0_0000),
+            Err(ParseEnterUsbError::InvalidCableSpeed)
+        );
+        assert_eq!(
+            EnterUsb::try_from(0x2002_0000),
+            Err(ParseEnterUsbError::InvalidCableCurrent)
+        );
+    }
+
+    #[test]
+    fn test_cable_info_from_passive_cable() {
+        let vdo = PassiveCableVdo {
+            usb_highest_speed: passive_cable_vdo::UsbHighestSpeed::Usb3p2,
+            vbus_current_handling_capability: passive_cable_vdo::VbusCurrentHandlingCapability::ThreeAmps,
+            maximum_vbus_voltage: passive_cable_vdo::MaximumVbusVoltage::TwentyVolt,
+            cable_termination_type: passive_cable_vdo::CableTerminationType::VconnNotRequired,
+            cable_latency: passive_cable_vdo::CableLatency::LessThan10ns,
+            epr_capable: false,
+            usb_type_c_or_captive: passive_cable_vdo::UsbTypeCPlugOrCaptive::UsbTypeC,
+            firmware_version: 0,
+            hw_version: 0,
+        };
+        assert_eq!(
+            CableInfo::from(&vdo),
+            CableInfo {
+                speed: CableSpeed::Gen2,
+                cable_type: CableType::Passive,
+                current: CableCurrent::Current3A,
+            }
+        );
+    }
+
+    #[test]
+    fn test_cable_info_from_active_cable() {
+        use active_cable_vdo::*;
+
+        let vdo1 = ActiveCableVdo1 {
+            usb_highest_speed: UsbHighestSpeed::Usb4Gen3,
+            soppp_controller_present: false,
+            vbus_through_cable: true,
+            vbus_current_handling_capability: VbusCurrentHandlingCapability::FiveAmps,
+            sbu_type: SbuType::Passive,
+            sbu_supported: true,
+            maximum_vbus_voltage: MaximumVbusVoltage::TwentyV,
+            cable_termination_type: CableTerminationType::OneEndActive,
+            cable_latency: CableLatency::LessThan10ns,
+            epr_capable: false,
+            usb_type_c_or_captive: UsbTypeCOrCaptive::UsbTypeC,
+            firmware_version: 0,
+            hw_version: 0,
+        };
+        let mut vdo2 = ActiveCableVdo2 {
+            usb_gen: UsbGen::Gen2OrHigher,
+            usb4_asymmetric_mode_supported: false,
+            optically_isolated_active_cable: false,
+            usb_lanes_supported: UsbLanesSupported::TwoLanes,
+            usb3p2_supported: true,
+            usb2p0_supported: true,
+            usb2p0_hub_hops_consumed: 0,
+            usb4_supported: true,
+            active_element: ActiveElement::Retimer,
+            physical_connection: PhysicalConnection::Copper,
+            u3_to_u0_transition_mode: U3ToU0TransitionMode::Direct,
+            u3_cld_power: U3CldPower::GreaterThan10Milliwatts,
+            shutdown_temperature: 0,
+            maximum_operating_temperature: 0,
+        };
+        assert_eq!(
+            CableInfo::from((&vdo1, &vdo2)),
+            CableInfo {
+                speed: CableSpeed::Usb4Gen3,
+                cable_type: CableType::ActiveRetimer,
+                current: CableCurrent::Current5A,
+            }
+        );
+
+        vdo2.optically_isolated_active_cable = true;
+        let vdo1 = ActiveCableVdo1 {
+            vbus_through_cable: false,
+            ..vdo1
+        };
+        assert_eq!(
+            CableInfo::from((&vdo1, &vdo2)),
+            CableInfo {
+                speed: CableSpeed::Usb4Gen3,
+                cable_type: CableType::OpticallyIsolated,
+                current: CableCurrent::VbusNotSupported,
+            }
+        );
+    }
+}
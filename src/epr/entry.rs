@@ -0,0 +1,338 @@
+//! Sink EPR mode entry sequencer as defined in 8.3.3.26.2 of the USB PD spec.
+//!
+//! The sequencer tracks the `EPR_Mode` message exchange used to enter EPR mode: the sink sends Enter, the source
+//! responds with Enter Acknowledged, performs cable discovery to check that the cable is EPR capable and finally
+//! responds with Enter Succeeded or Enter Failed. Once in EPR mode the sink must periodically send `EPR_KeepAlive`.
+//!
+//! The sequencer does not perform any IO. Received messages and timer expirations are provided through [`Input`] and
+//! the messages to send and timers to start are returned through [`Output`].
+
+use super::{EnterFailedReason, EprMode};
+use crate::constants::wrappers::Range;
+use crate::constants::T_SINK_EPR_KEEP_ALIVE_MS;
+use crate::constants::{T_ENTER_EPR_MS, T_PS_TRANSITION_EPR_MS, T_PS_TRANSITION_SPR_MS, T_SENDER_RESPONSE_MS};
+
+/// Timers used by the sequencer
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Timer {
+    /// `SenderResponseTimer`, waiting for Enter Acknowledged
+    SenderResponse,
+    /// `SinkEPREnterTimer`, waiting for Enter Succeeded
+    EnterEpr,
+    /// `SinkEPRKeepAliveTimer`, time until the next `EPR_KeepAlive`
+    SinkEprKeepAlive,
+}
+
+impl Timer {
+    /// Returns the timeout range for this timer in milliseconds
+    pub const fn duration_ms(self) -> Range<u16> {
+        match self {
+            Timer::SenderResponse => T_SENDER_RESPONSE_MS,
+            Timer::EnterEpr => T_ENTER_EPR_MS,
+            Timer::SinkEprKeepAlive => T_SINK_EPR_KEEP_ALIVE_MS,
+        }
+    }
+}
+
+/// Sequencer states
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum State {
+    /// Not in EPR mode
+    #[default]
+    Idle,
+    /// Enter sent, waiting for Enter Acknowledged
+    WaitEnterAck,
+    /// Enter acknowledged, waiting for the source to check the cable and respond with Enter Succeeded
+    WaitCableCheck,
+    /// In EPR mode
+    Active,
+}
+
+/// Inputs to the sequencer
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Input {
+    /// Policy request to enter EPR mode
+    Enter {
+        /// EPR sink operational PDP in W
+        pdp_w: u8,
+    },
+    /// Policy request to exit EPR mode
+    Exit,
+    /// `EPR_Mode` message received from the source
+    Received(EprMode),
+    /// Timer expired
+    TimerExpired(Timer),
+    /// Hard reset occurred
+    HardReset,
+}
+
+/// Outputs from the sequencer
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Output {
+    /// Send the `EPR_Mode` message and start the timer, if any, once it has been acknowledged with GoodCRC
+    Send {
+        /// Message to send
+        message: EprMode,
+        /// Timer to start
+        timer: Option<Timer>,
+    },
+    /// Start the timer, this replaces any other timer started by the sequencer
+    StartTimer(Timer),
+    /// EPR mode entered, the sink should wait for `EPR_Source_Capabilities` and start [`Timer::SinkEprKeepAlive`]
+    Entered,
+    /// Send `EPR_KeepAlive` and restart [`Timer::SinkEprKeepAlive`]
+    SendKeepAlive,
+    /// The source failed to enter EPR mode
+    Failed(EnterFailedReason),
+    /// The source did not respond in time, the sink should send `Soft_Reset`
+    SoftReset,
+    /// The source exited EPR mode
+    Exited,
+}
+
+/// Attempted transition that is not allowed by the sequencer
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidTransition {
+    /// The current state of the sequencer
+    pub state: State,
+    /// The input that was attempted
+    pub input: Input,
+}
+
+// Doctest tries to compile the mermaid code as rust so just disable it
+#[cfg_attr(not(doctest), aquamarine::aquamarine)]
+#[cfg_attr(not(doctest), doc = "include_mmd!(\"docs/epr/entry.mmd\")")]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Sequencer {
+    state: State,
+}
+
+impl Sequencer {
+    /// Create a new sequencer in the Idle state
+    pub const fn new() -> Self {
+        Sequencer { state: State::Idle }
+    }
+
+    /// Returns the current state
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Returns true if the port is in EPR mode
+    pub fn in_epr_mode(&self) -> bool {
+        self.state == State::Active
+    }
+
+    /// Returns the `tPSTransition` range in milliseconds that applies to the current mode
+    pub fn ps_transition_ms(&self) -> Range<u16> {
+        if self.in_epr_mode() {
+            T_PS_TRANSITION_EPR_MS
+        } else {
+            T_PS_TRANSITION_SPR_MS
+        }
+    }
+
+    /// Transition the sequencer based on the input and return the resulting action if any.
+    pub fn consume(&mut self, input: Input) -> Result<Option<Output>, InvalidTransition> {
+        use Input::*;
+        use Output::*;
+        use State::*;
+
+        let (next_state, output) = match (self.state, input) {
+            // Hard reset always exits EPR mode
+            (_, HardReset) => (Idle, None),
+
+            // Idle transitions
+            (Idle, Enter { pdp_w }) => (
+                WaitEnterAck,
+                Some(Send {
+                    message: EprMode::Enter { pdp_w },
+                    timer: Some(Timer::SenderResponse),
+                }),
+            ),
+
+            // WaitEnterAck transitions
+            (WaitEnterAck, Received(EprMode::EnterAcknowledged)) => (WaitCableCheck, Some(StartTimer(Timer::EnterEpr))),
+            (WaitEnterAck, TimerExpired(Timer::SenderResponse)) => (Idle, Some(SoftReset)),
+
+            // WaitCableCheck transitions
+            (WaitCableCheck, Received(EprMode::EnterSucceeded)) => (Active, Some(Entered)),
+            (WaitCableCheck, TimerExpired(Timer::EnterEpr)) => (Idle, Some(SoftReset)),
+
+            // Failure can be reported in place of either response
+            (WaitEnterAck | WaitCableCheck, Received(EprMode::EnterFailed(reason))) => (Idle, Some(Failed(reason))),
+
+            // Active transitions
+            (Active, TimerExpired(Timer::SinkEprKeepAlive)) => (Active, Some(SendKeepAlive)),
+            (Active, Exit) => (
+                Idle,
+                Some(Send {
+                    message: EprMode::Exit,
+                    timer: None,
+                }),
+            ),
+            (Active, Received(EprMode::Exit)) => (Idle, Some(Exited)),
+
+            // Invalid transition
+            _ => {
+                return Err(InvalidTransition {
+                    state: self.state,
+                    input,
+                })
+            }
+        };
+
+        self.state = next_state;
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    /// Drive the sequencer into the Active state
+    fn enter(sm: &mut Sequencer) {
+        assert_eq!(
+            sm.consume(Input::Enter { pdp_w: 140 }),
+            Ok(Some(Output::Send {
+                message: EprMode::Enter { pdp_w: 140 },
+                timer: Some(Timer::SenderResponse),
+            }))
+        );
+        assert_eq!(sm.state(), State::WaitEnterAck);
+
+        assert_eq!(
+            sm.consume(Input::Received(EprMode::EnterAcknowledged)),
+            Ok(Some(Output::StartTimer(Timer::EnterEpr)))
+        );
+        assert_eq!(sm.state(), State::WaitCableCheck);
+
+        assert_eq!(
+            sm.consume(Input::Received(EprMode::EnterSucceeded)),
+            Ok(Some(Output::Entered))
+        );
+        assert_eq!(sm.state(), State::Active);
+    }
+
+    #[test]
+    fn test_enter_and_keep_alive() {
+        let mut sm = Sequencer::new();
+        assert_eq!(sm.ps_transition_ms(), T_PS_TRANSITION_SPR_MS);
+
+        enter(&mut sm);
+        assert!(sm.in_epr_mode());
+        assert_eq!(sm.ps_transition_ms(), T_PS_TRANSITION_EPR_MS);
+
+        for _ in 0..3 {
+            assert_eq!(
+                sm.consume(Input::TimerExpired(Timer::SinkEprKeepAlive)),
+                Ok(Some(Output::SendKeepAlive))
+            );
+            assert_eq!(sm.state(), State::Active);
+        }
+    }
+
+    #[test]
+    fn test_enter_failed() {
+        let mut sm = Sequencer::new();
+        sm.state = State::WaitEnterAck;
+        assert_eq!(
+            sm.consume(Input::Received(EprMode::EnterFailed(EnterFailedReason::SourceUnable))),
+            Ok(Some(Output::Failed(EnterFailedReason::SourceUnable)))
+        );
+        assert_eq!(sm.state(), State::Idle);
+
+        // Cable check failure
+        sm.state = State::WaitCableCheck;
+        assert_eq!(
+            sm.consume(Input::Received(EprMode::EnterFailed(
+                EnterFailedReason::CableNotEprCapable
+            ))),
+            Ok(Some(Output::Failed(EnterFailedReason::CableNotEprCapable)))
+        );
+        assert_eq!(sm.state(), State::Idle);
+    }
+
+    #[test]
+    fn test_timeouts() {
+        let mut sm = Sequencer::new();
+        sm.state = State::WaitEnterAck;
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::SenderResponse)),
+            Ok(Some(Output::SoftReset))
+        );
+        assert_eq!(sm.state(), State::Idle);
+
+        sm.state = State::WaitCableCheck;
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::EnterEpr)),
+            Ok(Some(Output::SoftReset))
+        );
+        assert_eq!(sm.state(), State::Idle);
+
+        // Wrong timer for the state
+        sm.state = State::WaitCableCheck;
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::SenderResponse)),
+            Err(InvalidTransition {
+                state: State::WaitCableCheck,
+                input: Input::TimerExpired(Timer::SenderResponse),
+            })
+        );
+        assert_eq!(sm.state(), State::WaitCableCheck);
+    }
+
+    #[test]
+    fn test_exit() {
+        // Sink initiated
+        let mut sm = Sequencer::new();
+        enter(&mut sm);
+        assert_eq!(
+            sm.consume(Input::Exit),
+            Ok(Some(Output::Send {
+                message: EprMode::Exit,
+                timer: None,
+            }))
+        );
+        assert_eq!(sm.state(), State::Idle);
+
+        // Source initiated
+        enter(&mut sm);
+        assert_eq!(sm.consume(Input::Received(EprMode::Exit)), Ok(Some(Output::Exited)));
+        assert_eq!(sm.state(), State::Idle);
+
+        // Hard reset
+        enter(&mut sm);
+        assert_eq!(sm.consume(Input::HardReset), Ok(None));
+        assert_eq!(sm.state(), State::Idle);
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut sm = Sequencer::new();
+        assert_eq!(
+            sm.consume(Input::Received(EprMode::EnterSucceeded)),
+            Err(InvalidTransition {
+                state: State::Idle,
+                input: Input::Received(EprMode::EnterSucceeded),
+            })
+        );
+
+        // Can't enter twice
+        enter(&mut sm);
+        assert_eq!(
+            sm.consume(Input::Enter { pdp_w: 100 }),
+            Err(InvalidTransition {
+                state: State::Active,
+                input: Input::Enter { pdp_w: 100 },
+            })
+        );
+    }
+}
@@ -0,0 +1,181 @@
+//! EPR_Mode data object (EPRMDO) as defined in the USB PD specification 6.4.10
+//!
+//! The EPR_Mode message is used by a sink to enter and exit Extended Power Range (EPR) mode, and by a source to
+//! acknowledge entry and report its result.
+use bitfield::bitfield;
+
+use crate::PdError;
+
+pub mod entry;
+
+/// Error type for EPRMDO conversion, contains the complete undecoded EPRMDO
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidAction(pub u32);
+
+impl From<InvalidAction> for PdError {
+    fn from(_: InvalidAction) -> Self {
+        PdError::InvalidParams
+    }
+}
+
+bitfield! {
+    /// Raw EPRMDO type
+    #[derive(Copy, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    struct EprModeRaw(u32);
+    impl Debug;
+
+    /// Action
+    pub u8, action, set_action: 31, 24;
+    /// Action-specific data
+    pub u8, data, set_data: 23, 16;
+}
+
+/// Reason reported by the source when EPR mode entry fails
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum EnterFailedReason {
+    /// Unknown cause
+    #[default]
+    Unknown,
+    /// Cable is not EPR capable
+    CableNotEprCapable,
+    /// Source failed to become the VCONN source
+    VconnSourceFailed,
+    /// EPR capable bit not set in the RDO
+    RdoNotEprCapable,
+    /// Source is currently unable to enter EPR mode
+    SourceUnable,
+    /// EPR capable bit not set in the PDO
+    PdoNotEprCapable,
+}
+
+impl TryFrom<u8> for EnterFailedReason {
+    type Error = u8;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x00 => Ok(EnterFailedReason::Unknown),
+            0x01 => Ok(EnterFailedReason::CableNotEprCapable),
+            0x02 => Ok(EnterFailedReason::VconnSourceFailed),
+            0x03 => Ok(EnterFailedReason::RdoNotEprCapable),
+            0x04 => Ok(EnterFailedReason::SourceUnable),
+            0x05 => Ok(EnterFailedReason::PdoNotEprCapable),
+            _ => Err(value),
+        }
+    }
+}
+
+impl From<EnterFailedReason> for u8 {
+    fn from(value: EnterFailedReason) -> Self {
+        match value {
+            EnterFailedReason::Unknown => 0x00,
+            EnterFailedReason::CableNotEprCapable => 0x01,
+            EnterFailedReason::VconnSourceFailed => 0x02,
+            EnterFailedReason::RdoNotEprCapable => 0x03,
+            EnterFailedReason::SourceUnable => 0x04,
+            EnterFailedReason::PdoNotEprCapable => 0x05,
+        }
+    }
+}
+
+/// EPR_Mode data object
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum EprMode {
+    /// Sink request to enter EPR mode
+    Enter {
+        /// EPR sink operational PDP in W
+        pdp_w: u8,
+    },
+    /// Source acknowledged the enter request
+    EnterAcknowledged,
+    /// Source successfully entered EPR mode
+    EnterSucceeded,
+    /// Source failed to enter EPR mode
+    EnterFailed(EnterFailedReason),
+    /// Exit EPR mode
+    Exit,
+}
+
+impl TryFrom<u32> for EprMode {
+    type Error = InvalidAction;
+
+    fn try_from(value: u32) -> Result<Self, Self::Error> {
+        let raw = EprModeRaw(value);
+        match raw.action() {
+            0x01 => Ok(EprMode::Enter { pdp_w: raw.data() }),
+            0x02 => Ok(EprMode::EnterAcknowledged),
+            0x03 => Ok(EprMode::EnterSucceeded),
+            0x04 => EnterFailedReason::try_from(raw.data())
+                .map(EprMode::EnterFailed)
+                .map_err(|_| InvalidAction(value)),
+            0x05 => Ok(EprMode::Exit),
+            _ => Err(InvalidAction(value)),
+        }
+    }
+}
+
+impl From<EprMode> for u32 {
+    fn from(value: EprMode) -> Self {
+        let mut raw = EprModeRaw(0);
+
+        match value {
+            EprMode::Enter { pdp_w } => {
+                raw.set_action(0x01);
+                raw.set_data(pdp_w);
+            }
+            EprMode::EnterAcknowledged => raw.set_action(0x02),
+            EprMode::EnterSucceeded => raw.set_action(0x03),
+            EprMode::EnterFailed(reason) => {
+                raw.set_action(0x04);
+                raw.set_data(reason.into());
+            }
+            EprMode::Exit => raw.set_action(0x05),
+        }
+
+        raw.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_enter_roundtrip() {
+        // Enter with a 140 W operational PDP
+        const RAW_ENTER: u32 = 0x018C0000;
+        let epr_mode = EprMode::try_from(RAW_ENTER);
+        assert_eq!(epr_mode, Ok(EprMode::Enter { pdp_w: 140 }));
+        assert_eq!(u32::from(EprMode::Enter { pdp_w: 140 }), RAW_ENTER);
+    }
+
+    #[test]
+    fn test_actions_roundtrip() {
+        for (raw, expected) in [
+            (0x02000000, EprMode::EnterAcknowledged),
+            (0x03000000, EprMode::EnterSucceeded),
+            (0x04000000, EprMode::EnterFailed(EnterFailedReason::Unknown)),
+            (0x04010000, EprMode::EnterFailed(EnterFailedReason::CableNotEprCapable)),
+            (0x04020000, EprMode::EnterFailed(EnterFailedReason::VconnSourceFailed)),
+            (0x04030000, EprMode::EnterFailed(EnterFailedReason::RdoNotEprCapable)),
+            (0x04040000, EprMode::EnterFailed(EnterFailedReason::SourceUnable)),
+            (0x04050000, EprMode::EnterFailed(EnterFailedReason::PdoNotEprCapable)),
+            (0x05000000, EprMode::Exit),
+        ] {
+            assert_eq!(EprMode::try_from(raw), Ok(expected));
+            assert_eq!(u32::from(expected), raw);
+        }
+    }
+
+    #[test]
+    fn test_invalid() {
+        // Reserved action
+        assert_eq!(EprMode::try_from(0x06000000), Err(InvalidAction(0x06000000)));
+        assert_eq!(EprMode::try_from(0x00000000), Err(InvalidAction(0x00000000)));
+        // Reserved failure reason
+        assert_eq!(EprMode::try_from(0x04060000), Err(InvalidAction(0x04060000)));
+    }
+}
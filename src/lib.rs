@@ -1,9 +1,27 @@
 #![no_std]
 
 pub mod ado;
+pub mod battery;
+pub mod bist;
+pub mod budget;
+pub mod capabilities_extended;
 pub mod constants;
+pub mod controller;
+pub mod country;
+pub mod enter_usb;
+pub mod epr;
+pub mod manufacturer_info;
 pub mod pdinfo;
 pub mod pdo;
+pub mod phy;
+pub mod policy;
+pub mod protocol;
+pub mod revision;
+#[cfg(any(test, feature = "std"))]
+pub mod sim;
+pub mod source_info;
+pub mod status;
+pub mod tcpci;
 pub mod type_c;
 pub mod ucsi;
 pub mod usb;
@@ -126,6 +144,8 @@ pub enum PdError {
     InProgress,
     /// Command was valid, but could not be executed at this time
     Rejected,
+    /// Type-C error recovery is required
+    ErrorRecovery,
 }
 
 /// Top-level error type
@@ -188,3 +208,21 @@ impl<BE> From<PdError> for Error<BE> {
         Error::Pd(err)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use core::future::Future;
+    use core::pin::pin;
+    use core::task::{Context, Poll, Waker};
+
+    /// Poll a future to completion, shared by all tests whose mocks never return pending
+    pub(crate) fn block_on<F: Future>(future: F) -> F::Output {
+        let mut future = pin!(future);
+        let mut context = Context::from_waker(Waker::noop());
+        loop {
+            if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+                return output;
+            }
+        }
+    }
+}
@@ -0,0 +1,275 @@
+//! Manufacturer info messages as defined in the USB PD specification
+//!
+//! This covers the Get_Manufacturer_Info (6.5.6) and Manufacturer_Info (6.5.7) data blocks.
+use crate::battery::{BatteryRef, InvalidBatteryRef};
+use crate::usb::ProductId;
+use crate::PdError;
+
+/// Length of the Get_Manufacturer_Info data block in bytes
+pub const GET_MANUFACTURER_INFO_LEN: usize = 2;
+/// Length of the fixed portion of the Manufacturer_Info data block in bytes
+pub const MANUFACTURER_INFO_HEADER_LEN: usize = 4;
+/// Maximum length of the manufacturer string in bytes
+pub const MAX_MANUFACTURER_STRING_LEN: usize = 22;
+/// Maximum length of the Manufacturer_Info data block in bytes
+pub const MAX_MANUFACTURER_INFO_LEN: usize = MANUFACTURER_INFO_HEADER_LEN + MAX_MANUFACTURER_STRING_LEN;
+
+/// Null-terminated manufacturer string returned when the requested target or reference is not supported
+pub const NOT_SUPPORTED_STRING: &[u8] = b"Not Supported\0";
+
+/// Returns the string up to the first null terminator
+fn trim_nul(string: &[u8]) -> &[u8] {
+    let len = string.iter().position(|byte| *byte == 0).unwrap_or(string.len());
+    string.get(..len).unwrap_or_default()
+}
+
+/// Errors that can occur when parsing a Get_Manufacturer_Info data block
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ParseError {
+    /// The manufacturer info target contains an invalid value
+    InvalidTarget(u8),
+    /// The battery reference contains an invalid value
+    InvalidBatteryRef(u8),
+}
+
+impl From<InvalidBatteryRef> for ParseError {
+    fn from(value: InvalidBatteryRef) -> Self {
+        ParseError::InvalidBatteryRef(value.0)
+    }
+}
+
+impl From<ParseError> for PdError {
+    fn from(_: ParseError) -> Self {
+        PdError::InvalidParams
+    }
+}
+
+/// Get_Manufacturer_Info data block
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum GetManufacturerInfo {
+    /// Port or cable plug
+    PortOrCablePlug,
+    /// Battery
+    Battery(BatteryRef),
+}
+
+impl TryFrom<[u8; GET_MANUFACTURER_INFO_LEN]> for GetManufacturerInfo {
+    type Error = ParseError;
+
+    fn try_from(value: [u8; GET_MANUFACTURER_INFO_LEN]) -> Result<Self, Self::Error> {
+        let [target, battery_ref] = value;
+        match target {
+            0x0 => Ok(GetManufacturerInfo::PortOrCablePlug),
+            0x1 => Ok(GetManufacturerInfo::Battery(battery_ref.try_into()?)),
+            _ => Err(ParseError::InvalidTarget(target)),
+        }
+    }
+}
+
+impl From<GetManufacturerInfo> for [u8; GET_MANUFACTURER_INFO_LEN] {
+    fn from(value: GetManufacturerInfo) -> Self {
+        match value {
+            GetManufacturerInfo::PortOrCablePlug => [0x0, 0x0],
+            GetManufacturerInfo::Battery(battery_ref) => [0x1, battery_ref.into()],
+        }
+    }
+}
+
+/// Manufacturer_Info data block
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ManufacturerInfo {
+    /// USB vendor ID
+    pub usb_vendor_id: u16,
+    /// USB product ID
+    pub usb_product_id: ProductId,
+    /// Manufacturer string storage, only the first `string_len` bytes are valid
+    string: [u8; MAX_MANUFACTURER_STRING_LEN],
+    /// Length of the manufacturer string including any null terminator
+    string_len: usize,
+}
+
+impl ManufacturerInfo {
+    /// Create a new manufacturer info, fails if the string is longer than [`MAX_MANUFACTURER_STRING_LEN`]
+    pub fn new(usb_vendor_id: u16, usb_product_id: ProductId, string: &[u8]) -> Result<Self, PdError> {
+        let mut storage = [0u8; MAX_MANUFACTURER_STRING_LEN];
+        storage
+            .get_mut(..string.len())
+            .ok_or(PdError::InvalidParams)?
+            .copy_from_slice(string);
+
+        Ok(ManufacturerInfo {
+            usb_vendor_id,
+            usb_product_id,
+            string: storage,
+            string_len: string.len(),
+        })
+    }
+
+    /// Create the response sent when the requested target or reference is not supported
+    pub fn not_supported() -> Self {
+        let mut string = [0u8; MAX_MANUFACTURER_STRING_LEN];
+        for (dst, src) in string.iter_mut().zip(NOT_SUPPORTED_STRING) {
+            *dst = *src;
+        }
+
+        ManufacturerInfo {
+            usb_vendor_id: 0,
+            usb_product_id: ProductId(0),
+            string,
+            string_len: NOT_SUPPORTED_STRING.len(),
+        }
+    }
+
+    /// Returns true if this is a response to an unsupported target or reference
+    pub fn is_not_supported(&self) -> bool {
+        self.usb_vendor_id == 0 && self.usb_product_id.0 == 0 && self.string() == trim_nul(NOT_SUPPORTED_STRING)
+    }
+
+    /// Returns the manufacturer string as received, including any null terminator
+    fn raw_string(&self) -> &[u8] {
+        self.string.get(..self.string_len).unwrap_or_default()
+    }
+
+    /// Returns the manufacturer string up to the first null terminator
+    pub fn string(&self) -> &[u8] {
+        trim_nul(self.raw_string())
+    }
+
+    /// Returns the manufacturer string as a `str` if it is valid ASCII
+    pub fn as_str(&self) -> Option<&str> {
+        let string = self.string();
+        if string.is_ascii() {
+            core::str::from_utf8(string).ok()
+        } else {
+            None
+        }
+    }
+
+    /// Returns the length of the encoded data block in bytes
+    pub fn encoded_len(&self) -> usize {
+        MANUFACTURER_INFO_HEADER_LEN + self.string_len
+    }
+
+    /// Encode the data block into the given buffer, returns the number of bytes written
+    pub fn encode_into_slice(&self, buf: &mut [u8]) -> Result<usize, PdError> {
+        let buf = buf.get_mut(..self.encoded_len()).ok_or(PdError::InvalidParams)?;
+        let (vid, rest) = buf.split_at_mut(2);
+        let (pid, string) = rest.split_at_mut(2);
+        vid.copy_from_slice(&self.usb_vendor_id.to_le_bytes());
+        pid.copy_from_slice(&self.usb_product_id.0.to_le_bytes());
+        string.copy_from_slice(self.raw_string());
+        Ok(buf.len())
+    }
+}
+
+impl TryFrom<&[u8]> for ManufacturerInfo {
+    type Error = PdError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let (header, string) = value
+            .split_first_chunk::<MANUFACTURER_INFO_HEADER_LEN>()
+            .ok_or(PdError::InvalidParams)?;
+        let [vid0, vid1, pid0, pid1] = *header;
+        ManufacturerInfo::new(
+            u16::from_le_bytes([vid0, vid1]),
+            ProductId(u16::from_le_bytes([pid0, pid1])),
+            string,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_get_manufacturer_info() {
+        assert_eq!(
+            GetManufacturerInfo::try_from([0x0, 0x0]),
+            Ok(GetManufacturerInfo::PortOrCablePlug)
+        );
+        assert_eq!(
+            GetManufacturerInfo::try_from([0x1, 0x5]),
+            Ok(GetManufacturerInfo::Battery(BatteryRef::HotSwappable(1)))
+        );
+        assert_eq!(
+            <[u8; GET_MANUFACTURER_INFO_LEN]>::from(GetManufacturerInfo::Battery(BatteryRef::Fixed(2))),
+            [0x1, 0x2]
+        );
+        assert_eq!(
+            GetManufacturerInfo::try_from([0x2, 0x0]),
+            Err(ParseError::InvalidTarget(0x2))
+        );
+        assert_eq!(
+            GetManufacturerInfo::try_from([0x1, 0x8]),
+            Err(ParseError::InvalidBatteryRef(0x8))
+        );
+    }
+
+    #[test]
+    fn test_manufacturer_info_roundtrip() {
+        const RAW: [u8; 9] = [0x5E, 0x04, 0x34, 0x12, b'A', b'c', b'm', b'e', b'!'];
+
+        let info = ManufacturerInfo::try_from(RAW.as_slice());
+        assert_eq!(info, ManufacturerInfo::new(0x045E, ProductId(0x1234), b"Acme!"));
+        assert_eq!(info.map(|info| info.as_str() == Some("Acme!")), Ok(true));
+
+        let mut buf = [0u8; MAX_MANUFACTURER_INFO_LEN];
+        let len = info.and_then(|info| info.encode_into_slice(&mut buf));
+        assert_eq!(len, Ok(RAW.len()));
+        assert_eq!(buf.get(..RAW.len()), Some(RAW.as_slice()));
+    }
+
+    #[test]
+    fn test_manufacturer_info_invalid() {
+        // Too short
+        assert_eq!(
+            ManufacturerInfo::try_from([0x5E, 0x04, 0x34].as_slice()),
+            Err(PdError::InvalidParams)
+        );
+
+        // String too long
+        assert_eq!(
+            ManufacturerInfo::try_from([0u8; MAX_MANUFACTURER_INFO_LEN + 1].as_slice()),
+            Err(PdError::InvalidParams)
+        );
+
+        // Buffer too small
+        let mut buf = [0u8; MANUFACTURER_INFO_HEADER_LEN];
+        assert_eq!(
+            ManufacturerInfo::not_supported().encode_into_slice(&mut buf),
+            Err(PdError::InvalidParams)
+        );
+    }
+
+    #[test]
+    fn test_manufacturer_info_not_supported_decode() {
+        const RAW: [u8; 18] = [
+            0x00, 0x00, 0x00, 0x00, b'N', b'o', b't', b' ', b'S', b'u', b'p', b'p', b'o', b'r', b't', b'e', b'd', 0x00,
+        ];
+
+        let info = ManufacturerInfo::try_from(RAW.as_slice());
+        assert_eq!(info.map(|info| info.is_not_supported()), Ok(true));
+        assert_eq!(info.map(|info| info.as_str() == Some("Not Supported")), Ok(true));
+        assert_eq!(info, Ok(ManufacturerInfo::not_supported()));
+
+        let mut buf = [0u8; MAX_MANUFACTURER_INFO_LEN];
+        let len = ManufacturerInfo::not_supported().encode_into_slice(&mut buf);
+        assert_eq!(len, Ok(RAW.len()));
+        assert_eq!(buf.get(..RAW.len()), Some(RAW.as_slice()));
+    }
+
+    #[test]
+    fn test_manufacturer_info_not_supported() {
+        let info = ManufacturerInfo::not_supported();
+        assert!(info.is_not_supported());
+        assert_eq!(info.as_str(), Some("Not Supported"));
+        assert_eq!(
+            info.encoded_len(),
+            MANUFACTURER_INFO_HEADER_LEN + NOT_SUPPORTED_STRING.len()
+        );
+    }
+}
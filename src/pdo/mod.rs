@@ -4,9 +4,11 @@
 //! a type-safe version.
 use crate::PdError;
 
-mod rdo;
+pub(crate) mod rdo;
+pub mod select;
 pub mod sink;
 pub mod source;
+pub mod validate;
 
 pub use rdo::Rdo;
 
@@ -36,6 +38,28 @@ pub const MW1000_UNIT: u32 = 1000;
 /// Length of a PDO in bytes
 pub const PDO_LEN: usize = 4;
 
+/// Returns the power in mW for a voltage in mV and a current in mA
+pub fn power_mw(voltage_mv: u16, current_ma: u16) -> u32 {
+    voltage_mv as u32 * current_ma as u32 / 1000
+}
+
+/// Returns the source power data object power (PDP) in mW
+///
+/// This is the highest power of any fixed supply or the PDP of an EPR AVS. Returns `None` if `capabilities` contains
+/// neither.
+pub fn source_pdp_mw(capabilities: &[source::Pdo]) -> Option<u32> {
+    capabilities
+        .iter()
+        .filter(|pdo| {
+            matches!(
+                pdo,
+                source::Pdo::Fixed(_) | source::Pdo::Augmented(source::Apdo::EprAvs(_))
+            )
+        })
+        .map(|pdo| pdo.max_power_mw())
+        .max()
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum PdoKind {
@@ -154,6 +178,11 @@ pub trait Common: Copy + Clone + PartialEq + Eq + Into<Pdo> + Into<u32> {
     fn max_voltage_mv(&self) -> u16;
     /// Min voltage in mV
     fn min_voltage_mv(&self) -> u16;
+    /// Max power in mW, the PDP for EPR AVS and the highest power of either range for SPR AVS
+    fn max_power_mw(&self) -> u32 {
+        let pdo: Pdo = (*self).into();
+        pdo.max_power_mw()
+    }
 }
 
 /// This trait is for PDO values that have a definite power role. The power role of a PDO
@@ -210,6 +239,13 @@ impl Common for Pdo {
             Pdo::Sink(pdo) => pdo.min_voltage_mv(),
         }
     }
+
+    fn max_power_mw(&self) -> u32 {
+        match self {
+            Pdo::Source(pdo) => pdo.max_power_mw(),
+            Pdo::Sink(pdo) => pdo.max_power_mw(),
+        }
+    }
 }
 
 impl From<Pdo> for u32 {
@@ -253,6 +289,11 @@ impl Contract {
         Contract { pdo, rdo }
     }
 
+    /// Returns true if the contract is for an EPR capability
+    pub fn is_epr(&self) -> bool {
+        validate::is_epr(self.rdo.object_position(), self.pdo)
+    }
+
     /// Create a new contract from a sink PDO and RDO
     pub fn from_sink(pdo: sink::Pdo, rdo: Rdo) -> Self {
         Contract {
@@ -283,6 +324,29 @@ impl Contract {
             Rdo::Pps(data) => Some(data.operating_current_ma),
         }
     }
+
+    /// Returns the operating power in mW, uses maximum voltage for variable PDO calculation
+    pub fn operating_power_mw(&self) -> u32 {
+        match self.rdo {
+            Rdo::Fixed(data) | Rdo::Variable(data) => power_mw(self.pdo.max_voltage_mv(), data.operating_current_ma),
+            Rdo::Battery(data) => data.operating_power_mw,
+            Rdo::Avs(data) => power_mw(data.output_voltage_mv, data.operating_current_ma),
+            Rdo::Pps(data) => power_mw(data.output_voltage_mv, data.operating_current_ma),
+        }
+    }
+
+    /// Returns the maximum operating power in mW, uses maximum voltage for variable PDO calculation
+    ///
+    /// Programmable supply requests have no separate maximum, this is the same as [`Self::operating_power_mw`].
+    pub fn max_operating_power_mw(&self) -> u32 {
+        match self.rdo {
+            Rdo::Fixed(data) | Rdo::Variable(data) => {
+                power_mw(self.pdo.max_voltage_mv(), data.max_operating_current_ma)
+            }
+            Rdo::Battery(data) => data.max_operating_power_mw,
+            Rdo::Avs(_) | Rdo::Pps(_) => self.operating_power_mw(),
+        }
+    }
 }
 
 #[cfg(test)]
@@ -432,4 +496,172 @@ mod test {
 
         assert_eq!(contract.operating_current_ma(), Some(2500));
     }
+
+    #[test]
+    fn test_max_power_mw() {
+        let pdos = [
+            source::Pdo::Fixed(source::FixedData {
+                voltage_mv: 20000,
+                current_ma: 5000,
+                ..Default::default()
+            }),
+            source::Pdo::Variable(source::VariableData {
+                max_voltage_mv: 12000,
+                min_voltage_mv: 9000,
+                max_current_ma: 2000,
+            }),
+            source::Pdo::Battery(source::BatteryData {
+                max_voltage_mv: 12000,
+                min_voltage_mv: 9000,
+                max_power_mw: 30000,
+            }),
+            source::Pdo::Augmented(source::Apdo::SprPps(source::SprPpsData {
+                pps_power_limited: false,
+                max_voltage_mv: 11000,
+                min_voltage_mv: 3300,
+                max_current_ma: 3000,
+            })),
+            source::Pdo::Augmented(source::Apdo::SprAvs(source::SprAvsData {
+                peak_current: Default::default(),
+                max_current_15v_ma: 3000,
+                max_current_20v_ma: 2250,
+            })),
+            source::Pdo::Augmented(source::Apdo::EprAvs(source::EprAvsData {
+                peak_current: Default::default(),
+                max_voltage_mv: 48000,
+                min_voltage_mv: 15000,
+                pdp_mw: 140000,
+            })),
+        ];
+        let expected = [100000, 24000, 30000, 33000, 45000, 140000];
+        for (pdo, expected) in pdos.iter().zip(expected) {
+            assert_eq!(pdo.max_power_mw(), expected);
+            assert_eq!(Pdo::Source(*pdo).max_power_mw(), expected);
+        }
+
+        let pdo = sink::Pdo::Fixed(FixedData {
+            operational_current_ma: 1500,
+            voltage_mv: 9000,
+            dual_role_power: false,
+            higher_capability: false,
+            unconstrained_power: false,
+            usb_comms_capable: false,
+            dual_role_data: false,
+            frs_required_current: sink::FrsRequiredCurrent::None,
+        });
+        assert_eq!(pdo.max_power_mw(), 13500);
+        assert_eq!(power_mw(u16::MAX, u16::MAX), 4294836);
+    }
+
+    #[test]
+    fn test_contract_operating_power_mw_fixed() {
+        let contract = Contract::from_sink(
+            sink::Pdo::Fixed(FixedData {
+                operational_current_ma: 3000,
+                voltage_mv: 9000,
+                dual_role_power: false,
+                higher_capability: false,
+                unconstrained_power: false,
+                usb_comms_capable: false,
+                dual_role_data: false,
+                frs_required_current: sink::FrsRequiredCurrent::Current1A5,
+            }),
+            Rdo::Fixed(rdo::FixedVarData {
+                operating_current_ma: 1500,
+                max_operating_current_ma: 2000,
+                object_position: 0,
+                capability_mismatch: false,
+                usb_comm_capable: false,
+                no_usb_suspend: false,
+                unchunked_extended_messages_support: false,
+                epr_capable: false,
+            }),
+        );
+
+        assert_eq!(contract.operating_power_mw(), 13500);
+        assert_eq!(contract.max_operating_power_mw(), 18000);
+    }
+
+    #[test]
+    fn test_contract_operating_power_mw_battery() {
+        let contract = Contract::from_sink(
+            sink::Pdo::Battery(BatteryData {
+                max_voltage_mv: 20000,
+                min_voltage_mv: 15000,
+                operational_power_mw: 60000,
+            }),
+            Rdo::Battery(rdo::BatteryData {
+                operating_power_mw: 40000,
+                max_operating_power_mw: 45000,
+                object_position: 0,
+                capability_mismatch: false,
+                usb_comm_capable: false,
+                no_usb_suspend: false,
+                unchunked_extended_messages_support: false,
+                epr_capable: false,
+            }),
+        );
+
+        assert_eq!(contract.operating_power_mw(), 40000);
+        assert_eq!(contract.max_operating_power_mw(), 45000);
+    }
+
+    #[test]
+    fn test_contract_operating_power_mw_pps() {
+        let contract = Contract::from_sink(
+            sink::Pdo::Augmented(sink::Apdo::SprPps(SprPpsData {
+                max_voltage_mv: 21000,
+                min_voltage_mv: 15000,
+                max_current_ma: 3000,
+            })),
+            Rdo::Pps(rdo::PpsData {
+                operating_current_ma: 2500,
+                output_voltage_mv: 20000,
+                object_position: 0,
+                capability_mismatch: false,
+                usb_comm_capable: false,
+                no_usb_suspend: false,
+                unchunked_extended_messages_support: false,
+                epr_capable: false,
+            }),
+        );
+
+        assert_eq!(contract.operating_power_mw(), 50000);
+        assert_eq!(contract.max_operating_power_mw(), 50000);
+    }
+
+    #[test]
+    fn test_source_pdp_mw() {
+        let fixed = |voltage_mv, current_ma| {
+            source::Pdo::Fixed(source::FixedData {
+                voltage_mv,
+                current_ma,
+                ..Default::default()
+            })
+        };
+        let pps = source::Pdo::Augmented(source::Apdo::SprPps(source::SprPpsData {
+            pps_power_limited: false,
+            max_voltage_mv: 21000,
+            min_voltage_mv: 3300,
+            max_current_ma: 5000,
+        }));
+        let epr_avs = source::Pdo::Augmented(source::Apdo::EprAvs(source::EprAvsData {
+            peak_current: Default::default(),
+            max_voltage_mv: 48000,
+            min_voltage_mv: 15000,
+            pdp_mw: 140000,
+        }));
+
+        // PPS does not contribute to the PDP
+        assert_eq!(
+            source_pdp_mw(&[fixed(5000, 3000), fixed(20000, 3250), pps]),
+            Some(65000)
+        );
+        assert_eq!(
+            source_pdp_mw(&[fixed(5000, 3000), fixed(20000, 5000), fixed(28000, 5000), epr_avs]),
+            Some(140000)
+        );
+        assert_eq!(source_pdp_mw(&[pps]), None);
+        assert_eq!(source_pdp_mw(&[]), None);
+    }
 }
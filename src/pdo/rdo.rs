@@ -31,11 +31,25 @@ impl Rdo {
             PdoKind::Battery => Rdo::Battery(BatteryRaw(rdo).into()),
             PdoKind::Augmented => match pdo.apdo_kind()? {
                 ApdoKind::SprPps => Rdo::Pps(PpsRaw(rdo).into()),
-                ApdoKind::EprAvs => Rdo::Pps(PpsRaw(rdo).into()),
-                ApdoKind::SprAvs => Rdo::Avs(AvsRaw(rdo).into()),
+                ApdoKind::SprAvs | ApdoKind::EprAvs => Rdo::Avs(AvsRaw(rdo).into()),
             },
         })
     }
+
+    /// Returns the object position of a raw RDO, this is at the same location for all RDO types
+    pub fn raw_object_position(rdo: u32) -> u8 {
+        FixedVarRaw(rdo).object_position()
+    }
+
+    /// Returns the object position
+    pub fn object_position(&self) -> u8 {
+        match self {
+            Rdo::Fixed(data) | Rdo::Variable(data) => data.object_position,
+            Rdo::Battery(data) => data.object_position,
+            Rdo::Pps(data) => data.object_position,
+            Rdo::Avs(data) => data.object_position,
+        }
+    }
 }
 
 impl From<Rdo> for u32 {
@@ -358,9 +372,27 @@ impl From<AvsData> for u32 {
 }
 
 #[cfg(test)]
-mod tests {
+pub(crate) mod tests {
     use super::*;
 
+    /// Fixed RDO for object position 1 with 3 A operating and max current
+    pub(crate) const FIXED_RDO: u32 = 0x1004_B12C;
+
+    /// Returns [`FIXED_RDO`] decoded at the given object position
+    pub(crate) fn fixed_rdo(object_position: u8) -> Rdo {
+        let mut raw = FixedVarRaw(FIXED_RDO);
+        raw.set_object_position(object_position);
+        Rdo::Fixed(raw.into())
+    }
+
+    #[test]
+    fn test_fixed_rdo_fixture() {
+        assert_eq!(
+            Rdo::for_pdo(FIXED_RDO, source::Pdo::Fixed(Default::default())),
+            Some(fixed_rdo(1))
+        );
+    }
+
     #[test]
     fn test_fixed_roundtrip() {
         const RAW_FIXED: u32 = 0x3540C864;
@@ -498,5 +530,17 @@ mod tests {
         });
         assert_eq!(rdo, expected);
         assert_eq!(u32::from(expected), RAW_AVS);
+
+        // EPR AVS uses the same RDO
+        let rdo = Rdo::for_pdo(
+            RAW_AVS,
+            sink::Pdo::Augmented(sink::Apdo::EprAvs(sink::EprAvsData {
+                max_voltage_mv: 0,
+                min_voltage_mv: 0,
+                pdp_mw: 0,
+            })),
+        )
+        .unwrap();
+        assert_eq!(rdo, expected);
     }
 }
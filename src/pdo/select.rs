@@ -0,0 +1,612 @@
+//! Sink selection of a source capability as described in the USB PD specification 8.3.3.3.3
+//!
+//! [`SinkPolicy::select`] evaluates every source PDO against the sink's requirements and builds the request for the
+//! best match. Programmable supplies are only considered if enabled in the policy, the requested voltage is then
+//! chosen within the overlap of the supply range and the policy voltage window. EPR capabilities are only considered
+//! in EPR mode.
+use super::rdo::{AvsData, BatteryData, FixedVarData, PpsData};
+use super::validate::is_epr;
+use super::{source, Contract, Rdo, MA10_UNIT, MA50_UNIT, MV20_UNIT, MW250_UNIT};
+
+/// Voltage step used for AVS requests in mV
+const AVS_STEP_MV: u16 = 100;
+/// Maximum current that can be requested from an EPR AVS in mA
+const EPR_AVS_MAX_CURRENT_MA: u16 = 5000;
+/// Highest valid object position
+const MAX_OBJECT_POSITION: u8 = 15;
+
+/// Preference between candidates that satisfy the policy
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Preference {
+    /// Highest power, the lowest voltage on a tie
+    #[default]
+    MaxPower,
+    /// Lowest voltage, the highest power on a tie
+    LowestVoltage,
+}
+
+/// Sink requirements used to select a source capability
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SinkPolicy {
+    /// Minimum acceptable voltage in mV
+    pub min_voltage_mv: u16,
+    /// Maximum acceptable voltage in mV
+    pub max_voltage_mv: u16,
+    /// Minimum current needed in mA, capabilities offering less result in a capability mismatch
+    pub min_current_ma: u16,
+    /// Maximum current drawn in mA, this is the operating current requested
+    pub max_current_ma: u16,
+    /// Preference between candidates
+    pub preference: Preference,
+    /// Consider SPR PPS capabilities
+    pub use_pps: bool,
+    /// Consider SPR and EPR AVS capabilities
+    pub use_avs: bool,
+    /// In EPR mode, EPR capabilities are ignored otherwise
+    pub epr_mode: bool,
+    /// USB communications capable, copied into the request
+    pub usb_comm_capable: bool,
+    /// No USB suspend, copied into the request
+    pub no_usb_suspend: bool,
+    /// Unchunked extended messages supported, copied into the request
+    pub unchunked_extended_messages_support: bool,
+    /// EPR capable, copied into the request
+    pub epr_capable: bool,
+}
+
+impl Default for SinkPolicy {
+    fn default() -> Self {
+        Self {
+            min_voltage_mv: 5000,
+            max_voltage_mv: 5000,
+            min_current_ma: 0,
+            max_current_ma: 3000,
+            preference: Preference::MaxPower,
+            use_pps: false,
+            use_avs: false,
+            epr_mode: false,
+            usb_comm_capable: false,
+            no_usb_suspend: false,
+            unchunked_extended_messages_support: false,
+            epr_capable: false,
+        }
+    }
+}
+
+/// Selected capability and the request for it
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Selection {
+    /// Object position of the selected PDO, starting at 1
+    pub object_position: u8,
+    /// Selected PDO
+    pub pdo: source::Pdo,
+    /// Request for the selected PDO
+    pub rdo: Rdo,
+    /// Voltage in mV, the minimum voltage for variable and battery supplies
+    pub voltage_mv: u16,
+    /// Requested operating current in mA
+    pub current_ma: u16,
+    /// True if no capability satisfies the policy, the request has the capability mismatch bit set
+    pub capability_mismatch: bool,
+}
+
+impl Selection {
+    /// Returns the contract that results if the request is accepted
+    pub fn contract(&self) -> Contract {
+        Contract::from_source(self.pdo, self.rdo)
+    }
+}
+
+/// Operating point offered by a source PDO
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Candidate {
+    /// Object position
+    position: u8,
+    /// Source PDO
+    pdo: source::Pdo,
+    /// Voltage in mV, requested voltage for programmable supplies
+    voltage_mv: u16,
+    /// Maximum voltage in mV, differs from `voltage_mv` for variable and battery supplies
+    max_voltage_mv: u16,
+    /// Current available at `voltage_mv` in mA
+    available_ma: u16,
+}
+
+impl SinkPolicy {
+    /// Returns true if the voltage range is within the policy voltage window
+    fn contains(&self, min_voltage_mv: u16, max_voltage_mv: u16) -> bool {
+        min_voltage_mv >= self.min_voltage_mv && max_voltage_mv <= self.max_voltage_mv
+    }
+
+    /// Returns the voltage to request from a programmable supply, `None` if its range is outside the window
+    fn programmable_voltage_mv(&self, min_voltage_mv: u16, max_voltage_mv: u16, step_mv: u16) -> Option<u16> {
+        let low = min_voltage_mv.max(self.min_voltage_mv).div_ceil(step_mv) * step_mv;
+        let high = max_voltage_mv.min(self.max_voltage_mv) / step_mv * step_mv;
+        if low > high {
+            return None;
+        }
+
+        Some(match self.preference {
+            Preference::MaxPower => high,
+            Preference::LowestVoltage => low,
+        })
+    }
+
+    /// Returns the current that would be drawn from a candidate in mA
+    fn drawn_ma(&self, candidate: &Candidate) -> u16 {
+        candidate.available_ma.min(self.max_current_ma)
+    }
+
+    /// Returns the power that would be drawn from a candidate in mW
+    fn drawn_mw(&self, candidate: &Candidate) -> u32 {
+        candidate.voltage_mv as u32 * self.drawn_ma(candidate) as u32 / 1000
+    }
+
+    /// Returns true if `candidate` is preferred over `best`
+    fn is_better(&self, candidate: &Candidate, best: &Candidate) -> bool {
+        let power = self.drawn_mw(candidate).cmp(&self.drawn_mw(best));
+        // Lower voltage compares greater
+        let voltage = best.voltage_mv.cmp(&candidate.voltage_mv);
+        let ordering = match self.preference {
+            Preference::MaxPower => power.then(voltage),
+            Preference::LowestVoltage => voltage.then(power),
+        };
+        // Earlier positions win ties
+        ordering.is_gt()
+    }
+
+    /// Returns the operating points offered by a PDO that are within the voltage window
+    fn candidates(&self, position: u8, pdo: source::Pdo) -> [Option<Candidate>; 2] {
+        let candidate = |voltage_mv, max_voltage_mv, available_ma| Candidate {
+            position,
+            pdo,
+            voltage_mv,
+            max_voltage_mv,
+            available_ma,
+        };
+
+        match pdo {
+            source::Pdo::Fixed(data) => [
+                self.contains(data.voltage_mv, data.voltage_mv)
+                    .then(|| candidate(data.voltage_mv, data.voltage_mv, data.current_ma)),
+                None,
+            ],
+            source::Pdo::Variable(data) => [
+                self.contains(data.min_voltage_mv, data.max_voltage_mv)
+                    .then(|| candidate(data.min_voltage_mv, data.max_voltage_mv, data.max_current_ma)),
+                None,
+            ],
+            source::Pdo::Battery(data) => [
+                // The current available is limited by the power at the highest voltage
+                (self.contains(data.min_voltage_mv, data.max_voltage_mv) && data.max_voltage_mv > 0).then(|| {
+                    let available_ma = data.max_power_mw * 1000 / data.max_voltage_mv as u32;
+                    candidate(
+                        data.min_voltage_mv,
+                        data.max_voltage_mv,
+                        available_ma.min(u16::MAX as u32) as u16,
+                    )
+                }),
+                None,
+            ],
+            source::Pdo::Augmented(source::Apdo::SprPps(data)) if self.use_pps => [
+                self.programmable_voltage_mv(data.min_voltage_mv, data.max_voltage_mv, MV20_UNIT)
+                    .map(|voltage_mv| candidate(voltage_mv, voltage_mv, data.max_current_ma)),
+                None,
+            ],
+            source::Pdo::Augmented(source::Apdo::SprAvs(data)) if self.use_avs => [
+                self.programmable_voltage_mv(9000, 15000, AVS_STEP_MV)
+                    .map(|voltage_mv| candidate(voltage_mv, voltage_mv, data.max_current_15v_ma)),
+                (data.max_current_20v_ma > 0)
+                    .then(|| self.programmable_voltage_mv(15000, 20000, AVS_STEP_MV))
+                    .flatten()
+                    .map(|voltage_mv| candidate(voltage_mv, voltage_mv, data.max_current_20v_ma)),
+            ],
+            source::Pdo::Augmented(source::Apdo::EprAvs(data)) if self.use_avs => [
+                self.programmable_voltage_mv(data.min_voltage_mv, data.max_voltage_mv, AVS_STEP_MV)
+                    .filter(|voltage_mv| *voltage_mv > 0)
+                    .map(|voltage_mv| {
+                        let available_ma = (data.pdp_mw * 1000 / voltage_mv as u32).min(EPR_AVS_MAX_CURRENT_MA as u32);
+                        candidate(voltage_mv, voltage_mv, available_ma as u16)
+                    }),
+                None,
+            ],
+            source::Pdo::Augmented(_) => [None, None],
+        }
+    }
+
+    /// Build the request for a candidate
+    fn request(&self, candidate: &Candidate, capability_mismatch: bool) -> Selection {
+        let current_ma = self.drawn_ma(candidate);
+        // A sink with a capability mismatch reports the current it actually needs
+        let max_current_ma = if capability_mismatch {
+            self.max_current_ma
+        } else {
+            current_ma
+        };
+
+        let fixed_var = FixedVarData {
+            object_position: candidate.position,
+            capability_mismatch,
+            usb_comm_capable: self.usb_comm_capable,
+            no_usb_suspend: self.no_usb_suspend,
+            unchunked_extended_messages_support: self.unchunked_extended_messages_support,
+            epr_capable: self.epr_capable,
+            operating_current_ma: current_ma / MA10_UNIT * MA10_UNIT,
+            max_operating_current_ma: max_current_ma / MA10_UNIT * MA10_UNIT,
+        };
+        let power_mw =
+            |current_ma: u16| candidate.max_voltage_mv as u32 * current_ma as u32 / 1000 / MW250_UNIT * MW250_UNIT;
+        let pps = PpsData {
+            object_position: candidate.position,
+            capability_mismatch,
+            usb_comm_capable: self.usb_comm_capable,
+            no_usb_suspend: self.no_usb_suspend,
+            unchunked_extended_messages_support: self.unchunked_extended_messages_support,
+            epr_capable: self.epr_capable,
+            output_voltage_mv: candidate.voltage_mv,
+            operating_current_ma: current_ma / MA50_UNIT * MA50_UNIT,
+        };
+
+        let rdo = match candidate.pdo {
+            source::Pdo::Fixed(_) => Rdo::Fixed(fixed_var),
+            source::Pdo::Variable(_) => Rdo::Variable(fixed_var),
+            source::Pdo::Battery(_) => Rdo::Battery(BatteryData {
+                object_position: candidate.position,
+                capability_mismatch,
+                usb_comm_capable: self.usb_comm_capable,
+                no_usb_suspend: self.no_usb_suspend,
+                unchunked_extended_messages_support: self.unchunked_extended_messages_support,
+                epr_capable: self.epr_capable,
+                operating_power_mw: power_mw(current_ma),
+                max_operating_power_mw: power_mw(max_current_ma),
+            }),
+            source::Pdo::Augmented(source::Apdo::SprPps(_)) => Rdo::Pps(pps),
+            source::Pdo::Augmented(source::Apdo::SprAvs(_) | source::Apdo::EprAvs(_)) => Rdo::Avs(AvsData {
+                object_position: pps.object_position,
+                capability_mismatch: pps.capability_mismatch,
+                usb_comm_capable: pps.usb_comm_capable,
+                no_usb_suspend: pps.no_usb_suspend,
+                unchunked_extended_messages_support: pps.unchunked_extended_messages_support,
+                epr_capable: pps.epr_capable,
+                output_voltage_mv: pps.output_voltage_mv,
+                operating_current_ma: pps.operating_current_ma,
+            }),
+        };
+
+        Selection {
+            object_position: candidate.position,
+            pdo: candidate.pdo,
+            rdo,
+            voltage_mv: candidate.voltage_mv,
+            current_ma,
+            capability_mismatch,
+        }
+    }
+
+    /// Select a source capability and build the request for it
+    ///
+    /// The best capability offering at least [`SinkPolicy::min_current_ma`] is selected. If there is none the best
+    /// capability within the voltage window is requested with the capability mismatch bit set, if no capability is
+    /// within the window vSafe5V is requested. Returns `None` if `capabilities` is empty or does not start with the
+    /// vSafe5V fixed supply.
+    pub fn select(&self, capabilities: &[source::Pdo]) -> Option<Selection> {
+        let vsafe5v = match capabilities.first()? {
+            source::Pdo::Fixed(data) => Candidate {
+                position: 1,
+                pdo: source::Pdo::Fixed(*data),
+                voltage_mv: data.voltage_mv,
+                max_voltage_mv: data.voltage_mv,
+                available_ma: data.current_ma,
+            },
+            _ => return None,
+        };
+
+        let mut best: Option<Candidate> = None;
+        let mut best_mismatch: Option<Candidate> = None;
+        let candidates = capabilities
+            .iter()
+            .zip(1..=MAX_OBJECT_POSITION)
+            .filter(|(pdo, position)| self.epr_mode || !is_epr(*position, **pdo))
+            .flat_map(|(pdo, position)| self.candidates(position, *pdo))
+            .flatten();
+        for candidate in candidates {
+            let slot = if candidate.available_ma >= self.min_current_ma {
+                &mut best
+            } else {
+                &mut best_mismatch
+            };
+            if slot.is_none_or(|best| self.is_better(&candidate, &best)) {
+                *slot = Some(candidate);
+            }
+        }
+
+        Some(match (best, best_mismatch) {
+            (Some(best), _) => self.request(&best, false),
+            (None, Some(best)) => self.request(&best, true),
+            (None, None) => self.request(&vsafe5v, true),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixed(voltage_mv: u16, current_ma: u16) -> source::Pdo {
+        source::Pdo::Fixed(source::FixedData {
+            voltage_mv,
+            current_ma,
+            ..Default::default()
+        })
+    }
+
+    fn pps(min_voltage_mv: u16, max_voltage_mv: u16, max_current_ma: u16) -> source::Pdo {
+        source::Pdo::Augmented(source::Apdo::SprPps(source::SprPpsData {
+            pps_power_limited: false,
+            max_voltage_mv,
+            min_voltage_mv,
+            max_current_ma,
+        }))
+    }
+
+    fn caps() -> [source::Pdo; 5] {
+        [
+            fixed(5000, 3000),
+            fixed(9000, 3000),
+            fixed(15000, 3000),
+            fixed(20000, 2250),
+            pps(3300, 11000, 3000),
+        ]
+    }
+
+    fn policy(min_voltage_mv: u16, max_voltage_mv: u16, min_current_ma: u16, max_current_ma: u16) -> SinkPolicy {
+        SinkPolicy {
+            min_voltage_mv,
+            max_voltage_mv,
+            min_current_ma,
+            max_current_ma,
+            usb_comm_capable: true,
+            ..Default::default()
+        }
+    }
+
+    fn fixed_rdo(object_position: u8, operating_current_ma: u16, max_operating_current_ma: u16, mismatch: bool) -> Rdo {
+        Rdo::Fixed(FixedVarData {
+            object_position,
+            capability_mismatch: mismatch,
+            usb_comm_capable: true,
+            no_usb_suspend: false,
+            unchunked_extended_messages_support: false,
+            epr_capable: false,
+            operating_current_ma,
+            max_operating_current_ma,
+        })
+    }
+
+    #[test]
+    fn test_max_power() {
+        // 15 V at 3 A and 20 V at 2.25 A are both 45 W, the lower voltage wins the tie
+        let selection = policy(5000, 20000, 1000, 3000).select(&caps());
+        assert_eq!(selection.map(|s| s.rdo), Some(fixed_rdo(3, 3000, 3000, false)));
+        assert_eq!(selection.map(|s| (s.voltage_mv, s.current_ma)), Some((15000, 3000)));
+
+        // 20 V at 2 A is more power than 15 V at 2 A
+        let selection = policy(5000, 20000, 1000, 2000).select(&caps());
+        assert_eq!(selection.map(|s| s.rdo), Some(fixed_rdo(4, 2000, 2000, false)));
+        let selection = policy(5000, 12000, 1000, 3000).select(&caps());
+        assert_eq!(selection.map(|s| s.rdo), Some(fixed_rdo(2, 3000, 3000, false)));
+    }
+
+    #[test]
+    fn test_lowest_voltage() {
+        let policy = SinkPolicy {
+            preference: Preference::LowestVoltage,
+            ..policy(9000, 20000, 1500, 1500)
+        };
+        let selection = policy.select(&caps());
+        assert_eq!(selection.map(|s| s.rdo), Some(fixed_rdo(2, 1500, 1500, false)));
+        assert_eq!(selection.map(|s| s.contract().pdo), Some(fixed(9000, 3000).into()));
+    }
+
+    #[test]
+    fn test_capability_mismatch() {
+        // 20 V is only offered at 2.25 A
+        let selection = policy(20000, 20000, 3000, 3000).select(&caps());
+        assert_eq!(selection.map(|s| s.rdo), Some(fixed_rdo(4, 2250, 3000, true)));
+        assert_eq!(selection.map(|s| s.capability_mismatch), Some(true));
+
+        // Nothing within the window, fall back to vSafe5V
+        let selection = policy(28000, 48000, 1000, 3000).select(&caps());
+        assert_eq!(selection.map(|s| s.rdo), Some(fixed_rdo(1, 3000, 3000, true)));
+
+        assert_eq!(policy(5000, 5000, 0, 3000).select(&[]), None);
+        assert_eq!(policy(5000, 5000, 0, 3000).select(&[pps(3300, 11000, 3000)]), None);
+    }
+
+    #[test]
+    fn test_pps() {
+        let policy = SinkPolicy {
+            use_pps: true,
+            preference: Preference::LowestVoltage,
+            ..policy(3310, 4210, 2000, 2990)
+        };
+        let selection = policy.select(&caps());
+        assert_eq!(
+            selection.map(|s| s.rdo),
+            Some(Rdo::Pps(PpsData {
+                object_position: 5,
+                capability_mismatch: false,
+                usb_comm_capable: true,
+                no_usb_suspend: false,
+                unchunked_extended_messages_support: false,
+                epr_capable: false,
+                output_voltage_mv: 3320,
+                operating_current_ma: 2950,
+            }))
+        );
+
+        let policy = SinkPolicy {
+            preference: Preference::MaxPower,
+            ..policy
+        };
+        let selection = policy.select(&caps());
+        assert_eq!(selection.map(|s| (s.object_position, s.voltage_mv)), Some((5, 4200)));
+
+        // PPS is ignored unless enabled
+        let policy = SinkPolicy {
+            use_pps: false,
+            ..policy
+        };
+        assert_eq!(policy.select(&caps()).map(|s| s.object_position), Some(1));
+
+        // A 21 V PPS is an SPR capability
+        let policy = SinkPolicy {
+            use_pps: true,
+            min_voltage_mv: 5000,
+            max_voltage_mv: 21000,
+            ..policy
+        };
+        let selection = policy.select(&[fixed(5000, 3000), pps(3300, 21000, 3000)]);
+        assert_eq!(selection.map(|s| (s.object_position, s.voltage_mv)), Some((2, 21000)));
+    }
+
+    #[test]
+    fn test_avs() {
+        let caps = [
+            fixed(5000, 3000),
+            source::Pdo::Augmented(source::Apdo::SprAvs(source::SprAvsData {
+                peak_current: Default::default(),
+                max_current_15v_ma: 3000,
+                max_current_20v_ma: 2250,
+            })),
+            source::Pdo::Augmented(source::Apdo::EprAvs(source::EprAvsData {
+                peak_current: Default::default(),
+                max_voltage_mv: 48000,
+                min_voltage_mv: 15000,
+                pdp_mw: 140000,
+            })),
+        ];
+        let policy = SinkPolicy {
+            use_avs: true,
+            epr_mode: true,
+            ..policy(12050, 19990, 2000, 3000)
+        };
+        let selection = policy.select(&caps);
+        assert_eq!(
+            selection.map(|s| s.rdo),
+            Some(Rdo::Avs(AvsData {
+                object_position: 3,
+                capability_mismatch: false,
+                usb_comm_capable: true,
+                no_usb_suspend: false,
+                unchunked_extended_messages_support: false,
+                epr_capable: false,
+                output_voltage_mv: 19900,
+                operating_current_ma: 3000,
+            }))
+        );
+
+        // SPR AVS at 15 V and 3 A is more power than 19.9 V at 2.25 A
+        let selection = policy.select(caps.get(..2).unwrap_or_default());
+        assert_eq!(
+            selection.map(|s| (s.object_position, s.voltage_mv, s.current_ma)),
+            Some((2, 15000, 3000))
+        );
+
+        // EPR AVS is ignored outside EPR mode
+        let policy = SinkPolicy {
+            epr_mode: false,
+            ..policy
+        };
+        let selection = policy.select(&caps);
+        assert_eq!(
+            selection.map(|s| (s.object_position, s.voltage_mv, s.current_ma)),
+            Some((2, 15000, 3000))
+        );
+    }
+
+    #[test]
+    fn test_epr_mode() {
+        let caps = [
+            fixed(5000, 3000),
+            fixed(9000, 3000),
+            fixed(15000, 3000),
+            fixed(20000, 3000),
+            fixed(28000, 5000),
+            fixed(36000, 5000),
+            fixed(48000, 5000),
+            fixed(20000, 5000),
+        ];
+        let policy = policy(5000, 48000, 1000, 5000);
+        let selection = policy.select(&caps);
+        assert_eq!(selection.map(|s| s.rdo), Some(fixed_rdo(4, 3000, 3000, false)));
+
+        let epr_policy = SinkPolicy {
+            epr_mode: true,
+            ..policy
+        };
+        let selection = epr_policy.select(&caps);
+        assert_eq!(selection.map(|s| s.rdo), Some(fixed_rdo(7, 5000, 5000, false)));
+
+        // Positions from 8 are only valid in EPR mode, even at SPR voltages
+        let policy = SinkPolicy {
+            max_voltage_mv: 20000,
+            ..policy
+        };
+        assert_eq!(policy.select(&caps).map(|s| s.object_position), Some(4));
+        let epr_policy = SinkPolicy {
+            epr_mode: true,
+            ..policy
+        };
+        assert_eq!(epr_policy.select(&caps).map(|s| s.object_position), Some(8));
+    }
+
+    #[test]
+    fn test_variable_battery() {
+        let caps = [
+            fixed(5000, 900),
+            source::Pdo::Variable(source::VariableData {
+                max_voltage_mv: 12000,
+                min_voltage_mv: 9000,
+                max_current_ma: 2000,
+            }),
+            source::Pdo::Battery(source::BatteryData {
+                max_voltage_mv: 12000,
+                min_voltage_mv: 9000,
+                max_power_mw: 30000,
+            }),
+        ];
+        let selection = policy(5000, 12000, 1000, 3000).select(&caps);
+        assert_eq!(
+            selection.map(|s| s.rdo),
+            Some(Rdo::Battery(BatteryData {
+                object_position: 3,
+                capability_mismatch: false,
+                usb_comm_capable: true,
+                no_usb_suspend: false,
+                unchunked_extended_messages_support: false,
+                epr_capable: false,
+                operating_power_mw: 30000,
+                max_operating_power_mw: 30000,
+            }))
+        );
+
+        let selection = policy(5000, 12000, 1000, 2000).select(&caps);
+        assert_eq!(
+            selection.map(|s| s.rdo),
+            Some(Rdo::Variable(FixedVarData {
+                object_position: 2,
+                capability_mismatch: false,
+                usb_comm_capable: true,
+                no_usb_suspend: false,
+                unchunked_extended_messages_support: false,
+                epr_capable: false,
+                operating_current_ma: 2000,
+                max_operating_current_ma: 2000,
+            }))
+        );
+    }
+}
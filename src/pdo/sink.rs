@@ -97,6 +97,22 @@ impl Common for Pdo {
             },
         }
     }
+
+    fn max_power_mw(&self) -> u32 {
+        match self {
+            Pdo::Fixed(data) => power_mw(data.voltage_mv, data.operational_current_ma),
+            Pdo::Battery(data) => data.operational_power_mw,
+            Pdo::Variable(data) => power_mw(data.max_voltage_mv, data.operational_current_ma),
+            Pdo::Augmented(apdo) => match apdo {
+                Apdo::SprPps(data) => power_mw(data.max_voltage_mv, data.max_current_ma),
+                Apdo::EprAvs(data) => data.pdp_mw,
+                // Highest power of the 9-15V and 15-20V ranges
+                Apdo::SprAvs(data) => {
+                    power_mw(15000, data.max_current_15v_ma).max(power_mw(20000, data.max_current_20v_ma))
+                }
+            },
+        }
+    }
 }
 
 impl From<Pdo> for super::Pdo {
@@ -150,6 +166,22 @@ pub enum FrsRequiredCurrent {
     Current3A,
 }
 
+impl FrsRequiredCurrent {
+    /// Returns true if a new source advertising the given Type-C current can meet this fast role swap requirement
+    ///
+    /// A sink that does not support fast role swap can never be satisfied.
+    pub fn is_met_by(self, current: crate::type_c::Current) -> bool {
+        use crate::type_c::Current;
+
+        match self {
+            FrsRequiredCurrent::None => false,
+            FrsRequiredCurrent::Default => true,
+            FrsRequiredCurrent::Current1A5 => matches!(current, Current::Current1A5 | Current::Current3A0),
+            FrsRequiredCurrent::Current3A => current == Current::Current3A0,
+        }
+    }
+}
+
 impl From<u8> for FrsRequiredCurrent {
     fn from(value: u8) -> Self {
         // NOTE: If this mask changes, the panic safety comment below must be reevaluated
@@ -669,6 +701,18 @@ mod tests {
         assert_eq!(u32::from(expected), RAW_FIXED);
     }
 
+    #[test]
+    fn test_frs_required_current() {
+        use crate::type_c::Current;
+
+        assert!(!FrsRequiredCurrent::None.is_met_by(Current::Current3A0));
+        assert!(FrsRequiredCurrent::Default.is_met_by(Current::UsbDefault));
+        assert!(!FrsRequiredCurrent::Current1A5.is_met_by(Current::UsbDefault));
+        assert!(FrsRequiredCurrent::Current1A5.is_met_by(Current::Current3A0));
+        assert!(!FrsRequiredCurrent::Current3A.is_met_by(Current::Current1A5));
+        assert!(FrsRequiredCurrent::Current3A.is_met_by(Current::Current3A0));
+    }
+
     #[test]
     fn test_battery_roundtrip() {
         const RAW_BATTERY: u32 = 0x40300801;
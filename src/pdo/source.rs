@@ -97,6 +97,22 @@ impl Common for Pdo {
             },
         }
     }
+
+    fn max_power_mw(&self) -> u32 {
+        match self {
+            Pdo::Fixed(data) => power_mw(data.voltage_mv, data.current_ma),
+            Pdo::Battery(data) => data.max_power_mw,
+            Pdo::Variable(data) => power_mw(data.max_voltage_mv, data.max_current_ma),
+            Pdo::Augmented(apdo) => match apdo {
+                Apdo::SprPps(data) => power_mw(data.max_voltage_mv, data.max_current_ma),
+                Apdo::EprAvs(data) => data.pdp_mw,
+                // Highest power of the 9-15V and 15-20V ranges
+                Apdo::SprAvs(data) => {
+                    power_mw(15000, data.max_current_15v_ma).max(power_mw(20000, data.max_current_20v_ma))
+                }
+            },
+        }
+    }
 }
 
 impl From<Pdo> for super::Pdo {
@@ -146,6 +162,18 @@ pub enum PeakCurrent {
     Pct150,
 }
 
+impl PeakCurrent {
+    /// Returns the 10 ms @ 50% duty cycle overload as a percentage of nominal current
+    pub const fn overload_pct(self) -> u16 {
+        match self {
+            PeakCurrent::Pct100 => 100,
+            PeakCurrent::Pct110 => 110,
+            PeakCurrent::Pct125 => 125,
+            PeakCurrent::Pct150 => 150,
+        }
+    }
+}
+
 impl From<u8> for PeakCurrent {
     fn from(value: u8) -> Self {
         // NOTE: If this mask changes, the panic safety comment below must be reevaluated
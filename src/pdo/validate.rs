@@ -0,0 +1,391 @@
+//! Source validation of a received request as described in the USB PD specification 8.3.3.2.6
+//!
+//! [`validate`] decodes a raw RDO against the PDO at its object position and checks that the request can be met by
+//! that PDO. This only covers the advertised capabilities, the device policy manager must still check the request
+//! against the power that is presently available.
+use super::{power_mw, source, ApdoKind, Common, Contract, PdoKind, Rdo, MV100_UNIT, MV20_UNIT};
+
+/// Highest object position of an SPR capability, EPR capabilities start at the following position
+const MAX_SPR_OBJECT_POSITION: u8 = 7;
+/// Highest voltage of an SPR fixed, variable or battery supply in mV
+const MAX_SPR_VOLTAGE_MV: u16 = 20000;
+/// Lowest voltage of an SPR AVS in mV
+const SPR_AVS_MIN_VOLTAGE_MV: u16 = 9000;
+/// Highest voltage of the first SPR AVS range in mV
+const SPR_AVS_15V_MAX_VOLTAGE_MV: u16 = 15000;
+
+/// Reason a request is rejected
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Reject {
+    /// Object position is zero or past the last capability
+    InvalidObjectPosition(u8),
+    /// The RDO could not be decoded for the PDO at its object position
+    InvalidRdo,
+    /// Operating current is above the maximum current of the PDO
+    CurrentAboveMax {
+        /// Requested operating current in mA
+        requested_ma: u16,
+        /// Maximum current of the PDO in mA
+        max_ma: u16,
+    },
+    /// Operating power is above the maximum power of the PDO
+    PowerAboveMax {
+        /// Requested operating power in mW
+        requested_mw: u32,
+        /// Maximum power of the PDO in mW
+        max_mw: u32,
+    },
+    /// Output voltage is outside the range of a programmable supply
+    VoltageOutOfRange {
+        /// Requested output voltage in mV
+        requested_mv: u16,
+        /// Minimum voltage of the APDO in mV
+        min_mv: u16,
+        /// Maximum voltage of the APDO in mV
+        max_mv: u16,
+    },
+    /// Output voltage is not a multiple of the programmable supply voltage step
+    ///
+    /// Only returned for AVS requests. The PPS RDO encodes the output voltage in 20 mV units, which is the PPS voltage
+    /// step, so every decoded PPS request is aligned.
+    UnalignedVoltage {
+        /// Requested output voltage in mV
+        requested_mv: u16,
+        /// Voltage step of the APDO in mV
+        step_mv: u16,
+    },
+    /// The request is for an EPR capability while not in EPR mode
+    EprOutsideEprMode,
+}
+
+/// Returns true if the PDO at `position` can only be requested in EPR mode
+///
+/// APDOs are classified by type, an SPR PPS goes up to 21 V.
+pub(crate) fn is_epr(position: u8, pdo: impl Common) -> bool {
+    position > MAX_SPR_OBJECT_POSITION
+        || match pdo.kind() {
+            PdoKind::Fixed | PdoKind::Variable | PdoKind::Battery => pdo.max_voltage_mv() > MAX_SPR_VOLTAGE_MV,
+            PdoKind::Augmented => pdo.apdo_kind() == Some(ApdoKind::EprAvs),
+        }
+}
+
+/// Check that `requested_ma` does not exceed `max_ma`
+fn check_current(requested_ma: u16, max_ma: u16) -> Result<(), Reject> {
+    if requested_ma > max_ma {
+        Err(Reject::CurrentAboveMax { requested_ma, max_ma })
+    } else {
+        Ok(())
+    }
+}
+
+/// Check that `requested_mw` does not exceed `max_mw`
+fn check_power(requested_mw: u32, max_mw: u32) -> Result<(), Reject> {
+    if requested_mw > max_mw {
+        Err(Reject::PowerAboveMax { requested_mw, max_mw })
+    } else {
+        Ok(())
+    }
+}
+
+/// Check that a programmable supply output voltage is within range and aligned to the voltage step
+fn check_voltage(requested_mv: u16, min_mv: u16, max_mv: u16, step_mv: u16) -> Result<(), Reject> {
+    if !(min_mv..=max_mv).contains(&requested_mv) {
+        Err(Reject::VoltageOutOfRange {
+            requested_mv,
+            min_mv,
+            max_mv,
+        })
+    } else if requested_mv % step_mv != 0 {
+        Err(Reject::UnalignedVoltage { requested_mv, step_mv })
+    } else {
+        Ok(())
+    }
+}
+
+/// Validate a raw RDO against the advertised source capabilities
+///
+/// `epr_mode` must be true if the capabilities were sent in `EPR_Source_Capabilities` after entering EPR mode.
+/// Returns the contract that results from accepting the request.
+pub fn validate(capabilities: &[source::Pdo], rdo: u32, epr_mode: bool) -> Result<Contract, Reject> {
+    let position = Rdo::raw_object_position(rdo);
+    let pdo = *position
+        .checked_sub(1)
+        .and_then(|index| capabilities.get(index as usize))
+        .ok_or(Reject::InvalidObjectPosition(position))?;
+    let rdo = Rdo::for_pdo(rdo, pdo).ok_or(Reject::InvalidRdo)?;
+
+    if !epr_mode && is_epr(position, pdo) {
+        return Err(Reject::EprOutsideEprMode);
+    }
+
+    match (pdo, rdo) {
+        (source::Pdo::Fixed(pdo), Rdo::Fixed(rdo)) => check_current(rdo.operating_current_ma, pdo.current_ma),
+        (source::Pdo::Variable(pdo), Rdo::Variable(rdo)) => check_current(rdo.operating_current_ma, pdo.max_current_ma),
+        (source::Pdo::Battery(pdo), Rdo::Battery(rdo)) => check_power(rdo.operating_power_mw, pdo.max_power_mw),
+        (source::Pdo::Augmented(source::Apdo::SprPps(apdo)), Rdo::Pps(rdo)) => {
+            // Always aligned, see [`Reject::UnalignedVoltage`]
+            check_voltage(
+                rdo.output_voltage_mv,
+                apdo.min_voltage_mv,
+                apdo.max_voltage_mv,
+                MV20_UNIT,
+            )?;
+            check_current(rdo.operating_current_ma, apdo.max_current_ma)
+        }
+        (source::Pdo::Augmented(source::Apdo::SprAvs(apdo)), Rdo::Avs(rdo)) => {
+            check_voltage(
+                rdo.output_voltage_mv,
+                SPR_AVS_MIN_VOLTAGE_MV,
+                pdo.max_voltage_mv(),
+                MV100_UNIT,
+            )?;
+            let max_ma = if rdo.output_voltage_mv <= SPR_AVS_15V_MAX_VOLTAGE_MV {
+                apdo.max_current_15v_ma
+            } else {
+                apdo.max_current_20v_ma
+            };
+            check_current(rdo.operating_current_ma, max_ma)
+        }
+        (source::Pdo::Augmented(source::Apdo::EprAvs(apdo)), Rdo::Avs(rdo)) => {
+            check_voltage(
+                rdo.output_voltage_mv,
+                apdo.min_voltage_mv,
+                apdo.max_voltage_mv,
+                MV100_UNIT,
+            )?;
+            let requested_mw = power_mw(rdo.output_voltage_mv, rdo.operating_current_ma);
+            check_power(requested_mw, apdo.pdp_mw)
+        }
+        _ => Err(Reject::InvalidRdo),
+    }?;
+
+    Ok(Contract::from_source(pdo, rdo))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdo::rdo::{AvsData, BatteryData, FixedVarData, PpsData};
+
+    fn fixed(voltage_mv: u16, current_ma: u16) -> source::Pdo {
+        source::Pdo::Fixed(source::FixedData {
+            voltage_mv,
+            current_ma,
+            ..Default::default()
+        })
+    }
+
+    fn caps() -> [source::Pdo; 8] {
+        [
+            fixed(5000, 3000),
+            fixed(9000, 3000),
+            source::Pdo::Battery(source::BatteryData {
+                max_voltage_mv: 12000,
+                min_voltage_mv: 9000,
+                max_power_mw: 30000,
+            }),
+            source::Pdo::Variable(source::VariableData {
+                max_voltage_mv: 12000,
+                min_voltage_mv: 9000,
+                max_current_ma: 2000,
+            }),
+            source::Pdo::Augmented(source::Apdo::SprPps(source::SprPpsData {
+                pps_power_limited: false,
+                max_voltage_mv: 11000,
+                min_voltage_mv: 3300,
+                max_current_ma: 3000,
+            })),
+            source::Pdo::Augmented(source::Apdo::SprAvs(source::SprAvsData {
+                peak_current: Default::default(),
+                max_current_15v_ma: 3000,
+                max_current_20v_ma: 2250,
+            })),
+            source::Pdo::default(),
+            source::Pdo::Augmented(source::Apdo::EprAvs(source::EprAvsData {
+                peak_current: Default::default(),
+                max_voltage_mv: 48000,
+                min_voltage_mv: 15000,
+                pdp_mw: 140000,
+            })),
+        ]
+    }
+
+    fn fixed_var(object_position: u8, operating_current_ma: u16) -> FixedVarData {
+        FixedVarData {
+            object_position,
+            capability_mismatch: false,
+            usb_comm_capable: false,
+            no_usb_suspend: false,
+            unchunked_extended_messages_support: false,
+            epr_capable: false,
+            operating_current_ma,
+            max_operating_current_ma: operating_current_ma,
+        }
+    }
+
+    fn pps(object_position: u8, output_voltage_mv: u16, operating_current_ma: u16) -> PpsData {
+        PpsData {
+            object_position,
+            capability_mismatch: false,
+            usb_comm_capable: false,
+            no_usb_suspend: false,
+            unchunked_extended_messages_support: false,
+            epr_capable: false,
+            output_voltage_mv,
+            operating_current_ma,
+        }
+    }
+
+    fn avs(object_position: u8, output_voltage_mv: u16, operating_current_ma: u16) -> AvsData {
+        AvsData {
+            object_position,
+            capability_mismatch: false,
+            usb_comm_capable: false,
+            no_usb_suspend: false,
+            unchunked_extended_messages_support: false,
+            epr_capable: false,
+            output_voltage_mv,
+            operating_current_ma,
+        }
+    }
+
+    fn validate_rdo(rdo: Rdo, epr_mode: bool) -> Result<Rdo, Reject> {
+        validate(&caps(), rdo.into(), epr_mode).map(|contract| contract.rdo)
+    }
+
+    #[test]
+    fn test_accept() {
+        let requests = [
+            Rdo::Fixed(fixed_var(2, 3000)),
+            Rdo::Battery(BatteryData {
+                object_position: 3,
+                capability_mismatch: false,
+                usb_comm_capable: false,
+                no_usb_suspend: false,
+                unchunked_extended_messages_support: false,
+                epr_capable: false,
+                operating_power_mw: 30000,
+                max_operating_power_mw: 30000,
+            }),
+            Rdo::Variable(fixed_var(4, 2000)),
+            Rdo::Pps(pps(5, 3320, 3000)),
+            Rdo::Avs(avs(6, 9000, 3000)),
+            Rdo::Avs(avs(6, 20000, 2250)),
+        ];
+        for rdo in requests {
+            assert_eq!(validate_rdo(rdo, false), Ok(rdo));
+        }
+
+        let rdo = Rdo::Avs(avs(8, 48000, 2900));
+        assert_eq!(validate_rdo(rdo, true), Ok(rdo));
+    }
+
+    #[test]
+    fn test_invalid_object_position() {
+        assert_eq!(
+            validate_rdo(Rdo::Fixed(fixed_var(0, 3000)), false),
+            Err(Reject::InvalidObjectPosition(0))
+        );
+        assert_eq!(
+            validate_rdo(Rdo::Fixed(fixed_var(9, 3000)), true),
+            Err(Reject::InvalidObjectPosition(9))
+        );
+        assert_eq!(
+            validate(&[], Rdo::Fixed(fixed_var(1, 3000)).into(), false),
+            Err(Reject::InvalidObjectPosition(1))
+        );
+    }
+
+    #[test]
+    fn test_current_above_max() {
+        assert_eq!(
+            validate_rdo(Rdo::Fixed(fixed_var(1, 3500)), false),
+            Err(Reject::CurrentAboveMax {
+                requested_ma: 3500,
+                max_ma: 3000
+            })
+        );
+        assert_eq!(
+            validate_rdo(Rdo::Variable(fixed_var(4, 2010)), false),
+            Err(Reject::CurrentAboveMax {
+                requested_ma: 2010,
+                max_ma: 2000
+            })
+        );
+        // The 15-20 V AVS range has a lower current limit
+        assert_eq!(
+            validate_rdo(Rdo::Avs(avs(6, 15100, 3000)), false),
+            Err(Reject::CurrentAboveMax {
+                requested_ma: 3000,
+                max_ma: 2250
+            })
+        );
+    }
+
+    #[test]
+    fn test_power_above_max() {
+        assert_eq!(
+            validate_rdo(Rdo::Avs(avs(8, 48000, 3000)), true),
+            Err(Reject::PowerAboveMax {
+                requested_mw: 144000,
+                max_mw: 140000
+            })
+        );
+    }
+
+    #[test]
+    fn test_voltage() {
+        assert_eq!(
+            validate_rdo(Rdo::Pps(pps(5, 11020, 1000)), false),
+            Err(Reject::VoltageOutOfRange {
+                requested_mv: 11020,
+                min_mv: 3300,
+                max_mv: 11000
+            })
+        );
+        assert_eq!(
+            validate_rdo(Rdo::Avs(avs(6, 5000, 1000)), false),
+            Err(Reject::VoltageOutOfRange {
+                requested_mv: 5000,
+                min_mv: 9000,
+                max_mv: 20000
+            })
+        );
+        assert_eq!(
+            validate_rdo(Rdo::Avs(avs(6, 9020, 1000)), false),
+            Err(Reject::UnalignedVoltage {
+                requested_mv: 9020,
+                step_mv: 100
+            })
+        );
+    }
+
+    #[test]
+    fn test_epr_outside_epr_mode() {
+        assert_eq!(
+            validate_rdo(Rdo::Avs(avs(8, 28000, 1000)), false),
+            Err(Reject::EprOutsideEprMode)
+        );
+
+        let caps = [fixed(5000, 3000), fixed(28000, 5000)];
+        let rdo = Rdo::Fixed(fixed_var(2, 5000));
+        assert_eq!(validate(&caps, rdo.into(), false), Err(Reject::EprOutsideEprMode));
+        assert!(validate(&caps, rdo.into(), true).is_ok());
+    }
+
+    #[test]
+    fn test_spr_pps_21v() {
+        let caps = [
+            fixed(5000, 3000),
+            source::Pdo::Augmented(source::Apdo::SprPps(source::SprPpsData {
+                pps_power_limited: false,
+                max_voltage_mv: 21000,
+                min_voltage_mv: 3300,
+                max_current_ma: 3000,
+            })),
+        ];
+        let rdo = Rdo::Pps(pps(2, 21000, 3000));
+        assert_eq!(validate(&caps, rdo.into(), false).map(|contract| contract.rdo), Ok(rdo));
+    }
+}
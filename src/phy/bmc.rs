@@ -0,0 +1,97 @@
+//! Biphase Mark Coding as defined in the USB PD specification 5.8.1
+//!
+//! Every bit starts with a transition, a one has an additional transition in the middle of the unit interval.
+use crate::PdError;
+
+/// Number of bits in the preamble
+pub const PREAMBLE_BITS: usize = 64;
+
+/// Returns an iterator over the preamble bits, alternating and starting with a zero
+pub fn preamble() -> impl Iterator<Item = bool> {
+    (0..PREAMBLE_BITS).map(|bit| bit % 2 != 0)
+}
+
+/// BMC encoder, tracks the line level between calls
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct BmcEncoder {
+    /// Line level at the end of the last encoded bit
+    level: bool,
+}
+
+impl BmcEncoder {
+    /// Create a new encoder, the line starts low
+    pub const fn new() -> Self {
+        Self { level: false }
+    }
+
+    /// Returns the line level at the end of the last encoded bit
+    pub fn level(&self) -> bool {
+        self.level
+    }
+
+    /// Encode bits into line samples, returns the number of samples written
+    ///
+    /// `samples_per_bit` must be even and non-zero so a one can transition in the middle of the unit interval. The
+    /// trailing transition and hold low required after the last bit of a packet are not generated.
+    pub fn encode(
+        &mut self,
+        bits: impl IntoIterator<Item = bool>,
+        samples_per_bit: usize,
+        samples: &mut [bool],
+    ) -> Result<usize, PdError> {
+        if samples_per_bit == 0 || samples_per_bit % 2 != 0 {
+            return Err(PdError::InvalidParams);
+        }
+
+        let half = samples_per_bit / 2;
+        let mut chunks = samples.chunks_exact_mut(samples_per_bit);
+        let mut written = 0;
+        for bit in bits {
+            let chunk = chunks.next().ok_or(PdError::InvalidParams)?;
+            let (first, second) = chunk.split_at_mut(half);
+
+            self.level = !self.level;
+            first.fill(self.level);
+            if bit {
+                self.level = !self.level;
+            }
+            second.fill(self.level);
+            written += samples_per_bit;
+        }
+
+        Ok(written)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bmc_encode() {
+        let mut encoder = BmcEncoder::new();
+        let mut samples = [false; 8];
+        assert_eq!(encoder.encode([false, true, true, false], 2, &mut samples), Ok(8));
+        assert_eq!(samples, [true, true, false, true, false, true, false, false]);
+        assert!(!encoder.level());
+    }
+
+    #[test]
+    fn test_bmc_encode_invalid() {
+        let mut encoder = BmcEncoder::new();
+        let mut samples = [false; 4];
+        assert_eq!(encoder.encode([true], 3, &mut samples), Err(PdError::InvalidParams));
+        assert_eq!(
+            encoder.encode([true, false, true], 2, &mut samples),
+            Err(PdError::InvalidParams)
+        );
+    }
+
+    #[test]
+    fn test_preamble() {
+        assert_eq!(preamble().count(), PREAMBLE_BITS);
+        assert_eq!(preamble().next(), Some(false));
+        assert_eq!(preamble().last(), Some(true));
+    }
+}
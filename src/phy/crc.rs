@@ -0,0 +1,104 @@
+//! Message CRC as defined in the USB PD specification 5.6.2
+//!
+//! The CRC is the IEEE 802.3 CRC-32 computed over the message header and data, it is transmitted least significant
+//! byte first after the data.
+
+/// CRC length in bytes
+pub const CRC_LEN: usize = 4;
+
+/// CRC polynomial
+pub const CRC_POLYNOMIAL: u32 = 0x04C1_1DB7;
+
+/// CRC register initial value
+pub const CRC_INITIAL: u32 = 0xFFFF_FFFF;
+
+/// Residual left in the CRC register after processing a message and its valid CRC
+pub const CRC_RESIDUAL: u32 = 0xC704_DD7B;
+
+/// Incremental CRC-32 computation
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Crc32 {
+    /// CRC register, bits are processed least significant first so the register is kept bit reversed
+    register: u32,
+}
+
+impl Default for Crc32 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Crc32 {
+    /// Create a new CRC computation
+    pub const fn new() -> Self {
+        Self { register: CRC_INITIAL }
+    }
+
+    /// Process the given bytes
+    pub fn update(&mut self, data: &[u8]) {
+        let polynomial = CRC_POLYNOMIAL.reverse_bits();
+        for byte in data {
+            self.register ^= *byte as u32;
+            for _ in 0..8 {
+                self.register = if self.register & 1 != 0 {
+                    (self.register >> 1) ^ polynomial
+                } else {
+                    self.register >> 1
+                };
+            }
+        }
+    }
+
+    /// Returns the CRC to transmit for the bytes processed so far
+    pub fn finish(&self) -> u32 {
+        !self.register
+    }
+
+    /// Returns true if the bytes processed so far ended with a valid CRC
+    pub fn is_residual(&self) -> bool {
+        self.register.reverse_bits() == CRC_RESIDUAL
+    }
+}
+
+/// Compute the CRC of the given bytes
+pub fn crc32(data: &[u8]) -> u32 {
+    let mut crc = Crc32::new();
+    crc.update(data);
+    crc.finish()
+}
+
+/// Returns true if the given bytes end with a valid CRC
+pub fn check(data_with_crc: &[u8]) -> bool {
+    let mut crc = Crc32::new();
+    crc.update(data_with_crc);
+    data_with_crc.len() >= CRC_LEN && crc.is_residual()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_crc32() {
+        // Standard CRC-32 check value
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+        // GoodCRC with message ID 3, sink, UFP, revision 3.x
+        assert_eq!(crc32(&[0x81, 0x06]), 0x8A22_1EC0);
+    }
+
+    #[test]
+    fn test_crc32_check() {
+        assert!(check(&[0x81, 0x06, 0xC0, 0x1E, 0x22, 0x8A]));
+        assert!(!check(&[0x81, 0x06, 0xC0, 0x1E, 0x22, 0x8B]));
+        assert!(!check(&[]));
+    }
+
+    #[test]
+    fn test_crc32_incremental() {
+        let mut crc = Crc32::new();
+        crc.update(b"1234");
+        crc.update(b"56789");
+        assert_eq!(crc.finish(), 0xCBF4_3926);
+    }
+}
@@ -0,0 +1,200 @@
+//! Software USB PD physical layer encoding as defined in the USB PD specification chapter 5
+//!
+//! This provides pure computation for bit-banged or FPGA-assisted PHYs and for generating test vectors: 4b5b symbol
+//! coding, ordered sets, the message CRC and BMC line coding. A packet is the ordered set followed by the 4b5b
+//! encoded message, CRC and EOP. The preamble is transmitted as raw bits before the packet.
+use crate::PdError;
+
+pub mod bmc;
+pub mod crc;
+pub mod ordered_set;
+pub mod symbol;
+
+use ordered_set::{OrderedSet, ORDERED_SET_LEN};
+use symbol::{decode_byte, encode_byte, InvalidSymbol, KCode};
+
+/// Number of 5b symbols per encoded byte
+pub const SYMBOLS_PER_BYTE: usize = 2;
+
+/// Error type for packet decoding
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DecodeError {
+    /// Ordered set was not recognized
+    InvalidOrderedSet,
+    /// Invalid or unexpected 5b symbol
+    InvalidSymbol(u8),
+    /// Packet was not terminated with an EOP
+    MissingEop,
+    /// CRC did not match
+    InvalidCrc,
+    /// Output buffer is too small
+    BufferTooSmall,
+}
+
+impl From<InvalidSymbol> for DecodeError {
+    fn from(value: InvalidSymbol) -> Self {
+        DecodeError::InvalidSymbol(value.0)
+    }
+}
+
+impl From<DecodeError> for PdError {
+    fn from(_: DecodeError) -> Self {
+        PdError::InvalidParams
+    }
+}
+
+/// Returns the number of 5b symbols in a packet carrying `data_len` bytes of message
+pub const fn packet_len(data_len: usize) -> usize {
+    ORDERED_SET_LEN + (data_len + crc::CRC_LEN) * SYMBOLS_PER_BYTE + 1
+}
+
+/// Encode a message into 5b symbols, returns the number of symbols written
+///
+/// `data` contains the message header and data objects, the CRC and EOP are appended.
+pub fn encode_packet(ordered_set: OrderedSet, data: &[u8], symbols: &mut [u8]) -> Result<usize, PdError> {
+    let len = packet_len(data.len());
+    let symbols = symbols.get_mut(..len).ok_or(PdError::InvalidParams)?;
+    let (header, rest) = symbols.split_at_mut(ORDERED_SET_LEN);
+    header.copy_from_slice(&ordered_set.symbols());
+
+    let crc = crc::crc32(data).to_le_bytes();
+    for (chunk, byte) in rest
+        .chunks_exact_mut(SYMBOLS_PER_BYTE)
+        .zip(data.iter().chain(crc.iter()))
+    {
+        chunk.copy_from_slice(&encode_byte(*byte));
+    }
+
+    if let Some(eop) = rest.last_mut() {
+        *eop = KCode::Eop.symbol();
+    }
+
+    Ok(len)
+}
+
+/// Encode a Hard Reset or Cable Reset ordered set, these are not followed by a message or EOP
+pub fn encode_reset(ordered_set: OrderedSet) -> [u8; ORDERED_SET_LEN] {
+    ordered_set.symbols()
+}
+
+/// Decode a packet from 5b symbols, returns the ordered set and the number of message bytes written
+///
+/// The CRC is checked and not included in the output. Hard Reset and Cable Reset are returned without decoding any
+/// further symbols.
+pub fn decode_packet(symbols: &[u8], data: &mut [u8]) -> Result<(OrderedSet, usize), DecodeError> {
+    let (header, rest) = symbols
+        .split_first_chunk::<ORDERED_SET_LEN>()
+        .ok_or(DecodeError::InvalidOrderedSet)?;
+    let ordered_set = OrderedSet::detect(*header).ok_or(DecodeError::InvalidOrderedSet)?;
+    if matches!(ordered_set, OrderedSet::HardReset | OrderedSet::CableReset) {
+        return Ok((ordered_set, 0));
+    }
+
+    let eop = rest
+        .iter()
+        .position(|&symbol| symbol == KCode::Eop.symbol())
+        .ok_or(DecodeError::MissingEop)?;
+    let payload = rest.get(..eop).unwrap_or_default();
+    let pairs = payload.chunks_exact(SYMBOLS_PER_BYTE);
+    if let Some(symbol) = pairs.remainder().first() {
+        // Odd number of symbols before the EOP
+        return Err(DecodeError::InvalidSymbol(*symbol));
+    }
+
+    let len = pairs.len().checked_sub(crc::CRC_LEN).ok_or(DecodeError::InvalidCrc)?;
+    let mut crc = crc::Crc32::new();
+    for (i, pair) in pairs.enumerate() {
+        // Every chunk has exactly SYMBOLS_PER_BYTE symbols
+        let &[low, high] = pair else {
+            continue;
+        };
+        let byte = decode_byte([low, high])?;
+        crc.update(&[byte]);
+        if i < len {
+            *data.get_mut(i).ok_or(DecodeError::BufferTooSmall)? = byte;
+        }
+    }
+
+    if !crc.is_residual() {
+        return Err(DecodeError::InvalidCrc);
+    }
+
+    Ok((ordered_set, len))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::message::MAX_MESSAGE_LEN;
+
+    /// GoodCRC with message ID 3, sink, UFP, revision 3.x
+    const GOOD_CRC: [u8; 2] = [0x81, 0x06];
+
+    /// Encoded GoodCRC packet on SOP
+    #[rustfmt::skip]
+    const GOOD_CRC_PACKET: [u8; 17] = [
+        // SOP
+        0b11000, 0b11000, 0b11000, 0b10001,
+        // Header 0x0681
+        0b01001, 0b10010, 0b01110, 0b11110,
+        // CRC 0x8A221EC0
+        0b11110, 0b11010, 0b11100, 0b01001, 0b10100, 0b10100, 0b10110, 0b10010,
+        // EOP
+        0b01101,
+    ];
+
+    #[test]
+    fn test_encode_packet() {
+        let mut symbols = [0; packet_len(2)];
+        assert_eq!(encode_packet(OrderedSet::Sop, &GOOD_CRC, &mut symbols), Ok(17));
+        assert_eq!(symbols, GOOD_CRC_PACKET);
+
+        let mut short = [0; 16];
+        assert_eq!(
+            encode_packet(OrderedSet::Sop, &GOOD_CRC, &mut short),
+            Err(PdError::InvalidParams)
+        );
+    }
+
+    #[test]
+    fn test_decode_packet() {
+        let mut data = [0; MAX_MESSAGE_LEN];
+        assert_eq!(decode_packet(&GOOD_CRC_PACKET, &mut data), Ok((OrderedSet::Sop, 2)));
+        assert_eq!(data.get(..2), Some(GOOD_CRC.as_slice()));
+
+        // Hard reset has no payload
+        assert_eq!(
+            decode_packet(&encode_reset(OrderedSet::HardReset), &mut data),
+            Ok((OrderedSet::HardReset, 0))
+        );
+    }
+
+    #[test]
+    fn test_decode_packet_invalid() {
+        let mut data = [0; MAX_MESSAGE_LEN];
+
+        let mut corrupted = GOOD_CRC_PACKET;
+        if let Some(symbol) = corrupted.get_mut(5) {
+            *symbol = 0b01010;
+        }
+        assert_eq!(decode_packet(&corrupted, &mut data), Err(DecodeError::InvalidCrc));
+
+        if let Some(symbol) = corrupted.get_mut(5) {
+            *symbol = 0b00000;
+        }
+        assert_eq!(
+            decode_packet(&corrupted, &mut data),
+            Err(DecodeError::InvalidSymbol(0b00000))
+        );
+
+        assert_eq!(
+            decode_packet(GOOD_CRC_PACKET.get(..16).unwrap_or_default(), &mut data),
+            Err(DecodeError::MissingEop)
+        );
+        assert_eq!(
+            decode_packet(&GOOD_CRC_PACKET, &mut [0; 1]),
+            Err(DecodeError::BufferTooSmall)
+        );
+    }
+}
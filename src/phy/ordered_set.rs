@@ -0,0 +1,134 @@
+//! Ordered sets as defined in the USB PD specification 5.4
+use super::symbol::KCode;
+use crate::protocol::Sop;
+
+/// Number of K-codes in an ordered set
+pub const ORDERED_SET_LEN: usize = 4;
+
+/// Minimum number of K-codes that must match for an ordered set to be recognized
+pub const ORDERED_SET_MIN_MATCH: usize = 3;
+
+/// Ordered set
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum OrderedSet {
+    /// SOP
+    Sop,
+    /// SOP'
+    SopPrime,
+    /// SOP''
+    SopDoublePrime,
+    /// SOP'_Debug
+    SopPrimeDebug,
+    /// SOP''_Debug
+    SopDoublePrimeDebug,
+    /// Hard Reset
+    HardReset,
+    /// Cable Reset
+    CableReset,
+}
+
+impl OrderedSet {
+    const ALL: [OrderedSet; 7] = [
+        OrderedSet::Sop,
+        OrderedSet::SopPrime,
+        OrderedSet::SopDoublePrime,
+        OrderedSet::SopPrimeDebug,
+        OrderedSet::SopDoublePrimeDebug,
+        OrderedSet::HardReset,
+        OrderedSet::CableReset,
+    ];
+
+    /// Returns the K-codes of this ordered set in transmission order
+    pub const fn k_codes(self) -> [KCode; ORDERED_SET_LEN] {
+        use KCode::*;
+        match self {
+            OrderedSet::Sop => [Sync1, Sync1, Sync1, Sync2],
+            OrderedSet::SopPrime => [Sync1, Sync1, Sync3, Sync3],
+            OrderedSet::SopDoublePrime => [Sync1, Sync3, Sync1, Sync3],
+            OrderedSet::SopPrimeDebug => [Sync1, Rst2, Rst2, Sync3],
+            OrderedSet::SopDoublePrimeDebug => [Sync1, Rst2, Sync3, Sync2],
+            OrderedSet::HardReset => [Rst1, Rst1, Rst1, Rst2],
+            OrderedSet::CableReset => [Rst1, Sync1, Rst1, Sync3],
+        }
+    }
+
+    /// Returns the 5b symbols of this ordered set in transmission order
+    pub fn symbols(self) -> [u8; ORDERED_SET_LEN] {
+        self.k_codes().map(KCode::symbol)
+    }
+
+    /// Recognize an ordered set from received 5b symbols
+    ///
+    /// An ordered set is recognized if at least [`ORDERED_SET_MIN_MATCH`] symbols match. If a corrupted set matches
+    /// more than one ordered set, the first in declaration order is returned.
+    pub fn detect(symbols: [u8; ORDERED_SET_LEN]) -> Option<Self> {
+        Self::ALL.into_iter().find(|set| {
+            set.symbols()
+                .iter()
+                .zip(symbols.iter())
+                .filter(|(expected, received)| expected == received)
+                .count()
+                >= ORDERED_SET_MIN_MATCH
+        })
+    }
+}
+
+impl From<Sop> for OrderedSet {
+    fn from(value: Sop) -> Self {
+        match value {
+            Sop::Sop => OrderedSet::Sop,
+            Sop::SopPrime => OrderedSet::SopPrime,
+            Sop::SopDoublePrime => OrderedSet::SopDoublePrime,
+            Sop::SopPrimeDebug => OrderedSet::SopPrimeDebug,
+            Sop::SopDoublePrimeDebug => OrderedSet::SopDoublePrimeDebug,
+        }
+    }
+}
+
+impl TryFrom<OrderedSet> for Sop {
+    type Error = OrderedSet;
+
+    fn try_from(value: OrderedSet) -> Result<Self, Self::Error> {
+        match value {
+            OrderedSet::Sop => Ok(Sop::Sop),
+            OrderedSet::SopPrime => Ok(Sop::SopPrime),
+            OrderedSet::SopDoublePrime => Ok(Sop::SopDoublePrime),
+            OrderedSet::SopPrimeDebug => Ok(Sop::SopPrimeDebug),
+            OrderedSet::SopDoublePrimeDebug => Ok(Sop::SopDoublePrimeDebug),
+            OrderedSet::HardReset | OrderedSet::CableReset => Err(value),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_exact() {
+        for set in OrderedSet::ALL {
+            assert_eq!(OrderedSet::detect(set.symbols()), Some(set));
+        }
+    }
+
+    #[test]
+    fn test_detect_corrupted() {
+        // One corrupted K-code is tolerated
+        let [_, second, third, fourth] = OrderedSet::HardReset.symbols();
+        assert_eq!(
+            OrderedSet::detect([0b11111, second, third, fourth]),
+            Some(OrderedSet::HardReset)
+        );
+
+        // Two corrupted K-codes are not
+        assert_eq!(OrderedSet::detect([0b11111, 0b11111, third, fourth]), None);
+    }
+
+    #[test]
+    fn test_sop_conversion() {
+        assert_eq!(OrderedSet::from(Sop::SopPrime), OrderedSet::SopPrime);
+        assert_eq!(Sop::try_from(OrderedSet::SopDoublePrime), Ok(Sop::SopDoublePrime));
+        assert_eq!(Sop::try_from(OrderedSet::CableReset), Err(OrderedSet::CableReset));
+    }
+}
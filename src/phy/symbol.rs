@@ -0,0 +1,140 @@
+//! 4b5b line code as defined in the USB PD specification 5.3
+//!
+//! 5b symbols are stored in the low five bits of a `u8` and are transmitted least significant bit first.
+
+/// Error type for 5b symbol decoding, contains the raw symbol
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidSymbol(pub u8);
+
+/// 5b data symbols indexed by 4b value
+const DATA_SYMBOLS: [u8; 16] = [
+    0b11110, 0b01001, 0b10100, 0b10101, 0b01010, 0b01011, 0b01110, 0b01111, 0b10010, 0b10011, 0b10110, 0b10111,
+    0b11010, 0b11011, 0b11100, 0b11101,
+];
+
+/// K-codes used in ordered sets and to terminate packets
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum KCode {
+    /// Startsynch #1
+    Sync1,
+    /// Startsynch #2
+    Sync2,
+    /// Startsynch #3
+    Sync3,
+    /// Hard reset #1
+    Rst1,
+    /// Hard reset #2
+    Rst2,
+    /// End of packet
+    Eop,
+}
+
+impl KCode {
+    /// Returns the 5b symbol for this K-code
+    pub const fn symbol(self) -> u8 {
+        match self {
+            KCode::Sync1 => 0b11000,
+            KCode::Sync2 => 0b10001,
+            KCode::Sync3 => 0b00110,
+            KCode::Rst1 => 0b00111,
+            KCode::Rst2 => 0b11001,
+            KCode::Eop => 0b01101,
+        }
+    }
+}
+
+/// Decoded 5b symbol
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Symbol {
+    /// 4b data value
+    Data(u8),
+    /// K-code
+    K(KCode),
+}
+
+impl Symbol {
+    /// Returns the 5b symbol, only the low nibble of data values is encoded
+    pub fn encode(self) -> u8 {
+        match self {
+            Symbol::Data(value) => DATA_SYMBOLS.get((value & 0xF) as usize).copied().unwrap_or_default(),
+            Symbol::K(code) => code.symbol(),
+        }
+    }
+}
+
+impl TryFrom<u8> for Symbol {
+    type Error = InvalidSymbol;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        if let Some(data) = DATA_SYMBOLS.iter().position(|&symbol| symbol == value) {
+            return Ok(Symbol::Data(data as u8));
+        }
+
+        [
+            KCode::Sync1,
+            KCode::Sync2,
+            KCode::Sync3,
+            KCode::Rst1,
+            KCode::Rst2,
+            KCode::Eop,
+        ]
+        .into_iter()
+        .find(|code| code.symbol() == value)
+        .map(Symbol::K)
+        .ok_or(InvalidSymbol(value))
+    }
+}
+
+/// Encode a byte as two 5b symbols, low nibble first
+pub fn encode_byte(value: u8) -> [u8; 2] {
+    [Symbol::Data(value).encode(), Symbol::Data(value >> 4).encode()]
+}
+
+/// Decode two 5b data symbols, low nibble first, into a byte
+pub fn decode_byte(symbols: [u8; 2]) -> Result<u8, InvalidSymbol> {
+    let [low, high] = symbols;
+    match (Symbol::try_from(low)?, Symbol::try_from(high)?) {
+        (Symbol::Data(low), Symbol::Data(high)) => Ok((high << 4) | low),
+        (Symbol::K(_), _) => Err(InvalidSymbol(low)),
+        (_, Symbol::K(_)) => Err(InvalidSymbol(high)),
+    }
+}
+
+/// Returns an iterator over the bits of 5b symbols in transmission order
+pub fn symbol_bits(symbols: &[u8]) -> impl Iterator<Item = bool> + '_ {
+    symbols
+        .iter()
+        .flat_map(|&symbol| (0..5).map(move |bit| symbol & (1 << bit) != 0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_symbol_roundtrip() {
+        for value in 0..16 {
+            assert_eq!(Symbol::try_from(Symbol::Data(value).encode()), Ok(Symbol::Data(value)));
+        }
+        assert_eq!(Symbol::try_from(0b11000), Ok(Symbol::K(KCode::Sync1)));
+        assert_eq!(Symbol::try_from(0b01101), Ok(Symbol::K(KCode::Eop)));
+        assert_eq!(Symbol::try_from(0b00000), Err(InvalidSymbol(0b00000)));
+        assert_eq!(Symbol::try_from(0b11111), Err(InvalidSymbol(0b11111)));
+    }
+
+    #[test]
+    fn test_byte_roundtrip() {
+        assert_eq!(encode_byte(0xA1), [0b01001, 0b10110]);
+        assert_eq!(decode_byte([0b01001, 0b10110]), Ok(0xA1));
+        assert_eq!(decode_byte([0b01001, 0b01101]), Err(InvalidSymbol(0b01101)));
+    }
+
+    #[test]
+    fn test_symbol_bits() {
+        // Sync-1 is transmitted as 0, 0, 0, 1, 1
+        assert!(symbol_bits(&[0b11000]).eq([false, false, false, true, true]));
+    }
+}
@@ -0,0 +1,354 @@
+//! Fast role swap sequencer as defined in 8.3.3.19.2 and 8.3.3.19.4 of the USB PD spec.
+//!
+//! The initial source sends the fast role swap signal when it loses its power input, the initial sink detects the
+//! signal and starts supplying VBUS as soon as it drops below vSafe5V. The `FR_Swap`, `Accept` and `PS_RDY` exchange
+//! that follows flips the power roles. Whether the new source can supply the initial sink is checked with
+//! [`crate::pdo::sink::FrsRequiredCurrent::is_met_by`] before fast role swap is armed.
+//!
+//! The sequencer does not perform any IO and follows the conventions of [`super::swap`]. Unlike the other swaps, a fast
+//! role swap cannot be rejected, any failure requires Type-C error recovery.
+
+use crate::constants::{T_PS_SOURCE_OFF_MS, T_PS_SOURCE_ON_MS, T_SENDER_RESPONSE_MS};
+use crate::{PdError, PowerRole};
+
+/// Timers used by the fast role swap sequencer
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Timer {
+    /// `SenderResponseTimer`, the initial sink waiting for a response to `FR_Swap`
+    SenderResponse,
+    /// `PSSourceOffTimer`, the initial sink waiting for `PS_RDY` from the initial source
+    PsSourceOff,
+    /// `PSSourceOnTimer`, the initial source waiting for `PS_RDY` from the new source
+    PsSourceOn,
+}
+
+impl Timer {
+    /// Returns the timeout for this timer in milliseconds
+    pub const fn duration_ms(self) -> u16 {
+        match self {
+            Timer::SenderResponse => T_SENDER_RESPONSE_MS.maximum.0,
+            Timer::PsSourceOff => T_PS_SOURCE_OFF_MS.maximum.0,
+            Timer::PsSourceOn => T_PS_SOURCE_ON_MS.maximum.0,
+        }
+    }
+}
+
+/// Messages received from the port partner
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Received {
+    /// `FR_Swap`
+    FrSwap,
+    /// `Accept`
+    Accept,
+    /// `PS_RDY`
+    PsRdy,
+    /// `Reject`, `Wait` or any other message, none of which are valid during a fast role swap
+    Unexpected,
+}
+
+/// Messages to send to the port partner
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Message {
+    /// `FR_Swap`
+    FrSwap,
+    /// `Accept`
+    Accept,
+    /// `PS_RDY`
+    PsRdy,
+}
+
+/// Fast role swap sequencer states
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum State {
+    /// Waiting for the fast role swap signal to be sent or detected
+    Idle,
+    /// `PE_FRS_SRC_SNK_CC_Signal`, initial source waiting for `FR_Swap`
+    SignalSent,
+    /// `PE_FRS_SRC_SNK_Accept_Swap`, initial source sending `Accept`
+    SendAccept,
+    /// `PE_FRS_SRC_SNK_Transition_to_off`, initial source turning off its power supply
+    SourceOff,
+    /// `PE_FRS_SRC_SNK_Assert_Rd`, initial source asserting Rd
+    AssertRd,
+    /// `PE_FRS_SRC_SNK_Source_off`, initial source waiting for `PS_RDY` from the new source
+    WaitSourceOn,
+    /// `PE_FRS_SNK_SRC_Send_Swap`, initial sink waiting for `Accept`
+    SendSwap,
+    /// `PE_FRS_SNK_SRC_Transition_to_off`, initial sink waiting for `PS_RDY` from the initial source
+    WaitSourceOff,
+    /// `PE_FRS_SNK_SRC_Assert_Rp`, initial sink asserting Rp
+    AssertRp,
+    /// `PE_FRS_SNK_SRC_Source_on`, initial sink sending `PS_RDY`
+    SendPsRdy,
+    /// The fast role swap has completed, successfully or not
+    Complete,
+}
+
+/// Inputs to the fast role swap sequencer
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Input {
+    /// Initial source: the power input has been lost and the fast role swap signal must be sent
+    Initiate,
+    /// Initial sink: the fast role swap signal has been detected, `FR_Swap` must be sent within `tFRSwapInit`
+    SignalDetected,
+    /// Message received from the port partner
+    Received(Received),
+    /// The last sent message was acknowledged with GoodCRC
+    MessageSent,
+    /// The last sent message was not acknowledged after all retries
+    TransmitFailed,
+    /// Timer expired
+    TimerExpired(Timer),
+    /// The action requested by the last output has completed
+    ActionComplete,
+}
+
+/// Outputs from the fast role swap sequencer
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Output {
+    /// Send the message and start the timer, if any, once it has been acknowledged with GoodCRC
+    Send {
+        /// Message to send
+        message: Message,
+        /// Timer to start
+        timer: Option<Timer>,
+    },
+    /// Start the timer
+    StartTimer(Timer),
+    /// Drive CC low for `tFRSwap` to send the fast role swap signal
+    SendSignal,
+    /// Turn off the power supply and provide [`Input::ActionComplete`] once VBUS is at or below vSafe5V
+    SourceOff,
+    /// Assert Rd for [`PowerRole::Sink`] or Rp for [`PowerRole::Source`]
+    ///
+    /// The new source must already be supplying VBUS, it is required to do so within `tSrcFRSwap` of VBUS dropping
+    /// below vSafe5V whether or not the message exchange has completed.
+    SetPowerRole(PowerRole),
+    /// The fast role swap has completed, on failure the error is always [`PdError::ErrorRecovery`]
+    Complete(Result<(), PdError>),
+}
+
+/// Attempted transition that is not allowed by the state machine
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidTransition {
+    /// The current state of the state machine
+    pub state: State,
+    /// The input that was attempted
+    pub input: Input,
+}
+
+// Doctest tries to compile the mermaid code as rust so just disable it
+#[cfg_attr(not(doctest), aquamarine::aquamarine)]
+#[cfg_attr(not(doctest), doc = "include_mmd!(\"docs/policy/frs.mmd\")")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StateMachine {
+    state: State,
+}
+
+impl Default for StateMachine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StateMachine {
+    /// Create a new state machine in the Idle state
+    pub const fn new() -> Self {
+        Self { state: State::Idle }
+    }
+
+    /// Returns the current state
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Transition the state machine based on the input and return the resulting action if any.
+    pub fn consume(&mut self, input: Input) -> Result<Option<Output>, InvalidTransition> {
+        use State::*;
+
+        let (next_state, output) = match (self.state, input) {
+            // Initial source
+            (Idle, Input::Initiate) => (SignalSent, Some(Output::SendSignal)),
+            (SignalSent, Input::Received(Received::FrSwap)) => (
+                SendAccept,
+                Some(Output::Send {
+                    message: Message::Accept,
+                    timer: None,
+                }),
+            ),
+            (SendAccept, Input::MessageSent) => (SourceOff, Some(Output::SourceOff)),
+            (SourceOff, Input::ActionComplete) => (AssertRd, Some(Output::SetPowerRole(PowerRole::Sink))),
+            (AssertRd, Input::ActionComplete) => (
+                WaitSourceOn,
+                Some(Output::Send {
+                    message: Message::PsRdy,
+                    timer: Some(Timer::PsSourceOn),
+                }),
+            ),
+            (WaitSourceOn, Input::Received(Received::PsRdy)) => (Complete, Some(Output::Complete(Ok(())))),
+
+            // Initial sink
+            (Idle, Input::SignalDetected) => (
+                SendSwap,
+                Some(Output::Send {
+                    message: Message::FrSwap,
+                    timer: Some(Timer::SenderResponse),
+                }),
+            ),
+            (SendSwap, Input::Received(Received::Accept)) => {
+                (WaitSourceOff, Some(Output::StartTimer(Timer::PsSourceOff)))
+            }
+            (WaitSourceOff, Input::Received(Received::PsRdy)) => {
+                (AssertRp, Some(Output::SetPowerRole(PowerRole::Source)))
+            }
+            (AssertRp, Input::ActionComplete) => (
+                SendPsRdy,
+                Some(Output::Send {
+                    message: Message::PsRdy,
+                    timer: None,
+                }),
+            ),
+            (SendPsRdy, Input::MessageSent) => (Complete, Some(Output::Complete(Ok(())))),
+
+            // GoodCRC for a message that only starts a timer
+            (SendSwap | WaitSourceOn, Input::MessageSent) => (self.state, None),
+
+            // Any failure once the signal has been sent requires error recovery, the power roles are undefined
+            (SendSwap, Input::TimerExpired(Timer::SenderResponse))
+            | (WaitSourceOff, Input::TimerExpired(Timer::PsSourceOff))
+            | (WaitSourceOn, Input::TimerExpired(Timer::PsSourceOn))
+            | (SendAccept | WaitSourceOn | SendSwap | SendPsRdy, Input::TransmitFailed)
+            | (
+                SignalSent | SendAccept | SourceOff | AssertRd | WaitSourceOn | SendSwap | WaitSourceOff | AssertRp
+                | SendPsRdy,
+                Input::Received(_),
+            ) => (Complete, Some(Output::Complete(Err(PdError::ErrorRecovery)))),
+
+            // Invalid transition
+            _ => {
+                return Err(InvalidTransition {
+                    state: self.state,
+                    input,
+                })
+            }
+        };
+
+        self.state = next_state;
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn send(message: Message, timer: Option<Timer>) -> Result<Option<Output>, InvalidTransition> {
+        Ok(Some(Output::Send { message, timer }))
+    }
+
+    #[test]
+    fn test_initial_source() {
+        let mut sm = StateMachine::new();
+        assert_eq!(sm.consume(Input::Initiate), Ok(Some(Output::SendSignal)));
+        assert_eq!(
+            sm.consume(Input::Received(Received::FrSwap)),
+            send(Message::Accept, None)
+        );
+        assert_eq!(sm.consume(Input::MessageSent), Ok(Some(Output::SourceOff)));
+        assert_eq!(
+            sm.consume(Input::ActionComplete),
+            Ok(Some(Output::SetPowerRole(PowerRole::Sink)))
+        );
+        assert_eq!(
+            sm.consume(Input::ActionComplete),
+            send(Message::PsRdy, Some(Timer::PsSourceOn))
+        );
+        assert_eq!(sm.consume(Input::MessageSent), Ok(None));
+        assert_eq!(
+            sm.consume(Input::Received(Received::PsRdy)),
+            Ok(Some(Output::Complete(Ok(()))))
+        );
+        assert_eq!(sm.state(), State::Complete);
+    }
+
+    #[test]
+    fn test_initial_sink() {
+        let mut sm = StateMachine::new();
+        assert_eq!(
+            sm.consume(Input::SignalDetected),
+            send(Message::FrSwap, Some(Timer::SenderResponse))
+        );
+        assert_eq!(sm.consume(Input::MessageSent), Ok(None));
+        assert_eq!(
+            sm.consume(Input::Received(Received::Accept)),
+            Ok(Some(Output::StartTimer(Timer::PsSourceOff)))
+        );
+        assert_eq!(
+            sm.consume(Input::Received(Received::PsRdy)),
+            Ok(Some(Output::SetPowerRole(PowerRole::Source)))
+        );
+        assert_eq!(sm.consume(Input::ActionComplete), send(Message::PsRdy, None));
+        assert_eq!(sm.consume(Input::MessageSent), Ok(Some(Output::Complete(Ok(())))));
+    }
+
+    #[test]
+    fn test_failures() {
+        let mut sm = StateMachine::new();
+        sm.consume(Input::SignalDetected).ok();
+        assert_eq!(
+            sm.consume(Input::Received(Received::Unexpected)),
+            Ok(Some(Output::Complete(Err(PdError::ErrorRecovery))))
+        );
+
+        let mut sm = StateMachine::new();
+        sm.consume(Input::SignalDetected).ok();
+        sm.consume(Input::Received(Received::Accept)).ok();
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::PsSourceOff)),
+            Ok(Some(Output::Complete(Err(PdError::ErrorRecovery))))
+        );
+
+        let mut sm = StateMachine::new();
+        sm.consume(Input::Initiate).ok();
+        sm.consume(Input::Received(Received::FrSwap)).ok();
+        assert_eq!(
+            sm.consume(Input::TransmitFailed),
+            Ok(Some(Output::Complete(Err(PdError::ErrorRecovery))))
+        );
+
+        // Timers are only handled in the states that start them
+        let mut sm = StateMachine::new();
+        sm.consume(Input::Initiate).ok();
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::PsSourceOn)),
+            Err(InvalidTransition {
+                state: State::SignalSent,
+                input: Input::TimerExpired(Timer::PsSourceOn),
+            })
+        );
+        let mut sm = StateMachine::new();
+        sm.consume(Input::SignalDetected).ok();
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::SenderResponse)),
+            Ok(Some(Output::Complete(Err(PdError::ErrorRecovery))))
+        );
+
+        // Nothing to do before the signal
+        let mut sm = StateMachine::new();
+        assert_eq!(
+            sm.consume(Input::MessageSent),
+            Err(InvalidTransition {
+                state: State::Idle,
+                input: Input::MessageSent,
+            })
+        );
+    }
+}
@@ -0,0 +1,10 @@
+//! USB PD policy engine state machines as defined in 8.3.3 of the USB PD spec.
+//!
+//! The state machines do not perform any IO. Received messages, transmission results and timer expirations are
+//! provided as inputs and the messages to send, timers to run and power supply actions are returned as outputs. They
+//! are intended to sit on top of [`crate::protocol`], which handles message IDs, GoodCRC and retries.
+
+pub mod frs;
+pub mod sink;
+pub mod source;
+pub mod swap;
@@ -0,0 +1,659 @@
+//! Sink policy engine as defined in 8.3.3.3 of the USB PD spec.
+//!
+//! Capability evaluation is left to the device policy manager: when the state machine outputs
+//! [`Output::EvaluateCapabilities`] the caller selects a PDO from the received `Source_Capabilities`, builds the
+//! corresponding [`Contract`] and provides it through [`Input::Select`]. The contract is reported back through
+//! [`Output::ContractEstablished`] once the source has transitioned its power supply.
+//!
+//! At most one timer runs at a time. Starting a timer replaces the running one and any output other than
+//! [`Output::Send`] stops the running timer before it is acted on. The result of every [`Output::Send`] must be
+//! provided through [`Input::MessageSent`] or [`Input::TransmitFailed`].
+
+use crate::constants::{
+    N_HARD_RESET_COUNT, T_PS_TRANSITION_EPR_MS, T_PS_TRANSITION_SPR_MS, T_SENDER_RESPONSE_MS, T_SINK_REQUEST_MS,
+    T_TYPE_C_SINK_WAIT_CAP_MS,
+};
+use crate::pdo::{Contract, Rdo};
+
+/// Timers used by the sink policy engine
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Timer {
+    /// `SinkWaitCapTimer`, waiting for `Source_Capabilities`
+    SinkWaitCap,
+    /// `SenderResponseTimer`, waiting for a response to a sent message
+    SenderResponse,
+    /// `PSTransitionTimer`, waiting for `PS_RDY`, true if the requested contract is for an EPR capability
+    PsTransition(bool),
+    /// `SinkRequestTimer`, time to wait before re-sending a `Request` after `Wait`
+    SinkRequest,
+}
+
+impl Timer {
+    /// Returns the timeout for this timer in milliseconds
+    ///
+    /// Timers waiting on the source use the maximum allowed duration, [`Timer::SinkRequest`] uses the minimum.
+    pub const fn duration_ms(self) -> u16 {
+        match self {
+            Timer::SinkWaitCap => T_TYPE_C_SINK_WAIT_CAP_MS.maximum.0,
+            Timer::SenderResponse => T_SENDER_RESPONSE_MS.maximum.0,
+            Timer::PsTransition(false) => T_PS_TRANSITION_SPR_MS.maximum.0,
+            Timer::PsTransition(true) => T_PS_TRANSITION_EPR_MS.maximum.0,
+            Timer::SinkRequest => T_SINK_REQUEST_MS.0,
+        }
+    }
+}
+
+/// Messages received from the source
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Received {
+    /// `Source_Capabilities`, the capabilities are kept by the caller for evaluation
+    SourceCapabilities,
+    /// `Accept`
+    Accept,
+    /// `Reject`
+    Reject,
+    /// `Wait`
+    Wait,
+    /// `PS_RDY`
+    PsRdy,
+    /// `Get_Sink_Cap`
+    GetSinkCap,
+    /// `Soft_Reset`
+    SoftReset,
+    /// Any message the sink does not support
+    Unsupported,
+}
+
+/// Messages to send to the source
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Message {
+    /// `Request`
+    Request(Rdo),
+    /// `Sink_Capabilities`, the capabilities are provided by the caller
+    SinkCapabilities,
+    /// `Accept`
+    Accept,
+    /// `Soft_Reset`
+    SoftReset,
+    /// `Not_Supported`
+    NotSupported,
+}
+
+/// Sink policy engine states
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum State {
+    /// `PE_SNK_Startup`
+    #[default]
+    Startup,
+    /// `PE_SNK_Discovery`, waiting for VBUS
+    Discovery,
+    /// `PE_SNK_Wait_for_Capabilities`
+    WaitForCapabilities,
+    /// `PE_SNK_Evaluate_Capability`, waiting for the device policy manager to select a capability
+    EvaluateCapability,
+    /// `PE_SNK_Select_Capability`, `Request` sent and waiting for a response
+    SelectCapability(Contract),
+    /// `PE_SNK_Transition_Sink`, waiting for `PS_RDY`
+    TransitionSink(Contract),
+    /// `PE_SNK_Ready`
+    Ready,
+    /// `PE_SNK_Give_Sink_Cap`, sending `Sink_Capabilities`
+    GiveSinkCap,
+    /// `PE_SNK_Soft_Reset`, `Soft_Reset` received and sending `Accept`
+    SoftReset,
+    /// `PE_SNK_Send_Soft_Reset`, `Soft_Reset` sent and waiting for `Accept`
+    SendSoftReset,
+    /// `PE_SNK_Hard_Reset`, sending hard reset signaling
+    HardReset,
+    /// `PE_SNK_Transition_to_default`, waiting for the power supply to return to default
+    TransitionToDefault,
+}
+
+/// Inputs to the sink policy engine
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Input {
+    /// Start the policy engine after attach
+    Start,
+    /// VBUS is present
+    VbusPresent,
+    /// Message received from the source
+    Received(Received),
+    /// The last sent message was acknowledged with GoodCRC
+    MessageSent,
+    /// The last sent message was not acknowledged after all retries
+    TransmitFailed,
+    /// Timer expired
+    TimerExpired(Timer),
+    /// Capability selected by the device policy manager
+    Select(Contract),
+    /// Device policy manager request to send `Soft_Reset`
+    SoftReset,
+    /// Device policy manager request to send hard reset signaling
+    HardReset,
+    /// Hard reset signaling has been sent
+    HardResetSent,
+    /// Hard reset signaling received from the source
+    HardResetReceived,
+    /// The sink has returned to its default power and data role state
+    DefaultReached,
+}
+
+/// Outputs from the sink policy engine
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Output {
+    /// Send the message and start the timer, if any, once it has been acknowledged with GoodCRC
+    Send {
+        /// Message to send
+        message: Message,
+        /// Timer to start
+        timer: Option<Timer>,
+    },
+    /// Start the timer
+    StartTimer(Timer),
+    /// Stop the running timer
+    StopTimer,
+    /// Reset the protocol layer
+    ResetProtocolLayer,
+    /// Evaluate the received `Source_Capabilities` and provide [`Input::Select`]
+    EvaluateCapabilities,
+    /// An explicit contract has been established
+    ContractEstablished(Contract),
+    /// Send hard reset signaling and provide [`Input::HardResetSent`] once done
+    SendHardReset,
+    /// Return the sink to its default state and provide [`Input::DefaultReached`] once done
+    TransitionToDefault,
+}
+
+/// Attempted transition that is not allowed by the state machine
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidTransition {
+    /// The current state of the state machine
+    pub state: State,
+    /// The input that was attempted
+    pub input: Input,
+}
+
+// Doctest tries to compile the mermaid code as rust so just disable it
+#[cfg_attr(not(doctest), aquamarine::aquamarine)]
+#[cfg_attr(not(doctest), doc = "include_mmd!(\"docs/policy/sink.mmd\")")]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StateMachine {
+    state: State,
+    /// Current explicit contract
+    contract: Option<Contract>,
+    /// Last requested contract, re-sent when `SinkRequestTimer` expires
+    requested: Option<Contract>,
+    /// `HardResetCounter`
+    hard_reset_count: u8,
+}
+
+impl StateMachine {
+    /// Create a new state machine in the Startup state
+    pub const fn new() -> Self {
+        StateMachine {
+            state: State::Startup,
+            contract: None,
+            requested: None,
+            hard_reset_count: 0,
+        }
+    }
+
+    /// Returns the current state
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Returns the current explicit contract
+    pub fn contract(&self) -> Option<Contract> {
+        self.contract
+    }
+
+    /// Returns the number of hard resets sent since capabilities were last received
+    pub fn hard_reset_count(&self) -> u8 {
+        self.hard_reset_count
+    }
+
+    fn hard_reset(&mut self) -> (State, Option<Output>) {
+        self.hard_reset_count = self.hard_reset_count.saturating_add(1);
+        (State::HardReset, Some(Output::SendHardReset))
+    }
+
+    fn select(&mut self, contract: Contract) -> (State, Option<Output>) {
+        self.requested = Some(contract);
+        (
+            State::SelectCapability(contract),
+            Some(Output::Send {
+                message: Message::Request(contract.rdo),
+                timer: Some(Timer::SenderResponse),
+            }),
+        )
+    }
+
+    fn send_soft_reset() -> (State, Option<Output>) {
+        (
+            State::SendSoftReset,
+            Some(Output::Send {
+                message: Message::SoftReset,
+                timer: Some(Timer::SenderResponse),
+            }),
+        )
+    }
+
+    /// Transition the state machine based on the input and return the resulting action if any.
+    pub fn consume(&mut self, input: Input) -> Result<Option<Output>, InvalidTransition> {
+        use State::*;
+
+        let (next_state, output) = match (self.state, input) {
+            // Hard reset from the source can occur in any state
+            (_, Input::HardResetReceived) => (TransitionToDefault, Some(Output::TransitionToDefault)),
+
+            // Startup and discovery
+            (Startup, Input::Start) => (Discovery, Some(Output::ResetProtocolLayer)),
+            (Discovery, Input::VbusPresent) => (WaitForCapabilities, Some(Output::StartTimer(Timer::SinkWaitCap))),
+
+            // WaitForCapabilities transitions
+            (WaitForCapabilities | Ready, Input::Received(Received::SourceCapabilities)) => {
+                self.hard_reset_count = 0;
+                (EvaluateCapability, Some(Output::EvaluateCapabilities))
+            }
+            (WaitForCapabilities, Input::TimerExpired(Timer::SinkWaitCap)) => {
+                if self.hard_reset_count <= N_HARD_RESET_COUNT {
+                    self.hard_reset()
+                } else {
+                    // The source is not responsive, stay here until it sends capabilities
+                    (WaitForCapabilities, None)
+                }
+            }
+
+            // EvaluateCapability and Ready transitions
+            (EvaluateCapability | Ready, Input::Select(contract)) => self.select(contract),
+
+            // SelectCapability transitions
+            (SelectCapability(contract), Input::Received(Received::Accept)) => {
+                let timer = Timer::PsTransition(contract.is_epr());
+                (TransitionSink(contract), Some(Output::StartTimer(timer)))
+            }
+            (SelectCapability(_), Input::Received(Received::Wait)) if self.contract.is_some() => {
+                (Ready, Some(Output::StartTimer(Timer::SinkRequest)))
+            }
+            (SelectCapability(_), Input::Received(Received::Reject)) if self.contract.is_some() => {
+                (Ready, Some(Output::StopTimer))
+            }
+            (SelectCapability(_), Input::Received(Received::Reject | Received::Wait)) => {
+                (WaitForCapabilities, Some(Output::StartTimer(Timer::SinkWaitCap)))
+            }
+            (SelectCapability(_), Input::TimerExpired(Timer::SenderResponse)) => self.hard_reset(),
+
+            // TransitionSink transitions
+            (TransitionSink(contract), Input::Received(Received::PsRdy)) => {
+                self.contract = Some(contract);
+                (Ready, Some(Output::ContractEstablished(contract)))
+            }
+            (TransitionSink(_), Input::TimerExpired(Timer::PsTransition(_))) => self.hard_reset(),
+
+            // Ready transitions
+            (Ready, Input::TimerExpired(Timer::SinkRequest)) => match self.requested {
+                Some(contract) => self.select(contract),
+                None => {
+                    return Err(InvalidTransition {
+                        state: self.state,
+                        input,
+                    })
+                }
+            },
+            (Ready, Input::Received(Received::GetSinkCap)) => (
+                GiveSinkCap,
+                Some(Output::Send {
+                    message: Message::SinkCapabilities,
+                    timer: None,
+                }),
+            ),
+            (Ready, Input::Received(Received::Unsupported)) => (
+                Ready,
+                Some(Output::Send {
+                    message: Message::NotSupported,
+                    timer: None,
+                }),
+            ),
+
+            // GiveSinkCap transitions
+            (GiveSinkCap, Input::MessageSent) => (Ready, None),
+
+            // Soft reset received
+            (
+                WaitForCapabilities | EvaluateCapability | SelectCapability(_) | TransitionSink(_) | Ready
+                | GiveSinkCap | SendSoftReset,
+                Input::Received(Received::SoftReset),
+            ) => (
+                SoftReset,
+                Some(Output::Send {
+                    message: Message::Accept,
+                    timer: None,
+                }),
+            ),
+            (SoftReset, Input::MessageSent) => (WaitForCapabilities, Some(Output::StartTimer(Timer::SinkWaitCap))),
+            (SoftReset, Input::TransmitFailed) => self.hard_reset(),
+
+            // Soft reset sent, on request or after a protocol error
+            (
+                EvaluateCapability | SelectCapability(_) | Ready | GiveSinkCap,
+                Input::SoftReset | Input::TransmitFailed,
+            ) => Self::send_soft_reset(),
+            (SendSoftReset, Input::Received(Received::Accept)) => {
+                (WaitForCapabilities, Some(Output::StartTimer(Timer::SinkWaitCap)))
+            }
+            (SendSoftReset, Input::TimerExpired(Timer::SenderResponse) | Input::TransmitFailed) => self.hard_reset(),
+
+            // Hard reset
+            (
+                WaitForCapabilities | EvaluateCapability | SelectCapability(_) | TransitionSink(_) | Ready
+                | GiveSinkCap | SoftReset | SendSoftReset,
+                Input::HardReset,
+            ) => self.hard_reset(),
+            (HardReset, Input::HardResetSent) => (TransitionToDefault, Some(Output::TransitionToDefault)),
+            (TransitionToDefault, Input::DefaultReached) => (Discovery, Some(Output::ResetProtocolLayer)),
+
+            // GoodCRC for a message that only starts a timer or needs no follow-up
+            (_, Input::MessageSent) => (self.state, None),
+
+            // Invalid transition
+            _ => {
+                return Err(InvalidTransition {
+                    state: self.state,
+                    input,
+                })
+            }
+        };
+
+        // Any reset ends the explicit contract
+        if matches!(next_state, SoftReset | SendSoftReset | HardReset | TransitionToDefault) {
+            self.contract = None;
+            self.requested = None;
+        }
+
+        self.state = next_state;
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pdo::rdo::tests::fixed_rdo;
+    use crate::pdo::source;
+
+    fn fixed_contract(voltage_mv: u16, object_position: u8) -> Contract {
+        let pdo = source::Pdo::Fixed(source::FixedData {
+            voltage_mv,
+            current_ma: 3000,
+            ..Default::default()
+        });
+        Contract::from_source(pdo, fixed_rdo(object_position))
+    }
+
+    fn contract() -> Contract {
+        fixed_contract(5000, 1)
+    }
+
+    fn request(contract: Contract) -> Result<Option<Output>, InvalidTransition> {
+        Ok(Some(Output::Send {
+            message: Message::Request(contract.rdo),
+            timer: Some(Timer::SenderResponse),
+        }))
+    }
+
+    /// Drive the state machine to the WaitForCapabilities state
+    fn wait_for_capabilities(sm: &mut StateMachine) {
+        assert_eq!(sm.consume(Input::Start), Ok(Some(Output::ResetProtocolLayer)));
+        assert_eq!(sm.state(), State::Discovery);
+        assert_eq!(
+            sm.consume(Input::VbusPresent),
+            Ok(Some(Output::StartTimer(Timer::SinkWaitCap)))
+        );
+        assert_eq!(sm.state(), State::WaitForCapabilities);
+    }
+
+    /// Drive the state machine to the Ready state with an explicit contract
+    fn negotiate(sm: &mut StateMachine, contract: Contract) {
+        assert_eq!(
+            sm.consume(Input::Received(Received::SourceCapabilities)),
+            Ok(Some(Output::EvaluateCapabilities))
+        );
+        assert_eq!(sm.consume(Input::Select(contract)), request(contract));
+        assert_eq!(sm.consume(Input::MessageSent), Ok(None));
+        assert_eq!(sm.state(), State::SelectCapability(contract));
+
+        assert_eq!(
+            sm.consume(Input::Received(Received::Accept)),
+            Ok(Some(Output::StartTimer(Timer::PsTransition(contract.is_epr()))))
+        );
+        assert_eq!(
+            sm.consume(Input::Received(Received::PsRdy)),
+            Ok(Some(Output::ContractEstablished(contract)))
+        );
+        assert_eq!(sm.state(), State::Ready);
+        assert_eq!(sm.contract(), Some(contract));
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let contract = contract();
+        let mut sm = StateMachine::new();
+        wait_for_capabilities(&mut sm);
+        negotiate(&mut sm, contract);
+
+        // Updated capabilities restart negotiation
+        assert_eq!(
+            sm.consume(Input::Received(Received::SourceCapabilities)),
+            Ok(Some(Output::EvaluateCapabilities))
+        );
+        assert_eq!(sm.state(), State::EvaluateCapability);
+    }
+
+    #[test]
+    fn test_wait_and_reject() {
+        let contract = contract();
+        let mut sm = StateMachine::new();
+        wait_for_capabilities(&mut sm);
+
+        // Reject without a contract waits for new capabilities
+        sm.consume(Input::Received(Received::SourceCapabilities)).ok();
+        sm.consume(Input::Select(contract)).ok();
+        assert_eq!(
+            sm.consume(Input::Received(Received::Reject)),
+            Ok(Some(Output::StartTimer(Timer::SinkWaitCap)))
+        );
+        assert_eq!(sm.state(), State::WaitForCapabilities);
+
+        // Wait with a contract re-sends the request after SinkRequestTimer
+        negotiate(&mut sm, contract);
+        sm.consume(Input::Select(contract)).ok();
+        assert_eq!(
+            sm.consume(Input::Received(Received::Wait)),
+            Ok(Some(Output::StartTimer(Timer::SinkRequest)))
+        );
+        assert_eq!(sm.state(), State::Ready);
+        assert_eq!(sm.consume(Input::TimerExpired(Timer::SinkRequest)), request(contract));
+
+        // Reject with a contract keeps the contract
+        assert_eq!(
+            sm.consume(Input::Received(Received::Reject)),
+            Ok(Some(Output::StopTimer))
+        );
+        assert_eq!(sm.state(), State::Ready);
+        assert_eq!(sm.contract(), Some(contract));
+    }
+
+    #[test]
+    fn test_give_sink_cap() {
+        let contract = contract();
+        let mut sm = StateMachine::new();
+        wait_for_capabilities(&mut sm);
+        negotiate(&mut sm, contract);
+
+        assert_eq!(
+            sm.consume(Input::Received(Received::GetSinkCap)),
+            Ok(Some(Output::Send {
+                message: Message::SinkCapabilities,
+                timer: None,
+            }))
+        );
+        assert_eq!(sm.state(), State::GiveSinkCap);
+        assert_eq!(sm.consume(Input::MessageSent), Ok(None));
+        assert_eq!(sm.state(), State::Ready);
+
+        assert_eq!(
+            sm.consume(Input::Received(Received::Unsupported)),
+            Ok(Some(Output::Send {
+                message: Message::NotSupported,
+                timer: None,
+            }))
+        );
+    }
+
+    #[test]
+    fn test_soft_reset() {
+        let contract = contract();
+        let mut sm = StateMachine::new();
+        wait_for_capabilities(&mut sm);
+        negotiate(&mut sm, contract);
+
+        // Received soft reset
+        assert_eq!(
+            sm.consume(Input::Received(Received::SoftReset)),
+            Ok(Some(Output::Send {
+                message: Message::Accept,
+                timer: None,
+            }))
+        );
+        assert_eq!(sm.contract(), None);
+        assert_eq!(
+            sm.consume(Input::MessageSent),
+            Ok(Some(Output::StartTimer(Timer::SinkWaitCap)))
+        );
+        assert_eq!(sm.state(), State::WaitForCapabilities);
+
+        // Protocol error triggers a soft reset
+        negotiate(&mut sm, contract);
+        assert_eq!(
+            sm.consume(Input::TransmitFailed),
+            Ok(Some(Output::Send {
+                message: Message::SoftReset,
+                timer: Some(Timer::SenderResponse),
+            }))
+        );
+        assert_eq!(sm.state(), State::SendSoftReset);
+        assert_eq!(
+            sm.consume(Input::Received(Received::Accept)),
+            Ok(Some(Output::StartTimer(Timer::SinkWaitCap)))
+        );
+
+        // Unanswered soft reset escalates to hard reset
+        negotiate(&mut sm, contract);
+        sm.consume(Input::SoftReset).ok();
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::SenderResponse)),
+            Ok(Some(Output::SendHardReset))
+        );
+        assert_eq!(sm.state(), State::HardReset);
+    }
+
+    #[test]
+    fn test_hard_reset() {
+        let mut sm = StateMachine::new();
+        wait_for_capabilities(&mut sm);
+
+        // Hard reset on SinkWaitCapTimer until nHardResetCount is exceeded
+        for count in 1..=N_HARD_RESET_COUNT + 1 {
+            assert_eq!(
+                sm.consume(Input::TimerExpired(Timer::SinkWaitCap)),
+                Ok(Some(Output::SendHardReset))
+            );
+            assert_eq!(sm.hard_reset_count(), count);
+            assert_eq!(sm.consume(Input::HardResetSent), Ok(Some(Output::TransitionToDefault)));
+            assert_eq!(sm.consume(Input::DefaultReached), Ok(Some(Output::ResetProtocolLayer)));
+            sm.consume(Input::VbusPresent).ok();
+        }
+        assert_eq!(sm.consume(Input::TimerExpired(Timer::SinkWaitCap)), Ok(None));
+        assert_eq!(sm.state(), State::WaitForCapabilities);
+
+        // Capabilities reset the counter
+        sm.consume(Input::Received(Received::SourceCapabilities)).ok();
+        assert_eq!(sm.hard_reset_count(), 0);
+
+        // Hard reset from the source
+        assert_eq!(
+            sm.consume(Input::HardResetReceived),
+            Ok(Some(Output::TransitionToDefault))
+        );
+        assert_eq!(sm.state(), State::TransitionToDefault);
+    }
+
+    #[test]
+    fn test_ps_transition_timeout() {
+        let contract = contract();
+        let mut sm = StateMachine::new();
+        wait_for_capabilities(&mut sm);
+        sm.consume(Input::Received(Received::SourceCapabilities)).ok();
+        sm.consume(Input::Select(contract)).ok();
+        sm.consume(Input::Received(Received::Accept)).ok();
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::PsTransition(false))),
+            Ok(Some(Output::SendHardReset))
+        );
+        assert_eq!(sm.contract(), None);
+    }
+
+    #[test]
+    fn test_ps_transition_epr() {
+        let contract = fixed_contract(28000, 8);
+        assert!(contract.is_epr());
+        assert_eq!(
+            Timer::PsTransition(true).duration_ms(),
+            T_PS_TRANSITION_EPR_MS.maximum.0
+        );
+        assert_eq!(
+            Timer::PsTransition(false).duration_ms(),
+            T_PS_TRANSITION_SPR_MS.maximum.0
+        );
+
+        let mut sm = StateMachine::new();
+        wait_for_capabilities(&mut sm);
+        sm.consume(Input::Received(Received::SourceCapabilities)).ok();
+        sm.consume(Input::Select(contract)).ok();
+        assert_eq!(
+            sm.consume(Input::Received(Received::Accept)),
+            Ok(Some(Output::StartTimer(Timer::PsTransition(true))))
+        );
+    }
+
+    #[test]
+    fn test_invalid() {
+        let mut sm = StateMachine::new();
+        assert_eq!(
+            sm.consume(Input::VbusPresent),
+            Err(InvalidTransition {
+                state: State::Startup,
+                input: Input::VbusPresent,
+            })
+        );
+
+        // PS_RDY without a request
+        wait_for_capabilities(&mut sm);
+        assert_eq!(
+            sm.consume(Input::Received(Received::PsRdy)),
+            Err(InvalidTransition {
+                state: State::WaitForCapabilities,
+                input: Input::Received(Received::PsRdy),
+            })
+        );
+    }
+}
@@ -0,0 +1,725 @@
+//! Source policy engine as defined in 8.3.3.2 of the USB PD spec.
+//!
+//! The advertised capabilities are set with [`StateMachine::set_capabilities`] and sent whenever the state machine
+//! outputs [`Message::SourceCapabilities`]. Received `Request` messages are checked against the advertised
+//! capabilities before being passed to the device policy manager through [`Output::EvaluateRequest`], which responds
+//! with [`Input::Evaluated`]. Power supply transitions are requested through [`Output::TransitionPowerSupply`] and
+//! reported back through [`Input::PowerSupplyReady`].
+//!
+//! At most one timer runs at a time. Starting a timer replaces the running one and any output other than
+//! [`Output::Send`] stops the running timer before it is acted on. The result of every [`Output::Send`] must be
+//! provided through [`Input::MessageSent`] or [`Input::TransmitFailed`].
+//!
+//! `NoResponseTimer` and `HardResetCounter` are not modeled, a sink that stops acknowledging `Source_Capabilities`
+//! after a hard reset ends in [`State::Disabled`] once the `CapsCounter` is exceeded.
+
+use crate::constants::{
+    N_CAPS_COUNT, T_PS_HARD_RESET_MS, T_SENDER_RESPONSE_MS, T_SRC_TRANSITION_MS, T_TYPE_C_SEND_SOURCE_CAP_MS,
+};
+use crate::pdo::source::Pdo;
+use crate::pdo::{validate, Contract};
+use crate::protocol::message::MAX_DATA_OBJECTS;
+use crate::PdError;
+
+/// Timers used by the source policy engine
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Timer {
+    /// `SourceCapabilityTimer`, time until `Source_Capabilities` is re-sent while the sink is not responding
+    SourceCapability,
+    /// `SenderResponseTimer`, waiting for a response to a sent message
+    SenderResponse,
+    /// Time from `Accept` to the start of the power supply transition, `tSrcTransition`
+    SrcTransition,
+    /// `PSHardResetTimer`, time from hard reset signaling to the power supply returning to default
+    PsHardReset,
+}
+
+impl Timer {
+    /// Returns the timeout for this timer in milliseconds
+    ///
+    /// Timers waiting on the sink use the maximum allowed duration, timers delaying the source use the nominal one.
+    pub const fn duration_ms(self) -> u16 {
+        match self {
+            Timer::SourceCapability => T_TYPE_C_SEND_SOURCE_CAP_MS.nominal.0,
+            Timer::SenderResponse => T_SENDER_RESPONSE_MS.maximum.0,
+            Timer::SrcTransition => T_SRC_TRANSITION_MS.nominal.0,
+            Timer::PsHardReset => T_PS_HARD_RESET_MS.nominal.0,
+        }
+    }
+}
+
+/// Messages received from the sink
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Received {
+    /// `Request` with the raw RDO
+    Request(u32),
+    /// `Accept`
+    Accept,
+    /// `Get_Source_Cap`
+    GetSourceCap,
+    /// `Soft_Reset`
+    SoftReset,
+    /// Any message the source does not support
+    Unsupported,
+}
+
+/// Messages to send to the sink
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Message {
+    /// `Source_Capabilities` with the capabilities returned by [`StateMachine::capabilities`]
+    SourceCapabilities,
+    /// `Accept`
+    Accept,
+    /// `Reject`
+    Reject,
+    /// `Wait`
+    Wait,
+    /// `PS_RDY`
+    PsRdy,
+    /// `Soft_Reset`
+    SoftReset,
+    /// `Not_Supported`
+    NotSupported,
+}
+
+/// Device policy manager response to a request
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Response {
+    /// The request can be met
+    Accept,
+    /// The request can never be met
+    Reject,
+    /// The request can be met later
+    Wait,
+}
+
+/// Progress of the power supply transition
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SupplyPhase {
+    /// `Accept` sent, waiting `tSrcTransition`
+    SrcTransition,
+    /// Power supply transitioning
+    Transitioning,
+    /// Sending `PS_RDY`
+    PsRdy,
+}
+
+/// Source policy engine states
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum State {
+    /// `PE_SRC_Startup`
+    #[default]
+    Startup,
+    /// `PE_SRC_Discovery`, waiting to re-send `Source_Capabilities`
+    Discovery,
+    /// `PE_SRC_Send_Capabilities`
+    SendCapabilities,
+    /// `PE_SRC_Disabled`, the sink is not PD capable
+    Disabled,
+    /// `PE_SRC_Negotiate_Capability`, waiting for the device policy manager to evaluate the request
+    NegotiateCapability(Contract),
+    /// `PE_SRC_Transition_Supply`
+    TransitionSupply(Contract, SupplyPhase),
+    /// `PE_SRC_Ready`
+    Ready,
+    /// `PE_SRC_Capability_Response`, sending `Reject` or `Wait`
+    CapabilityResponse,
+    /// `PE_SRC_Wait_New_Capabilities`, waiting for the device policy manager to provide new capabilities
+    WaitNewCapabilities,
+    /// `PE_SRC_Soft_Reset`, `Soft_Reset` received and sending `Accept`
+    SoftReset,
+    /// `PE_SRC_Send_Soft_Reset`, `Soft_Reset` sent and waiting for `Accept`
+    SendSoftReset,
+    /// `PE_SRC_Hard_Reset`, sending hard reset signaling
+    HardReset,
+    /// `PE_SRC_Transition_to_default`, waiting for the power supply to return to default
+    TransitionToDefault,
+}
+
+/// Inputs to the source policy engine
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Input {
+    /// Start the policy engine after attach or after returning to default
+    Start,
+    /// Message received from the sink
+    Received(Received),
+    /// The last sent message was acknowledged with GoodCRC
+    MessageSent,
+    /// The last sent message was not acknowledged after all retries
+    TransmitFailed,
+    /// Timer expired
+    TimerExpired(Timer),
+    /// Device policy manager response to [`Output::EvaluateRequest`]
+    Evaluated(Response),
+    /// The power supply has completed the requested transition
+    PowerSupplyReady,
+    /// Device policy manager request to send updated capabilities
+    SendCapabilities,
+    /// Device policy manager request to send `Soft_Reset`
+    SoftReset,
+    /// Device policy manager request to send hard reset signaling
+    HardReset,
+    /// Hard reset signaling has been sent
+    HardResetSent,
+    /// Hard reset signaling received from the sink
+    HardResetReceived,
+    /// The power supply has returned to vSafe5V
+    DefaultReached,
+}
+
+/// Outputs from the source policy engine
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Output {
+    /// Send the message and start the timer, if any, once it has been acknowledged with GoodCRC
+    Send {
+        /// Message to send
+        message: Message,
+        /// Timer to start
+        timer: Option<Timer>,
+    },
+    /// Start the timer
+    StartTimer(Timer),
+    /// Stop the running timer
+    StopTimer,
+    /// Reset the protocol layer
+    ResetProtocolLayer,
+    /// Evaluate the request against present power availability and provide [`Input::Evaluated`]
+    EvaluateRequest(Contract),
+    /// Transition the power supply to the contract and provide [`Input::PowerSupplyReady`] once done
+    TransitionPowerSupply(Contract),
+    /// An explicit contract has been established
+    ContractEstablished(Contract),
+    /// Send hard reset signaling and provide [`Input::HardResetSent`] once done
+    SendHardReset,
+    /// Transition VBUS to vSafe0V and then vSafe5V and provide [`Input::DefaultReached`] once done
+    TransitionToDefault,
+}
+
+/// Attempted transition that is not allowed by the state machine
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidTransition {
+    /// The current state of the state machine
+    pub state: State,
+    /// The input that was attempted
+    pub input: Input,
+}
+
+/// Returns the contract for a raw RDO if it is within the advertised capabilities
+fn check_request(capabilities: &[Pdo], rdo: u32) -> Option<Contract> {
+    validate::validate(capabilities, rdo, false).ok()
+}
+
+// Doctest tries to compile the mermaid code as rust so just disable it
+#[cfg_attr(not(doctest), aquamarine::aquamarine)]
+#[cfg_attr(not(doctest), doc = "include_mmd!(\"docs/policy/source.mmd\")")]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StateMachine {
+    state: State,
+    /// Advertised capabilities
+    capabilities: [Pdo; MAX_DATA_OBJECTS],
+    /// Number of advertised capabilities
+    num_capabilities: usize,
+    /// Current explicit contract
+    contract: Option<Contract>,
+    /// True once the sink has acknowledged `Source_Capabilities`
+    pd_connected: bool,
+    /// `CapsCounter`
+    caps_count: u8,
+}
+
+impl StateMachine {
+    /// Create a new state machine in the Startup state with no capabilities
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the current state
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Returns the current explicit contract
+    pub fn contract(&self) -> Option<Contract> {
+        self.contract
+    }
+
+    /// Returns the advertised capabilities
+    pub fn capabilities(&self) -> &[Pdo] {
+        self.capabilities.get(..self.num_capabilities).unwrap_or_default()
+    }
+
+    /// Set the capabilities to advertise, the first PDO must be the vSafe5V fixed PDO
+    ///
+    /// The new capabilities are advertised the next time `Source_Capabilities` is sent, see [`Input::SendCapabilities`].
+    pub fn set_capabilities(&mut self, capabilities: &[Pdo]) -> Result<(), PdError> {
+        match capabilities.first() {
+            Some(Pdo::Fixed(pdo)) if pdo.voltage_mv == 5000 => {}
+            _ => return Err(PdError::InvalidParams),
+        }
+
+        self.capabilities
+            .get_mut(..capabilities.len())
+            .ok_or(PdError::InvalidParams)?
+            .copy_from_slice(capabilities);
+        self.num_capabilities = capabilities.len();
+        Ok(())
+    }
+
+    /// Returns the number of `Source_Capabilities` messages sent without a response
+    pub fn caps_count(&self) -> u8 {
+        self.caps_count
+    }
+
+    fn send_capabilities(&mut self) -> (State, Option<Output>) {
+        self.caps_count = self.caps_count.saturating_add(1);
+        (
+            State::SendCapabilities,
+            Some(Output::Send {
+                message: Message::SourceCapabilities,
+                timer: Some(Timer::SenderResponse),
+            }),
+        )
+    }
+
+    fn hard_reset() -> (State, Option<Output>) {
+        (State::HardReset, Some(Output::SendHardReset))
+    }
+
+    fn negotiate(&mut self, rdo: u32) -> (State, Option<Output>) {
+        match check_request(self.capabilities(), rdo) {
+            Some(contract) => (
+                State::NegotiateCapability(contract),
+                Some(Output::EvaluateRequest(contract)),
+            ),
+            None => (
+                State::CapabilityResponse,
+                Some(Output::Send {
+                    message: Message::Reject,
+                    timer: None,
+                }),
+            ),
+        }
+    }
+
+    /// Transition the state machine based on the input and return the resulting action if any.
+    pub fn consume(&mut self, input: Input) -> Result<Option<Output>, InvalidTransition> {
+        use State::*;
+
+        let (next_state, output) = match (self.state, input) {
+            // Hard reset from the sink can occur in any state
+            (_, Input::HardResetReceived) => (TransitionToDefault, Some(Output::TransitionToDefault)),
+
+            // Startup
+            (Startup, Input::Start) => self.send_capabilities(),
+
+            // SendCapabilities transitions
+            (SendCapabilities, Input::MessageSent) => {
+                self.pd_connected = true;
+                self.caps_count = 0;
+                (SendCapabilities, None)
+            }
+            (SendCapabilities, Input::TransmitFailed) if !self.pd_connected => {
+                (Discovery, Some(Output::StartTimer(Timer::SourceCapability)))
+            }
+            (SendCapabilities, Input::TimerExpired(Timer::SenderResponse)) => Self::hard_reset(),
+            (SendCapabilities | Ready, Input::Received(Received::Request(rdo))) => self.negotiate(rdo),
+
+            // Discovery transitions
+            (Discovery, Input::TimerExpired(Timer::SourceCapability)) => {
+                if self.caps_count < N_CAPS_COUNT {
+                    self.send_capabilities()
+                } else {
+                    (Disabled, None)
+                }
+            }
+
+            // NegotiateCapability transitions
+            (NegotiateCapability(contract), Input::Evaluated(Response::Accept)) => (
+                TransitionSupply(contract, SupplyPhase::SrcTransition),
+                Some(Output::Send {
+                    message: Message::Accept,
+                    timer: Some(Timer::SrcTransition),
+                }),
+            ),
+            (NegotiateCapability(_), Input::Evaluated(response @ (Response::Reject | Response::Wait))) => (
+                CapabilityResponse,
+                Some(Output::Send {
+                    message: if response == Response::Wait {
+                        Message::Wait
+                    } else {
+                        Message::Reject
+                    },
+                    timer: None,
+                }),
+            ),
+
+            // TransitionSupply transitions
+            (TransitionSupply(contract, SupplyPhase::SrcTransition), Input::TimerExpired(Timer::SrcTransition)) => (
+                TransitionSupply(contract, SupplyPhase::Transitioning),
+                Some(Output::TransitionPowerSupply(contract)),
+            ),
+            (TransitionSupply(contract, SupplyPhase::Transitioning), Input::PowerSupplyReady) => (
+                TransitionSupply(contract, SupplyPhase::PsRdy),
+                Some(Output::Send {
+                    message: Message::PsRdy,
+                    timer: None,
+                }),
+            ),
+            (TransitionSupply(contract, SupplyPhase::PsRdy), Input::MessageSent) => {
+                self.contract = Some(contract);
+                (Ready, Some(Output::ContractEstablished(contract)))
+            }
+            (TransitionSupply(_, SupplyPhase::SrcTransition), Input::TransmitFailed) => {
+                (SendSoftReset, Some(Self::soft_reset_output()))
+            }
+            (TransitionSupply(_, SupplyPhase::PsRdy), Input::TransmitFailed) => Self::hard_reset(),
+
+            // CapabilityResponse transitions
+            (CapabilityResponse, Input::MessageSent) => {
+                if self.contract.is_some() {
+                    (Ready, None)
+                } else {
+                    (WaitNewCapabilities, None)
+                }
+            }
+            (WaitNewCapabilities | Ready, Input::SendCapabilities) => self.send_capabilities(),
+
+            // Ready transitions
+            (Ready, Input::Received(Received::GetSourceCap)) => self.send_capabilities(),
+            (Ready, Input::Received(Received::Unsupported)) => (
+                Ready,
+                Some(Output::Send {
+                    message: Message::NotSupported,
+                    timer: None,
+                }),
+            ),
+
+            // Soft reset received
+            (
+                SendCapabilities
+                | NegotiateCapability(_)
+                | Ready
+                | CapabilityResponse
+                | WaitNewCapabilities
+                | SendSoftReset,
+                Input::Received(Received::SoftReset),
+            ) => (
+                SoftReset,
+                Some(Output::Send {
+                    message: Message::Accept,
+                    timer: None,
+                }),
+            ),
+            (SoftReset, Input::MessageSent) => self.send_capabilities(),
+            (SoftReset, Input::TransmitFailed) => Self::hard_reset(),
+
+            // Soft reset sent, on request or after a protocol error
+            (
+                SendCapabilities | NegotiateCapability(_) | Ready | CapabilityResponse,
+                Input::SoftReset | Input::TransmitFailed,
+            ) => (SendSoftReset, Some(Self::soft_reset_output())),
+            (SendSoftReset, Input::Received(Received::Accept)) => self.send_capabilities(),
+            (SendSoftReset, Input::TimerExpired(Timer::SenderResponse) | Input::TransmitFailed) => Self::hard_reset(),
+
+            // Hard reset
+            (
+                SendCapabilities
+                | NegotiateCapability(_)
+                | TransitionSupply(..)
+                | Ready
+                | CapabilityResponse
+                | WaitNewCapabilities
+                | SoftReset
+                | SendSoftReset,
+                Input::HardReset,
+            ) => Self::hard_reset(),
+            (HardReset, Input::HardResetSent) => (HardReset, Some(Output::StartTimer(Timer::PsHardReset))),
+            (HardReset, Input::TimerExpired(Timer::PsHardReset)) => {
+                (TransitionToDefault, Some(Output::TransitionToDefault))
+            }
+            (TransitionToDefault, Input::DefaultReached) => (Startup, Some(Output::ResetProtocolLayer)),
+
+            // GoodCRC for a message that only starts a timer or needs no follow-up
+            (_, Input::MessageSent) => (self.state, None),
+
+            // Invalid transition
+            _ => {
+                return Err(InvalidTransition {
+                    state: self.state,
+                    input,
+                })
+            }
+        };
+
+        // Any reset ends the explicit contract
+        if matches!(next_state, SoftReset | SendSoftReset | HardReset | TransitionToDefault) {
+            self.contract = None;
+        }
+
+        // A reset returns the source to its initial PD connection state
+        if next_state == TransitionToDefault {
+            self.pd_connected = false;
+        }
+
+        self.state = next_state;
+        Ok(output)
+    }
+
+    fn soft_reset_output() -> Output {
+        Output::Send {
+            message: Message::SoftReset,
+            timer: Some(Timer::SenderResponse),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::pdo::rdo::tests::{fixed_rdo, FIXED_RDO};
+    use crate::pdo::source::{Apdo, FixedData, SprPpsData};
+
+    /// Fixed RDO for object position 1 with 3.5 A operating and max current
+    const FIXED_RDO_OVER: u32 = 0x1005_785E;
+    /// PPS RDO for object position 2 at 9 V and 2 A
+    const PPS_RDO: u32 = 0x2003_8428;
+
+    const CAPABILITIES: [Pdo; 2] = [
+        Pdo::Fixed(FixedData {
+            dual_role_power: false,
+            usb_suspend_supported: false,
+            unconstrained_power: false,
+            usb_comms_capable: false,
+            dual_role_data: false,
+            unchunked_extended_messages_support: false,
+            epr_capable: false,
+            peak_current: crate::pdo::source::PeakCurrent::Pct100,
+            voltage_mv: 5000,
+            current_ma: 3000,
+        }),
+        Pdo::Augmented(Apdo::SprPps(SprPpsData {
+            pps_power_limited: false,
+            max_voltage_mv: 11000,
+            min_voltage_mv: 3300,
+            max_current_ma: 3000,
+        })),
+    ];
+
+    fn send(message: Message, timer: Option<Timer>) -> Result<Option<Output>, InvalidTransition> {
+        Ok(Some(Output::Send { message, timer }))
+    }
+
+    fn contract(rdo: u32) -> Option<Contract> {
+        check_request(&CAPABILITIES, rdo)
+    }
+
+    /// Drive the state machine until the sink has acknowledged Source_Capabilities
+    fn send_capabilities(sm: &mut StateMachine) {
+        assert_eq!(sm.set_capabilities(&CAPABILITIES), Ok(()));
+        assert_eq!(
+            sm.consume(Input::Start),
+            send(Message::SourceCapabilities, Some(Timer::SenderResponse))
+        );
+        assert_eq!(sm.consume(Input::MessageSent), Ok(None));
+        assert_eq!(sm.state(), State::SendCapabilities);
+    }
+
+    /// Drive the state machine to the Ready state with an explicit contract
+    fn negotiate(sm: &mut StateMachine, rdo: u32) {
+        let contract = contract(rdo);
+        assert!(contract.is_some());
+        assert_eq!(
+            sm.consume(Input::Received(Received::Request(rdo))),
+            Ok(contract.map(Output::EvaluateRequest))
+        );
+        assert_eq!(
+            sm.consume(Input::Evaluated(Response::Accept)),
+            send(Message::Accept, Some(Timer::SrcTransition))
+        );
+        assert_eq!(sm.consume(Input::MessageSent), Ok(None));
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::SrcTransition)),
+            Ok(contract.map(Output::TransitionPowerSupply))
+        );
+        assert_eq!(sm.consume(Input::PowerSupplyReady), send(Message::PsRdy, None));
+        assert_eq!(
+            sm.consume(Input::MessageSent),
+            Ok(contract.map(Output::ContractEstablished))
+        );
+        assert_eq!(sm.state(), State::Ready);
+        assert_eq!(sm.contract(), contract);
+    }
+
+    #[test]
+    fn test_check_request() {
+        assert_eq!(contract(FIXED_RDO).map(|contract| contract.rdo), Some(fixed_rdo(1)));
+        assert!(contract(PPS_RDO).is_some());
+        // Current above the PDO maximum
+        assert_eq!(contract(FIXED_RDO_OVER), None);
+        // Object position out of range
+        assert_eq!(contract(FIXED_RDO & 0x0FFF_FFFF), None);
+        assert_eq!(contract((FIXED_RDO & 0x0FFF_FFFF) | 0x3000_0000), None);
+    }
+
+    #[test]
+    fn test_negotiate() {
+        let mut sm = StateMachine::new();
+        send_capabilities(&mut sm);
+        negotiate(&mut sm, FIXED_RDO);
+        assert!(sm.contract().is_some());
+
+        // Renegotiate from Ready
+        negotiate(&mut sm, PPS_RDO);
+        assert_eq!(sm.contract(), contract(PPS_RDO));
+    }
+
+    #[test]
+    fn test_invalid_request() {
+        let mut sm = StateMachine::new();
+        send_capabilities(&mut sm);
+        assert_eq!(
+            sm.consume(Input::Received(Received::Request(FIXED_RDO_OVER))),
+            send(Message::Reject, None)
+        );
+        assert_eq!(sm.state(), State::CapabilityResponse);
+
+        // No explicit contract, wait for new capabilities
+        assert_eq!(sm.consume(Input::MessageSent), Ok(None));
+        assert_eq!(sm.state(), State::WaitNewCapabilities);
+        assert_eq!(
+            sm.consume(Input::SendCapabilities),
+            send(Message::SourceCapabilities, Some(Timer::SenderResponse))
+        );
+    }
+
+    #[test]
+    fn test_capability_response() {
+        let mut sm = StateMachine::new();
+        send_capabilities(&mut sm);
+        negotiate(&mut sm, FIXED_RDO);
+
+        // Wait with an explicit contract returns to Ready
+        sm.consume(Input::Received(Received::Request(PPS_RDO))).ok();
+        assert_eq!(sm.consume(Input::Evaluated(Response::Wait)), send(Message::Wait, None));
+        assert_eq!(sm.consume(Input::MessageSent), Ok(None));
+        assert_eq!(sm.state(), State::Ready);
+        assert_eq!(sm.contract(), contract(FIXED_RDO));
+    }
+
+    #[test]
+    fn test_discovery() {
+        let mut sm = StateMachine::new();
+        assert_eq!(sm.set_capabilities(&CAPABILITIES), Ok(()));
+        sm.consume(Input::Start).ok();
+
+        // No GoodCRC, the sink is not PD connected yet
+        for _ in 1..N_CAPS_COUNT {
+            assert_eq!(
+                sm.consume(Input::TransmitFailed),
+                Ok(Some(Output::StartTimer(Timer::SourceCapability)))
+            );
+            assert_eq!(
+                sm.consume(Input::TimerExpired(Timer::SourceCapability)),
+                send(Message::SourceCapabilities, Some(Timer::SenderResponse))
+            );
+        }
+        assert_eq!(sm.caps_count(), N_CAPS_COUNT);
+        sm.consume(Input::TransmitFailed).ok();
+        assert_eq!(sm.consume(Input::TimerExpired(Timer::SourceCapability)), Ok(None));
+        assert_eq!(sm.state(), State::Disabled);
+    }
+
+    /// Drive the state machine through a hard reset back to Startup
+    fn hard_reset(sm: &mut StateMachine) {
+        assert_eq!(
+            sm.consume(Input::HardResetSent),
+            Ok(Some(Output::StartTimer(Timer::PsHardReset)))
+        );
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::PsHardReset)),
+            Ok(Some(Output::TransitionToDefault))
+        );
+        assert_eq!(sm.consume(Input::DefaultReached), Ok(Some(Output::ResetProtocolLayer)));
+        assert_eq!(sm.state(), State::Startup);
+    }
+
+    #[test]
+    fn test_hard_reset() {
+        let mut sm = StateMachine::new();
+        send_capabilities(&mut sm);
+
+        // The sink acknowledges Source_Capabilities but never sends a Request, this repeats indefinitely
+        for _ in 0..3 {
+            assert_eq!(
+                sm.consume(Input::TimerExpired(Timer::SenderResponse)),
+                Ok(Some(Output::SendHardReset))
+            );
+            hard_reset(&mut sm);
+            sm.consume(Input::Start).ok();
+            assert_eq!(sm.consume(Input::MessageSent), Ok(None));
+            assert_eq!(sm.state(), State::SendCapabilities);
+        }
+
+        // The sink stops responding after the hard reset
+        sm.consume(Input::TimerExpired(Timer::SenderResponse)).ok();
+        hard_reset(&mut sm);
+        sm.consume(Input::Start).ok();
+        assert_eq!(
+            sm.consume(Input::TransmitFailed),
+            Ok(Some(Output::StartTimer(Timer::SourceCapability)))
+        );
+        assert_eq!(sm.state(), State::Discovery);
+    }
+
+    #[test]
+    fn test_soft_reset() {
+        let mut sm = StateMachine::new();
+        send_capabilities(&mut sm);
+        negotiate(&mut sm, FIXED_RDO);
+
+        assert_eq!(
+            sm.consume(Input::Received(Received::SoftReset)),
+            send(Message::Accept, None)
+        );
+        assert_eq!(sm.contract(), None);
+        assert_eq!(
+            sm.consume(Input::MessageSent),
+            send(Message::SourceCapabilities, Some(Timer::SenderResponse))
+        );
+
+        // Protocol error in Ready
+        sm.consume(Input::MessageSent).ok();
+        negotiate(&mut sm, FIXED_RDO);
+        assert_eq!(
+            sm.consume(Input::TransmitFailed),
+            send(Message::SoftReset, Some(Timer::SenderResponse))
+        );
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::SenderResponse)),
+            Ok(Some(Output::SendHardReset))
+        );
+    }
+
+    #[test]
+    fn test_set_capabilities() {
+        let mut sm = StateMachine::new();
+        assert_eq!(sm.set_capabilities(&[]), Err(PdError::InvalidParams));
+        assert_eq!(sm.set_capabilities(&CAPABILITIES[1..]), Err(PdError::InvalidParams));
+        assert_eq!(
+            sm.set_capabilities(&[CAPABILITIES[0]; MAX_DATA_OBJECTS + 1]),
+            Err(PdError::InvalidParams)
+        );
+        assert_eq!(sm.set_capabilities(&CAPABILITIES), Ok(()));
+        assert_eq!(sm.capabilities(), CAPABILITIES);
+    }
+}
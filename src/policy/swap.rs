@@ -0,0 +1,635 @@
+//! Power role, data role and VCONN swap sequencers as defined in 8.3.3.18 - 8.3.3.20 of the USB PD spec.
+//!
+//! A [`StateMachine`] runs a single swap sequence, either as the initiator after [`Input::Initiate`] or as the
+//! responder after receiving the swap request. Actions that need the power supply, CC pull resistors or VCONN are
+//! requested through [`Output`] and must be confirmed with [`Input::ActionComplete`]. The sequence ends with
+//! [`Output::Complete`], after which the caller returns to the policy engine for the resulting power role.
+//!
+//! As with the other policy engines, at most one timer runs at a time, any output other than [`Output::Send`] stops the
+//! running timer and the result of every [`Output::Send`] must be provided through [`Input::MessageSent`] or
+//! [`Input::TransmitFailed`].
+
+use crate::constants::{
+    T_DR_SWAP_WAIT_MS, T_PR_SWAP_WAIT_MS, T_PS_SOURCE_OFF_MS, T_PS_SOURCE_ON_MS, T_SENDER_RESPONSE_MS,
+    T_SRC_TRANSITION_MS, T_VCONN_SOURCE_TIMEOUT_MS, T_VCONN_SWAP_WAIT_MS,
+};
+use crate::{DataRole, PdError, PowerRole};
+
+/// Swap type
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SwapType {
+    /// `PR_Swap`
+    PowerRole,
+    /// `DR_Swap`
+    DataRole,
+    /// `VCONN_Swap`
+    Vconn,
+}
+
+impl SwapType {
+    /// Returns the minimum time to wait in milliseconds before re-sending the request after a `Wait` response
+    pub const fn wait_ms(self) -> u16 {
+        match self {
+            SwapType::PowerRole => T_PR_SWAP_WAIT_MS.0,
+            SwapType::DataRole => T_DR_SWAP_WAIT_MS.0,
+            SwapType::Vconn => T_VCONN_SWAP_WAIT_MS.0,
+        }
+    }
+}
+
+/// Swap to perform, with the role of the port before the swap
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Swap {
+    /// Power role swap from the given power role
+    PowerRole(PowerRole),
+    /// Data role swap from the given data role
+    DataRole(DataRole),
+    /// VCONN swap, true if the port is currently the VCONN source
+    Vconn(bool),
+}
+
+impl Swap {
+    /// Returns the swap type
+    pub const fn swap_type(self) -> SwapType {
+        match self {
+            Swap::PowerRole(_) => SwapType::PowerRole,
+            Swap::DataRole(_) => SwapType::DataRole,
+            Swap::Vconn(_) => SwapType::Vconn,
+        }
+    }
+}
+
+/// Timers used by the swap sequencers
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Timer {
+    /// `SenderResponseTimer`, waiting for a response to the swap request
+    SenderResponse,
+    /// Time from `Accept` to the old source turning off, `tSrcTransition`
+    SrcTransition,
+    /// `PSSourceOffTimer`, the old sink waiting for `PS_RDY` from the old source
+    PsSourceOff,
+    /// `PSSourceOnTimer`, the old source waiting for `PS_RDY` from the new source
+    PsSourceOn,
+    /// `VCONNOnTimer`, the old VCONN source waiting for `PS_RDY` from the new VCONN source
+    VconnOn,
+}
+
+impl Timer {
+    /// Returns the timeout for this timer in milliseconds
+    ///
+    /// Timers waiting on the port partner use the maximum allowed duration, timers delaying the port use the nominal
+    /// one.
+    pub const fn duration_ms(self) -> u16 {
+        match self {
+            Timer::SenderResponse => T_SENDER_RESPONSE_MS.maximum.0,
+            Timer::SrcTransition => T_SRC_TRANSITION_MS.nominal.0,
+            Timer::PsSourceOff => T_PS_SOURCE_OFF_MS.maximum.0,
+            Timer::PsSourceOn => T_PS_SOURCE_ON_MS.maximum.0,
+            Timer::VconnOn => T_VCONN_SOURCE_TIMEOUT_MS.maximum.0,
+        }
+    }
+}
+
+/// Messages received from the port partner
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Received {
+    /// `PR_Swap`, `DR_Swap` or `VCONN_Swap`
+    Swap(SwapType),
+    /// `Accept`
+    Accept,
+    /// `Reject`
+    Reject,
+    /// `Wait`
+    Wait,
+    /// `PS_RDY`
+    PsRdy,
+}
+
+/// Messages to send to the port partner
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Message {
+    /// `PR_Swap`, `DR_Swap` or `VCONN_Swap`
+    Swap(SwapType),
+    /// `Accept`
+    Accept,
+    /// `Reject`
+    Reject,
+    /// `Wait`
+    Wait,
+    /// `PS_RDY`
+    PsRdy,
+}
+
+/// Device policy manager response to a swap request
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Response {
+    /// The swap can be performed
+    Accept,
+    /// The swap is not supported
+    Reject,
+    /// The swap can be performed later
+    Wait,
+}
+
+/// Swap sequencer states
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum State {
+    /// Waiting for the swap to be initiated or received
+    Idle,
+    /// Swap request sent, waiting for a response
+    SendSwap,
+    /// Waiting for the device policy manager to evaluate the received request
+    EvaluateSwap,
+    /// Sending `Accept` to the received request
+    SendAccept,
+    /// Sending `Reject` or `Wait` to the received request
+    SendReject(Response),
+    /// Old source waiting `tSrcTransition` before turning off
+    SourceTransitionToOff,
+    /// Old source turning off its power supply
+    SourceOff,
+    /// Old source asserting Rd
+    AssertRd,
+    /// Old source waiting for `PS_RDY` from the new source
+    WaitSourceOn,
+    /// Old sink reducing its current draw to standby
+    SinkStandby,
+    /// Old sink waiting for `PS_RDY` from the old source
+    WaitSourceOff,
+    /// Old sink asserting Rp and turning on its power supply
+    SourceOn,
+    /// Changing the data role
+    ChangeDataRole,
+    /// Old VCONN source waiting for `PS_RDY` from the new VCONN source
+    WaitVconnOn,
+    /// Old VCONN source turning off VCONN
+    VconnOff,
+    /// New VCONN source turning on VCONN
+    VconnOn,
+    /// Sending the final `PS_RDY`
+    SendPsRdy,
+    /// The swap sequence has completed, successfully or not
+    Complete,
+}
+
+/// Inputs to the swap sequencers
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Input {
+    /// Device policy manager request to initiate the swap
+    Initiate,
+    /// Message received from the port partner
+    Received(Received),
+    /// The last sent message was acknowledged with GoodCRC
+    MessageSent,
+    /// The last sent message was not acknowledged after all retries
+    TransmitFailed,
+    /// Timer expired
+    TimerExpired(Timer),
+    /// Device policy manager response to [`Output::EvaluateSwap`]
+    Evaluated(Response),
+    /// The action requested by the last output has completed
+    ActionComplete,
+}
+
+/// Outputs from the swap sequencers
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Output {
+    /// Send the message and start the timer, if any, once it has been acknowledged with GoodCRC
+    Send {
+        /// Message to send
+        message: Message,
+        /// Timer to start
+        timer: Option<Timer>,
+    },
+    /// Start the timer
+    StartTimer(Timer),
+    /// Evaluate the received swap request and provide [`Input::Evaluated`]
+    EvaluateSwap(SwapType),
+    /// Transition the power supply to vSafe0V
+    SourceOff,
+    /// Reduce the sink current draw to `iSnkStdby`
+    SinkStandby,
+    /// Assert Rd for [`PowerRole::Sink`], or assert Rp and turn on the power supply to vSafe5V for
+    /// [`PowerRole::Source`]
+    SetPowerRole(PowerRole),
+    /// Change to the given data role
+    SetDataRole(DataRole),
+    /// Turn VCONN on or off
+    SetVconn(bool),
+    /// The swap sequence has completed
+    ///
+    /// On failure the error is one of:
+    /// * [`PdError::SwapRejectedPartner`] if the port partner responded with `Reject`
+    /// * [`PdError::Busy`] if the port partner responded with `Wait`, see [`SwapType::wait_ms`]
+    /// * [`PdError::SwapRejected`] if the device policy manager rejected the request or responded with `Wait`
+    /// * [`PdError::Timeout`] if the port partner did not respond to the request
+    /// * [`PdError::Failed`] if a message could not be sent before any roles changed, a soft reset is required
+    /// * [`PdError::ErrorRecovery`] if a power role swap failed after the power roles started changing
+    /// * [`PdError::HardReset`] if a VCONN swap failed after VCONN started changing
+    Complete(Result<(), PdError>),
+}
+
+/// Attempted transition that is not allowed by the state machine
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidTransition {
+    /// The current state of the state machine
+    pub state: State,
+    /// The input that was attempted
+    pub input: Input,
+}
+
+// Doctest tries to compile the mermaid code as rust so just disable it
+#[cfg_attr(not(doctest), aquamarine::aquamarine)]
+#[cfg_attr(not(doctest), doc = "include_mmd!(\"docs/policy/swap.mmd\")")]
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StateMachine {
+    state: State,
+    swap: Swap,
+}
+
+impl StateMachine {
+    /// Create a new state machine for the given swap in the Idle state
+    pub const fn new(swap: Swap) -> Self {
+        Self {
+            state: State::Idle,
+            swap,
+        }
+    }
+
+    /// Returns the current state
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Returns the swap performed by this state machine
+    pub fn swap(&self) -> Swap {
+        self.swap
+    }
+
+    /// Returns the first step after `Accept` has been sent or received
+    fn accepted(&self) -> (State, Option<Output>) {
+        match self.swap {
+            Swap::PowerRole(PowerRole::Source) => (
+                State::SourceTransitionToOff,
+                Some(Output::StartTimer(Timer::SrcTransition)),
+            ),
+            Swap::PowerRole(PowerRole::Sink) => (State::SinkStandby, Some(Output::SinkStandby)),
+            Swap::DataRole(role) => {
+                let role = match role {
+                    DataRole::Ufp => DataRole::Dfp,
+                    DataRole::Dfp => DataRole::Ufp,
+                };
+                (State::ChangeDataRole, Some(Output::SetDataRole(role)))
+            }
+            Swap::Vconn(true) => (State::WaitVconnOn, Some(Output::StartTimer(Timer::VconnOn))),
+            Swap::Vconn(false) => (State::VconnOn, Some(Output::SetVconn(true))),
+        }
+    }
+
+    /// Returns the completion for a failure after roles started changing
+    fn role_change_failed(&self) -> (State, Option<Output>) {
+        // A hard reset has no defined power role in the middle of a power role swap
+        let error = match self.swap {
+            Swap::PowerRole(_) => PdError::ErrorRecovery,
+            Swap::DataRole(_) | Swap::Vconn(_) => PdError::HardReset,
+        };
+        (State::Complete, Some(Output::Complete(Err(error))))
+    }
+
+    /// Transition the state machine based on the input and return the resulting action if any.
+    pub fn consume(&mut self, input: Input) -> Result<Option<Output>, InvalidTransition> {
+        use State::*;
+
+        let swap_type = self.swap.swap_type();
+        let (next_state, output) = match (self.state, input) {
+            // Initiator
+            (Idle, Input::Initiate) => (
+                SendSwap,
+                Some(Output::Send {
+                    message: Message::Swap(swap_type),
+                    timer: Some(Timer::SenderResponse),
+                }),
+            ),
+            (SendSwap, Input::Received(Received::Accept)) => self.accepted(),
+            (SendSwap, Input::Received(Received::Reject)) => {
+                (Complete, Some(Output::Complete(Err(PdError::SwapRejectedPartner))))
+            }
+            (SendSwap, Input::Received(Received::Wait)) => (Complete, Some(Output::Complete(Err(PdError::Busy)))),
+            (SendSwap, Input::TimerExpired(Timer::SenderResponse)) => {
+                (Complete, Some(Output::Complete(Err(PdError::Timeout))))
+            }
+
+            // Responder
+            (Idle, Input::Received(Received::Swap(received))) if received == swap_type => {
+                (EvaluateSwap, Some(Output::EvaluateSwap(swap_type)))
+            }
+            (EvaluateSwap, Input::Evaluated(Response::Accept)) => (
+                SendAccept,
+                Some(Output::Send {
+                    message: Message::Accept,
+                    timer: None,
+                }),
+            ),
+            (EvaluateSwap, Input::Evaluated(response @ (Response::Reject | Response::Wait))) => (
+                SendReject(response),
+                Some(Output::Send {
+                    message: if response == Response::Wait {
+                        Message::Wait
+                    } else {
+                        Message::Reject
+                    },
+                    timer: None,
+                }),
+            ),
+            (SendAccept, Input::MessageSent) => self.accepted(),
+            (SendReject(_), Input::MessageSent) => (Complete, Some(Output::Complete(Err(PdError::SwapRejected)))),
+
+            // Nothing has changed yet, the caller performs a soft reset
+            (SendSwap | SendAccept | SendReject(_), Input::TransmitFailed) => {
+                (Complete, Some(Output::Complete(Err(PdError::Failed))))
+            }
+
+            // Old source
+            (SourceTransitionToOff, Input::TimerExpired(Timer::SrcTransition)) => (SourceOff, Some(Output::SourceOff)),
+            (SourceOff, Input::ActionComplete) => (AssertRd, Some(Output::SetPowerRole(PowerRole::Sink))),
+            (AssertRd, Input::ActionComplete) => (
+                WaitSourceOn,
+                Some(Output::Send {
+                    message: Message::PsRdy,
+                    timer: Some(Timer::PsSourceOn),
+                }),
+            ),
+            (WaitSourceOn, Input::Received(Received::PsRdy)) => (Complete, Some(Output::Complete(Ok(())))),
+
+            // Old sink
+            (SinkStandby, Input::ActionComplete) => (WaitSourceOff, Some(Output::StartTimer(Timer::PsSourceOff))),
+            (WaitSourceOff, Input::Received(Received::PsRdy)) => {
+                (SourceOn, Some(Output::SetPowerRole(PowerRole::Source)))
+            }
+            (SourceOn, Input::ActionComplete) => (
+                SendPsRdy,
+                Some(Output::Send {
+                    message: Message::PsRdy,
+                    timer: None,
+                }),
+            ),
+
+            // Data role
+            (ChangeDataRole, Input::ActionComplete) => (Complete, Some(Output::Complete(Ok(())))),
+
+            // Old VCONN source
+            (WaitVconnOn, Input::Received(Received::PsRdy)) => (VconnOff, Some(Output::SetVconn(false))),
+            (VconnOff, Input::ActionComplete) => (Complete, Some(Output::Complete(Ok(())))),
+
+            // New VCONN source
+            (VconnOn, Input::ActionComplete) => (
+                SendPsRdy,
+                Some(Output::Send {
+                    message: Message::PsRdy,
+                    timer: None,
+                }),
+            ),
+
+            (SendPsRdy, Input::MessageSent) => (Complete, Some(Output::Complete(Ok(())))),
+
+            // Failures once roles have started changing
+            (WaitSourceOn, Input::TimerExpired(Timer::PsSourceOn))
+            | (WaitSourceOff, Input::TimerExpired(Timer::PsSourceOff))
+            | (WaitVconnOn, Input::TimerExpired(Timer::VconnOn))
+            | (WaitSourceOn | SendPsRdy, Input::TransmitFailed) => self.role_change_failed(),
+
+            // GoodCRC for a message that only starts a timer or needs no follow-up
+            (_, Input::MessageSent) => (self.state, None),
+
+            // Invalid transition
+            _ => {
+                return Err(InvalidTransition {
+                    state: self.state,
+                    input,
+                })
+            }
+        };
+
+        self.state = next_state;
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn send(message: Message, timer: Option<Timer>) -> Result<Option<Output>, InvalidTransition> {
+        Ok(Some(Output::Send { message, timer }))
+    }
+
+    fn complete(result: Result<(), PdError>) -> Result<Option<Output>, InvalidTransition> {
+        Ok(Some(Output::Complete(result)))
+    }
+
+    /// Drive the state machine through the initiator request and Accept response and return the first action
+    fn initiate(sm: &mut StateMachine) -> Option<Output> {
+        let swap_type = sm.swap().swap_type();
+        assert_eq!(
+            sm.consume(Input::Initiate),
+            send(Message::Swap(swap_type), Some(Timer::SenderResponse))
+        );
+        assert_eq!(sm.consume(Input::MessageSent), Ok(None));
+        sm.consume(Input::Received(Received::Accept)).ok().flatten()
+    }
+
+    /// Drive the state machine through the received request and Accept response and return the first action
+    fn respond(sm: &mut StateMachine) -> Option<Output> {
+        let swap_type = sm.swap().swap_type();
+        assert_eq!(
+            sm.consume(Input::Received(Received::Swap(swap_type))),
+            Ok(Some(Output::EvaluateSwap(swap_type)))
+        );
+        assert_eq!(
+            sm.consume(Input::Evaluated(Response::Accept)),
+            send(Message::Accept, None)
+        );
+        sm.consume(Input::MessageSent).ok().flatten()
+    }
+
+    fn source_to_sink(sm: &mut StateMachine) {
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::SrcTransition)),
+            Ok(Some(Output::SourceOff))
+        );
+        assert_eq!(
+            sm.consume(Input::ActionComplete),
+            Ok(Some(Output::SetPowerRole(PowerRole::Sink)))
+        );
+        assert_eq!(
+            sm.consume(Input::ActionComplete),
+            send(Message::PsRdy, Some(Timer::PsSourceOn))
+        );
+        assert_eq!(sm.consume(Input::MessageSent), Ok(None));
+        assert_eq!(sm.consume(Input::Received(Received::PsRdy)), complete(Ok(())));
+    }
+
+    fn sink_to_source(sm: &mut StateMachine) {
+        assert_eq!(
+            sm.consume(Input::ActionComplete),
+            Ok(Some(Output::StartTimer(Timer::PsSourceOff)))
+        );
+        assert_eq!(
+            sm.consume(Input::Received(Received::PsRdy)),
+            Ok(Some(Output::SetPowerRole(PowerRole::Source)))
+        );
+        assert_eq!(sm.consume(Input::ActionComplete), send(Message::PsRdy, None));
+        assert_eq!(sm.consume(Input::MessageSent), complete(Ok(())));
+    }
+
+    #[test]
+    fn test_power_role_swap() {
+        let mut sm = StateMachine::new(Swap::PowerRole(PowerRole::Source));
+        assert_eq!(initiate(&mut sm), Some(Output::StartTimer(Timer::SrcTransition)));
+        source_to_sink(&mut sm);
+
+        let mut sm = StateMachine::new(Swap::PowerRole(PowerRole::Source));
+        assert_eq!(respond(&mut sm), Some(Output::StartTimer(Timer::SrcTransition)));
+        source_to_sink(&mut sm);
+
+        let mut sm = StateMachine::new(Swap::PowerRole(PowerRole::Sink));
+        assert_eq!(initiate(&mut sm), Some(Output::SinkStandby));
+        sink_to_source(&mut sm);
+
+        let mut sm = StateMachine::new(Swap::PowerRole(PowerRole::Sink));
+        assert_eq!(respond(&mut sm), Some(Output::SinkStandby));
+        sink_to_source(&mut sm);
+        assert_eq!(sm.state(), State::Complete);
+    }
+
+    #[test]
+    fn test_power_role_swap_timeout() {
+        let mut sm = StateMachine::new(Swap::PowerRole(PowerRole::Sink));
+        assert_eq!(initiate(&mut sm), Some(Output::SinkStandby));
+        sm.consume(Input::ActionComplete).ok();
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::PsSourceOff)),
+            complete(Err(PdError::ErrorRecovery))
+        );
+
+        let mut sm = StateMachine::new(Swap::PowerRole(PowerRole::Source));
+        initiate(&mut sm);
+        sm.consume(Input::TimerExpired(Timer::SrcTransition)).ok();
+        sm.consume(Input::ActionComplete).ok();
+        sm.consume(Input::ActionComplete).ok();
+        assert_eq!(sm.state(), State::WaitSourceOn);
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::PsSourceOn)),
+            complete(Err(PdError::ErrorRecovery))
+        );
+
+        // PS_RDY not delivered by the new source
+        let mut sm = StateMachine::new(Swap::PowerRole(PowerRole::Sink));
+        initiate(&mut sm);
+        sm.consume(Input::ActionComplete).ok();
+        sm.consume(Input::Received(Received::PsRdy)).ok();
+        sm.consume(Input::ActionComplete).ok();
+        assert_eq!(sm.state(), State::SendPsRdy);
+        assert_eq!(sm.consume(Input::TransmitFailed), complete(Err(PdError::ErrorRecovery)));
+    }
+
+    #[test]
+    fn test_data_role_swap() {
+        let mut sm = StateMachine::new(Swap::DataRole(DataRole::Ufp));
+        assert_eq!(initiate(&mut sm), Some(Output::SetDataRole(DataRole::Dfp)));
+        assert_eq!(sm.consume(Input::ActionComplete), complete(Ok(())));
+
+        let mut sm = StateMachine::new(Swap::DataRole(DataRole::Dfp));
+        assert_eq!(respond(&mut sm), Some(Output::SetDataRole(DataRole::Ufp)));
+        assert_eq!(sm.consume(Input::ActionComplete), complete(Ok(())));
+    }
+
+    #[test]
+    fn test_vconn_swap() {
+        let mut sm = StateMachine::new(Swap::Vconn(true));
+        assert_eq!(initiate(&mut sm), Some(Output::StartTimer(Timer::VconnOn)));
+        assert_eq!(
+            sm.consume(Input::Received(Received::PsRdy)),
+            Ok(Some(Output::SetVconn(false)))
+        );
+        assert_eq!(sm.consume(Input::ActionComplete), complete(Ok(())));
+
+        let mut sm = StateMachine::new(Swap::Vconn(false));
+        assert_eq!(respond(&mut sm), Some(Output::SetVconn(true)));
+        assert_eq!(sm.consume(Input::ActionComplete), send(Message::PsRdy, None));
+        assert_eq!(sm.consume(Input::MessageSent), complete(Ok(())));
+
+        let mut sm = StateMachine::new(Swap::Vconn(true));
+        assert_eq!(respond(&mut sm), Some(Output::StartTimer(Timer::VconnOn)));
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::VconnOn)),
+            complete(Err(PdError::HardReset))
+        );
+
+        let mut sm = StateMachine::new(Swap::Vconn(false));
+        respond(&mut sm);
+        sm.consume(Input::ActionComplete).ok();
+        assert_eq!(sm.consume(Input::TransmitFailed), complete(Err(PdError::HardReset)));
+    }
+
+    #[test]
+    fn test_initiator_failures() {
+        let mut sm = StateMachine::new(Swap::DataRole(DataRole::Ufp));
+        sm.consume(Input::Initiate).ok();
+        assert_eq!(
+            sm.consume(Input::Received(Received::Reject)),
+            complete(Err(PdError::SwapRejectedPartner))
+        );
+
+        let mut sm = StateMachine::new(Swap::DataRole(DataRole::Ufp));
+        sm.consume(Input::Initiate).ok();
+        assert_eq!(
+            sm.consume(Input::Received(Received::Wait)),
+            complete(Err(PdError::Busy))
+        );
+
+        let mut sm = StateMachine::new(Swap::DataRole(DataRole::Ufp));
+        sm.consume(Input::Initiate).ok();
+        sm.consume(Input::MessageSent).ok();
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::SenderResponse)),
+            complete(Err(PdError::Timeout))
+        );
+
+        let mut sm = StateMachine::new(Swap::DataRole(DataRole::Ufp));
+        sm.consume(Input::Initiate).ok();
+        assert_eq!(sm.consume(Input::TransmitFailed), complete(Err(PdError::Failed)));
+    }
+
+    #[test]
+    fn test_responder_reject() {
+        for (response, message) in [(Response::Reject, Message::Reject), (Response::Wait, Message::Wait)] {
+            let mut sm = StateMachine::new(Swap::PowerRole(PowerRole::Sink));
+            sm.consume(Input::Received(Received::Swap(SwapType::PowerRole))).ok();
+            assert_eq!(sm.consume(Input::Evaluated(response)), send(message, None));
+            assert_eq!(sm.consume(Input::MessageSent), complete(Err(PdError::SwapRejected)));
+        }
+
+        // Request for a different swap type
+        let mut sm = StateMachine::new(Swap::PowerRole(PowerRole::Sink));
+        let input = Input::Received(Received::Swap(SwapType::Vconn));
+        assert_eq!(
+            sm.consume(input),
+            Err(InvalidTransition {
+                state: State::Idle,
+                input
+            })
+        );
+    }
+}
@@ -0,0 +1,534 @@
+//! Message header as defined in the USB PD specification 6.2.1.1
+use bitfield::bitfield;
+
+use crate::revision::{InvalidSpecRevision, SpecRevision};
+use crate::{DataRole, PdError, PowerRole};
+
+/// Error type for message type conversion, contains the raw value
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidMessageType(pub u8);
+
+impl From<InvalidMessageType> for PdError {
+    fn from(_: InvalidMessageType) -> Self {
+        PdError::InvalidParams
+    }
+}
+
+/// Control message types, see PD spec 6.3
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ControlMessageType {
+    /// `GoodCRC`
+    GoodCrc,
+    /// `GotoMin`
+    GotoMin,
+    /// `Accept`
+    Accept,
+    /// `Reject`
+    Reject,
+    /// `Ping`
+    Ping,
+    /// `PS_RDY`
+    PsRdy,
+    /// `Get_Source_Cap`
+    GetSourceCap,
+    /// `Get_Sink_Cap`
+    GetSinkCap,
+    /// `DR_Swap`
+    DrSwap,
+    /// `PR_Swap`
+    PrSwap,
+    /// `VCONN_Swap`
+    VconnSwap,
+    /// `Wait`
+    Wait,
+    /// `Soft_Reset`
+    SoftReset,
+    /// `Data_Reset`
+    DataReset,
+    /// `Data_Reset_Complete`
+    DataResetComplete,
+    /// `Not_Supported`
+    NotSupported,
+    /// `Get_Source_Cap_Extended`
+    GetSourceCapExtended,
+    /// `Get_Status`
+    GetStatus,
+    /// `FR_Swap`
+    FrSwap,
+    /// `Get_PPS_Status`
+    GetPpsStatus,
+    /// `Get_Country_Codes`
+    GetCountryCodes,
+    /// `Get_Sink_Cap_Extended`
+    GetSinkCapExtended,
+    /// `Get_Source_Info`
+    GetSourceInfo,
+    /// `Get_Revision`
+    GetRevision,
+}
+
+impl TryFrom<u8> for ControlMessageType {
+    type Error = InvalidMessageType;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        use ControlMessageType::*;
+        Ok(match value {
+            0x01 => GoodCrc,
+            0x02 => GotoMin,
+            0x03 => Accept,
+            0x04 => Reject,
+            0x05 => Ping,
+            0x06 => PsRdy,
+            0x07 => GetSourceCap,
+            0x08 => GetSinkCap,
+            0x09 => DrSwap,
+            0x0A => PrSwap,
+            0x0B => VconnSwap,
+            0x0C => Wait,
+            0x0D => SoftReset,
+            0x0E => DataReset,
+            0x0F => DataResetComplete,
+            0x10 => NotSupported,
+            0x11 => GetSourceCapExtended,
+            0x12 => GetStatus,
+            0x13 => FrSwap,
+            0x14 => GetPpsStatus,
+            0x15 => GetCountryCodes,
+            0x16 => GetSinkCapExtended,
+            0x17 => GetSourceInfo,
+            0x18 => GetRevision,
+            _ => return Err(InvalidMessageType(value)),
+        })
+    }
+}
+
+impl From<ControlMessageType> for u8 {
+    fn from(value: ControlMessageType) -> Self {
+        use ControlMessageType::*;
+        match value {
+            GoodCrc => 0x01,
+            GotoMin => 0x02,
+            Accept => 0x03,
+            Reject => 0x04,
+            Ping => 0x05,
+            PsRdy => 0x06,
+            GetSourceCap => 0x07,
+            GetSinkCap => 0x08,
+            DrSwap => 0x09,
+            PrSwap => 0x0A,
+            VconnSwap => 0x0B,
+            Wait => 0x0C,
+            SoftReset => 0x0D,
+            DataReset => 0x0E,
+            DataResetComplete => 0x0F,
+            NotSupported => 0x10,
+            GetSourceCapExtended => 0x11,
+            GetStatus => 0x12,
+            FrSwap => 0x13,
+            GetPpsStatus => 0x14,
+            GetCountryCodes => 0x15,
+            GetSinkCapExtended => 0x16,
+            GetSourceInfo => 0x17,
+            GetRevision => 0x18,
+        }
+    }
+}
+
+/// Data message types, see PD spec 6.4
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum DataMessageType {
+    /// `Source_Capabilities`
+    SourceCapabilities,
+    /// `Request`
+    Request,
+    /// `BIST`
+    Bist,
+    /// `Sink_Capabilities`
+    SinkCapabilities,
+    /// `Battery_Status`
+    BatteryStatus,
+    /// `Alert`
+    Alert,
+    /// `Get_Country_Info`
+    GetCountryInfo,
+    /// `Enter_USB`
+    EnterUsb,
+    /// `EPR_Request`
+    EprRequest,
+    /// `EPR_Mode`
+    EprMode,
+    /// `Source_Info`
+    SourceInfo,
+    /// `Revision`
+    Revision,
+    /// `Vendor_Defined`
+    VendorDefined,
+}
+
+impl TryFrom<u8> for DataMessageType {
+    type Error = InvalidMessageType;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        use DataMessageType::*;
+        Ok(match value {
+            0x01 => SourceCapabilities,
+            0x02 => Request,
+            0x03 => Bist,
+            0x04 => SinkCapabilities,
+            0x05 => BatteryStatus,
+            0x06 => Alert,
+            0x07 => GetCountryInfo,
+            0x08 => EnterUsb,
+            0x09 => EprRequest,
+            0x0A => EprMode,
+            0x0B => SourceInfo,
+            0x0C => Revision,
+            0x0F => VendorDefined,
+            _ => return Err(InvalidMessageType(value)),
+        })
+    }
+}
+
+impl From<DataMessageType> for u8 {
+    fn from(value: DataMessageType) -> Self {
+        use DataMessageType::*;
+        match value {
+            SourceCapabilities => 0x01,
+            Request => 0x02,
+            Bist => 0x03,
+            SinkCapabilities => 0x04,
+            BatteryStatus => 0x05,
+            Alert => 0x06,
+            GetCountryInfo => 0x07,
+            EnterUsb => 0x08,
+            EprRequest => 0x09,
+            EprMode => 0x0A,
+            SourceInfo => 0x0B,
+            Revision => 0x0C,
+            VendorDefined => 0x0F,
+        }
+    }
+}
+
+/// Extended message types, see PD spec 6.5
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ExtendedMessageType {
+    /// `Source_Capabilities_Extended`
+    SourceCapabilitiesExtended,
+    /// `Status`
+    Status,
+    /// `Get_Battery_Cap`
+    GetBatteryCap,
+    /// `Get_Battery_Status`
+    GetBatteryStatus,
+    /// `Battery_Capabilities`
+    BatteryCapabilities,
+    /// `Get_Manufacturer_Info`
+    GetManufacturerInfo,
+    /// `Manufacturer_Info`
+    ManufacturerInfo,
+    /// `Security_Request`
+    SecurityRequest,
+    /// `Security_Response`
+    SecurityResponse,
+    /// `Firmware_Update_Request`
+    FirmwareUpdateRequest,
+    /// `Firmware_Update_Response`
+    FirmwareUpdateResponse,
+    /// `PPS_Status`
+    PpsStatus,
+    /// `Country_Info`
+    CountryInfo,
+    /// `Country_Codes`
+    CountryCodes,
+    /// `Sink_Capabilities_Extended`
+    SinkCapabilitiesExtended,
+    /// `Extended_Control`
+    ExtendedControl,
+    /// `EPR_Source_Capabilities`
+    EprSourceCapabilities,
+    /// `EPR_Sink_Capabilities`
+    EprSinkCapabilities,
+    /// `Vendor_Defined_Extended`
+    VendorDefinedExtended,
+}
+
+impl TryFrom<u8> for ExtendedMessageType {
+    type Error = InvalidMessageType;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        use ExtendedMessageType::*;
+        Ok(match value {
+            0x01 => SourceCapabilitiesExtended,
+            0x02 => Status,
+            0x03 => GetBatteryCap,
+            0x04 => GetBatteryStatus,
+            0x05 => BatteryCapabilities,
+            0x06 => GetManufacturerInfo,
+            0x07 => ManufacturerInfo,
+            0x08 => SecurityRequest,
+            0x09 => SecurityResponse,
+            0x0A => FirmwareUpdateRequest,
+            0x0B => FirmwareUpdateResponse,
+            0x0C => PpsStatus,
+            0x0D => CountryInfo,
+            0x0E => CountryCodes,
+            0x0F => SinkCapabilitiesExtended,
+            0x10 => ExtendedControl,
+            0x11 => EprSourceCapabilities,
+            0x12 => EprSinkCapabilities,
+            0x1E => VendorDefinedExtended,
+            _ => return Err(InvalidMessageType(value)),
+        })
+    }
+}
+
+impl From<ExtendedMessageType> for u8 {
+    fn from(value: ExtendedMessageType) -> Self {
+        use ExtendedMessageType::*;
+        match value {
+            SourceCapabilitiesExtended => 0x01,
+            Status => 0x02,
+            GetBatteryCap => 0x03,
+            GetBatteryStatus => 0x04,
+            BatteryCapabilities => 0x05,
+            GetManufacturerInfo => 0x06,
+            ManufacturerInfo => 0x07,
+            SecurityRequest => 0x08,
+            SecurityResponse => 0x09,
+            FirmwareUpdateRequest => 0x0A,
+            FirmwareUpdateResponse => 0x0B,
+            PpsStatus => 0x0C,
+            CountryInfo => 0x0D,
+            CountryCodes => 0x0E,
+            SinkCapabilitiesExtended => 0x0F,
+            ExtendedControl => 0x10,
+            EprSourceCapabilities => 0x11,
+            EprSinkCapabilities => 0x12,
+            VendorDefinedExtended => 0x1E,
+        }
+    }
+}
+
+/// Message type, the message class is determined by the extended bit and the number of data objects
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum MessageType {
+    /// Control message, no data objects
+    Control(ControlMessageType),
+    /// Data message, one or more data objects
+    Data(DataMessageType),
+    /// Extended message
+    Extended(ExtendedMessageType),
+}
+
+bitfield! {
+    /// Raw message header
+    #[derive(Copy, Clone, Default, PartialEq, Eq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    struct MessageHeaderRaw(u16);
+    impl Debug;
+
+    /// Message type
+    pub u8, message_type, set_message_type: 4, 0;
+    /// Port data role, reserved for SOP' and SOP''
+    pub bool, port_data_role, set_port_data_role: 5;
+    /// Specification revision
+    pub u8, spec_revision, set_spec_revision: 7, 6;
+    /// Port power role for SOP, cable plug for SOP' and SOP''
+    pub bool, port_power_role, set_port_power_role: 8;
+    /// Message ID
+    pub u8, message_id, set_message_id: 11, 9;
+    /// Number of data objects
+    pub u8, num_data_objects, set_num_data_objects: 14, 12;
+    /// Extended message
+    pub bool, extended, set_extended: 15;
+}
+
+/// Message header
+///
+/// Only the message type is validated when decoding, so the message ID of any received message can be read to
+/// generate a GoodCRC.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MessageHeader(MessageHeaderRaw);
+
+impl MessageHeader {
+    /// Create a new header for the given message type and number of data objects
+    pub fn new(message_type: MessageType, num_data_objects: u8) -> Self {
+        let mut header = MessageHeader::default();
+        header.set_message_type(message_type);
+        header.0.set_num_data_objects(num_data_objects);
+        header
+    }
+
+    /// Returns the message type
+    pub fn message_type(&self) -> Result<MessageType, InvalidMessageType> {
+        let message_type = self.0.message_type();
+        if self.0.extended() {
+            Ok(MessageType::Extended(message_type.try_into()?))
+        } else if self.0.num_data_objects() == 0 {
+            Ok(MessageType::Control(message_type.try_into()?))
+        } else {
+            Ok(MessageType::Data(message_type.try_into()?))
+        }
+    }
+
+    /// Set the message type, this also sets the extended bit
+    pub fn set_message_type(&mut self, message_type: MessageType) {
+        let (extended, raw) = match message_type {
+            MessageType::Control(message_type) => (false, message_type.into()),
+            MessageType::Data(message_type) => (false, message_type.into()),
+            MessageType::Extended(message_type) => (true, message_type.into()),
+        };
+        self.0.set_extended(extended);
+        self.0.set_message_type(raw);
+    }
+
+    /// Returns true if this is the given control message
+    pub fn is_control(&self, message_type: ControlMessageType) -> bool {
+        self.message_type() == Ok(MessageType::Control(message_type))
+    }
+
+    /// Returns the port data role, only valid for SOP messages
+    pub fn port_data_role(&self) -> DataRole {
+        if self.0.port_data_role() {
+            DataRole::Dfp
+        } else {
+            DataRole::Ufp
+        }
+    }
+
+    /// Set the port data role, only valid for SOP messages
+    pub fn set_port_data_role(&mut self, role: DataRole) {
+        self.0.set_port_data_role(role == DataRole::Dfp);
+    }
+
+    /// Returns the specification revision
+    pub fn spec_revision(&self) -> Result<SpecRevision, InvalidSpecRevision> {
+        self.0.spec_revision().try_into()
+    }
+
+    /// Set the specification revision
+    pub fn set_spec_revision(&mut self, revision: SpecRevision) {
+        self.0.set_spec_revision(revision.into());
+    }
+
+    /// Returns the port power role, only valid for SOP messages
+    pub fn port_power_role(&self) -> PowerRole {
+        if self.0.port_power_role() {
+            PowerRole::Source
+        } else {
+            PowerRole::Sink
+        }
+    }
+
+    /// Set the port power role, only valid for SOP messages
+    pub fn set_port_power_role(&mut self, role: PowerRole) {
+        self.0.set_port_power_role(role == PowerRole::Source);
+    }
+
+    /// Returns true if the message originated from a cable plug, only valid for SOP' and SOP'' messages
+    pub fn cable_plug(&self) -> bool {
+        self.0.port_power_role()
+    }
+
+    /// Set the cable plug bit, only valid for SOP' and SOP'' messages
+    pub fn set_cable_plug(&mut self, cable_plug: bool) {
+        self.0.set_port_power_role(cable_plug);
+    }
+
+    /// Returns the message ID
+    pub fn message_id(&self) -> u8 {
+        self.0.message_id()
+    }
+
+    /// Set the message ID
+    pub fn set_message_id(&mut self, message_id: u8) {
+        self.0.set_message_id(message_id);
+    }
+
+    /// Returns the number of data objects
+    pub fn num_data_objects(&self) -> u8 {
+        self.0.num_data_objects()
+    }
+
+    /// Set the number of data objects
+    pub fn set_num_data_objects(&mut self, num_data_objects: u8) {
+        self.0.set_num_data_objects(num_data_objects);
+    }
+
+    /// Returns true if this is an extended message
+    pub fn extended(&self) -> bool {
+        self.0.extended()
+    }
+}
+
+impl From<u16> for MessageHeader {
+    fn from(value: u16) -> Self {
+        MessageHeader(MessageHeaderRaw(value))
+    }
+}
+
+impl From<MessageHeader> for u16 {
+    fn from(value: MessageHeader) -> Self {
+        value.0 .0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_message_header_decode() {
+        // Source_Capabilities with 2 PDOs, message ID 3, source, DFP, revision 3.x
+        let header = MessageHeader::from(0x27A1);
+        assert_eq!(
+            header.message_type(),
+            Ok(MessageType::Data(DataMessageType::SourceCapabilities))
+        );
+        assert_eq!(header.port_data_role(), DataRole::Dfp);
+        assert_eq!(header.spec_revision(), Ok(SpecRevision::Rev3));
+        assert_eq!(header.port_power_role(), PowerRole::Source);
+        assert_eq!(header.message_id(), 3);
+        assert_eq!(header.num_data_objects(), 2);
+        assert!(!header.extended());
+    }
+
+    #[test]
+    fn test_message_header_encode() {
+        let mut header = MessageHeader::new(MessageType::Control(ControlMessageType::GoodCrc), 0);
+        header.set_port_data_role(DataRole::Ufp);
+        header.set_spec_revision(SpecRevision::Rev3);
+        header.set_port_power_role(PowerRole::Sink);
+        header.set_message_id(5);
+        assert_eq!(u16::from(header), 0x0A81);
+        assert!(header.is_control(ControlMessageType::GoodCrc));
+
+        let header = MessageHeader::new(MessageType::Extended(ExtendedMessageType::Status), 2);
+        assert_eq!(u16::from(header), 0xA002);
+        assert_eq!(
+            header.message_type(),
+            Ok(MessageType::Extended(ExtendedMessageType::Status))
+        );
+    }
+
+    #[test]
+    fn test_message_header_invalid_type() {
+        // Reserved control message type
+        assert_eq!(
+            MessageHeader::from(0x001F).message_type(),
+            Err(InvalidMessageType(0x1F))
+        );
+        // Reserved data message type
+        assert_eq!(
+            MessageHeader::from(0x100D).message_type(),
+            Err(InvalidMessageType(0x0D))
+        );
+    }
+}
@@ -0,0 +1,228 @@
+//! Per-SOP* protocol layer state as defined in the USB PD specification 6.12
+//!
+//! This tracks the MessageID counter, the last received MessageID and the retry counter for a single SOP* type. It
+//! performs no I/O, the caller is responsible for transmitting messages and feeding received headers back in.
+use super::header::{ControlMessageType, MessageHeader, MessageType};
+use crate::constants::{N_MESSAGE_ID_COUNT, N_RETRY_COUNT};
+
+/// Result of a failed transmission
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TransmitOutcome {
+    /// The message should be retransmitted
+    Retry,
+    /// All retries have been exhausted
+    Failed,
+}
+
+/// Result of processing a received message
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum ReceiveOutcome {
+    /// New message that should be passed to the policy engine
+    Message,
+    /// Retransmission of the previous message that should be acknowledged and discarded
+    Duplicate,
+    /// Soft_Reset message, the protocol layer has been reset
+    SoftReset,
+}
+
+/// Protocol layer state for a single SOP* type
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ProtocolLayer {
+    /// MessageIDCounter, the ID of the next message to transmit
+    message_id_counter: u8,
+    /// Stored MessageID of the last received message
+    stored_message_id: Option<u8>,
+    /// RetryCounter for the message being transmitted
+    retry_counter: u8,
+}
+
+impl ProtocolLayer {
+    /// Create a new protocol layer
+    pub const fn new() -> Self {
+        Self {
+            message_id_counter: 0,
+            stored_message_id: None,
+            retry_counter: 0,
+        }
+    }
+
+    /// Reset the protocol layer, this occurs on soft reset, hard reset and on attach
+    pub fn reset(&mut self) {
+        *self = Self::new();
+    }
+
+    /// Returns the ID of the next message to transmit
+    pub fn message_id_counter(&self) -> u8 {
+        self.message_id_counter
+    }
+
+    /// Returns the ID of the last received message
+    pub fn stored_message_id(&self) -> Option<u8> {
+        self.stored_message_id
+    }
+
+    /// Prepare a message for transmission by stamping the message ID into the header
+    ///
+    /// A Soft_Reset message resets the protocol layer before being transmitted.
+    pub fn start_transmit(&mut self, header: &mut MessageHeader) {
+        if header.is_control(ControlMessageType::SoftReset) {
+            self.reset();
+        }
+
+        header.set_message_id(self.message_id_counter);
+        self.retry_counter = 0;
+    }
+
+    /// Returns true if the given header is a GoodCRC acknowledging the message being transmitted
+    pub fn is_good_crc_for_transmit(&self, header: &MessageHeader) -> bool {
+        header.is_control(ControlMessageType::GoodCrc) && header.message_id() == self.message_id_counter
+    }
+
+    /// The message was acknowledged with a GoodCRC
+    pub fn transmit_complete(&mut self) {
+        self.increment_message_id();
+    }
+
+    /// No GoodCRC was received for the message being transmitted
+    pub fn transmit_failed(&mut self) -> TransmitOutcome {
+        if self.retry_counter < N_RETRY_COUNT {
+            self.retry_counter += 1;
+            TransmitOutcome::Retry
+        } else {
+            self.increment_message_id();
+            TransmitOutcome::Failed
+        }
+    }
+
+    /// The PHY has given up on the message being transmitted after performing its own retries
+    pub fn transmit_aborted(&mut self) {
+        self.increment_message_id();
+    }
+
+    /// A message was received before the GoodCRC, the message being transmitted is discarded
+    pub fn transmit_discarded(&mut self) {
+        self.increment_message_id();
+    }
+
+    /// Process the header of a received message, GoodCRC messages must not be passed to this function
+    pub fn receive(&mut self, header: &MessageHeader) -> ReceiveOutcome {
+        let message_id = header.message_id();
+        if header.is_control(ControlMessageType::SoftReset) {
+            self.reset();
+            self.stored_message_id = Some(message_id);
+            ReceiveOutcome::SoftReset
+        } else if self.stored_message_id == Some(message_id) {
+            ReceiveOutcome::Duplicate
+        } else {
+            self.stored_message_id = Some(message_id);
+            ReceiveOutcome::Message
+        }
+    }
+
+    /// Returns the header of the GoodCRC acknowledging the given received message
+    ///
+    /// Port roles and specification revision are left to the caller to fill in.
+    pub fn good_crc(&self, received: &MessageHeader) -> MessageHeader {
+        let mut header = MessageHeader::new(MessageType::Control(ControlMessageType::GoodCrc), 0);
+        header.set_message_id(received.message_id());
+        header
+    }
+
+    fn increment_message_id(&mut self) {
+        self.message_id_counter = (self.message_id_counter + 1) % (N_MESSAGE_ID_COUNT + 1);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::header::DataMessageType;
+
+    fn header(message_type: MessageType, message_id: u8) -> MessageHeader {
+        let mut header = MessageHeader::new(message_type, 0);
+        header.set_message_id(message_id);
+        header
+    }
+
+    fn control(message_type: ControlMessageType, message_id: u8) -> MessageHeader {
+        header(MessageType::Control(message_type), message_id)
+    }
+
+    #[test]
+    fn test_transmit_message_id_wraps() {
+        let mut layer = ProtocolLayer::new();
+        for expected in (0..=N_MESSAGE_ID_COUNT).chain([0]) {
+            let mut header = control(ControlMessageType::Accept, 0);
+            layer.start_transmit(&mut header);
+            assert_eq!(header.message_id(), expected);
+            assert!(layer.is_good_crc_for_transmit(&control(ControlMessageType::GoodCrc, expected)));
+            layer.transmit_complete();
+        }
+    }
+
+    #[test]
+    fn test_transmit_retries() {
+        let mut layer = ProtocolLayer::new();
+        let mut header = control(ControlMessageType::GetSourceCap, 0);
+        layer.start_transmit(&mut header);
+        assert!(!layer.is_good_crc_for_transmit(&control(ControlMessageType::GoodCrc, 1)));
+        assert!(!layer.is_good_crc_for_transmit(&control(ControlMessageType::Accept, 0)));
+
+        for _ in 0..N_RETRY_COUNT {
+            assert_eq!(layer.transmit_failed(), TransmitOutcome::Retry);
+        }
+        assert_eq!(layer.transmit_failed(), TransmitOutcome::Failed);
+        // The message ID is still incremented for a failed transmission
+        assert_eq!(layer.message_id_counter(), 1);
+
+        // Retry counter is reset for the next message
+        layer.start_transmit(&mut header);
+        assert_eq!(layer.transmit_failed(), TransmitOutcome::Retry);
+    }
+
+    #[test]
+    fn test_receive_duplicate() {
+        let mut layer = ProtocolLayer::new();
+        let request = header(MessageType::Data(DataMessageType::Request), 3);
+        assert_eq!(layer.receive(&request), ReceiveOutcome::Message);
+        assert_eq!(layer.receive(&request), ReceiveOutcome::Duplicate);
+        assert_eq!(layer.stored_message_id(), Some(3));
+        assert_eq!(layer.good_crc(&request), control(ControlMessageType::GoodCrc, 3));
+
+        assert_eq!(
+            layer.receive(&control(ControlMessageType::Accept, 4)),
+            ReceiveOutcome::Message
+        );
+    }
+
+    #[test]
+    fn test_soft_reset() {
+        let mut layer = ProtocolLayer::new();
+        let mut header = control(ControlMessageType::Accept, 0);
+        layer.start_transmit(&mut header);
+        layer.transmit_complete();
+        assert_eq!(
+            layer.receive(&control(ControlMessageType::PsRdy, 5)),
+            ReceiveOutcome::Message
+        );
+
+        // Received soft reset clears the counters and stores its own ID
+        assert_eq!(
+            layer.receive(&control(ControlMessageType::SoftReset, 0)),
+            ReceiveOutcome::SoftReset
+        );
+        assert_eq!(layer.message_id_counter(), 0);
+        assert_eq!(layer.stored_message_id(), Some(0));
+
+        // Transmitted soft reset always uses message ID 0
+        layer.start_transmit(&mut header);
+        layer.transmit_complete();
+        let mut soft_reset = control(ControlMessageType::SoftReset, 0);
+        layer.start_transmit(&mut soft_reset);
+        assert_eq!(soft_reset.message_id(), 0);
+        assert_eq!(layer.stored_message_id(), None);
+    }
+}
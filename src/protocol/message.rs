@@ -0,0 +1,230 @@
+//! Raw PD messages and the codec trait used to produce typed messages
+use super::header::{ControlMessageType, MessageHeader, MessageType};
+use crate::PdError;
+
+/// Length of the message header in bytes
+pub const HEADER_LEN: usize = 2;
+/// Length of a data object in bytes
+pub const DATA_OBJECT_LEN: usize = 4;
+/// Maximum number of data objects in a message
+pub const MAX_DATA_OBJECTS: usize = 7;
+/// Maximum payload length in bytes, excluding the header
+pub const MAX_PAYLOAD_LEN: usize = MAX_DATA_OBJECTS * DATA_OBJECT_LEN;
+/// Maximum message length in bytes, excluding the CRC
+pub const MAX_MESSAGE_LEN: usize = HEADER_LEN + MAX_PAYLOAD_LEN;
+
+/// Message as received or transmitted on the wire, a header followed by an opaque payload
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RawMessage {
+    /// Message header
+    pub header: MessageHeader,
+    /// Payload storage
+    payload: [u8; MAX_PAYLOAD_LEN],
+    /// Payload length
+    len: usize,
+}
+
+impl RawMessage {
+    /// Create a new message with the given header and payload
+    pub fn new(header: MessageHeader, payload: &[u8]) -> Result<Self, PdError> {
+        let mut message = RawMessage {
+            header,
+            payload: [0; MAX_PAYLOAD_LEN],
+            len: payload.len(),
+        };
+        message
+            .payload
+            .get_mut(..payload.len())
+            .ok_or(PdError::InvalidParams)?
+            .copy_from_slice(payload);
+        Ok(message)
+    }
+
+    /// Create a new message from data objects, the number of data objects in the header is updated
+    pub fn from_data_objects(mut header: MessageHeader, objects: &[u32]) -> Result<Self, PdError> {
+        if objects.len() > MAX_DATA_OBJECTS {
+            return Err(PdError::InvalidParams);
+        }
+
+        header.set_num_data_objects(objects.len() as u8);
+        let mut message = RawMessage::new(header, &[])?;
+        for (chunk, object) in message.payload.chunks_exact_mut(DATA_OBJECT_LEN).zip(objects) {
+            chunk.copy_from_slice(&object.to_le_bytes());
+        }
+        message.len = objects.len() * DATA_OBJECT_LEN;
+        Ok(message)
+    }
+
+    /// Returns the message payload
+    pub fn payload(&self) -> &[u8] {
+        self.payload.get(..self.len).unwrap_or_default()
+    }
+
+    /// Returns an iterator over the data objects in the payload
+    pub fn data_objects(&self) -> impl Iterator<Item = u32> + '_ {
+        self.payload().chunks_exact(DATA_OBJECT_LEN).map(|chunk| {
+            let mut bytes = [0; DATA_OBJECT_LEN];
+            bytes.copy_from_slice(chunk);
+            u32::from_le_bytes(bytes)
+        })
+    }
+
+    /// Returns the encoded length of the message
+    pub fn encoded_len(&self) -> usize {
+        HEADER_LEN + self.len
+    }
+
+    /// Encode the message into the given buffer, returns the number of bytes written
+    pub fn encode_into_slice(&self, buf: &mut [u8]) -> Result<usize, PdError> {
+        let len = self.encoded_len();
+        let buf = buf.get_mut(..len).ok_or(PdError::InvalidParams)?;
+        let (header, payload) = buf.split_at_mut(HEADER_LEN);
+        header.copy_from_slice(&u16::from(self.header).to_le_bytes());
+        payload.copy_from_slice(self.payload());
+        Ok(len)
+    }
+}
+
+impl TryFrom<&[u8]> for RawMessage {
+    type Error = PdError;
+
+    fn try_from(value: &[u8]) -> Result<Self, Self::Error> {
+        let header = value.get(..HEADER_LEN).ok_or(PdError::InvalidParams)?;
+        let header = MessageHeader::from(u16::from_le_bytes([
+            header.first().copied().unwrap_or_default(),
+            header.get(1).copied().unwrap_or_default(),
+        ]));
+        let payload = value.get(HEADER_LEN..).unwrap_or_default();
+
+        // Non-extended messages must carry exactly the advertised number of data objects
+        if !header.extended() && payload.len() != header.num_data_objects() as usize * DATA_OBJECT_LEN {
+            return Err(PdError::InvalidParams);
+        }
+
+        RawMessage::new(header, payload)
+    }
+}
+
+/// Codec trait implemented by typed messages handled by the protocol layer
+///
+/// The protocol layer fills in the message ID, port roles and specification revision of the header when
+/// transmitting, implementations only need to provide the message type and payload.
+pub trait Message: Sized {
+    /// Decode the message from a raw message
+    fn from_raw(raw: &RawMessage) -> Result<Self, PdError>;
+    /// Encode the message into a raw message
+    fn to_raw(&self) -> Result<RawMessage, PdError>;
+}
+
+impl Message for RawMessage {
+    fn from_raw(raw: &RawMessage) -> Result<Self, PdError> {
+        Ok(*raw)
+    }
+
+    fn to_raw(&self) -> Result<RawMessage, PdError> {
+        Ok(*self)
+    }
+}
+
+impl Message for ControlMessageType {
+    fn from_raw(raw: &RawMessage) -> Result<Self, PdError> {
+        match raw.header.message_type()? {
+            MessageType::Control(message_type) if raw.payload().is_empty() => Ok(message_type),
+            _ => Err(PdError::InvalidParams),
+        }
+    }
+
+    fn to_raw(&self) -> Result<RawMessage, PdError> {
+        RawMessage::new(MessageHeader::new(MessageType::Control(*self), 0), &[])
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::pdo::rdo::tests::FIXED_RDO;
+    use crate::protocol::header::DataMessageType;
+
+    /// Returns a message without a payload, unlike [`RawMessage::new`] this cannot fail
+    pub(crate) fn header_only(header: MessageHeader) -> RawMessage {
+        RawMessage {
+            header,
+            payload: [0; MAX_PAYLOAD_LEN],
+            len: 0,
+        }
+    }
+
+    #[test]
+    fn test_header_only_fixture() {
+        let header = MessageHeader::new(MessageType::Control(ControlMessageType::Accept), 0);
+        assert_eq!(RawMessage::new(header, &[]), Ok(header_only(header)));
+    }
+
+    #[test]
+    fn test_raw_message_roundtrip() {
+        // Source_Capabilities with a single 5 V 3 A fixed PDO
+        let bytes = [0xA1, 0x11, 0x2C, 0x91, 0x01, 0x08];
+        let message = RawMessage::try_from(bytes.as_slice());
+        assert_eq!(
+            message.map(|message| message.header.message_type()),
+            Ok(Ok(MessageType::Data(DataMessageType::SourceCapabilities)))
+        );
+        assert!(message.is_ok_and(|message| message.data_objects().eq([0x0801_912C])));
+
+        let mut buf = [0; MAX_MESSAGE_LEN];
+        assert_eq!(message.and_then(|message| message.encode_into_slice(&mut buf)), Ok(6));
+        assert_eq!(buf.get(..6), Some(bytes.as_slice()));
+    }
+
+    #[test]
+    fn test_raw_message_from_data_objects() {
+        let header = MessageHeader::new(MessageType::Data(DataMessageType::Request), 0);
+        let message = RawMessage::from_data_objects(header, &[FIXED_RDO]);
+        assert!(message.is_ok_and(
+            |message| message.header.num_data_objects() == 1 && message.payload() == [0x2C, 0xB1, 0x04, 0x10]
+        ));
+        assert_eq!(
+            RawMessage::from_data_objects(header, &[0; MAX_DATA_OBJECTS + 1]),
+            Err(PdError::InvalidParams)
+        );
+    }
+
+    #[test]
+    fn test_raw_message_invalid() {
+        // Too short for a header
+        assert_eq!(RawMessage::try_from([0xA1].as_slice()), Err(PdError::InvalidParams));
+        // Header advertises one data object but none present
+        assert_eq!(
+            RawMessage::try_from([0xA1, 0x11].as_slice()),
+            Err(PdError::InvalidParams)
+        );
+    }
+
+    #[test]
+    fn test_control_message() {
+        // PS_RDY, message ID 2, source, DFP, revision 3.x
+        let raw = RawMessage::try_from([0xA6, 0x05].as_slice());
+        assert_eq!(
+            raw.and_then(|raw| ControlMessageType::from_raw(&raw)),
+            Ok(ControlMessageType::PsRdy)
+        );
+
+        let raw = ControlMessageType::Accept.to_raw();
+        let mut buf = [0; MAX_MESSAGE_LEN];
+        assert_eq!(raw.and_then(|raw| raw.encode_into_slice(&mut buf)), Ok(2));
+        assert_eq!(buf.get(..2), Some([0x03, 0x00].as_slice()));
+        assert_eq!(
+            raw.and_then(|raw| ControlMessageType::from_raw(&raw)),
+            Ok(ControlMessageType::Accept)
+        );
+
+        // Data messages are not control messages
+        let header = MessageHeader::new(MessageType::Data(DataMessageType::Request), 0);
+        let raw = RawMessage::from_data_objects(header, &[FIXED_RDO]);
+        assert_eq!(
+            raw.and_then(|raw| ControlMessageType::from_raw(&raw)),
+            Err(PdError::InvalidParams)
+        );
+    }
+}
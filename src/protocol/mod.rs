@@ -0,0 +1,541 @@
+//! Transport-independent USB PD protocol layer as defined in the USB PD specification 6.12
+//!
+//! The protocol layer sits between the policy engine and the physical layer. It handles MessageID counters,
+//! duplicate detection, GoodCRC generation and checking, retries and Soft_Reset for each SOP* type. The per-SOP*
+//! bookkeeping is implemented without I/O in [`layer::ProtocolLayer`], [`Protocol`] drives it on top of a
+//! [`PhyTransmit`]/[`PhyReceive`] implementation.
+use crate::constants::T_RECEIVE_US;
+use crate::revision::SpecRevision;
+use crate::{DataRole, Error, PdError, PowerRole};
+
+pub mod header;
+pub mod layer;
+pub mod message;
+
+use header::{ControlMessageType, MessageHeader, MessageType};
+use layer::{ProtocolLayer, ReceiveOutcome, TransmitOutcome};
+use message::{Message, RawMessage, MAX_MESSAGE_LEN};
+
+/// SOP* packet type
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Sop {
+    /// Port partner
+    Sop,
+    /// Cable plug closest to the source
+    SopPrime,
+    /// Cable plug furthest from the source
+    SopDoublePrime,
+    /// Debug, cable plug closest to the source
+    SopPrimeDebug,
+    /// Debug, cable plug furthest from the source
+    SopDoublePrimeDebug,
+}
+
+/// Physical layer transmit interface
+///
+/// Implementations are responsible for framing, 4b5b encoding and CRC generation.
+#[allow(async_fn_in_trait)]
+pub trait PhyTransmit {
+    /// Bus error type
+    type Error;
+
+    /// True if the PHY retries transmissions itself and reports the final outcome
+    ///
+    /// The protocol layer does not retry transmissions on such a PHY, a transmission without a GoodCRC has failed.
+    const HANDLES_RETRIES: bool = false;
+
+    /// Transmit an encoded message, `data` contains the header and payload without the CRC
+    async fn transmit(&mut self, sop: Sop, data: &[u8]) -> Result<(), Self::Error>;
+    /// Transmit a Hard_Reset ordered set
+    async fn transmit_hard_reset(&mut self) -> Result<(), Self::Error>;
+}
+
+/// Physical layer receive interface
+///
+/// Implementations are responsible for discarding messages with an invalid CRC.
+#[allow(async_fn_in_trait)]
+pub trait PhyReceive {
+    /// Bus error type
+    type Error;
+
+    /// Receive a message into `buf`, returns the SOP* type and the length of the header and payload
+    ///
+    /// Returns `None` if no message was received within `timeout_us`, waits indefinitely if `timeout_us` is `None`.
+    async fn receive(&mut self, buf: &mut [u8], timeout_us: Option<u16>) -> Result<Option<(Sop, usize)>, Self::Error>;
+}
+
+/// Protocol layer configuration, used to fill in message headers
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Config {
+    /// Negotiated specification revision
+    pub spec_revision: SpecRevision,
+    /// Current power role
+    pub power_role: PowerRole,
+    /// Current data role
+    pub data_role: DataRole,
+}
+
+/// Protocol layer driver
+pub struct Protocol<P> {
+    /// Physical layer
+    phy: P,
+    /// Header configuration
+    pub config: Config,
+    /// SOP protocol layer
+    sop: ProtocolLayer,
+    /// SOP' protocol layer
+    sop_prime: ProtocolLayer,
+    /// SOP'' protocol layer
+    sop_double_prime: ProtocolLayer,
+    /// Message received while waiting for a GoodCRC
+    pending: Option<(Sop, RawMessage)>,
+}
+
+impl<E, P: PhyTransmit<Error = E> + PhyReceive<Error = E>> Protocol<P> {
+    /// Create a new protocol layer driver
+    pub fn new(phy: P, config: Config) -> Self {
+        Self {
+            phy,
+            config,
+            sop: ProtocolLayer::new(),
+            sop_prime: ProtocolLayer::new(),
+            sop_double_prime: ProtocolLayer::new(),
+            pending: None,
+        }
+    }
+
+    /// Returns the physical layer
+    pub fn phy(&mut self) -> &mut P {
+        &mut self.phy
+    }
+
+    /// Returns the protocol layer state for the given SOP* type
+    pub fn layer(&self, sop: Sop) -> Result<&ProtocolLayer, PdError> {
+        match sop {
+            Sop::Sop => Ok(&self.sop),
+            Sop::SopPrime => Ok(&self.sop_prime),
+            Sop::SopDoublePrime => Ok(&self.sop_double_prime),
+            Sop::SopPrimeDebug | Sop::SopDoublePrimeDebug => Err(PdError::InvalidParams),
+        }
+    }
+
+    fn layer_mut(&mut self, sop: Sop) -> Result<&mut ProtocolLayer, PdError> {
+        match sop {
+            Sop::Sop => Ok(&mut self.sop),
+            Sop::SopPrime => Ok(&mut self.sop_prime),
+            Sop::SopDoublePrime => Ok(&mut self.sop_double_prime),
+            Sop::SopPrimeDebug | Sop::SopDoublePrimeDebug => Err(PdError::InvalidParams),
+        }
+    }
+
+    /// Fill in the port roles and specification revision of a header to transmit
+    fn stamp_header(&self, sop: Sop, header: &mut MessageHeader) {
+        header.set_spec_revision(self.config.spec_revision);
+        if sop == Sop::Sop {
+            header.set_port_data_role(self.config.data_role);
+            header.set_port_power_role(self.config.power_role);
+        } else {
+            // Port data role is reserved for SOP' and SOP'', messages from a port are never from a cable plug
+            header.set_port_data_role(DataRole::Ufp);
+            header.set_cable_plug(false);
+        }
+    }
+
+    async fn transmit_raw(&mut self, sop: Sop, message: &RawMessage) -> Result<(), Error<E>> {
+        let mut buf = [0; MAX_MESSAGE_LEN];
+        let len = message.encode_into_slice(&mut buf)?;
+        self.phy
+            .transmit(sop, buf.get(..len).unwrap_or_default())
+            .await
+            .map_err(Error::Bus)
+    }
+
+    /// Transmit a message and wait for the GoodCRC, retrying as needed
+    ///
+    /// Returns [`PdError::Failed`] if no GoodCRC was received after all retries, retries are left to the PHY if it
+    /// sets [`PhyTransmit::HANDLES_RETRIES`]. If a message is received before the
+    /// GoodCRC the transmission is discarded and [`PdError::Busy`] is returned, the received message is returned by
+    /// the next call to [`Protocol::receive`].
+    pub async fn transmit<M: Message>(&mut self, sop: Sop, message: &M) -> Result<(), Error<E>> {
+        let mut raw = message.to_raw()?;
+        self.stamp_header(sop, &mut raw.header);
+        self.layer_mut(sop)?.start_transmit(&mut raw.header);
+
+        loop {
+            self.transmit_raw(sop, &raw).await?;
+
+            let mut buf = [0; MAX_MESSAGE_LEN];
+            let received = self
+                .phy
+                .receive(&mut buf, Some(T_RECEIVE_US.maximum.0))
+                .await
+                .map_err(Error::Bus)?;
+
+            // A frame that cannot be decoded counts as a missing GoodCRC
+            let received = received.and_then(|(rx_sop, len)| {
+                RawMessage::try_from(buf.get(..len).unwrap_or_default())
+                    .ok()
+                    .map(|rx| (rx_sop, rx))
+            });
+            if let Some((rx_sop, rx)) = received {
+                if rx.header.is_control(ControlMessageType::GoodCrc) {
+                    if rx_sop == sop && self.layer(sop)?.is_good_crc_for_transmit(&rx.header) {
+                        self.layer_mut(sop)?.transmit_complete();
+                        return Ok(());
+                    }
+                } else if let Some(rx) = self.process_received(rx_sop, rx).await? {
+                    self.layer_mut(sop)?.transmit_discarded();
+                    self.pending = Some((rx_sop, rx));
+                    return Err(PdError::Busy.into());
+                }
+            }
+
+            let layer = self.layer_mut(sop)?;
+            if P::HANDLES_RETRIES {
+                layer.transmit_aborted();
+                return Err(PdError::Failed.into());
+            }
+
+            if layer.transmit_failed() == TransmitOutcome::Failed {
+                return Err(PdError::Failed.into());
+            }
+        }
+    }
+
+    /// Acknowledge a received message, returns the message if it should be passed up
+    async fn process_received(&mut self, sop: Sop, message: RawMessage) -> Result<Option<RawMessage>, Error<E>> {
+        let Ok(layer) = self.layer_mut(sop) else {
+            // Debug SOP* types are not handled by the protocol layer
+            return Ok(None);
+        };
+
+        let outcome = layer.receive(&message.header);
+        let mut good_crc = layer.good_crc(&message.header);
+        self.stamp_header(sop, &mut good_crc);
+        self.transmit_raw(sop, &RawMessage::new(good_crc, &[])?).await?;
+
+        match outcome {
+            ReceiveOutcome::Message | ReceiveOutcome::SoftReset => Ok(Some(message)),
+            ReceiveOutcome::Duplicate => Ok(None),
+        }
+    }
+
+    /// Wait for the next message and decode it
+    ///
+    /// GoodCRC is sent for every received message and retransmitted messages are discarded. A received Soft_Reset
+    /// resets the protocol layer for its SOP* type and is passed up so the policy engine can respond.
+    pub async fn receive<M: Message>(&mut self) -> Result<(Sop, M), Error<E>> {
+        let (sop, raw) = match self.pending.take() {
+            Some(pending) => pending,
+            None => loop {
+                let mut buf = [0; MAX_MESSAGE_LEN];
+                let Some((sop, len)) = self.phy.receive(&mut buf, None).await.map_err(Error::Bus)? else {
+                    continue;
+                };
+
+                let Ok(raw) = RawMessage::try_from(buf.get(..len).unwrap_or_default()) else {
+                    continue;
+                };
+
+                if raw.header.is_control(ControlMessageType::GoodCrc) {
+                    // Stray GoodCRC not associated with a transmission
+                    continue;
+                }
+
+                if let Some(raw) = self.process_received(sop, raw).await? {
+                    break (sop, raw);
+                }
+            },
+        };
+
+        Ok((sop, M::from_raw(&raw)?))
+    }
+
+    /// Transmit a Soft_Reset message
+    ///
+    /// If the Soft_Reset fails on SOP a hard reset is performed and [`PdError::HardReset`] is returned.
+    pub async fn soft_reset(&mut self, sop: Sop) -> Result<(), Error<E>> {
+        let header = MessageHeader::new(MessageType::Control(ControlMessageType::SoftReset), 0);
+        match self.transmit(sop, &RawMessage::new(header, &[])?).await {
+            Err(Error::Pd(PdError::Failed)) if sop == Sop::Sop => {
+                self.hard_reset().await?;
+                Err(PdError::HardReset.into())
+            }
+            result => result,
+        }
+    }
+
+    /// Transmit a Hard_Reset, this resets the protocol layer for all SOP* types
+    pub async fn hard_reset(&mut self) -> Result<(), Error<E>> {
+        self.sop.reset();
+        self.sop_prime.reset();
+        self.sop_double_prime.reset();
+        self.pending = None;
+        self.phy.transmit_hard_reset().await.map_err(Error::Bus)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use std::collections::VecDeque;
+    use std::vec::Vec;
+
+    use super::header::DataMessageType;
+    use super::*;
+    use crate::pdo::rdo::tests::FIXED_RDO;
+    use crate::test::block_on;
+
+    #[derive(Default)]
+    struct MockPhy {
+        /// Messages to receive
+        rx: VecDeque<(Sop, Vec<u8>)>,
+        /// Transmitted messages
+        tx: Vec<(Sop, Vec<u8>)>,
+        /// Number of transmissions to leave unacknowledged before the partner sends GoodCRC
+        drop_count: usize,
+        /// Answer dropped transmissions with an undecodable frame instead of nothing
+        corrupt: bool,
+        /// Number of hard resets transmitted
+        hard_resets: usize,
+    }
+
+    impl PhyTransmit for MockPhy {
+        type Error = ();
+
+        async fn transmit(&mut self, sop: Sop, data: &[u8]) -> Result<(), Self::Error> {
+            self.tx.push((sop, data.to_vec()));
+            let header = RawMessage::try_from(data).map_err(|_| ())?.header;
+            if header.is_control(ControlMessageType::GoodCrc) {
+                return Ok(());
+            }
+
+            if self.drop_count > 0 {
+                self.drop_count -= 1;
+                if self.corrupt {
+                    self.rx.push_front((sop, std::vec![0xFF]));
+                }
+            } else {
+                let mut good_crc = MessageHeader::new(MessageType::Control(ControlMessageType::GoodCrc), 0);
+                good_crc.set_message_id(header.message_id());
+                self.rx.push_front((sop, u16::from(good_crc).to_le_bytes().to_vec()));
+            }
+            Ok(())
+        }
+
+        async fn transmit_hard_reset(&mut self) -> Result<(), Self::Error> {
+            self.hard_resets += 1;
+            Ok(())
+        }
+    }
+
+    impl PhyReceive for MockPhy {
+        type Error = ();
+
+        async fn receive(
+            &mut self,
+            buf: &mut [u8],
+            timeout_us: Option<u16>,
+        ) -> Result<Option<(Sop, usize)>, Self::Error> {
+            match self.rx.pop_front() {
+                Some((sop, data)) => {
+                    buf.get_mut(..data.len()).ok_or(())?.copy_from_slice(&data);
+                    Ok(Some((sop, data.len())))
+                }
+                // Waiting indefinitely would hang the test
+                None => timeout_us.map(|_| None).ok_or(()),
+            }
+        }
+    }
+
+    const CONFIG: Config = Config {
+        spec_revision: SpecRevision::Rev3,
+        power_role: PowerRole::Sink,
+        data_role: DataRole::Ufp,
+    };
+
+    fn control(message_type: ControlMessageType) -> Result<RawMessage, PdError> {
+        RawMessage::new(MessageHeader::new(MessageType::Control(message_type), 0), &[])
+    }
+
+    fn partner_message(message_type: ControlMessageType, message_id: u8) -> (Sop, Vec<u8>) {
+        let mut header = MessageHeader::new(MessageType::Control(message_type), 0);
+        header.set_message_id(message_id);
+        header.set_port_power_role(PowerRole::Source);
+        (Sop::Sop, u16::from(header).to_le_bytes().to_vec())
+    }
+
+    fn tx_headers(protocol: &mut Protocol<MockPhy>) -> Vec<u16> {
+        protocol
+            .phy()
+            .tx
+            .iter()
+            .filter_map(|(_, data)| RawMessage::try_from(data.as_slice()).ok())
+            .map(|message| u16::from(message.header))
+            .collect()
+    }
+
+    #[test]
+    fn test_transmit() {
+        let mut protocol = Protocol::new(MockPhy::default(), CONFIG);
+        let request = RawMessage::from_data_objects(
+            MessageHeader::new(MessageType::Data(DataMessageType::Request), 0),
+            &[FIXED_RDO],
+        );
+        assert_eq!(request.map(|_| ()), Ok(()));
+
+        if let Ok(request) = request {
+            assert_eq!(block_on(protocol.transmit(Sop::Sop, &request)), Ok(()));
+            assert_eq!(block_on(protocol.transmit(Sop::Sop, &request)), Ok(()));
+        }
+
+        // Request with message IDs 0 and 1, sink, UFP, revision 3.x
+        assert_eq!(tx_headers(&mut protocol), [0x1082, 0x1282]);
+        assert!(protocol
+            .layer(Sop::Sop)
+            .is_ok_and(|layer| layer.message_id_counter() == 2));
+        assert!(protocol
+            .layer(Sop::SopPrime)
+            .is_ok_and(|layer| layer.message_id_counter() == 0));
+    }
+
+    #[test]
+    fn test_transmit_retry() {
+        let mut protocol = Protocol::new(
+            MockPhy {
+                drop_count: 2,
+                ..Default::default()
+            },
+            CONFIG,
+        );
+        let message = control(ControlMessageType::GetSourceCap).map_err(Error::Pd);
+        assert_eq!(
+            message.and_then(|message| block_on(protocol.transmit(Sop::SopPrime, &message))),
+            Ok(())
+        );
+        assert_eq!(protocol.phy().tx.len(), 3);
+
+        let mut protocol = Protocol::new(
+            MockPhy {
+                drop_count: 3,
+                ..Default::default()
+            },
+            CONFIG,
+        );
+        let message = control(ControlMessageType::GetSourceCap).map_err(Error::Pd);
+        assert_eq!(
+            message.and_then(|message| block_on(protocol.transmit(Sop::Sop, &message))),
+            Err(Error::Pd(PdError::Failed))
+        );
+        assert!(protocol
+            .layer(Sop::Sop)
+            .is_ok_and(|layer| layer.message_id_counter() == 1));
+    }
+
+    #[test]
+    fn test_transmit_corrupt_good_crc() {
+        let mut protocol = Protocol::new(
+            MockPhy {
+                drop_count: usize::MAX,
+                corrupt: true,
+                ..Default::default()
+            },
+            CONFIG,
+        );
+        let message = control(ControlMessageType::GetSourceCap).map_err(Error::Pd);
+        assert_eq!(
+            message.and_then(|message| block_on(protocol.transmit(Sop::Sop, &message))),
+            Err(Error::Pd(PdError::Failed))
+        );
+        // The original transmission and nRetryCount retries
+        assert_eq!(protocol.phy().tx.len(), 3);
+    }
+
+    #[test]
+    fn test_receive() {
+        let mut protocol = Protocol::new(MockPhy::default(), CONFIG);
+        protocol.phy().rx.extend([
+            partner_message(ControlMessageType::GoodCrc, 7),
+            partner_message(ControlMessageType::Accept, 3),
+            partner_message(ControlMessageType::Accept, 3),
+            partner_message(ControlMessageType::PsRdy, 4),
+        ]);
+
+        let received = block_on(protocol.receive::<RawMessage>());
+        assert_eq!(
+            received.map(|(sop, message)| (sop, message.header.message_type())),
+            Ok((Sop::Sop, Ok(MessageType::Control(ControlMessageType::Accept))))
+        );
+
+        // Duplicate Accept is acknowledged and dropped
+        let received = block_on(protocol.receive::<RawMessage>());
+        assert_eq!(received.map(|(_, message)| message.header.message_id()), Ok(4));
+
+        // GoodCRC for message IDs 3, 3 and 4
+        assert_eq!(tx_headers(&mut protocol), [0x0681, 0x0681, 0x0881]);
+    }
+
+    #[test]
+    fn test_receive_during_transmit() {
+        let mut protocol = Protocol::new(
+            MockPhy {
+                drop_count: 1,
+                ..Default::default()
+            },
+            CONFIG,
+        );
+        protocol
+            .phy()
+            .rx
+            .push_back(partner_message(ControlMessageType::PsRdy, 2));
+
+        let message = control(ControlMessageType::GetSinkCap).map_err(Error::Pd);
+        assert_eq!(
+            message.and_then(|message| block_on(protocol.transmit(Sop::Sop, &message))),
+            Err(Error::Pd(PdError::Busy))
+        );
+        assert!(protocol
+            .layer(Sop::Sop)
+            .is_ok_and(|layer| layer.message_id_counter() == 1));
+
+        let received = block_on(protocol.receive::<RawMessage>());
+        assert_eq!(
+            received.map(|(_, message)| message.header.message_type()),
+            Ok(Ok(MessageType::Control(ControlMessageType::PsRdy)))
+        );
+    }
+
+    #[test]
+    fn test_soft_reset() {
+        let mut protocol = Protocol::new(MockPhy::default(), CONFIG);
+        protocol.phy().rx.extend([
+            partner_message(ControlMessageType::Accept, 5),
+            partner_message(ControlMessageType::SoftReset, 0),
+        ]);
+
+        let received = block_on(protocol.receive::<RawMessage>());
+        assert_eq!(received.map(|(_, message)| message.header.message_id()), Ok(5));
+        let received = block_on(protocol.receive::<RawMessage>());
+        assert_eq!(
+            received.map(|(_, message)| message.header.message_type()),
+            Ok(Ok(MessageType::Control(ControlMessageType::SoftReset)))
+        );
+        assert!(protocol
+            .layer(Sop::Sop)
+            .is_ok_and(|layer| layer.stored_message_id() == Some(0)));
+
+        // Failed Soft_Reset escalates to a hard reset
+        protocol.phy().drop_count = 3;
+        assert_eq!(
+            block_on(protocol.soft_reset(Sop::Sop)),
+            Err(Error::Pd(PdError::HardReset))
+        );
+        assert_eq!(protocol.phy().hard_resets, 1);
+        assert!(protocol
+            .layer(Sop::Sop)
+            .is_ok_and(|layer| layer.message_id_counter() == 0 && layer.stored_message_id().is_none()));
+    }
+}
@@ -0,0 +1,197 @@
+//! Specification revision as defined in the USB PD specification
+//!
+//! This covers the Specification Revision field of the message header (6.2.1.1.5) and the Revision data object
+//! (6.4.12), along with the negotiation of the revision used between port partners (6.2.1.1.5).
+use bitfield::bitfield;
+
+use crate::usb::Bcd;
+use crate::PdError;
+
+/// Error type for specification revision conversion, contains the raw value
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidSpecRevision(pub u8);
+
+impl From<InvalidSpecRevision> for PdError {
+    fn from(_: InvalidSpecRevision) -> Self {
+        PdError::InvalidParams
+    }
+}
+
+/// Specification revision reported in the message header
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum SpecRevision {
+    /// Revision 1.0, deprecated
+    Rev1,
+    /// Revision 2.0
+    Rev2,
+    /// Revision 3.x
+    #[default]
+    Rev3,
+}
+
+impl SpecRevision {
+    /// Returns the revision to use for communication with a port partner, this is the lower of the two revisions
+    pub fn negotiate(self, partner: SpecRevision) -> SpecRevision {
+        self.min(partner)
+    }
+
+    /// Returns true if the Revision message is supported at this revision
+    pub fn supports_revision_message(self) -> bool {
+        self >= SpecRevision::Rev3
+    }
+}
+
+impl TryFrom<u8> for SpecRevision {
+    type Error = InvalidSpecRevision;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x0 => Ok(SpecRevision::Rev1),
+            0x1 => Ok(SpecRevision::Rev2),
+            0x2 => Ok(SpecRevision::Rev3),
+            _ => Err(InvalidSpecRevision(value)),
+        }
+    }
+}
+
+impl From<SpecRevision> for u8 {
+    fn from(value: SpecRevision) -> Self {
+        match value {
+            SpecRevision::Rev1 => 0x0,
+            SpecRevision::Rev2 => 0x1,
+            SpecRevision::Rev3 => 0x2,
+        }
+    }
+}
+
+bitfield! {
+    /// Raw Revision data object
+    #[derive(Copy, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    struct RevisionRaw(u32);
+    impl Debug;
+
+    /// Revision major
+    pub u8, revision_major, set_revision_major: 31, 28;
+    /// Revision minor
+    pub u8, revision_minor, set_revision_minor: 27, 24;
+    /// Version major
+    pub u8, version_major, set_version_major: 23, 20;
+    /// Version minor
+    pub u8, version_minor, set_version_minor: 19, 16;
+}
+
+/// Revision data object
+///
+/// Fields are ordered so that the derived ordering compares the revision first and then the version.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Revision {
+    /// Revision major
+    pub revision_major: u8,
+    /// Revision minor
+    pub revision_minor: u8,
+    /// Version major
+    pub version_major: u8,
+    /// Version minor
+    pub version_minor: u8,
+}
+
+impl Revision {
+    /// Create a new revision
+    pub const fn new(revision_major: u8, revision_minor: u8, version_major: u8, version_minor: u8) -> Self {
+        Self {
+            revision_major,
+            revision_minor,
+            version_major,
+            version_minor,
+        }
+    }
+
+    /// Returns the revision to use for communication with a port partner, this is the lower of the two revisions
+    pub fn negotiate(self, partner: Revision) -> Revision {
+        self.min(partner)
+    }
+
+    /// Returns the corresponding message header specification revision
+    pub fn spec_revision(&self) -> SpecRevision {
+        match self.revision_major {
+            0..=1 => SpecRevision::Rev1,
+            2 => SpecRevision::Rev2,
+            _ => SpecRevision::Rev3,
+        }
+    }
+
+    /// Returns the revision in BCD format, e.g. 0x0310 for revision 3.1
+    pub fn revision_bcd(&self) -> Bcd {
+        Bcd(((self.revision_major as u16) << 8) | ((self.revision_minor as u16) << 4))
+    }
+
+    /// Returns the version in BCD format, e.g. 0x0180 for version 1.8
+    pub fn version_bcd(&self) -> Bcd {
+        Bcd(((self.version_major as u16) << 8) | ((self.version_minor as u16) << 4))
+    }
+}
+
+impl From<u32> for Revision {
+    fn from(value: u32) -> Self {
+        let raw = RevisionRaw(value);
+        Revision {
+            revision_major: raw.revision_major(),
+            revision_minor: raw.revision_minor(),
+            version_major: raw.version_major(),
+            version_minor: raw.version_minor(),
+        }
+    }
+}
+
+impl From<Revision> for u32 {
+    fn from(value: Revision) -> Self {
+        let mut raw = RevisionRaw(0);
+        raw.set_revision_major(value.revision_major);
+        raw.set_revision_minor(value.revision_minor);
+        raw.set_version_major(value.version_major);
+        raw.set_version_minor(value.version_minor);
+        raw.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_spec_revision() {
+        assert_eq!(SpecRevision::try_from(0x1), Ok(SpecRevision::Rev2));
+        assert_eq!(SpecRevision::try_from(0x3), Err(InvalidSpecRevision(0x3)));
+        assert_eq!(u8::from(SpecRevision::Rev3), 0x2);
+        assert_eq!(SpecRevision::Rev3.negotiate(SpecRevision::Rev2), SpecRevision::Rev2);
+        assert_eq!(SpecRevision::Rev2.negotiate(SpecRevision::Rev3), SpecRevision::Rev2);
+        assert!(!SpecRevision::Rev2.supports_revision_message());
+    }
+
+    #[test]
+    fn test_revision_roundtrip() {
+        // Revision 3.1, version 1.8
+        let revision = Revision::new(3, 1, 1, 8);
+        assert_eq!(Revision::from(0x3118_0000), revision);
+        assert_eq!(u32::from(revision), 0x3118_0000);
+        assert_eq!(revision.revision_bcd(), Bcd(0x0310));
+        assert_eq!(revision.version_bcd(), Bcd(0x0180));
+        assert_eq!(revision.spec_revision(), SpecRevision::Rev3);
+    }
+
+    #[test]
+    fn test_revision_negotiate() {
+        let rev3_1 = Revision::new(3, 1, 1, 8);
+        let rev3_0 = Revision::new(3, 0, 2, 0);
+        assert_eq!(rev3_1.negotiate(rev3_0), rev3_0);
+        assert_eq!(rev3_0.negotiate(rev3_1), rev3_0);
+
+        // Same revision, lower version wins
+        let rev3_1_v1_7 = Revision::new(3, 1, 1, 7);
+        assert_eq!(rev3_1.negotiate(rev3_1_v1_7), rev3_1_v1_7);
+    }
+}
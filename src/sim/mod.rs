@@ -0,0 +1,557 @@
+//! In-memory simulation of a PD link for host tests
+//!
+//! A [`Link`] connects two [`SimPhy`] endpoints that implement [`PhyTransmit`] and [`PhyReceive`], so a
+//! [`crate::protocol::Protocol`] and the policy code above it can run on each end without hardware. Messages can be
+//! lost, delayed or have their GoodCRC fail, and a [`vbus::VirtualVbus`] follows the power supply transitions of the
+//! source. Time is simulated: the shared clock only advances when an endpoint waits for a message.
+//!
+//! Both endpoints run on a single thread, [`join`] drives a future for each end until both complete.
+extern crate std;
+
+use core::cell::RefCell;
+use core::future::Future;
+use core::pin::{pin, Pin};
+use core::task::{Context, Poll, Waker};
+use std::collections::VecDeque;
+use std::rc::Rc;
+use std::vec::Vec;
+
+use crate::pdo::Contract;
+use crate::protocol::header::{ControlMessageType, MessageHeader};
+use crate::protocol::{PhyReceive, PhyTransmit, Sop};
+use crate::PdError;
+
+pub mod vbus;
+
+use vbus::VirtualVbus;
+
+/// Number of polls after which [`block_on`] and [`join`] give up
+pub const MAX_POLLS: usize = 100_000;
+
+/// Link configuration
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct LinkConfig {
+    /// Delay from transmission to reception in us
+    pub delay_us: u32,
+    /// Percentage of messages other than GoodCRC that are lost
+    pub loss_percent: u8,
+    /// Percentage of GoodCRC messages that are lost
+    pub good_crc_failure_percent: u8,
+    /// Seed for the random loss, must not be zero
+    pub seed: u32,
+}
+
+impl Default for LinkConfig {
+    fn default() -> Self {
+        Self {
+            delay_us: 0,
+            loss_percent: 0,
+            good_crc_failure_percent: 0,
+            seed: 1,
+        }
+    }
+}
+
+/// End of a link
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Side {
+    /// First endpoint
+    A,
+    /// Second endpoint
+    B,
+}
+
+impl Side {
+    /// Returns the other end of the link
+    pub const fn other(self) -> Self {
+        match self {
+            Side::A => Side::B,
+            Side::B => Side::A,
+        }
+    }
+}
+
+/// Message transmitted on the link
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Transmission {
+    /// Transmitting endpoint
+    pub from: Side,
+    /// SOP* type
+    pub sop: Sop,
+    /// Header and payload
+    pub data: Vec<u8>,
+    /// True if the message was lost
+    pub lost: bool,
+}
+
+impl Transmission {
+    /// Returns the message header
+    pub fn header(&self) -> Option<MessageHeader> {
+        let header = self.data.first_chunk::<2>()?;
+        Some(MessageHeader::from(u16::from_le_bytes(*header)))
+    }
+}
+
+/// Message in flight
+struct Frame {
+    /// SOP* type
+    sop: Sop,
+    /// Header and payload
+    data: Vec<u8>,
+    /// Time at which the message is received
+    deliver_at_us: u64,
+}
+
+/// State of one end of the link
+#[derive(Default)]
+struct Endpoint {
+    /// Messages in flight to this endpoint
+    rx: VecDeque<Frame>,
+    /// Hard resets received and not yet taken
+    hard_resets: usize,
+}
+
+/// Shared link state
+struct LinkState {
+    /// Configuration
+    config: LinkConfig,
+    /// Random number generator state
+    rng: u32,
+    /// Simulated time
+    now_us: u64,
+    /// First endpoint
+    a: Endpoint,
+    /// Second endpoint
+    b: Endpoint,
+    /// Number of messages other than GoodCRC to lose unconditionally
+    lose_messages: usize,
+    /// Number of GoodCRC messages to lose unconditionally
+    lose_good_crcs: usize,
+    /// Transmission log
+    transmissions: Vec<Transmission>,
+    /// VBUS
+    vbus: VirtualVbus,
+}
+
+impl LinkState {
+    fn endpoint(&mut self, side: Side) -> &mut Endpoint {
+        match side {
+            Side::A => &mut self.a,
+            Side::B => &mut self.b,
+        }
+    }
+
+    /// Returns true with the given probability
+    fn chance(&mut self, percent: u8) -> bool {
+        // xorshift32
+        self.rng ^= self.rng << 13;
+        self.rng ^= self.rng >> 17;
+        self.rng ^= self.rng << 5;
+        self.rng % 100 < percent as u32
+    }
+
+    /// Returns true if a message with the given header is lost
+    fn lose(&mut self, header: MessageHeader) -> bool {
+        if header.is_control(ControlMessageType::GoodCrc) {
+            if self.lose_good_crcs > 0 {
+                self.lose_good_crcs -= 1;
+                return true;
+            }
+            self.chance(self.config.good_crc_failure_percent)
+        } else {
+            if self.lose_messages > 0 {
+                self.lose_messages -= 1;
+                return true;
+            }
+            self.chance(self.config.loss_percent)
+        }
+    }
+}
+
+/// Simulated link between two endpoints
+#[derive(Clone)]
+pub struct Link(Rc<RefCell<LinkState>>);
+
+impl Link {
+    /// Create a new link
+    pub fn new(config: LinkConfig) -> Self {
+        Self(Rc::new(RefCell::new(LinkState {
+            config,
+            rng: config.seed.max(1),
+            now_us: 0,
+            a: Endpoint::default(),
+            b: Endpoint::default(),
+            lose_messages: 0,
+            lose_good_crcs: 0,
+            transmissions: Vec::new(),
+            vbus: VirtualVbus::default(),
+        })))
+    }
+
+    /// Returns the PHY for the given end of the link
+    pub fn phy(&self, side: Side) -> SimPhy {
+        SimPhy {
+            link: self.clone(),
+            side,
+        }
+    }
+
+    /// Change the link configuration
+    pub fn set_config(&self, config: LinkConfig) {
+        let mut state = self.0.borrow_mut();
+        state.config = config;
+        state.rng = config.seed.max(1);
+    }
+
+    /// Lose the next `count` messages other than GoodCRC
+    pub fn lose_messages(&self, count: usize) {
+        self.0.borrow_mut().lose_messages = count;
+    }
+
+    /// Lose the next `count` GoodCRC messages
+    pub fn lose_good_crcs(&self, count: usize) {
+        self.0.borrow_mut().lose_good_crcs = count;
+    }
+
+    /// Returns the simulated time in us
+    pub fn now_us(&self) -> u64 {
+        self.0.borrow().now_us
+    }
+
+    /// Advance the simulated time
+    pub fn advance_us(&self, us: u64) {
+        self.0.borrow_mut().now_us += us;
+    }
+
+    /// Returns all transmissions so far
+    pub fn transmissions(&self) -> Vec<Transmission> {
+        self.0.borrow().transmissions.clone()
+    }
+
+    /// Returns the VBUS model
+    pub fn vbus(&self) -> VirtualVbus {
+        self.0.borrow().vbus
+    }
+
+    /// Returns the VBUS voltage in mV
+    pub fn vbus_mv(&self) -> u16 {
+        let state = self.0.borrow();
+        state.vbus.voltage_mv(state.now_us)
+    }
+
+    /// Start a VBUS transition to the given voltage, 0 turns the source off
+    pub fn set_vbus_mv(&self, target_mv: u16) {
+        let mut state = self.0.borrow_mut();
+        let now_us = state.now_us;
+        state.vbus.set_target(target_mv, now_us);
+    }
+
+    /// Start the VBUS transition for a new contract
+    pub fn transition_vbus(&self, contract: &Contract) {
+        let mut state = self.0.borrow_mut();
+        let now_us = state.now_us;
+        state.vbus.transition(contract, now_us);
+    }
+
+    /// Advance the simulated time until VBUS has reached its target voltage
+    pub fn settle_vbus(&self) {
+        let mut state = self.0.borrow_mut();
+        state.now_us = state.now_us.max(state.vbus.settled_at_us());
+    }
+}
+
+/// PHY at one end of a [`Link`]
+#[derive(Clone)]
+pub struct SimPhy {
+    /// Link
+    link: Link,
+    /// End of the link
+    side: Side,
+}
+
+impl SimPhy {
+    /// Returns the link
+    pub fn link(&self) -> &Link {
+        &self.link
+    }
+
+    /// Returns true if a hard reset has been received since the last call
+    pub fn take_hard_reset(&mut self) -> bool {
+        let mut state = self.link.0.borrow_mut();
+        let endpoint = state.endpoint(self.side);
+        let received = endpoint.hard_resets > 0;
+        endpoint.hard_resets = endpoint.hard_resets.saturating_sub(1);
+        received
+    }
+
+    /// Take the next message if it is received by `deadline_us`
+    fn take_frame(&mut self, deadline_us: Option<u64>) -> Option<Frame> {
+        let mut state = self.link.0.borrow_mut();
+        let deliver_at_us = state.endpoint(self.side).rx.front()?.deliver_at_us;
+        if deadline_us.is_some_and(|deadline_us| deliver_at_us > deadline_us) {
+            return None;
+        }
+
+        state.now_us = state.now_us.max(deliver_at_us);
+        state.endpoint(self.side).rx.pop_front()
+    }
+}
+
+impl PhyTransmit for SimPhy {
+    type Error = PdError;
+
+    async fn transmit(&mut self, sop: Sop, data: &[u8]) -> Result<(), Self::Error> {
+        let header = data.first_chunk::<2>().ok_or(PdError::InvalidParams)?;
+        let header = MessageHeader::from(u16::from_le_bytes(*header));
+
+        let mut state = self.link.0.borrow_mut();
+        let lost = state.lose(header);
+        state.transmissions.push(Transmission {
+            from: self.side,
+            sop,
+            data: data.to_vec(),
+            lost,
+        });
+
+        if !lost {
+            let deliver_at_us = state.now_us + state.config.delay_us as u64;
+            state.endpoint(self.side.other()).rx.push_back(Frame {
+                sop,
+                data: data.to_vec(),
+                deliver_at_us,
+            });
+        }
+        Ok(())
+    }
+
+    async fn transmit_hard_reset(&mut self) -> Result<(), Self::Error> {
+        let mut state = self.link.0.borrow_mut();
+        // Hard reset signaling discards any messages in flight
+        state.a.rx.clear();
+        state.b.rx.clear();
+        state.endpoint(self.side.other()).hard_resets += 1;
+        Ok(())
+    }
+}
+
+impl PhyReceive for SimPhy {
+    type Error = PdError;
+
+    async fn receive(&mut self, buf: &mut [u8], timeout_us: Option<u16>) -> Result<Option<(Sop, usize)>, Self::Error> {
+        let deadline_us = timeout_us.map(|timeout_us| self.link.now_us() + timeout_us as u64);
+        let mut yielded = false;
+        loop {
+            if let Some(frame) = self.take_frame(deadline_us) {
+                buf.get_mut(..frame.data.len())
+                    .ok_or(PdError::InvalidParams)?
+                    .copy_from_slice(&frame.data);
+                return Ok(Some((frame.sop, frame.data.len())));
+            }
+
+            // Give the other end a chance to transmit before timing out
+            if let (Some(deadline_us), true) = (deadline_us, yielded) {
+                let mut state = self.link.0.borrow_mut();
+                state.now_us = state.now_us.max(deadline_us);
+                return Ok(None);
+            }
+
+            YieldNow(false).await;
+            yielded = true;
+        }
+    }
+}
+
+/// Future that returns pending once
+struct YieldNow(bool);
+
+impl Future for YieldNow {
+    type Output = ();
+
+    fn poll(mut self: Pin<&mut Self>, context: &mut Context<'_>) -> Poll<Self::Output> {
+        if self.0 {
+            Poll::Ready(())
+        } else {
+            self.0 = true;
+            context.waker().wake_by_ref();
+            Poll::Pending
+        }
+    }
+}
+
+/// Poll a future to completion, returns `None` if it did not complete within [`MAX_POLLS`]
+pub fn block_on<F: Future>(future: F) -> Option<F::Output> {
+    let mut future = pin!(future);
+    let mut context = Context::from_waker(Waker::noop());
+    for _ in 0..MAX_POLLS {
+        if let Poll::Ready(output) = future.as_mut().poll(&mut context) {
+            return Some(output);
+        }
+    }
+    None
+}
+
+/// Poll two futures alternately until both complete, returns `None` if they did not complete within [`MAX_POLLS`]
+pub fn join<A: Future, B: Future>(a: A, b: B) -> Option<(A::Output, B::Output)> {
+    let mut a = pin!(a);
+    let mut b = pin!(b);
+    let mut a_output = None;
+    let mut b_output = None;
+    let mut context = Context::from_waker(Waker::noop());
+    for _ in 0..MAX_POLLS {
+        if a_output.is_none() {
+            if let Poll::Ready(output) = a.as_mut().poll(&mut context) {
+                a_output = Some(output);
+            }
+        }
+        if b_output.is_none() {
+            if let Poll::Ready(output) = b.as_mut().poll(&mut context) {
+                b_output = Some(output);
+            }
+        }
+        if a_output.is_some() && b_output.is_some() {
+            return a_output.zip(b_output);
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::N_RETRY_COUNT;
+    use crate::protocol::header::MessageType;
+    use crate::protocol::message::tests::header_only;
+    use crate::protocol::message::RawMessage;
+    use crate::protocol::{Config, Protocol};
+    use crate::revision::SpecRevision;
+    use crate::{DataRole, Error, PowerRole};
+
+    fn protocols(link: &Link) -> (Protocol<SimPhy>, Protocol<SimPhy>) {
+        let config = |power_role, data_role| Config {
+            spec_revision: SpecRevision::Rev3,
+            power_role,
+            data_role,
+        };
+        (
+            Protocol::new(link.phy(Side::A), config(PowerRole::Source, DataRole::Dfp)),
+            Protocol::new(link.phy(Side::B), config(PowerRole::Sink, DataRole::Ufp)),
+        )
+    }
+
+    fn accept() -> RawMessage {
+        header_only(MessageHeader::new(MessageType::Control(ControlMessageType::Accept), 0))
+    }
+
+    /// Returns the number of transmissions of the given message type
+    fn count(link: &Link, message_type: ControlMessageType) -> usize {
+        link.transmissions()
+            .iter()
+            .filter(|transmission| {
+                transmission
+                    .header()
+                    .is_some_and(|header| header.is_control(message_type))
+            })
+            .count()
+    }
+
+    #[test]
+    fn test_exchange() {
+        let link = Link::new(LinkConfig {
+            delay_us: 100,
+            ..Default::default()
+        });
+        let (mut source, mut sink) = protocols(&link);
+        let accept = accept();
+
+        let result = join(source.transmit(Sop::Sop, &accept), sink.receive::<RawMessage>());
+        let result = result.map(|(transmitted, received)| {
+            let received = received.map(|(sop, message)| (sop, message.header.is_control(ControlMessageType::Accept)));
+            (transmitted, received)
+        });
+        assert_eq!(result, Some((Ok(()), Ok((Sop::Sop, true)))));
+
+        // Message and GoodCRC each took 100 us
+        assert_eq!(link.now_us(), 200);
+        assert_eq!(count(&link, ControlMessageType::GoodCrc), 1);
+    }
+
+    #[test]
+    fn test_loss() {
+        let link = Link::new(LinkConfig::default());
+        link.lose_messages(1);
+        let (mut source, mut sink) = protocols(&link);
+        let accept = accept();
+
+        let result = join(source.transmit(Sop::Sop, &accept), sink.receive::<RawMessage>());
+        assert!(result.is_some_and(|(transmitted, received)| transmitted.is_ok() && received.is_ok()));
+        assert_eq!(count(&link, ControlMessageType::Accept), 2);
+        assert_eq!(
+            link.transmissions().first().map(|transmission| transmission.lost),
+            Some(true)
+        );
+    }
+
+    #[test]
+    fn test_good_crc_failure() {
+        let link = Link::new(LinkConfig {
+            good_crc_failure_percent: 100,
+            ..Default::default()
+        });
+        let (mut source, mut sink) = protocols(&link);
+        let accept = accept();
+
+        let result = join(source.transmit(Sop::Sop, &accept), sink.receive::<RawMessage>());
+        let result = result.map(|(transmitted, received)| (transmitted, received.is_ok()));
+        assert_eq!(result, Some((Err(Error::Pd(PdError::Failed)), true)));
+        assert_eq!(count(&link, ControlMessageType::Accept), N_RETRY_COUNT as usize + 1);
+    }
+
+    #[test]
+    fn test_random_loss() {
+        let link = Link::new(LinkConfig {
+            loss_percent: 50,
+            seed: 0x1234_5678,
+            ..Default::default()
+        });
+        let mut phy = link.phy(Side::A);
+        let accept = accept();
+        let mut buf = [0; 2];
+        let len = accept.encode_into_slice(&mut buf);
+        assert_eq!(len, Ok(2));
+
+        for _ in 0..100 {
+            assert_eq!(block_on(phy.transmit(Sop::Sop, buf.as_slice())), Some(Ok(())));
+        }
+        let lost = link
+            .transmissions()
+            .iter()
+            .filter(|transmission| transmission.lost)
+            .count();
+        assert!((25..75).contains(&lost));
+    }
+
+    #[test]
+    fn test_hard_reset() {
+        let link = Link::new(LinkConfig::default());
+        let (mut source, _) = protocols(&link);
+        let mut sink = link.phy(Side::B);
+        assert!(!sink.take_hard_reset());
+        assert_eq!(block_on(source.hard_reset()), Some(Ok(())));
+        assert!(sink.take_hard_reset());
+        assert!(!sink.take_hard_reset());
+    }
+
+    #[test]
+    fn test_vbus() {
+        let link = Link::new(LinkConfig::default());
+        link.set_vbus_mv(5000);
+        assert_eq!(link.vbus_mv(), 0);
+        link.settle_vbus();
+        assert_eq!(link.vbus_mv(), 5000);
+        assert!(link.vbus().is_present(link.now_us()));
+
+        link.set_vbus_mv(0);
+        link.advance_us(100);
+        assert_eq!(link.vbus_mv(), 2000);
+    }
+}
@@ -0,0 +1,152 @@
+//! Virtual VBUS model
+//!
+//! VBUS moves linearly towards the voltage set by the source at a fixed slew rate. Times are in us of simulated time.
+use crate::pdo::{Common, Contract, Rdo};
+
+/// Default slew rate in mV/us, `vSrcSlewPos` max
+pub const DEFAULT_SLEW_MV_PER_US: u16 = 30;
+
+/// Minimum vSafe5V voltage in mV
+pub const VSAFE5V_MIN_MV: u16 = 4750;
+
+/// Maximum vSafe0V voltage in mV
+pub const VSAFE0V_MAX_MV: u16 = 800;
+
+/// Virtual VBUS
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct VirtualVbus {
+    /// Slew rate in mV/us
+    slew_mv_per_us: u16,
+    /// Voltage when the current transition started
+    from_mv: u16,
+    /// Voltage the source is transitioning to
+    target_mv: u16,
+    /// Start of the current transition
+    start_us: u64,
+}
+
+impl VirtualVbus {
+    /// Create a new VBUS at 0 V with the given slew rate
+    pub const fn new(slew_mv_per_us: u16) -> Self {
+        Self {
+            slew_mv_per_us,
+            from_mv: 0,
+            target_mv: 0,
+            start_us: 0,
+        }
+    }
+
+    /// Returns the voltage the source is transitioning to
+    pub fn target_mv(&self) -> u16 {
+        self.target_mv
+    }
+
+    /// Returns the VBUS voltage at the given time
+    pub fn voltage_mv(&self, now_us: u64) -> u16 {
+        let elapsed_us = now_us.saturating_sub(self.start_us);
+        let delta_mv = (self.slew_mv_per_us as u64).saturating_mul(elapsed_us);
+        if self.target_mv >= self.from_mv {
+            (self.from_mv as u64 + delta_mv).min(self.target_mv as u64) as u16
+        } else {
+            (self.from_mv as u64)
+                .saturating_sub(delta_mv)
+                .max(self.target_mv as u64) as u16
+        }
+    }
+
+    /// Start a transition to the given voltage
+    pub fn set_target(&mut self, target_mv: u16, now_us: u64) {
+        self.from_mv = self.voltage_mv(now_us);
+        self.target_mv = target_mv;
+        self.start_us = now_us;
+    }
+
+    /// Start the transition for a new contract
+    pub fn transition(&mut self, contract: &Contract, now_us: u64) {
+        self.set_target(contract_voltage_mv(contract), now_us);
+    }
+
+    /// Returns the time at which VBUS reaches the target voltage
+    pub fn settled_at_us(&self) -> u64 {
+        if self.slew_mv_per_us == 0 {
+            return u64::MAX;
+        }
+
+        let delta_mv = self.target_mv.abs_diff(self.from_mv) as u64;
+        self.start_us + delta_mv.div_ceil(self.slew_mv_per_us as u64)
+    }
+
+    /// Returns true if VBUS has reached the target voltage
+    pub fn is_settled(&self, now_us: u64) -> bool {
+        self.voltage_mv(now_us) == self.target_mv
+    }
+
+    /// Returns true if VBUS is at vSafe0V
+    pub fn is_vsafe0v(&self, now_us: u64) -> bool {
+        self.voltage_mv(now_us) < VSAFE0V_MAX_MV
+    }
+
+    /// Returns true if VBUS is present, at or above vSafe5V
+    pub fn is_present(&self, now_us: u64) -> bool {
+        self.voltage_mv(now_us) >= VSAFE5V_MIN_MV
+    }
+}
+
+impl Default for VirtualVbus {
+    fn default() -> Self {
+        Self::new(DEFAULT_SLEW_MV_PER_US)
+    }
+}
+
+/// Returns the VBUS voltage for a contract, the requested voltage for programmable supplies
+pub fn contract_voltage_mv(contract: &Contract) -> u16 {
+    match contract.rdo {
+        Rdo::Pps(data) => data.output_voltage_mv,
+        Rdo::Avs(data) => data.output_voltage_mv,
+        Rdo::Fixed(_) | Rdo::Variable(_) | Rdo::Battery(_) => contract.pdo.max_voltage_mv(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pdo::rdo::tests::fixed_rdo;
+
+    #[test]
+    fn test_ramp() {
+        let mut vbus = VirtualVbus::default();
+        assert!(vbus.is_vsafe0v(0));
+
+        vbus.set_target(5000, 1000);
+        assert_eq!(vbus.voltage_mv(1000), 0);
+        assert_eq!(vbus.voltage_mv(1050), 1500);
+        assert!(!vbus.is_present(1050));
+        assert_eq!(vbus.settled_at_us(), 1167);
+        assert!(vbus.is_present(vbus.settled_at_us()));
+        assert!(vbus.is_settled(vbus.settled_at_us()));
+
+        // Reversing mid-transition starts from the current voltage
+        vbus.set_target(0, 1100);
+        assert_eq!(vbus.voltage_mv(1100), 3000);
+        assert_eq!(vbus.voltage_mv(1150), 1500);
+        assert_eq!(vbus.settled_at_us(), 1200);
+        assert!(vbus.is_vsafe0v(1200));
+
+        // 5 V to 20 V settles well within tSrcReady
+        vbus.set_target(5000, 2000);
+        vbus.set_target(20000, vbus.settled_at_us());
+        assert_eq!(vbus.settled_at_us(), 2667);
+    }
+
+    #[test]
+    fn test_contract_voltage() {
+        use crate::pdo::source;
+
+        let pdo = source::Pdo::Fixed(source::FixedData {
+            voltage_mv: 9000,
+            current_ma: 3000,
+            ..Default::default()
+        });
+        assert_eq!(contract_voltage_mv(&Contract::from_source(pdo, fixed_rdo(2))), 9000);
+    }
+}
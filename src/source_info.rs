@@ -0,0 +1,93 @@
+//! Source_Info data object as defined in the USB PD specification 6.4.11
+//!
+//! The Source Information Data Object (SIDO) reports the power the source is able to supply on this port.
+use bitfield::bitfield;
+
+use crate::pdo::MW1000_UNIT;
+
+/// Source port type
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PortType {
+    /// Power is shared between ports and can change
+    #[default]
+    Managed,
+    /// The port maximum PDP is always available
+    Guaranteed,
+}
+
+bitfield! {
+    /// Raw Source_Info data object
+    #[derive(Copy, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    struct SourceInfoRaw(u32);
+    impl Debug;
+
+    /// Port type
+    pub bool, port_type, set_port_type: 31;
+    /// Port maximum PDP in W
+    pub u32, port_max_pdp, set_port_max_pdp: 23, 16;
+    /// Port present PDP in W
+    pub u32, port_present_pdp, set_port_present_pdp: 15, 8;
+    /// Port reported PDP in W
+    pub u32, port_reported_pdp, set_port_reported_pdp: 7, 0;
+}
+
+/// Source_Info data object
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SourceInfo {
+    /// Port type
+    pub port_type: PortType,
+    /// Maximum PDP the port is designed to supply in mW
+    pub port_max_pdp_mw: u32,
+    /// PDP the port is presently capable of supplying in mW
+    pub port_present_pdp_mw: u32,
+    /// PDP reported in the last Source_Capabilities message in mW
+    pub port_reported_pdp_mw: u32,
+}
+
+impl From<u32> for SourceInfo {
+    fn from(value: u32) -> Self {
+        let raw = SourceInfoRaw(value);
+        SourceInfo {
+            port_type: if raw.port_type() {
+                PortType::Guaranteed
+            } else {
+                PortType::Managed
+            },
+            port_max_pdp_mw: raw.port_max_pdp() * MW1000_UNIT,
+            port_present_pdp_mw: raw.port_present_pdp() * MW1000_UNIT,
+            port_reported_pdp_mw: raw.port_reported_pdp() * MW1000_UNIT,
+        }
+    }
+}
+
+impl From<SourceInfo> for u32 {
+    fn from(value: SourceInfo) -> Self {
+        let mut raw = SourceInfoRaw(0);
+        raw.set_port_type(value.port_type == PortType::Guaranteed);
+        raw.set_port_max_pdp(value.port_max_pdp_mw / MW1000_UNIT);
+        raw.set_port_present_pdp(value.port_present_pdp_mw / MW1000_UNIT);
+        raw.set_port_reported_pdp(value.port_reported_pdp_mw / MW1000_UNIT);
+        raw.0
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_source_info_roundtrip() {
+        // Guaranteed port, 100 W max, 65 W present and reported
+        let info = SourceInfo {
+            port_type: PortType::Guaranteed,
+            port_max_pdp_mw: 100000,
+            port_present_pdp_mw: 65000,
+            port_reported_pdp_mw: 65000,
+        };
+        assert_eq!(SourceInfo::from(0x8064_4141), info);
+        assert_eq!(u32::from(info), 0x8064_4141);
+    }
+}
@@ -0,0 +1,601 @@
+//! Status and PPS_Status extended messages as defined in the USB PD specification 6.5.2 and 6.5.10
+//!
+//! The Status Data Block (SDB) reports the present status of the port partner, the PPS Status Data Block (PPSSDB)
+//! reports the output of a source operating under a PPS contract.
+use bitfield::{bitfield, Bit};
+
+use crate::ado::{Alert, MAX_BATTERY_INDEX};
+use crate::pdo::{MA50_UNIT, MV20_UNIT};
+use crate::PdError;
+
+/// Length of the Status Data Block in bytes
+pub const STATUS_LEN: usize = 7;
+/// Length of the PPS Status Data Block in bytes
+pub const PPS_STATUS_LEN: usize = 4;
+
+/// Error type for data block conversion, contains the byte that failed to decode
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidField(pub u8);
+
+impl From<InvalidField> for PdError {
+    fn from(_: InvalidField) -> Self {
+        PdError::InvalidParams
+    }
+}
+
+/// Temperature status
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TemperatureStatus {
+    /// Not supported
+    #[default]
+    NotSupported,
+    /// Normal
+    Normal,
+    /// Warning
+    Warning,
+    /// Over temperature
+    OverTemperature,
+}
+
+impl From<u8> for TemperatureStatus {
+    fn from(value: u8) -> Self {
+        // NOTE: If this mask changes, the panic safety comment below must be reevaluated
+        const TEMPERATURE_STATUS_MASK: u8 = 0x3;
+        match value & TEMPERATURE_STATUS_MASK {
+            0x0 => TemperatureStatus::NotSupported,
+            0x1 => TemperatureStatus::Normal,
+            0x2 => TemperatureStatus::Warning,
+            0x3 => TemperatureStatus::OverTemperature,
+            // Panic safety: This will never panic if the mask above does not change
+            #[allow(clippy::unreachable)]
+            _ => unreachable!(),
+        }
+    }
+}
+
+impl From<TemperatureStatus> for u8 {
+    fn from(value: TemperatureStatus) -> Self {
+        match value {
+            TemperatureStatus::NotSupported => 0x0,
+            TemperatureStatus::Normal => 0x1,
+            TemperatureStatus::Warning => 0x2,
+            TemperatureStatus::OverTemperature => 0x3,
+        }
+    }
+}
+
+/// Present input
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PresentInput {
+    /// External power present
+    pub external_power: bool,
+    /// External power is AC, only valid if `external_power` is set
+    pub external_power_ac: bool,
+    /// Internal power from battery
+    pub internal_battery: bool,
+    /// Internal power from a non-battery power source
+    pub internal_non_battery: bool,
+}
+
+/// Batteries currently providing input power
+///
+/// Uses the same battery indices as [`crate::ado::BatteryStatusChange`].
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PresentBatteryInput {
+    fixed: u8,
+    hot_swappable: u8,
+}
+
+impl PresentBatteryInput {
+    /// Create a new instance from fixed and hot swappable battery bitmaps, only the lower four bits are used
+    pub fn new(fixed: u8, hot_swappable: u8) -> Self {
+        PresentBatteryInput {
+            fixed: fixed & 0xF,
+            hot_swappable: hot_swappable & 0xF,
+        }
+    }
+
+    /// Returns true if the fixed battery at the given index is providing input power
+    pub fn fixed_battery(&self, index: usize) -> Result<bool, PdError> {
+        if index > MAX_BATTERY_INDEX {
+            return Err(PdError::InvalidParams);
+        }
+        Ok(self.fixed.bit(index))
+    }
+
+    /// Returns true if the hot swappable battery at the given index is providing input power
+    pub fn hot_swappable_battery(&self, index: usize) -> Result<bool, PdError> {
+        if index > MAX_BATTERY_INDEX {
+            return Err(PdError::InvalidParams);
+        }
+        Ok(self.hot_swappable.bit(index))
+    }
+}
+
+/// Event flags
+///
+/// The OCP, OTP and OVP flags report the same events as the corresponding [`Alert`] values.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct EventFlags {
+    /// Over-current event
+    pub ocp: bool,
+    /// Over-temperature event
+    pub otp: bool,
+    /// Over-voltage event
+    pub ovp: bool,
+    /// PPS source is operating in current foldback mode instead of constant voltage mode
+    pub current_foldback: bool,
+}
+
+impl EventFlags {
+    /// Returns true if the event reported by the alert is set, returns false for alerts without an event flag
+    pub fn is_set(&self, alert: Alert) -> bool {
+        match alert {
+            Alert::Ocp => self.ocp,
+            Alert::Otp => self.otp,
+            Alert::Ovp => self.ovp,
+            Alert::OperatingConditionChange | Alert::SourceInputChange => false,
+        }
+    }
+}
+
+/// Reasons for source power being limited
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PowerStatus {
+    /// Limited due to the current supported by the cable
+    pub cable_current: bool,
+    /// Limited due to insufficient power available while sourcing other ports
+    pub multiple_ports: bool,
+    /// Limited due to insufficient external power
+    pub external_power: bool,
+    /// Limited due to the events in [`EventFlags`]
+    pub event_flags: bool,
+    /// Limited due to temperature
+    pub temperature: bool,
+}
+
+/// System power state
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PowerState {
+    /// Status not reported
+    #[default]
+    NotReported,
+    /// S0
+    S0,
+    /// Modern standby
+    ModernStandby,
+    /// S3
+    S3,
+    /// S4
+    S4,
+    /// S5, off with battery or external power
+    S5,
+    /// G3, off with no battery or external power
+    G3,
+}
+
+impl TryFrom<u8> for PowerState {
+    type Error = InvalidField;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x0 => Ok(PowerState::NotReported),
+            0x1 => Ok(PowerState::S0),
+            0x2 => Ok(PowerState::ModernStandby),
+            0x3 => Ok(PowerState::S3),
+            0x4 => Ok(PowerState::S4),
+            0x5 => Ok(PowerState::S5),
+            0x6 => Ok(PowerState::G3),
+            _ => Err(InvalidField(value)),
+        }
+    }
+}
+
+impl From<PowerState> for u8 {
+    fn from(value: PowerState) -> Self {
+        match value {
+            PowerState::NotReported => 0x0,
+            PowerState::S0 => 0x1,
+            PowerState::ModernStandby => 0x2,
+            PowerState::S3 => 0x3,
+            PowerState::S4 => 0x4,
+            PowerState::S5 => 0x5,
+            PowerState::G3 => 0x6,
+        }
+    }
+}
+
+/// Power state indicator
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum PowerIndicator {
+    /// Off LED
+    #[default]
+    Off,
+    /// On LED
+    On,
+    /// Blinking LED
+    Blinking,
+    /// Breathing LED
+    Breathing,
+}
+
+impl TryFrom<u8> for PowerIndicator {
+    type Error = InvalidField;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0x0 => Ok(PowerIndicator::Off),
+            0x1 => Ok(PowerIndicator::On),
+            0x2 => Ok(PowerIndicator::Blinking),
+            0x3 => Ok(PowerIndicator::Breathing),
+            _ => Err(InvalidField(value)),
+        }
+    }
+}
+
+impl From<PowerIndicator> for u8 {
+    fn from(value: PowerIndicator) -> Self {
+        match value {
+            PowerIndicator::Off => 0x0,
+            PowerIndicator::On => 0x1,
+            PowerIndicator::Blinking => 0x2,
+            PowerIndicator::Breathing => 0x3,
+        }
+    }
+}
+
+bitfield! {
+    /// Raw Status Data Block
+    #[derive(Copy, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    struct StatusRaw([u8]);
+    impl Debug;
+
+    /// Internal temperature in degrees Celsius
+    pub u8, internal_temp, set_internal_temp: 7, 0;
+    /// External power present
+    pub bool, external_power, set_external_power: 9;
+    /// External power is AC
+    pub bool, external_power_ac, set_external_power_ac: 10;
+    /// Internal power from battery
+    pub bool, internal_battery, set_internal_battery: 11;
+    /// Internal power from a non-battery power source
+    pub bool, internal_non_battery, set_internal_non_battery: 12;
+    /// Present fixed battery input
+    pub u8, fixed_battery_input, set_fixed_battery_input: 19, 16;
+    /// Present hot swappable battery input
+    pub u8, hot_swappable_battery_input, set_hot_swappable_battery_input: 23, 20;
+    /// OCP event
+    pub bool, ocp, set_ocp: 25;
+    /// OTP event
+    pub bool, otp, set_otp: 26;
+    /// OVP event
+    pub bool, ovp, set_ovp: 27;
+    /// Current foldback mode
+    pub bool, current_foldback, set_current_foldback: 28;
+    /// Temperature status
+    pub u8, temperature_status, set_temperature_status: 34, 33;
+    /// Power limited due to cable current
+    pub bool, power_limited_cable_current, set_power_limited_cable_current: 41;
+    /// Power limited due to multiple ports
+    pub bool, power_limited_multiple_ports, set_power_limited_multiple_ports: 42;
+    /// Power limited due to external power
+    pub bool, power_limited_external_power, set_power_limited_external_power: 43;
+    /// Power limited due to event flags
+    pub bool, power_limited_event_flags, set_power_limited_event_flags: 44;
+    /// Power limited due to temperature
+    pub bool, power_limited_temperature, set_power_limited_temperature: 45;
+    /// New power state
+    pub u8, power_state, set_power_state: 50, 48;
+    /// New power state indicator
+    pub u8, power_indicator, set_power_indicator: 53, 51;
+}
+
+/// Status Data Block
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Status {
+    /// Internal temperature in degrees Celsius, `None` if not supported. A value of 1 indicates less than 2 degrees.
+    pub internal_temp_c: Option<u8>,
+    /// Present input
+    pub present_input: PresentInput,
+    /// Batteries providing input power, only valid if [`PresentInput::internal_battery`] is set
+    pub present_battery_input: PresentBatteryInput,
+    /// Event flags
+    pub event_flags: EventFlags,
+    /// Temperature status
+    pub temperature_status: TemperatureStatus,
+    /// Power status
+    pub power_status: PowerStatus,
+    /// New power state
+    pub power_state: PowerState,
+    /// New power state indicator
+    pub power_indicator: PowerIndicator,
+}
+
+impl TryFrom<[u8; STATUS_LEN]> for Status {
+    type Error = InvalidField;
+
+    fn try_from(value: [u8; STATUS_LEN]) -> Result<Self, Self::Error> {
+        let raw = StatusRaw(value);
+        Ok(Status {
+            internal_temp_c: match raw.internal_temp() {
+                0 => None,
+                temp => Some(temp),
+            },
+            present_input: PresentInput {
+                external_power: raw.external_power(),
+                external_power_ac: raw.external_power_ac(),
+                internal_battery: raw.internal_battery(),
+                internal_non_battery: raw.internal_non_battery(),
+            },
+            present_battery_input: PresentBatteryInput::new(
+                raw.fixed_battery_input(),
+                raw.hot_swappable_battery_input(),
+            ),
+            event_flags: EventFlags {
+                ocp: raw.ocp(),
+                otp: raw.otp(),
+                ovp: raw.ovp(),
+                current_foldback: raw.current_foldback(),
+            },
+            temperature_status: raw.temperature_status().into(),
+            power_status: PowerStatus {
+                cable_current: raw.power_limited_cable_current(),
+                multiple_ports: raw.power_limited_multiple_ports(),
+                external_power: raw.power_limited_external_power(),
+                event_flags: raw.power_limited_event_flags(),
+                temperature: raw.power_limited_temperature(),
+            },
+            power_state: raw.power_state().try_into()?,
+            power_indicator: raw.power_indicator().try_into()?,
+        })
+    }
+}
+
+impl From<Status> for [u8; STATUS_LEN] {
+    fn from(value: Status) -> Self {
+        let mut raw = StatusRaw([0u8; STATUS_LEN]);
+        raw.set_internal_temp(value.internal_temp_c.unwrap_or(0));
+        raw.set_external_power(value.present_input.external_power);
+        raw.set_external_power_ac(value.present_input.external_power_ac);
+        raw.set_internal_battery(value.present_input.internal_battery);
+        raw.set_internal_non_battery(value.present_input.internal_non_battery);
+        raw.set_fixed_battery_input(value.present_battery_input.fixed);
+        raw.set_hot_swappable_battery_input(value.present_battery_input.hot_swappable);
+        raw.set_ocp(value.event_flags.ocp);
+        raw.set_otp(value.event_flags.otp);
+        raw.set_ovp(value.event_flags.ovp);
+        raw.set_current_foldback(value.event_flags.current_foldback);
+        raw.set_temperature_status(value.temperature_status.into());
+        raw.set_power_limited_cable_current(value.power_status.cable_current);
+        raw.set_power_limited_multiple_ports(value.power_status.multiple_ports);
+        raw.set_power_limited_external_power(value.power_status.external_power);
+        raw.set_power_limited_event_flags(value.power_status.event_flags);
+        raw.set_power_limited_temperature(value.power_status.temperature);
+        raw.set_power_state(value.power_state.into());
+        raw.set_power_indicator(value.power_indicator.into());
+        raw.0
+    }
+}
+
+bitfield! {
+    /// Raw PPS Status Data Block
+    #[derive(Copy, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    struct PpsStatusRaw([u8]);
+    impl Debug;
+
+    /// Output voltage in 20 mV units
+    pub u16, output_voltage, set_output_voltage: 15, 0;
+    /// Output current in 50 mA units
+    pub u16, output_current, set_output_current: 23, 16;
+    /// Present temperature flag
+    pub u8, temperature_status, set_temperature_status: 26, 25;
+    /// Operating mode flag, current foldback when set
+    pub bool, current_foldback, set_current_foldback: 27;
+}
+
+/// PPS Status Data Block
+///
+/// Encoding fails if the voltage or current is too large for its field.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PpsStatus {
+    /// Output voltage in mV, `None` if not supported
+    pub output_voltage_mv: Option<u32>,
+    /// Output current in mA, `None` if not supported
+    pub output_current_ma: Option<u16>,
+    /// Present temperature status
+    pub temperature_status: TemperatureStatus,
+    /// Source is operating in current foldback mode instead of constant voltage mode
+    pub current_foldback: bool,
+}
+
+/// Raw output voltage indicating that it is not supported
+const PPS_VOLTAGE_NOT_SUPPORTED: u16 = 0xFFFF;
+/// Raw output current indicating that it is not supported
+const PPS_CURRENT_NOT_SUPPORTED: u16 = 0xFF;
+
+impl From<[u8; PPS_STATUS_LEN]> for PpsStatus {
+    fn from(value: [u8; PPS_STATUS_LEN]) -> Self {
+        let raw = PpsStatusRaw(value);
+        PpsStatus {
+            output_voltage_mv: match raw.output_voltage() {
+                PPS_VOLTAGE_NOT_SUPPORTED => None,
+                voltage => Some(voltage as u32 * MV20_UNIT as u32),
+            },
+            output_current_ma: match raw.output_current() {
+                PPS_CURRENT_NOT_SUPPORTED => None,
+                current => Some(current * MA50_UNIT),
+            },
+            temperature_status: raw.temperature_status().into(),
+            current_foldback: raw.current_foldback(),
+        }
+    }
+}
+
+impl TryFrom<PpsStatus> for [u8; PPS_STATUS_LEN] {
+    type Error = PdError;
+
+    fn try_from(value: PpsStatus) -> Result<Self, Self::Error> {
+        let voltage = match value.output_voltage_mv {
+            None => PPS_VOLTAGE_NOT_SUPPORTED,
+            Some(voltage_mv) => u16::try_from(voltage_mv / MV20_UNIT as u32)
+                .ok()
+                .filter(|voltage| *voltage != PPS_VOLTAGE_NOT_SUPPORTED)
+                .ok_or(PdError::InvalidParams)?,
+        };
+        let current = match value.output_current_ma {
+            None => PPS_CURRENT_NOT_SUPPORTED,
+            Some(current_ma) if current_ma / MA50_UNIT < PPS_CURRENT_NOT_SUPPORTED => current_ma / MA50_UNIT,
+            Some(_) => return Err(PdError::InvalidParams),
+        };
+
+        let mut raw = PpsStatusRaw([0u8; PPS_STATUS_LEN]);
+        raw.set_output_voltage(voltage);
+        raw.set_output_current(current);
+        raw.set_temperature_status(value.temperature_status.into());
+        raw.set_current_foldback(value.current_foldback);
+        Ok(raw.0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_roundtrip() {
+        // 45 C, external AC power, OCP and OTP events, over temperature,
+        // power limited due to event flags and temperature, S0 with LED on
+        const RAW_STATUS: [u8; STATUS_LEN] = [0x2D, 0x06, 0x00, 0x06, 0x06, 0x30, 0x09];
+        let expected = Status {
+            internal_temp_c: Some(45),
+            present_input: PresentInput {
+                external_power: true,
+                external_power_ac: true,
+                internal_battery: false,
+                internal_non_battery: false,
+            },
+            present_battery_input: PresentBatteryInput::default(),
+            event_flags: EventFlags {
+                ocp: true,
+                otp: true,
+                ovp: false,
+                current_foldback: false,
+            },
+            temperature_status: TemperatureStatus::OverTemperature,
+            power_status: PowerStatus {
+                cable_current: false,
+                multiple_ports: false,
+                external_power: false,
+                event_flags: true,
+                temperature: true,
+            },
+            power_state: PowerState::S0,
+            power_indicator: PowerIndicator::On,
+        };
+        assert_eq!(Status::try_from(RAW_STATUS), Ok(expected));
+        assert_eq!(<[u8; STATUS_LEN]>::from(expected), RAW_STATUS);
+
+        assert!(expected.event_flags.is_set(Alert::Ocp));
+        assert!(expected.event_flags.is_set(Alert::Otp));
+        assert!(!expected.event_flags.is_set(Alert::Ovp));
+        assert!(!expected.event_flags.is_set(Alert::SourceInputChange));
+    }
+
+    #[test]
+    fn test_status_battery_input() {
+        // Internal battery power from fixed battery 1 and hot swappable battery 2
+        const RAW_STATUS: [u8; STATUS_LEN] = [0x00, 0x08, 0x42, 0x00, 0x00, 0x00, 0x00];
+        let expected = Status {
+            internal_temp_c: None,
+            present_input: PresentInput {
+                internal_battery: true,
+                ..Default::default()
+            },
+            present_battery_input: PresentBatteryInput::new(0x2, 0x4),
+            ..Default::default()
+        };
+        assert_eq!(Status::try_from(RAW_STATUS), Ok(expected));
+        assert_eq!(<[u8; STATUS_LEN]>::from(expected), RAW_STATUS);
+
+        let batteries = expected.present_battery_input;
+        assert_eq!(batteries.fixed_battery(0), Ok(false));
+        assert_eq!(batteries.fixed_battery(1), Ok(true));
+        assert_eq!(batteries.hot_swappable_battery(1), Ok(false));
+        assert_eq!(batteries.hot_swappable_battery(2), Ok(true));
+        assert_eq!(batteries.fixed_battery(4), Err(PdError::InvalidParams));
+    }
+
+    #[test]
+    fn test_status_invalid() {
+        // Reserved power state
+        assert_eq!(
+            Status::try_from([0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x07]),
+            Err(InvalidField(0x07))
+        );
+        // Reserved power indicator
+        assert_eq!(
+            Status::try_from([0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x20]),
+            Err(InvalidField(0x04))
+        );
+    }
+
+    #[test]
+    fn test_pps_status_roundtrip() {
+        // 9.02 V, 2.95 A, normal temperature, current foldback
+        const RAW_PPS_STATUS: [u8; PPS_STATUS_LEN] = [0xC3, 0x01, 0x3B, 0x0A];
+        let expected = PpsStatus {
+            output_voltage_mv: Some(9020),
+            output_current_ma: Some(2950),
+            temperature_status: TemperatureStatus::Normal,
+            current_foldback: true,
+        };
+        assert_eq!(PpsStatus::from(RAW_PPS_STATUS), expected);
+        assert_eq!(<[u8; PPS_STATUS_LEN]>::try_from(expected), Ok(RAW_PPS_STATUS));
+    }
+
+    #[test]
+    fn test_pps_status_not_supported() {
+        const RAW_PPS_STATUS: [u8; PPS_STATUS_LEN] = [0xFF, 0xFF, 0xFF, 0x00];
+        let expected = PpsStatus {
+            output_voltage_mv: None,
+            output_current_ma: None,
+            temperature_status: TemperatureStatus::NotSupported,
+            current_foldback: false,
+        };
+        assert_eq!(PpsStatus::from(RAW_PPS_STATUS), expected);
+        assert_eq!(<[u8; PPS_STATUS_LEN]>::try_from(expected), Ok(RAW_PPS_STATUS));
+    }
+
+    #[test]
+    fn test_pps_status_range() {
+        // The highest voltage does not overflow
+        let status = PpsStatus::from([0xFE, 0xFF, 0xFE, 0x00]);
+        assert_eq!(status.output_voltage_mv, Some(1_310_680));
+        assert_eq!(status.output_current_ma, Some(12700));
+        assert_eq!(<[u8; PPS_STATUS_LEN]>::try_from(status), Ok([0xFE, 0xFF, 0xFE, 0x00]));
+
+        // Values that would encode as not supported or truncate are rejected
+        let status = PpsStatus {
+            output_voltage_mv: Some(1_310_700),
+            ..Default::default()
+        };
+        assert_eq!(<[u8; PPS_STATUS_LEN]>::try_from(status), Err(PdError::InvalidParams));
+        let status = PpsStatus {
+            output_current_ma: Some(12750),
+            ..Default::default()
+        };
+        assert_eq!(<[u8; PPS_STATUS_LEN]>::try_from(status), Err(PdError::InvalidParams));
+    }
+}
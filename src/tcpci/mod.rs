@@ -0,0 +1,500 @@
+//! Driver for USB Type-C Port Controllers implementing the TCPCI specification, revision 2.0
+//!
+//! [`Tcpci`] provides typed access to the standard register set over an async I2C bus, [`phy::TcpciPhy`] adapts it to
+//! the protocol layer.
+use embedded_hal_async::i2c::I2c;
+
+use crate::protocol::Sop;
+use crate::type_c::state_machine::Cc;
+use crate::{Error, PdError};
+
+pub mod phy;
+pub mod registers;
+
+use registers::{
+    frame_type, vbus_voltage_mv, Alerts, CcStatus, Command, MessageHeaderInfo, PowerControl, PowerStatus,
+    ReceiveDetect, Register, RoleControl, TcpcControl, Transmit, TransmitType,
+};
+
+/// Maximum number of retries the TCPC can perform for a transmission
+pub const MAX_TRANSMIT_RETRIES: u8 = 3;
+
+/// Size of the receive and transmit buffers, frame type or byte count followed by the header and up to 7 data objects
+const BUFFER_LEN: usize = 31;
+
+/// Vendor, product and device IDs
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct DeviceId {
+    /// USB vendor ID
+    pub vendor_id: u16,
+    /// Product ID
+    pub product_id: u16,
+    /// Device ID
+    pub device_id: u16,
+}
+
+/// TCPCI driver
+pub struct Tcpci<I2C> {
+    /// I2C bus
+    i2c: I2C,
+    /// 7-bit I2C address
+    address: u8,
+}
+
+impl<I2C: I2c> Tcpci<I2C> {
+    /// Create a new driver for the TCPC at the given 7-bit I2C address
+    pub fn new(i2c: I2C, address: u8) -> Self {
+        Self { i2c, address }
+    }
+
+    /// Release the I2C bus
+    pub fn release(self) -> I2C {
+        self.i2c
+    }
+
+    /// Read consecutive registers starting at `register`
+    pub async fn read(&mut self, register: Register, buf: &mut [u8]) -> Result<(), Error<I2C::Error>> {
+        self.i2c
+            .write_read(self.address, &[register.address()], buf)
+            .await
+            .map_err(Error::Bus)
+    }
+
+    /// Read an 8-bit register
+    pub async fn read_u8(&mut self, register: Register) -> Result<u8, Error<I2C::Error>> {
+        let mut buf = [0; 1];
+        self.read(register, &mut buf).await?;
+        Ok(buf[0])
+    }
+
+    /// Read a 16-bit register
+    pub async fn read_u16(&mut self, register: Register) -> Result<u16, Error<I2C::Error>> {
+        let mut buf = [0; 2];
+        self.read(register, &mut buf).await?;
+        Ok(u16::from_le_bytes(buf))
+    }
+
+    /// Write an 8-bit register
+    pub async fn write_u8(&mut self, register: Register, value: u8) -> Result<(), Error<I2C::Error>> {
+        self.i2c
+            .write(self.address, &[register.address(), value])
+            .await
+            .map_err(Error::Bus)
+    }
+
+    /// Write a 16-bit register
+    pub async fn write_u16(&mut self, register: Register, value: u16) -> Result<(), Error<I2C::Error>> {
+        let [low, high] = value.to_le_bytes();
+        self.i2c
+            .write(self.address, &[register.address(), low, high])
+            .await
+            .map_err(Error::Bus)
+    }
+
+    /// Read the vendor, product and device IDs, these can be read while the TCPC is still initializing
+    pub async fn device_id(&mut self) -> Result<DeviceId, Error<I2C::Error>> {
+        Ok(DeviceId {
+            vendor_id: self.read_u16(Register::VendorId).await?,
+            product_id: self.read_u16(Register::ProductId).await?,
+            device_id: self.read_u16(Register::DeviceId).await?,
+        })
+    }
+
+    /// Initialize the TCPC, clears all alerts and unmasks the given alerts
+    ///
+    /// Returns [`PdError::Busy`] if the TCPC is still initializing.
+    pub async fn init(&mut self, alert_mask: Alerts) -> Result<(), Error<I2C::Error>> {
+        if self.power_status().await?.tcpc_initializing {
+            return PdError::Busy.into();
+        }
+
+        self.clear_alert(Alerts::from(u16::MAX)).await?;
+        self.set_alert_mask(alert_mask).await
+    }
+
+    /// Read pending alerts
+    pub async fn alert(&mut self) -> Result<Alerts, Error<I2C::Error>> {
+        Ok(self.read_u16(Register::Alert).await?.into())
+    }
+
+    /// Clear the given alerts
+    pub async fn clear_alert(&mut self, alerts: Alerts) -> Result<(), Error<I2C::Error>> {
+        self.write_u16(Register::Alert, alerts.into()).await
+    }
+
+    /// Set the alerts that assert the ALERT# pin
+    pub async fn set_alert_mask(&mut self, alerts: Alerts) -> Result<(), Error<I2C::Error>> {
+        self.write_u16(Register::AlertMask, alerts.into()).await
+    }
+
+    /// Read ROLE_CONTROL
+    pub async fn role_control(&mut self) -> Result<RoleControl, Error<I2C::Error>> {
+        Ok(RoleControl::try_from(self.read_u8(Register::RoleControl).await?).map_err(PdError::from)?)
+    }
+
+    /// Write ROLE_CONTROL
+    pub async fn set_role_control(&mut self, role_control: RoleControl) -> Result<(), Error<I2C::Error>> {
+        self.write_u8(Register::RoleControl, role_control.into()).await
+    }
+
+    /// Read CC_STATUS
+    pub async fn cc_status(&mut self) -> Result<CcStatus, Error<I2C::Error>> {
+        Ok(self.read_u8(Register::CcStatus).await?.into())
+    }
+
+    /// Returns the detected CC state of both pins
+    pub async fn cc(&mut self) -> Result<Cc, Error<I2C::Error>> {
+        let role_control = self.role_control().await?;
+        Ok(self.cc_status().await?.cc(role_control))
+    }
+
+    /// Read POWER_STATUS
+    pub async fn power_status(&mut self) -> Result<PowerStatus, Error<I2C::Error>> {
+        Ok(self.read_u8(Register::PowerStatus).await?.into())
+    }
+
+    /// Read TCPC_CONTROL
+    pub async fn tcpc_control(&mut self) -> Result<TcpcControl, Error<I2C::Error>> {
+        Ok(self.read_u8(Register::TcpcControl).await?.into())
+    }
+
+    /// Write TCPC_CONTROL
+    pub async fn set_tcpc_control(&mut self, control: TcpcControl) -> Result<(), Error<I2C::Error>> {
+        self.write_u8(Register::TcpcControl, control.into()).await
+    }
+
+    /// Read POWER_CONTROL
+    pub async fn power_control(&mut self) -> Result<PowerControl, Error<I2C::Error>> {
+        Ok(self.read_u8(Register::PowerControl).await?.into())
+    }
+
+    /// Write POWER_CONTROL
+    pub async fn set_power_control(&mut self, control: PowerControl) -> Result<(), Error<I2C::Error>> {
+        self.write_u8(Register::PowerControl, control.into()).await
+    }
+
+    /// Execute a command
+    pub async fn command(&mut self, command: Command) -> Result<(), Error<I2C::Error>> {
+        self.write_u8(Register::Command, command.into()).await
+    }
+
+    /// Read MESSAGE_HEADER_INFO
+    pub async fn message_header_info(&mut self) -> Result<MessageHeaderInfo, Error<I2C::Error>> {
+        Ok(MessageHeaderInfo::try_from(self.read_u8(Register::MessageHeaderInfo).await?).map_err(PdError::from)?)
+    }
+
+    /// Write MESSAGE_HEADER_INFO, this must be updated on every role swap so GoodCRC headers are correct
+    pub async fn set_message_header_info(&mut self, info: MessageHeaderInfo) -> Result<(), Error<I2C::Error>> {
+        self.write_u8(Register::MessageHeaderInfo, info.into()).await
+    }
+
+    /// Write RECEIVE_DETECT
+    pub async fn set_receive_detect(&mut self, detect: ReceiveDetect) -> Result<(), Error<I2C::Error>> {
+        self.write_u8(Register::ReceiveDetect, detect.into()).await
+    }
+
+    /// Returns the VBUS voltage in mV
+    pub async fn vbus_voltage_mv(&mut self) -> Result<u32, Error<I2C::Error>> {
+        Ok(vbus_voltage_mv(self.read_u16(Register::VbusVoltage).await?))
+    }
+
+    /// Read a received message into `buf` and release the receive buffer
+    ///
+    /// Returns the SOP* type and length of the header and payload, or `None` if no message is pending. Cable resets
+    /// and frames with an unknown SOP* type are discarded.
+    pub async fn read_message(&mut self, buf: &mut [u8]) -> Result<Option<(Sop, usize)>, Error<I2C::Error>> {
+        if !self.alert().await?.contains(registers::Alert::ReceivedSopMessage) {
+            return Ok(None);
+        }
+
+        // READABLE_BYTE_COUNT includes RX_BUF_FRAME_TYPE
+        let count = self.read_u8(Register::ReceiveBuffer).await? as usize;
+        let mut frame = [0; BUFFER_LEN + 1];
+        let frame = frame.get_mut(..count + 1).ok_or(PdError::InvalidResponse)?;
+        self.read(Register::ReceiveBuffer, frame).await?;
+        self.clear_alert(Alerts::new().with_alert(registers::Alert::ReceivedSopMessage))
+            .await?;
+
+        let Some((&frame_type_value, message)) = frame.get(1..).and_then(|frame| frame.split_first()) else {
+            return Ok(None);
+        };
+        let Ok(sop) = frame_type(frame_type_value) else {
+            return Ok(None);
+        };
+
+        buf.get_mut(..message.len())
+            .ok_or(PdError::InvalidParams)?
+            .copy_from_slice(message);
+        Ok(Some((sop, message.len())))
+    }
+
+    /// Load `data` into the transmit buffer and transmit it, the outcome is reported through the transmit alerts
+    ///
+    /// `data` contains the header and payload, the TCPC appends the CRC. The TCPC retries up to `retries` times if no
+    /// GoodCRC is received, at most [`MAX_TRANSMIT_RETRIES`].
+    pub async fn transmit(&mut self, sop: Sop, data: &[u8], retries: u8) -> Result<(), Error<I2C::Error>> {
+        if retries > MAX_TRANSMIT_RETRIES {
+            return PdError::InvalidParams.into();
+        }
+
+        // Register address followed by I2C_WRITE_BYTE_COUNT and the message
+        let mut buf = [0; BUFFER_LEN + 1];
+        buf[0] = Register::TransmitBuffer.address();
+        buf[1] = data.len() as u8;
+        buf.get_mut(2..data.len() + 2)
+            .ok_or(PdError::InvalidParams)?
+            .copy_from_slice(data);
+        self.i2c
+            .write(self.address, buf.get(..data.len() + 2).unwrap_or_default())
+            .await
+            .map_err(Error::Bus)?;
+
+        self.write_u8(
+            Register::Transmit,
+            Transmit {
+                transmit_type: TransmitType::Message(sop),
+                retries,
+            }
+            .into(),
+        )
+        .await
+    }
+
+    /// Transmit a Hard_Reset, completion is reported through the transmit alerts
+    pub async fn transmit_hard_reset(&mut self) -> Result<(), Error<I2C::Error>> {
+        self.write_u8(
+            Register::Transmit,
+            Transmit {
+                transmit_type: TransmitType::HardReset,
+                retries: 0,
+            }
+            .into(),
+        )
+        .await
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod test {
+    extern crate std;
+
+    use core::convert::Infallible;
+
+    use embedded_hal_async::i2c::{ErrorType, Operation};
+
+    use super::registers::{Alert, CcTermination};
+    use super::*;
+    use crate::test::block_on;
+    use crate::type_c::state_machine::CcState;
+    use crate::type_c::Current;
+
+    /// I2C address used in tests
+    pub(crate) const ADDRESS: u8 = 0x50;
+
+    /// Mock TCPC register file
+    ///
+    /// ALERT is write-1-to-clear, writing TRANSMIT raises the alert given by `transmit_result`.
+    pub(crate) struct MockTcpc {
+        /// Register contents
+        pub registers: [u8; 256],
+        /// Alert raised when TRANSMIT is written
+        pub transmit_result: Alert,
+        /// Messages transmitted, contents of the transmit buffer and the TRANSMIT value
+        pub transmitted: std::vec::Vec<(std::vec::Vec<u8>, u8)>,
+    }
+
+    impl Default for MockTcpc {
+        fn default() -> Self {
+            Self {
+                registers: [0; 256],
+                transmit_result: Alert::TransmitSuccessful,
+                transmitted: std::vec::Vec::new(),
+            }
+        }
+    }
+
+    impl MockTcpc {
+        /// Set an 8-bit register
+        pub fn set_u8(&mut self, register: Register, value: u8) {
+            if let Some(dest) = self.registers.get_mut(register.address() as usize) {
+                *dest = value;
+            }
+        }
+
+        /// Set a 16-bit register
+        pub fn set_u16(&mut self, register: Register, value: u16) {
+            let address = register.address() as usize;
+            if let Some(dest) = self.registers.get_mut(address..address + 2) {
+                dest.copy_from_slice(&value.to_le_bytes());
+            }
+        }
+
+        /// Get a 16-bit register
+        pub fn u16(&self, register: Register) -> u16 {
+            let address = register.address() as usize;
+            let low = self.registers.get(address).copied().unwrap_or_default();
+            let high = self.registers.get(address + 1).copied().unwrap_or_default();
+            u16::from_le_bytes([low, high])
+        }
+
+        /// Place a message in the receive buffer and raise the receive alert
+        pub fn receive(&mut self, frame_type: u8, data: &[u8]) {
+            let address = Register::ReceiveBuffer.address() as usize;
+            if let Some(dest) = self.registers.get_mut(address..address + data.len() + 2) {
+                let (count, message) = dest.split_at_mut(2);
+                count.copy_from_slice(&[data.len() as u8 + 1, frame_type]);
+                message.copy_from_slice(data);
+            }
+            let alert = self.u16(Register::Alert) | u16::from(Alerts::new().with_alert(Alert::ReceivedSopMessage));
+            self.set_u16(Register::Alert, alert);
+        }
+
+        fn write(&mut self, data: &[u8]) {
+            let Some((&address, data)) = data.split_first() else {
+                return;
+            };
+
+            if address == Register::Alert.address() {
+                let [low, high] = self.u16(Register::Alert).to_le_bytes();
+                let clear = |value: u8, index: usize| value & !data.get(index).copied().unwrap_or_default();
+                self.set_u16(Register::Alert, u16::from_le_bytes([clear(low, 0), clear(high, 1)]));
+                return;
+            }
+
+            let address = address as usize;
+            if let Some(dest) = self.registers.get_mut(address..address + data.len()) {
+                dest.copy_from_slice(data);
+            }
+
+            if address == Register::Transmit.address() as usize {
+                let start = Register::TransmitBuffer.address() as usize;
+                let count = self.registers.get(start).copied().unwrap_or_default() as usize;
+                let buffer = self
+                    .registers
+                    .get(start + 1..start + 1 + count)
+                    .unwrap_or_default()
+                    .to_vec();
+                self.transmitted
+                    .push((buffer, data.first().copied().unwrap_or_default()));
+                let alert = self.u16(Register::Alert) | u16::from(Alerts::new().with_alert(self.transmit_result));
+                self.set_u16(Register::Alert, alert);
+            }
+        }
+    }
+
+    impl ErrorType for MockTcpc {
+        type Error = Infallible;
+    }
+
+    impl I2c for MockTcpc {
+        async fn transaction(&mut self, address: u8, operations: &mut [Operation<'_>]) -> Result<(), Self::Error> {
+            if address != ADDRESS {
+                return Ok(());
+            }
+
+            let mut pointer = 0;
+            for operation in operations {
+                match operation {
+                    Operation::Write(data) => {
+                        pointer = data.first().copied().unwrap_or_default() as usize;
+                        self.write(data);
+                    }
+                    Operation::Read(buf) => {
+                        for (i, byte) in buf.iter_mut().enumerate() {
+                            *byte = self.registers.get(pointer + i).copied().unwrap_or_default();
+                        }
+                    }
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_init() {
+        let mut mock = MockTcpc::default();
+        mock.set_u16(Register::VendorId, 0x1FC9);
+        mock.set_u16(Register::ProductId, 0x5110);
+        mock.set_u16(Register::Alert, 0x0041);
+        mock.set_u8(Register::PowerStatus, 0x40);
+        let mut tcpci = Tcpci::new(mock, ADDRESS);
+
+        let alerts = Alerts::new().with_alert(Alert::CcStatus);
+        assert_eq!(block_on(tcpci.init(alerts)), Err(Error::Pd(PdError::Busy)));
+        assert_eq!(
+            block_on(tcpci.device_id()),
+            Ok(DeviceId {
+                vendor_id: 0x1FC9,
+                product_id: 0x5110,
+                device_id: 0,
+            })
+        );
+
+        let mut mock = tcpci.release();
+        mock.set_u8(Register::PowerStatus, 0x00);
+        let mut tcpci = Tcpci::new(mock, ADDRESS);
+        assert_eq!(block_on(tcpci.init(alerts)), Ok(()));
+        assert_eq!(block_on(tcpci.alert()), Ok(Alerts::new()));
+
+        let mock = tcpci.release();
+        assert_eq!(mock.u16(Register::AlertMask), 0x0001);
+    }
+
+    #[test]
+    fn test_cc() {
+        let mut tcpci = Tcpci::new(MockTcpc::default(), ADDRESS);
+        let role_control = RoleControl::both(CcTermination::Rd, Current::UsbDefault);
+        assert_eq!(block_on(tcpci.set_role_control(role_control)), Ok(()));
+        assert_eq!(block_on(tcpci.role_control()), Ok(role_control));
+
+        let mut mock = tcpci.release();
+        mock.set_u8(Register::CcStatus, 0x03);
+        let mut tcpci = Tcpci::new(mock, ADDRESS);
+        assert_eq!(
+            block_on(tcpci.cc()),
+            Ok(Cc::new(CcState::Rp(Current::Current3A0), CcState::Open))
+        );
+    }
+
+    #[test]
+    fn test_read_message() {
+        let mut tcpci = Tcpci::new(MockTcpc::default(), ADDRESS);
+        let mut buf = [0; 30];
+        assert_eq!(block_on(tcpci.read_message(&mut buf)), Ok(None));
+
+        let mut mock = tcpci.release();
+        mock.receive(0x01, &[0x41, 0x10, 0x01, 0x02, 0x03, 0x04]);
+        let mut tcpci = Tcpci::new(mock, ADDRESS);
+        assert_eq!(block_on(tcpci.read_message(&mut buf)), Ok(Some((Sop::SopPrime, 6))));
+        assert_eq!(buf.get(..6), Some([0x41, 0x10, 0x01, 0x02, 0x03, 0x04].as_slice()));
+        assert_eq!(block_on(tcpci.alert()), Ok(Alerts::new()));
+
+        // Cable reset is discarded
+        let mut mock = tcpci.release();
+        mock.receive(0x06, &[]);
+        let mut tcpci = Tcpci::new(mock, ADDRESS);
+        assert_eq!(block_on(tcpci.read_message(&mut buf)), Ok(None));
+    }
+
+    #[test]
+    fn test_transmit() {
+        let mut tcpci = Tcpci::new(MockTcpc::default(), ADDRESS);
+        assert_eq!(block_on(tcpci.transmit(Sop::Sop, &[0x41, 0x10], 2)), Ok(()));
+        assert_eq!(
+            block_on(tcpci.transmit(Sop::Sop, &[0x41, 0x10], 4)),
+            Err(Error::Pd(PdError::InvalidParams))
+        );
+        assert_eq!(
+            block_on(tcpci.alert()),
+            Ok(Alerts::new().with_alert(Alert::TransmitSuccessful))
+        );
+        assert_eq!(block_on(tcpci.transmit_hard_reset()), Ok(()));
+
+        let mock = tcpci.release();
+        assert_eq!(
+            mock.transmitted,
+            [(std::vec![0x41, 0x10], 0x20), (std::vec![0x41, 0x10], 0x05)]
+        );
+    }
+}
@@ -0,0 +1,299 @@
+//! Protocol layer PHY implementation on top of a TCPC
+//!
+//! A TCPC generates and checks GoodCRC itself, while [`crate::protocol::Protocol`] sends and expects GoodCRC messages.
+//! [`TcpciPhy`] bridges the two: GoodCRC transmits are dropped and a successful transmission is reported by returning a
+//! GoodCRC with the transmitted MessageID from the next receive. The TCPC performs the `nRetryCount` retries itself
+//! to meet `tRetry`, so the protocol layer does not retry. If the TCPC discards a transmission because a message is
+//! being received, the next receive waits for that message so the protocol layer handles it first.
+use embedded_hal_async::delay::DelayNs;
+use embedded_hal_async::i2c::I2c;
+
+use super::registers::{Alert, Alerts};
+use super::Tcpci;
+use crate::constants::N_RETRY_COUNT;
+use crate::protocol::header::{ControlMessageType, MessageHeader, MessageType};
+use crate::protocol::{PhyReceive, PhyTransmit, Sop};
+use crate::{Error, PdError};
+
+/// Interval between ALERT polls in us
+pub const POLL_INTERVAL_US: u16 = 50;
+
+/// Maximum time to wait for the TCPC to report the outcome of a transmission in us
+pub const TRANSMIT_TIMEOUT_US: u16 = 5000;
+
+/// Alerts reporting the outcome of a transmission
+const TRANSMIT_ALERTS: Alerts = Alerts::new()
+    .with_alert(Alert::TransmitSuccessful)
+    .with_alert(Alert::TransmitFailed)
+    .with_alert(Alert::TransmitDiscarded);
+
+/// PHY adapter for a TCPC
+pub struct TcpciPhy<I2C, D> {
+    /// TCPC driver
+    tcpci: Tcpci<I2C>,
+    /// Delay used when polling ALERT
+    delay: D,
+    /// GoodCRC to return from the next receive, SOP* type and MessageID of the acknowledged transmission
+    good_crc: Option<(Sop, u8)>,
+    /// The last transmission was discarded because a message is being received
+    discarded: bool,
+}
+
+impl<I2C: I2c, D: DelayNs> TcpciPhy<I2C, D> {
+    /// Create a new adapter, the TCPC must already be configured to receive messages
+    pub fn new(tcpci: Tcpci<I2C>, delay: D) -> Self {
+        Self {
+            tcpci,
+            delay,
+            good_crc: None,
+            discarded: false,
+        }
+    }
+
+    /// Returns the TCPC driver
+    pub fn tcpci(&mut self) -> &mut Tcpci<I2C> {
+        &mut self.tcpci
+    }
+
+    /// Release the TCPC driver and delay
+    pub fn release(self) -> (Tcpci<I2C>, D) {
+        (self.tcpci, self.delay)
+    }
+
+    /// Wait for the TCPC to report the outcome of a transmission, returns the transmit alert that was raised
+    async fn wait_transmit(&mut self) -> Result<Alert, Error<I2C::Error>> {
+        let mut elapsed_us = 0;
+        loop {
+            let alerts = self.tcpci.alert().await?;
+            let outcome = [
+                Alert::TransmitSuccessful,
+                Alert::TransmitDiscarded,
+                Alert::TransmitFailed,
+            ]
+            .into_iter()
+            .find(|alert| alerts.contains(*alert));
+            if let Some(outcome) = outcome {
+                self.tcpci.clear_alert(TRANSMIT_ALERTS).await?;
+                return Ok(outcome);
+            }
+
+            if elapsed_us >= TRANSMIT_TIMEOUT_US {
+                return PdError::Timeout.into();
+            }
+
+            self.delay.delay_us(POLL_INTERVAL_US as u32).await;
+            elapsed_us += POLL_INTERVAL_US;
+        }
+    }
+}
+
+impl<I2C: I2c, D: DelayNs> PhyTransmit for TcpciPhy<I2C, D> {
+    type Error = Error<I2C::Error>;
+
+    const HANDLES_RETRIES: bool = true;
+
+    async fn transmit(&mut self, sop: Sop, data: &[u8]) -> Result<(), Self::Error> {
+        let Some(&[low, high]) = data.first_chunk::<2>() else {
+            return PdError::InvalidParams.into();
+        };
+
+        let header = MessageHeader::from(u16::from_le_bytes([low, high]));
+        if header.is_control(ControlMessageType::GoodCrc) {
+            // The TCPC has already sent the GoodCRC
+            return Ok(());
+        }
+
+        self.good_crc = None;
+        self.discarded = false;
+        self.tcpci.clear_alert(TRANSMIT_ALERTS).await?;
+        self.tcpci.transmit(sop, data, N_RETRY_COUNT).await?;
+        match self.wait_transmit().await? {
+            Alert::TransmitSuccessful => self.good_crc = Some((sop, header.message_id())),
+            Alert::TransmitDiscarded => self.discarded = true,
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn transmit_hard_reset(&mut self) -> Result<(), Self::Error> {
+        self.good_crc = None;
+        self.discarded = false;
+        self.tcpci.clear_alert(TRANSMIT_ALERTS).await?;
+        self.tcpci.transmit_hard_reset().await?;
+        self.wait_transmit().await?;
+        Ok(())
+    }
+}
+
+impl<I2C: I2c, D: DelayNs> PhyReceive for TcpciPhy<I2C, D> {
+    type Error = Error<I2C::Error>;
+
+    async fn receive(&mut self, buf: &mut [u8], timeout_us: Option<u16>) -> Result<Option<(Sop, usize)>, Self::Error> {
+        if let Some((sop, message_id)) = self.good_crc.take() {
+            let mut header = MessageHeader::new(MessageType::Control(ControlMessageType::GoodCrc), 0);
+            header.set_message_id(message_id);
+            let bytes = u16::from(header).to_le_bytes();
+            buf.get_mut(..bytes.len())
+                .ok_or(PdError::InvalidParams)?
+                .copy_from_slice(&bytes);
+            return Ok(Some((sop, bytes.len())));
+        }
+
+        // The message that caused a discard may still be arriving
+        let timeout_us = if core::mem::take(&mut self.discarded) {
+            timeout_us.map(|timeout_us| timeout_us.max(TRANSMIT_TIMEOUT_US))
+        } else {
+            timeout_us
+        };
+
+        let mut elapsed_us = 0;
+        loop {
+            if let Some(received) = self.tcpci.read_message(buf).await? {
+                return Ok(Some(received));
+            }
+
+            if timeout_us.is_some_and(|timeout_us| elapsed_us >= timeout_us) {
+                return Ok(None);
+            }
+
+            self.delay.delay_us(POLL_INTERVAL_US as u32).await;
+            elapsed_us = elapsed_us.saturating_add(POLL_INTERVAL_US);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    extern crate std;
+
+    use std::vec::Vec;
+
+    use super::super::test::{MockTcpc, ADDRESS};
+    use super::*;
+    use crate::protocol::message::tests::header_only;
+    use crate::protocol::message::RawMessage;
+    use crate::protocol::{Config, Protocol};
+    use crate::revision::SpecRevision;
+    use crate::test::block_on;
+    use crate::{DataRole, PowerRole};
+
+    /// Delay that completes immediately
+    struct NoDelay;
+
+    impl DelayNs for NoDelay {
+        async fn delay_ns(&mut self, _ns: u32) {}
+    }
+
+    fn protocol(mock: MockTcpc) -> Protocol<TcpciPhy<MockTcpc, NoDelay>> {
+        Protocol::new(
+            TcpciPhy::new(Tcpci::new(mock, ADDRESS), NoDelay),
+            Config {
+                spec_revision: SpecRevision::Rev3,
+                power_role: PowerRole::Sink,
+                data_role: DataRole::Ufp,
+            },
+        )
+    }
+
+    fn header(message_type: ControlMessageType, message_id: u8) -> MessageHeader {
+        let mut header = MessageHeader::new(MessageType::Control(message_type), 0);
+        header.set_message_id(message_id);
+        header
+    }
+
+    /// Returns the message IDs and TRANSMIT values of all transmissions
+    fn transmitted(protocol: &mut Protocol<TcpciPhy<MockTcpc, NoDelay>>) -> Vec<(u8, u8)> {
+        protocol
+            .phy()
+            .tcpci
+            .i2c
+            .transmitted
+            .iter()
+            .map(|(data, transmit)| {
+                let header = data.first_chunk::<2>().copied().unwrap_or_default();
+                (MessageHeader::from(u16::from_le_bytes(header)).message_id(), *transmit)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_transmit() {
+        let mut protocol = protocol(MockTcpc::default());
+        let accept = header_only(header(ControlMessageType::Accept, 0));
+
+        assert_eq!(block_on(protocol.transmit(Sop::Sop, &accept)), Ok(()));
+        assert_eq!(block_on(protocol.transmit(Sop::SopPrime, &accept)), Ok(()));
+        assert_eq!(block_on(protocol.transmit(Sop::Sop, &accept)), Ok(()));
+        // The TCPC performs nRetryCount retries
+        assert_eq!(transmitted(&mut protocol), [(0, 0x20), (0, 0x21), (1, 0x20)]);
+        assert_eq!(block_on(protocol.phy().tcpci().alert()), Ok(Alerts::new()));
+    }
+
+    #[test]
+    fn test_transmit_failed() {
+        let mut protocol = protocol(MockTcpc {
+            transmit_result: Alert::TransmitFailed,
+            ..Default::default()
+        });
+        let accept = header_only(header(ControlMessageType::Accept, 0));
+
+        assert_eq!(
+            block_on(protocol.transmit(Sop::Sop, &accept)),
+            Err(Error::Pd(PdError::Failed))
+        );
+        // Retries are left to the TCPC
+        assert_eq!(transmitted(&mut protocol), [(0, 0x20)]);
+        assert!(protocol
+            .layer(Sop::Sop)
+            .is_ok_and(|layer| layer.message_id_counter() == 1));
+    }
+
+    #[test]
+    fn test_transmit_discarded() {
+        let mut mock = MockTcpc {
+            transmit_result: Alert::TransmitDiscarded,
+            ..Default::default()
+        };
+        let mut ps_rdy = header(ControlMessageType::PsRdy, 3);
+        ps_rdy.set_port_power_role(PowerRole::Source);
+        mock.receive(0x00, &u16::from(ps_rdy).to_le_bytes());
+
+        let mut protocol = protocol(mock);
+        let accept = header_only(header(ControlMessageType::Accept, 0));
+
+        // The incoming message is handled instead of retrying
+        assert_eq!(
+            block_on(protocol.transmit(Sop::Sop, &accept)),
+            Err(Error::Pd(PdError::Busy))
+        );
+        assert_eq!(transmitted(&mut protocol), [(0, 0x20)]);
+        let received = block_on(protocol.receive::<RawMessage>());
+        assert_eq!(received.map(|(_, message)| message.header), Ok(ps_rdy));
+    }
+
+    #[test]
+    fn test_receive() {
+        let mut mock = MockTcpc::default();
+        let mut accept = header(ControlMessageType::Accept, 5);
+        accept.set_port_power_role(PowerRole::Source);
+        mock.receive(0x00, &u16::from(accept).to_le_bytes());
+
+        let mut protocol = protocol(mock);
+        let received = block_on(protocol.receive::<RawMessage>());
+        assert_eq!(
+            received.map(|(sop, message)| (sop, message.header)),
+            Ok((Sop::Sop, accept))
+        );
+
+        // GoodCRC is sent by the TCPC
+        assert_eq!(transmitted(&mut protocol), []);
+        assert_eq!(block_on(protocol.phy().tcpci().alert()), Ok(Alerts::new()));
+    }
+
+    #[test]
+    fn test_hard_reset() {
+        let mut protocol = protocol(MockTcpc::default());
+        assert_eq!(block_on(protocol.hard_reset()), Ok(()));
+        assert_eq!(transmitted(&mut protocol), [(0, 0x05)]);
+    }
+}
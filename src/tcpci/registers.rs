@@ -0,0 +1,1077 @@
+//! TCPCI register map as defined in section 4.4 of the USB Type-C Port Controller Interface specification, revision 2.0
+use bitfield::bitfield;
+
+use crate::protocol::Sop;
+use crate::revision::SpecRevision;
+use crate::type_c::state_machine::{Cc, CcState};
+use crate::type_c::Current;
+use crate::{DataRole, PdError, PlugOrientation, PowerRole};
+
+/// Error type for register decoding, contains the complete undecoded register value
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidRegisterValue(pub u16);
+
+impl From<InvalidRegisterValue> for PdError {
+    fn from(_: InvalidRegisterValue) -> Self {
+        PdError::InvalidParams
+    }
+}
+
+/// TCPCI registers
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Register {
+    /// VENDOR_ID, 16 bits
+    VendorId,
+    /// PRODUCT_ID, 16 bits
+    ProductId,
+    /// DEVICE_ID, 16 bits
+    DeviceId,
+    /// USBTYPEC_REV, 16 bits
+    UsbTypeCRev,
+    /// USBPD_REV_VER, 16 bits
+    UsbPdRevVer,
+    /// PD_INTERFACE_REV, 16 bits
+    PdInterfaceRev,
+    /// ALERT, 16 bits
+    Alert,
+    /// ALERT_MASK, 16 bits
+    AlertMask,
+    /// POWER_STATUS_MASK
+    PowerStatusMask,
+    /// FAULT_STATUS_MASK
+    FaultStatusMask,
+    /// EXTENDED_STATUS_MASK
+    ExtendedStatusMask,
+    /// ALERT_EXTENDED_MASK
+    AlertExtendedMask,
+    /// CONFIG_STANDARD_OUTPUT
+    ConfigStandardOutput,
+    /// TCPC_CONTROL
+    TcpcControl,
+    /// ROLE_CONTROL
+    RoleControl,
+    /// FAULT_CONTROL
+    FaultControl,
+    /// POWER_CONTROL
+    PowerControl,
+    /// CC_STATUS
+    CcStatus,
+    /// POWER_STATUS
+    PowerStatus,
+    /// FAULT_STATUS
+    FaultStatus,
+    /// EXTENDED_STATUS
+    ExtendedStatus,
+    /// ALERT_EXTENDED
+    AlertExtended,
+    /// COMMAND
+    Command,
+    /// DEVICE_CAPABILITIES_1, 16 bits
+    DeviceCapabilities1,
+    /// DEVICE_CAPABILITIES_2, 16 bits
+    DeviceCapabilities2,
+    /// STANDARD_INPUT_CAPABILITIES
+    StandardInputCapabilities,
+    /// STANDARD_OUTPUT_CAPABILITIES
+    StandardOutputCapabilities,
+    /// CONFIG_EXTENDED1
+    ConfigExtended1,
+    /// MESSAGE_HEADER_INFO
+    MessageHeaderInfo,
+    /// RECEIVE_DETECT
+    ReceiveDetect,
+    /// RECEIVE_BUFFER, READABLE_BYTE_COUNT followed by RX_BUF_FRAME_TYPE and the message
+    ReceiveBuffer,
+    /// TRANSMIT
+    Transmit,
+    /// TRANSMIT_BUFFER, I2C_WRITE_BYTE_COUNT followed by the message
+    TransmitBuffer,
+    /// VBUS_VOLTAGE, 16 bits
+    VbusVoltage,
+    /// VBUS_SINK_DISCONNECT_THRESHOLD, 16 bits
+    VbusSinkDisconnectThreshold,
+    /// VBUS_STOP_DISCHARGE_THRESHOLD, 16 bits
+    VbusStopDischargeThreshold,
+    /// VBUS_VOLTAGE_ALARM_HI_CFG, 16 bits
+    VbusVoltageAlarmHiCfg,
+    /// VBUS_VOLTAGE_ALARM_LO_CFG, 16 bits
+    VbusVoltageAlarmLoCfg,
+}
+
+impl Register {
+    /// Returns the register address
+    pub const fn address(self) -> u8 {
+        match self {
+            Register::VendorId => 0x00,
+            Register::ProductId => 0x02,
+            Register::DeviceId => 0x04,
+            Register::UsbTypeCRev => 0x06,
+            Register::UsbPdRevVer => 0x08,
+            Register::PdInterfaceRev => 0x0A,
+            Register::Alert => 0x10,
+            Register::AlertMask => 0x12,
+            Register::PowerStatusMask => 0x14,
+            Register::FaultStatusMask => 0x15,
+            Register::ExtendedStatusMask => 0x16,
+            Register::AlertExtendedMask => 0x17,
+            Register::ConfigStandardOutput => 0x18,
+            Register::TcpcControl => 0x19,
+            Register::RoleControl => 0x1A,
+            Register::FaultControl => 0x1B,
+            Register::PowerControl => 0x1C,
+            Register::CcStatus => 0x1D,
+            Register::PowerStatus => 0x1E,
+            Register::FaultStatus => 0x1F,
+            Register::ExtendedStatus => 0x20,
+            Register::AlertExtended => 0x21,
+            Register::Command => 0x23,
+            Register::DeviceCapabilities1 => 0x24,
+            Register::DeviceCapabilities2 => 0x26,
+            Register::StandardInputCapabilities => 0x28,
+            Register::StandardOutputCapabilities => 0x29,
+            Register::ConfigExtended1 => 0x2A,
+            Register::MessageHeaderInfo => 0x2E,
+            Register::ReceiveDetect => 0x2F,
+            Register::ReceiveBuffer => 0x30,
+            Register::Transmit => 0x50,
+            Register::TransmitBuffer => 0x51,
+            Register::VbusVoltage => 0x70,
+            Register::VbusSinkDisconnectThreshold => 0x72,
+            Register::VbusStopDischargeThreshold => 0x74,
+            Register::VbusVoltageAlarmHiCfg => 0x76,
+            Register::VbusVoltageAlarmLoCfg => 0x78,
+        }
+    }
+}
+
+/// Alerts reported in the ALERT register
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Alert {
+    /// CC_STATUS changed
+    CcStatus,
+    /// POWER_STATUS changed
+    PowerStatus,
+    /// A message has been received into RECEIVE_BUFFER
+    ReceivedSopMessage,
+    /// Hard reset received
+    ReceivedHardReset,
+    /// Transmission failed, no GoodCRC was received
+    TransmitFailed,
+    /// Transmission discarded due to an incoming message
+    TransmitDiscarded,
+    /// Transmission successful, GoodCRC received
+    TransmitSuccessful,
+    /// VBUS voltage above the high alarm
+    VbusVoltageAlarmHi,
+    /// VBUS voltage below the low alarm
+    VbusVoltageAlarmLo,
+    /// FAULT_STATUS changed
+    Fault,
+    /// Received message discarded because RECEIVE_BUFFER was full
+    RxBufferOverflow,
+    /// VBUS below VBUS_SINK_DISCONNECT_THRESHOLD
+    VbusSinkDisconnectDetected,
+    /// Start of a message detected
+    BeginningSopMessage,
+    /// EXTENDED_STATUS changed
+    ExtendedStatus,
+    /// ALERT_EXTENDED changed
+    AlertExtended,
+    /// Vendor defined alert
+    VendorDefined,
+}
+
+impl Alert {
+    /// All alerts in bit order
+    const ALL: [Alert; 16] = [
+        Alert::CcStatus,
+        Alert::PowerStatus,
+        Alert::ReceivedSopMessage,
+        Alert::ReceivedHardReset,
+        Alert::TransmitFailed,
+        Alert::TransmitDiscarded,
+        Alert::TransmitSuccessful,
+        Alert::VbusVoltageAlarmHi,
+        Alert::VbusVoltageAlarmLo,
+        Alert::Fault,
+        Alert::RxBufferOverflow,
+        Alert::VbusSinkDisconnectDetected,
+        Alert::BeginningSopMessage,
+        Alert::ExtendedStatus,
+        Alert::AlertExtended,
+        Alert::VendorDefined,
+    ];
+
+    /// Returns the bit of the alert in the ALERT register
+    const fn bit(self) -> u16 {
+        match self {
+            Alert::CcStatus => 0,
+            Alert::PowerStatus => 1,
+            Alert::ReceivedSopMessage => 2,
+            Alert::ReceivedHardReset => 3,
+            Alert::TransmitFailed => 4,
+            Alert::TransmitDiscarded => 5,
+            Alert::TransmitSuccessful => 6,
+            Alert::VbusVoltageAlarmHi => 7,
+            Alert::VbusVoltageAlarmLo => 8,
+            Alert::Fault => 9,
+            Alert::RxBufferOverflow => 10,
+            Alert::VbusSinkDisconnectDetected => 11,
+            Alert::BeginningSopMessage => 12,
+            Alert::ExtendedStatus => 13,
+            Alert::AlertExtended => 14,
+            Alert::VendorDefined => 15,
+        }
+    }
+}
+
+/// Contents of the ALERT and ALERT_MASK registers, a set of alerts
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Alerts(u16);
+
+impl Alerts {
+    /// Create an empty alert set
+    pub const fn new() -> Self {
+        Self(0)
+    }
+
+    /// Returns the set with the given alert added
+    pub const fn with_alert(mut self, alert: Alert) -> Self {
+        self.0 |= 1 << alert.bit();
+        self
+    }
+
+    /// Returns true if the given alert is set
+    pub const fn contains(&self, alert: Alert) -> bool {
+        self.0 & (1 << alert.bit()) != 0
+    }
+
+    /// Returns true if no alerts are set
+    pub const fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns the alerts that are set
+    pub fn alerts(&self) -> impl Iterator<Item = Alert> {
+        let alerts = *self;
+        Alert::ALL.into_iter().filter(move |alert| alerts.contains(*alert))
+    }
+}
+
+impl From<u16> for Alerts {
+    fn from(value: u16) -> Self {
+        Self(value)
+    }
+}
+
+impl From<Alerts> for u16 {
+    fn from(value: Alerts) -> Self {
+        value.0
+    }
+}
+
+/// CC termination set through ROLE_CONTROL
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CcTermination {
+    /// Ra
+    Ra,
+    /// Rp
+    Rp,
+    /// Rd
+    Rd,
+    /// Open
+    Open,
+}
+
+impl From<u8> for CcTermination {
+    fn from(value: u8) -> Self {
+        match value & 0x3 {
+            0x0 => CcTermination::Ra,
+            0x1 => CcTermination::Rp,
+            0x2 => CcTermination::Rd,
+            _ => CcTermination::Open,
+        }
+    }
+}
+
+impl From<CcTermination> for u8 {
+    fn from(value: CcTermination) -> Self {
+        match value {
+            CcTermination::Ra => 0x0,
+            CcTermination::Rp => 0x1,
+            CcTermination::Rd => 0x2,
+            CcTermination::Open => 0x3,
+        }
+    }
+}
+
+bitfield! {
+    /// Raw ROLE_CONTROL register
+    #[derive(Copy, Clone, PartialEq, Eq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    struct RoleControlRaw(u8);
+    impl Debug;
+
+    /// DRP
+    pub bool, drp, set_drp: 6;
+    /// Rp value
+    pub u8, rp_value, set_rp_value: 5, 4;
+    /// CC2 termination
+    pub u8, cc2, set_cc2: 3, 2;
+    /// CC1 termination
+    pub u8, cc1, set_cc1: 1, 0;
+}
+
+/// ROLE_CONTROL register
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct RoleControl {
+    /// Autonomously toggle between Rp and Rd, CC1 and CC2 must be set to the same termination
+    pub drp: bool,
+    /// Current advertised when Rp is applied
+    pub rp_value: Current,
+    /// CC1 termination
+    pub cc1: CcTermination,
+    /// CC2 termination
+    pub cc2: CcTermination,
+}
+
+impl RoleControl {
+    /// Returns the role control with the same termination on both CC pins
+    pub const fn both(termination: CcTermination, rp_value: Current) -> Self {
+        Self {
+            drp: false,
+            rp_value,
+            cc1: termination,
+            cc2: termination,
+        }
+    }
+}
+
+impl TryFrom<u8> for RoleControl {
+    type Error = InvalidRegisterValue;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        let raw = RoleControlRaw(value);
+        Ok(RoleControl {
+            drp: raw.drp(),
+            rp_value: match raw.rp_value() {
+                0x0 => Current::UsbDefault,
+                0x1 => Current::Current1A5,
+                0x2 => Current::Current3A0,
+                _ => return Err(InvalidRegisterValue(value as u16)),
+            },
+            cc1: raw.cc1().into(),
+            cc2: raw.cc2().into(),
+        })
+    }
+}
+
+impl From<RoleControl> for u8 {
+    fn from(value: RoleControl) -> Self {
+        let mut raw = RoleControlRaw(0);
+        raw.set_drp(value.drp);
+        raw.set_rp_value(match value.rp_value {
+            Current::UsbDefault => 0x0,
+            Current::Current1A5 => 0x1,
+            Current::Current3A0 => 0x2,
+        });
+        raw.set_cc2(value.cc2.into());
+        raw.set_cc1(value.cc1.into());
+        raw.0
+    }
+}
+
+bitfield! {
+    /// Raw CC_STATUS register
+    #[derive(Copy, Clone, Default, PartialEq, Eq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    struct CcStatusRaw(u8);
+    impl Debug;
+
+    /// Looking for connection
+    pub bool, looking_for_connection, set_looking_for_connection: 5;
+    /// Connect result, true if presenting Rd
+    pub bool, connect_result, set_connect_result: 4;
+    /// CC2 state
+    pub u8, cc2_state, set_cc2_state: 3, 2;
+    /// CC1 state
+    pub u8, cc1_state, set_cc1_state: 1, 0;
+}
+
+/// CC_STATUS register
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CcStatus(CcStatusRaw);
+
+impl CcStatus {
+    /// Returns true if the TCPC is toggling or looking for a connection
+    pub fn looking_for_connection(&self) -> bool {
+        self.0.looking_for_connection()
+    }
+
+    /// Returns the termination the TCPC settled on while toggling as a DRP
+    pub fn connect_result(&self) -> CcTermination {
+        if self.0.connect_result() {
+            CcTermination::Rd
+        } else {
+            CcTermination::Rp
+        }
+    }
+
+    /// Returns the detected CC state, interpreted according to the termination applied by the TCPC
+    pub fn cc(&self, role_control: RoleControl) -> Cc {
+        let decode = |termination, state| match (termination, state) {
+            (CcTermination::Rp, 0x1) => CcState::Ra,
+            (CcTermination::Rp, 0x2) => CcState::Rd,
+            (CcTermination::Rd, 0x1) => CcState::Rp(Current::UsbDefault),
+            (CcTermination::Rd, 0x2) => CcState::Rp(Current::Current1A5),
+            (CcTermination::Rd, 0x3) => CcState::Rp(Current::Current3A0),
+            _ => CcState::Open,
+        };
+
+        let (cc1, cc2) = if role_control.drp {
+            (self.connect_result(), self.connect_result())
+        } else {
+            (role_control.cc1, role_control.cc2)
+        };
+        Cc::new(decode(cc1, self.0.cc1_state()), decode(cc2, self.0.cc2_state()))
+    }
+}
+
+impl From<u8> for CcStatus {
+    fn from(value: u8) -> Self {
+        Self(CcStatusRaw(value))
+    }
+}
+
+impl From<CcStatus> for u8 {
+    fn from(value: CcStatus) -> Self {
+        value.0 .0
+    }
+}
+
+bitfield! {
+    /// Raw POWER_STATUS register
+    #[derive(Copy, Clone, Default, PartialEq, Eq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    struct PowerStatusRaw(u8);
+    impl Debug;
+
+    /// Debug accessory connected
+    pub bool, debug_accessory_connected, set_debug_accessory_connected: 7;
+    /// TCPC initialization status, true while still initializing
+    pub bool, tcpc_initializing, set_tcpc_initializing: 6;
+    /// Sourcing high voltage
+    pub bool, sourcing_high_voltage, set_sourcing_high_voltage: 5;
+    /// Sourcing VBUS
+    pub bool, sourcing_vbus, set_sourcing_vbus: 4;
+    /// VBUS detection enabled
+    pub bool, vbus_detection_enabled, set_vbus_detection_enabled: 3;
+    /// VBUS present
+    pub bool, vbus_present, set_vbus_present: 2;
+    /// VCONN present
+    pub bool, vconn_present, set_vconn_present: 1;
+    /// Sinking VBUS
+    pub bool, sinking_vbus, set_sinking_vbus: 0;
+}
+
+/// POWER_STATUS register
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PowerStatus {
+    /// Debug accessory connected
+    pub debug_accessory_connected: bool,
+    /// TCPC still initializing, registers other than the ID registers must not be accessed
+    pub tcpc_initializing: bool,
+    /// Sourcing a voltage above vSafe5V
+    pub sourcing_high_voltage: bool,
+    /// Sourcing VBUS
+    pub sourcing_vbus: bool,
+    /// VBUS detection enabled
+    pub vbus_detection_enabled: bool,
+    /// VBUS present
+    pub vbus_present: bool,
+    /// VCONN present
+    pub vconn_present: bool,
+    /// Sinking VBUS
+    pub sinking_vbus: bool,
+}
+
+impl From<u8> for PowerStatus {
+    fn from(value: u8) -> Self {
+        let raw = PowerStatusRaw(value);
+        PowerStatus {
+            debug_accessory_connected: raw.debug_accessory_connected(),
+            tcpc_initializing: raw.tcpc_initializing(),
+            sourcing_high_voltage: raw.sourcing_high_voltage(),
+            sourcing_vbus: raw.sourcing_vbus(),
+            vbus_detection_enabled: raw.vbus_detection_enabled(),
+            vbus_present: raw.vbus_present(),
+            vconn_present: raw.vconn_present(),
+            sinking_vbus: raw.sinking_vbus(),
+        }
+    }
+}
+
+bitfield! {
+    /// Raw TCPC_CONTROL register
+    #[derive(Copy, Clone, Default, PartialEq, Eq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    struct TcpcControlRaw(u8);
+    impl Debug;
+
+    /// Enable SMBus PEC
+    pub bool, smbus_pec, set_smbus_pec: 7;
+    /// Enable looking for connection alert
+    pub bool, looking_for_connection_alert, set_looking_for_connection_alert: 6;
+    /// Enable watchdog timer
+    pub bool, watchdog, set_watchdog: 5;
+    /// Debug accessory control, true if controlled by the TCPM
+    pub bool, debug_accessory_control, set_debug_accessory_control: 4;
+    /// I2C clock stretching control
+    pub u8, clock_stretching, set_clock_stretching: 3, 2;
+    /// BIST test mode
+    pub bool, bist_test_mode, set_bist_test_mode: 1;
+    /// Plug orientation, true if CC2 is used for communication
+    pub bool, plug_orientation, set_plug_orientation: 0;
+}
+
+/// TCPC_CONTROL register
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct TcpcControl {
+    /// Enable SMBus PEC
+    pub smbus_pec: bool,
+    /// Raise [`Alert::CcStatus`] when looking for connection changes
+    pub looking_for_connection_alert: bool,
+    /// Enable the watchdog timer
+    pub watchdog: bool,
+    /// Debug accessory controlled by the TCPM rather than autonomously by the TCPC
+    pub debug_accessory_control: bool,
+    /// I2C clock stretching control
+    pub clock_stretching: u8,
+    /// BIST test mode, received messages are not passed to the TCPM
+    pub bist_test_mode: bool,
+    /// Plug orientation, VCONN is applied to the other CC pin
+    pub plug_orientation: PlugOrientation,
+}
+
+impl From<u8> for TcpcControl {
+    fn from(value: u8) -> Self {
+        let raw = TcpcControlRaw(value);
+        TcpcControl {
+            smbus_pec: raw.smbus_pec(),
+            looking_for_connection_alert: raw.looking_for_connection_alert(),
+            watchdog: raw.watchdog(),
+            debug_accessory_control: raw.debug_accessory_control(),
+            clock_stretching: raw.clock_stretching(),
+            bist_test_mode: raw.bist_test_mode(),
+            plug_orientation: if raw.plug_orientation() {
+                PlugOrientation::CC2
+            } else {
+                PlugOrientation::CC1
+            },
+        }
+    }
+}
+
+impl From<TcpcControl> for u8 {
+    fn from(value: TcpcControl) -> Self {
+        let mut raw = TcpcControlRaw(0);
+        raw.set_smbus_pec(value.smbus_pec);
+        raw.set_looking_for_connection_alert(value.looking_for_connection_alert);
+        raw.set_watchdog(value.watchdog);
+        raw.set_debug_accessory_control(value.debug_accessory_control);
+        raw.set_clock_stretching(value.clock_stretching);
+        raw.set_bist_test_mode(value.bist_test_mode);
+        raw.set_plug_orientation(value.plug_orientation.flipped());
+        raw.0
+    }
+}
+
+bitfield! {
+    /// Raw POWER_CONTROL register
+    #[derive(Copy, Clone, Default, PartialEq, Eq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    struct PowerControlRaw(u8);
+    impl Debug;
+
+    /// Fast role swap enable
+    pub bool, fast_role_swap, set_fast_role_swap: 7;
+    /// Disable VBUS voltage monitoring
+    pub bool, disable_vbus_monitor, set_disable_vbus_monitor: 6;
+    /// Disable voltage alarms
+    pub bool, disable_voltage_alarms, set_disable_voltage_alarms: 5;
+    /// Auto discharge on disconnect
+    pub bool, auto_discharge_disconnect, set_auto_discharge_disconnect: 4;
+    /// Enable bleed discharge
+    pub bool, bleed_discharge, set_bleed_discharge: 3;
+    /// Force discharge
+    pub bool, force_discharge, set_force_discharge: 2;
+    /// VCONN power supported
+    pub bool, vconn_power_supported, set_vconn_power_supported: 1;
+    /// Enable VCONN
+    pub bool, vconn, set_vconn: 0;
+}
+
+/// POWER_CONTROL register
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct PowerControl {
+    /// Enable fast role swap signal detection
+    pub fast_role_swap: bool,
+    /// Disable VBUS voltage monitoring
+    pub disable_vbus_monitor: bool,
+    /// Disable VBUS voltage alarms
+    pub disable_voltage_alarms: bool,
+    /// Discharge VBUS automatically on disconnect
+    pub auto_discharge_disconnect: bool,
+    /// Enable bleed discharge
+    pub bleed_discharge: bool,
+    /// Force VBUS discharge
+    pub force_discharge: bool,
+    /// Supply VCONN with more than 1 W
+    pub vconn_power_supported: bool,
+    /// Supply VCONN on the CC pin not selected by [`TcpcControl::plug_orientation`]
+    pub vconn: bool,
+}
+
+impl From<u8> for PowerControl {
+    fn from(value: u8) -> Self {
+        let raw = PowerControlRaw(value);
+        PowerControl {
+            fast_role_swap: raw.fast_role_swap(),
+            disable_vbus_monitor: raw.disable_vbus_monitor(),
+            disable_voltage_alarms: raw.disable_voltage_alarms(),
+            auto_discharge_disconnect: raw.auto_discharge_disconnect(),
+            bleed_discharge: raw.bleed_discharge(),
+            force_discharge: raw.force_discharge(),
+            vconn_power_supported: raw.vconn_power_supported(),
+            vconn: raw.vconn(),
+        }
+    }
+}
+
+impl From<PowerControl> for u8 {
+    fn from(value: PowerControl) -> Self {
+        let mut raw = PowerControlRaw(0);
+        raw.set_fast_role_swap(value.fast_role_swap);
+        raw.set_disable_vbus_monitor(value.disable_vbus_monitor);
+        raw.set_disable_voltage_alarms(value.disable_voltage_alarms);
+        raw.set_auto_discharge_disconnect(value.auto_discharge_disconnect);
+        raw.set_bleed_discharge(value.bleed_discharge);
+        raw.set_force_discharge(value.force_discharge);
+        raw.set_vconn_power_supported(value.vconn_power_supported);
+        raw.set_vconn(value.vconn);
+        raw.0
+    }
+}
+
+/// Commands written to the COMMAND register
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Command {
+    /// WakeI2C
+    WakeI2c,
+    /// DisableVbusDetect
+    DisableVbusDetect,
+    /// EnableVbusDetect
+    EnableVbusDetect,
+    /// DisableSinkVbus
+    DisableSinkVbus,
+    /// SinkVbus
+    SinkVbus,
+    /// DisableSourceVbus
+    DisableSourceVbus,
+    /// SourceVbusDefaultVoltage
+    SourceVbusDefaultVoltage,
+    /// SourceVbusHighVoltage
+    SourceVbusHighVoltage,
+    /// Look4Connection
+    LookForConnection,
+    /// RxOneMore
+    RxOneMore,
+    /// SendFRSwapSignal
+    SendFrSwapSignal,
+    /// ResetTransmitBuffer
+    ResetTransmitBuffer,
+    /// ResetReceiveBuffer
+    ResetReceiveBuffer,
+    /// I2CIdle
+    I2cIdle,
+}
+
+impl From<Command> for u8 {
+    fn from(value: Command) -> Self {
+        match value {
+            Command::WakeI2c => 0x11,
+            Command::DisableVbusDetect => 0x22,
+            Command::EnableVbusDetect => 0x33,
+            Command::DisableSinkVbus => 0x44,
+            Command::SinkVbus => 0x55,
+            Command::DisableSourceVbus => 0x66,
+            Command::SourceVbusDefaultVoltage => 0x77,
+            Command::SourceVbusHighVoltage => 0x88,
+            Command::LookForConnection => 0x99,
+            Command::RxOneMore => 0xAA,
+            Command::SendFrSwapSignal => 0xCC,
+            Command::ResetTransmitBuffer => 0xDD,
+            Command::ResetReceiveBuffer => 0xEE,
+            Command::I2cIdle => 0xFF,
+        }
+    }
+}
+
+bitfield! {
+    /// Raw MESSAGE_HEADER_INFO register
+    #[derive(Copy, Clone, Default, PartialEq, Eq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    struct MessageHeaderInfoRaw(u8);
+    impl Debug;
+
+    /// Cable plug
+    pub bool, cable_plug, set_cable_plug: 4;
+    /// Data role, true for DFP
+    pub bool, data_role, set_data_role: 3;
+    /// USB PD specification revision
+    pub u8, spec_revision, set_spec_revision: 2, 1;
+    /// Power role, true for source
+    pub bool, power_role, set_power_role: 0;
+}
+
+/// MESSAGE_HEADER_INFO register, used by the TCPC to fill in GoodCRC headers
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct MessageHeaderInfo {
+    /// Messages originate from a cable plug
+    pub cable_plug: bool,
+    /// Data role
+    pub data_role: DataRole,
+    /// Specification revision
+    pub spec_revision: SpecRevision,
+    /// Power role
+    pub power_role: PowerRole,
+}
+
+impl TryFrom<u8> for MessageHeaderInfo {
+    type Error = InvalidRegisterValue;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        let raw = MessageHeaderInfoRaw(value);
+        Ok(MessageHeaderInfo {
+            cable_plug: raw.cable_plug(),
+            data_role: if raw.data_role() { DataRole::Dfp } else { DataRole::Ufp },
+            spec_revision: SpecRevision::try_from(raw.spec_revision())
+                .map_err(|_| InvalidRegisterValue(value as u16))?,
+            power_role: if raw.power_role() {
+                PowerRole::Source
+            } else {
+                PowerRole::Sink
+            },
+        })
+    }
+}
+
+impl From<MessageHeaderInfo> for u8 {
+    fn from(value: MessageHeaderInfo) -> Self {
+        let mut raw = MessageHeaderInfoRaw(0);
+        raw.set_cable_plug(value.cable_plug);
+        raw.set_data_role(value.data_role == DataRole::Dfp);
+        raw.set_spec_revision(value.spec_revision.into());
+        raw.set_power_role(value.power_role == PowerRole::Source);
+        raw.0
+    }
+}
+
+bitfield! {
+    /// Raw RECEIVE_DETECT register
+    #[derive(Copy, Clone, Default, PartialEq, Eq)]
+    #[cfg_attr(feature = "defmt", derive(defmt::Format))]
+    struct ReceiveDetectRaw(u8);
+    impl Debug;
+
+    /// Enable cable reset
+    pub bool, cable_reset, set_cable_reset: 6;
+    /// Enable hard reset
+    pub bool, hard_reset, set_hard_reset: 5;
+    /// Enable SOP''_DBG messages
+    pub bool, sop_double_prime_debug, set_sop_double_prime_debug: 4;
+    /// Enable SOP'_DBG messages
+    pub bool, sop_prime_debug, set_sop_prime_debug: 3;
+    /// Enable SOP'' messages
+    pub bool, sop_double_prime, set_sop_double_prime: 2;
+    /// Enable SOP' messages
+    pub bool, sop_prime, set_sop_prime: 1;
+    /// Enable SOP messages
+    pub bool, sop, set_sop: 0;
+}
+
+/// RECEIVE_DETECT register, all false disables the receiver
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct ReceiveDetect {
+    /// Detect cable reset
+    pub cable_reset: bool,
+    /// Detect hard reset
+    pub hard_reset: bool,
+    /// Receive SOP''_DBG messages
+    pub sop_double_prime_debug: bool,
+    /// Receive SOP'_DBG messages
+    pub sop_prime_debug: bool,
+    /// Receive SOP'' messages
+    pub sop_double_prime: bool,
+    /// Receive SOP' messages
+    pub sop_prime: bool,
+    /// Receive SOP messages
+    pub sop: bool,
+}
+
+impl ReceiveDetect {
+    /// Receive SOP messages and hard reset, as used by a port not communicating with a cable
+    pub const fn sop() -> Self {
+        Self {
+            cable_reset: false,
+            hard_reset: true,
+            sop_double_prime_debug: false,
+            sop_prime_debug: false,
+            sop_double_prime: false,
+            sop_prime: false,
+            sop: true,
+        }
+    }
+}
+
+impl From<u8> for ReceiveDetect {
+    fn from(value: u8) -> Self {
+        let raw = ReceiveDetectRaw(value);
+        ReceiveDetect {
+            cable_reset: raw.cable_reset(),
+            hard_reset: raw.hard_reset(),
+            sop_double_prime_debug: raw.sop_double_prime_debug(),
+            sop_prime_debug: raw.sop_prime_debug(),
+            sop_double_prime: raw.sop_double_prime(),
+            sop_prime: raw.sop_prime(),
+            sop: raw.sop(),
+        }
+    }
+}
+
+impl From<ReceiveDetect> for u8 {
+    fn from(value: ReceiveDetect) -> Self {
+        let mut raw = ReceiveDetectRaw(0);
+        raw.set_cable_reset(value.cable_reset);
+        raw.set_hard_reset(value.hard_reset);
+        raw.set_sop_double_prime_debug(value.sop_double_prime_debug);
+        raw.set_sop_prime_debug(value.sop_prime_debug);
+        raw.set_sop_double_prime(value.sop_double_prime);
+        raw.set_sop_prime(value.sop_prime);
+        raw.set_sop(value.sop);
+        raw.0
+    }
+}
+
+/// Returns the SOP* type of a RX_BUF_FRAME_TYPE value
+pub fn frame_type(value: u8) -> Result<Sop, InvalidRegisterValue> {
+    match value & 0x7 {
+        0x0 => Ok(Sop::Sop),
+        0x1 => Ok(Sop::SopPrime),
+        0x2 => Ok(Sop::SopDoublePrime),
+        0x3 => Ok(Sop::SopPrimeDebug),
+        0x4 => Ok(Sop::SopDoublePrimeDebug),
+        _ => Err(InvalidRegisterValue(value as u16)),
+    }
+}
+
+/// Signaling requested through the TRANSMIT register
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TransmitType {
+    /// Transmit the contents of TRANSMIT_BUFFER with the given SOP*
+    Message(Sop),
+    /// Hard reset
+    HardReset,
+    /// Cable reset
+    CableReset,
+    /// BIST carrier mode 2
+    BistCarrierMode2,
+}
+
+/// TRANSMIT register
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Transmit {
+    /// Signaling to transmit
+    pub transmit_type: TransmitType,
+    /// Number of retries if no GoodCRC is received, up to 3
+    pub retries: u8,
+}
+
+impl From<Transmit> for u8 {
+    fn from(value: Transmit) -> Self {
+        let transmit_type = match value.transmit_type {
+            TransmitType::Message(Sop::Sop) => 0x0,
+            TransmitType::Message(Sop::SopPrime) => 0x1,
+            TransmitType::Message(Sop::SopDoublePrime) => 0x2,
+            TransmitType::Message(Sop::SopPrimeDebug) => 0x3,
+            TransmitType::Message(Sop::SopDoublePrimeDebug) => 0x4,
+            TransmitType::HardReset => 0x5,
+            TransmitType::CableReset => 0x6,
+            TransmitType::BistCarrierMode2 => 0x7,
+        };
+        ((value.retries & 0x3) << 4) | transmit_type
+    }
+}
+
+/// Decode the VBUS_VOLTAGE register into mV
+pub fn vbus_voltage_mv(value: u16) -> u32 {
+    /// Measurement unit in mV
+    const MV25_UNIT: u32 = 25;
+
+    let measurement = (value & 0x3FF) as u32;
+    let scale = match (value >> 10) & 0x3 {
+        0x1 => 2,
+        0x2 => 4,
+        _ => 1,
+    };
+    measurement * scale * MV25_UNIT
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alerts() {
+        let alerts = Alerts::from(0x0045);
+        assert!(alerts.contains(Alert::CcStatus));
+        assert!(alerts.contains(Alert::ReceivedSopMessage));
+        assert!(alerts.contains(Alert::TransmitSuccessful));
+        assert!(!alerts.contains(Alert::TransmitFailed));
+        assert!(alerts
+            .alerts()
+            .eq([Alert::CcStatus, Alert::ReceivedSopMessage, Alert::TransmitSuccessful]));
+
+        let alerts = Alerts::new()
+            .with_alert(Alert::VendorDefined)
+            .with_alert(Alert::PowerStatus);
+        assert_eq!(u16::from(alerts), 0x8002);
+        assert!(Alerts::new().is_empty());
+    }
+
+    #[test]
+    fn test_role_control() {
+        let role_control = RoleControl::both(CcTermination::Rp, Current::Current3A0);
+        assert_eq!(u8::from(role_control), 0x25);
+        assert_eq!(RoleControl::try_from(0x25), Ok(role_control));
+
+        let role_control = RoleControl {
+            drp: true,
+            ..RoleControl::both(CcTermination::Rd, Current::UsbDefault)
+        };
+        assert_eq!(u8::from(role_control), 0x4A);
+        assert_eq!(RoleControl::try_from(0x30), Err(InvalidRegisterValue(0x30)));
+    }
+
+    #[test]
+    fn test_cc_status() {
+        // Sink with 1.5 A Rp on CC2
+        let role_control = RoleControl::both(CcTermination::Rd, Current::UsbDefault);
+        assert_eq!(
+            CcStatus::from(0x08).cc(role_control),
+            Cc::new(CcState::Open, CcState::Rp(Current::Current1A5))
+        );
+
+        // Source with Rd on CC1 and Ra on CC2
+        let role_control = RoleControl::both(CcTermination::Rp, Current::UsbDefault);
+        assert_eq!(CcStatus::from(0x06).cc(role_control), Cc::new(CcState::Rd, CcState::Ra));
+
+        // DRP settled as a sink
+        let role_control = RoleControl {
+            drp: true,
+            ..role_control
+        };
+        let status = CcStatus::from(0x13);
+        assert!(!status.looking_for_connection());
+        assert_eq!(status.connect_result(), CcTermination::Rd);
+        assert_eq!(
+            status.cc(role_control),
+            Cc::new(CcState::Rp(Current::Current3A0), CcState::Open)
+        );
+    }
+
+    #[test]
+    fn test_power_status() {
+        let status = PowerStatus::from(0x05);
+        assert!(status.sinking_vbus);
+        assert!(status.vbus_present);
+        assert!(!status.sourcing_vbus);
+        assert!(!status.tcpc_initializing);
+    }
+
+    #[test]
+    fn test_control_registers() {
+        let control = TcpcControl {
+            plug_orientation: PlugOrientation::CC2,
+            looking_for_connection_alert: true,
+            ..Default::default()
+        };
+        assert_eq!(u8::from(control), 0x41);
+        assert_eq!(TcpcControl::from(0x41), control);
+
+        let control = PowerControl {
+            vconn: true,
+            auto_discharge_disconnect: true,
+            ..Default::default()
+        };
+        assert_eq!(u8::from(control), 0x11);
+        assert_eq!(PowerControl::from(0x11), control);
+    }
+
+    #[test]
+    fn test_message_header_info() {
+        let info = MessageHeaderInfo {
+            cable_plug: false,
+            data_role: DataRole::Dfp,
+            spec_revision: SpecRevision::Rev3,
+            power_role: PowerRole::Source,
+        };
+        assert_eq!(u8::from(info), 0x0D);
+        assert_eq!(MessageHeaderInfo::try_from(0x0D), Ok(info));
+        assert_eq!(MessageHeaderInfo::try_from(0x06), Err(InvalidRegisterValue(0x06)));
+    }
+
+    #[test]
+    fn test_transmit() {
+        assert_eq!(u8::from(ReceiveDetect::sop()), 0x21);
+        assert_eq!(
+            u8::from(Transmit {
+                transmit_type: TransmitType::Message(Sop::SopPrime),
+                retries: 3,
+            }),
+            0x31
+        );
+        assert_eq!(
+            u8::from(Transmit {
+                transmit_type: TransmitType::HardReset,
+                retries: 0,
+            }),
+            0x05
+        );
+        assert_eq!(frame_type(0x02), Ok(Sop::SopDoublePrime));
+        assert_eq!(frame_type(0x06), Err(InvalidRegisterValue(0x06)));
+    }
+
+    #[test]
+    fn test_vbus_voltage() {
+        assert_eq!(vbus_voltage_mv(200), 5000);
+        assert_eq!(vbus_voltage_mv(0x0400 | 400), 20000);
+        // Largest measurement with a scale factor of 4
+        assert_eq!(vbus_voltage_mv(0x0800 | 0x3FF), 102300);
+    }
+}
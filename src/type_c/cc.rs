@@ -0,0 +1,277 @@
+//! CC voltage classification as defined in 4.11.3 of the Type-C spec.
+//!
+//! A sink measures the voltage across its Rd to determine the current advertised by the source. A source measures the
+//! voltage on CC with its Rp applied to detect Ra and Rd. The thresholds are the same whether Rp is implemented as a
+//! current source or as a pull-up to 3.3 V or 5 V, [`RpSupply`] gives the pull-up values for discrete implementations.
+
+use super::state_machine::CcState;
+use super::Current;
+use crate::constants::{T_PD_DEBOUNCE_MS, T_SINK_ADJ_MS};
+
+/// Maximum CC voltage in mV for a sink to consider CC open, `vRa` max
+pub const SINK_OPEN_MAX_MV: u16 = 200;
+/// Maximum CC voltage in mV for a sink to detect default USB current, `vRd-USB` max
+pub const SINK_DEFAULT_MAX_MV: u16 = 660;
+/// Maximum CC voltage in mV for a sink to detect 1.5 A, `vRd-1.5` max
+pub const SINK_1A5_MAX_MV: u16 = 1230;
+
+/// Supply voltage of a pull-up resistor implementing Rp
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum RpSupply {
+    /// 3.3 V
+    V3_3,
+    /// 5 V
+    V5,
+}
+
+impl RpSupply {
+    /// Returns the supply voltage in mV
+    pub const fn mv(self) -> u16 {
+        match self {
+            RpSupply::V3_3 => 3300,
+            RpSupply::V5 => 5000,
+        }
+    }
+
+    /// Returns the nominal pull-up resistance in ohms to advertise the given current
+    pub const fn rp_ohms(self, current: Current) -> u32 {
+        match (self, current) {
+            (RpSupply::V3_3, Current::UsbDefault) => 36_000,
+            (RpSupply::V3_3, Current::Current1A5) => 12_000,
+            (RpSupply::V3_3, Current::Current3A0) => 4_700,
+            (RpSupply::V5, Current::UsbDefault) => 56_000,
+            (RpSupply::V5, Current::Current1A5) => 22_000,
+            (RpSupply::V5, Current::Current3A0) => 10_000,
+        }
+    }
+}
+
+/// Source CC voltage thresholds in mV for the advertised current
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct SourceThresholds {
+    /// Voltages below this indicate Ra, `vRa` threshold
+    pub ra_max_mv: u16,
+    /// Voltages below this and at or above `ra_max_mv` indicate Rd, `vRd` threshold
+    pub rd_max_mv: u16,
+}
+
+impl SourceThresholds {
+    /// Returns the thresholds for the given advertised current
+    pub const fn for_current(current: Current) -> Self {
+        match current {
+            Current::UsbDefault => Self {
+                ra_max_mv: 200,
+                rd_max_mv: 1600,
+            },
+            Current::Current1A5 => Self {
+                ra_max_mv: 400,
+                rd_max_mv: 1600,
+            },
+            Current::Current3A0 => Self {
+                ra_max_mv: 800,
+                rd_max_mv: 2600,
+            },
+        }
+    }
+}
+
+/// Classify the CC voltage measured by a sink across Rd
+pub const fn classify_sink(cc_mv: u16) -> CcState {
+    if cc_mv < SINK_OPEN_MAX_MV {
+        CcState::Open
+    } else if cc_mv < SINK_DEFAULT_MAX_MV {
+        CcState::Rp(Current::UsbDefault)
+    } else if cc_mv < SINK_1A5_MAX_MV {
+        CcState::Rp(Current::Current1A5)
+    } else {
+        CcState::Rp(Current::Current3A0)
+    }
+}
+
+/// Classify the CC voltage measured by a source advertising the given current
+pub const fn classify_source(cc_mv: u16, current: Current) -> CcState {
+    let thresholds = SourceThresholds::for_current(current);
+    if cc_mv < thresholds.ra_max_mv {
+        CcState::Ra
+    } else if cc_mv < thresholds.rd_max_mv {
+        CcState::Rd
+    } else {
+        CcState::Open
+    }
+}
+
+/// Change of the current advertised by the source
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CurrentChange {
+    /// Previously advertised current, `None` if no Rp was detected
+    pub previous: Option<Current>,
+    /// Newly advertised current, `None` if Rp is no longer detected
+    pub current: Option<Current>,
+    /// Time in ms by which the sink must have reduced its current draw, `None` if the current did not decrease
+    pub adjust_by_ms: Option<u32>,
+}
+
+/// Debounces the current advertised by a source as seen by a sink
+///
+/// A change is reported once the new value has been stable for `tPDDebounce`. When the advertised current decreases
+/// the sink must reduce its draw within `tSinkAdj` of the change, which is reported in
+/// [`CurrentChange::adjust_by_ms`]. Times are in ms from an arbitrary wrapping epoch.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct CurrentTracker {
+    /// Debounced advertised current
+    current: Option<Current>,
+    /// Advertised current waiting for debounce and the time it was first seen
+    pending: Option<(Option<Current>, u32)>,
+}
+
+impl CurrentTracker {
+    /// Create a new tracker with no Rp detected
+    pub const fn new() -> Self {
+        Self {
+            current: None,
+            pending: None,
+        }
+    }
+
+    /// Returns the debounced advertised current, `None` if no Rp is detected
+    pub fn current(&self) -> Option<Current> {
+        self.current
+    }
+
+    /// Update the tracker with the CC state sampled at the given time and return the change if one has been debounced
+    ///
+    /// Ra and Rd are never seen by a sink and are treated as open.
+    pub fn update(&mut self, cc: CcState, now_ms: u32) -> Option<CurrentChange> {
+        let sampled = match cc {
+            CcState::Rp(current) => Some(current),
+            CcState::Open | CcState::Ra | CcState::Rd => None,
+        };
+
+        if sampled == self.current {
+            self.pending = None;
+            return None;
+        }
+
+        let since_ms = match self.pending {
+            Some((pending, since_ms)) if pending == sampled => since_ms,
+            _ => {
+                self.pending = Some((sampled, now_ms));
+                now_ms
+            }
+        };
+
+        if now_ms.wrapping_sub(since_ms) < T_PD_DEBOUNCE_MS.minimum.0 as u32 {
+            return None;
+        }
+
+        let ma = |current: Option<Current>| current.map_or(0, |current| current.to_ma(false));
+        let previous = self.current;
+        self.current = sampled;
+        self.pending = None;
+        Some(CurrentChange {
+            previous,
+            current: sampled,
+            adjust_by_ms: (ma(sampled) < ma(previous)).then_some(since_ms.wrapping_add(T_SINK_ADJ_MS.0 as u32)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Rd resistance in ohms
+    const RD_OHMS: u32 = 5_100;
+    /// Ra resistance in ohms
+    const RA_OHMS: u32 = 1_000;
+
+    /// Returns the CC voltage in mV for a pull-up to `supply` through `rp_ohms` and a pull-down of `ohms`
+    fn divider_mv(supply: RpSupply, rp_ohms: u32, ohms: u32) -> u16 {
+        (supply.mv() as u32 * ohms / (rp_ohms + ohms)) as u16
+    }
+
+    #[test]
+    fn test_classify_sink() {
+        assert_eq!(classify_sink(0), CcState::Open);
+        assert_eq!(classify_sink(199), CcState::Open);
+        assert_eq!(classify_sink(200), CcState::Rp(Current::UsbDefault));
+        assert_eq!(classify_sink(660), CcState::Rp(Current::Current1A5));
+        assert_eq!(classify_sink(1230), CcState::Rp(Current::Current3A0));
+        assert_eq!(classify_sink(2040), CcState::Rp(Current::Current3A0));
+    }
+
+    #[test]
+    fn test_classify_pull_up() {
+        for supply in [RpSupply::V3_3, RpSupply::V5] {
+            for current in [Current::UsbDefault, Current::Current1A5, Current::Current3A0] {
+                let rp_ohms = supply.rp_ohms(current);
+
+                let rd_mv = divider_mv(supply, rp_ohms, RD_OHMS);
+                assert_eq!(classify_sink(rd_mv), CcState::Rp(current));
+                assert_eq!(classify_source(rd_mv, current), CcState::Rd);
+
+                let ra_mv = divider_mv(supply, rp_ohms, RA_OHMS);
+                assert_eq!(classify_source(ra_mv, current), CcState::Ra);
+
+                assert_eq!(classify_source(supply.mv(), current), CcState::Open);
+            }
+        }
+    }
+
+    #[test]
+    fn test_current_tracker() {
+        let mut tracker = CurrentTracker::new();
+        assert_eq!(tracker.update(CcState::Rp(Current::Current3A0), 0), None);
+        assert_eq!(
+            tracker.update(CcState::Rp(Current::Current3A0), 10),
+            Some(CurrentChange {
+                previous: None,
+                current: Some(Current::Current3A0),
+                adjust_by_ms: None,
+            })
+        );
+        assert_eq!(tracker.current(), Some(Current::Current3A0));
+
+        // Glitch shorter than tPDDebounce
+        assert_eq!(tracker.update(CcState::Rp(Current::Current1A5), 100), None);
+        assert_eq!(tracker.update(CcState::Rp(Current::Current3A0), 105), None);
+        assert_eq!(tracker.update(CcState::Rp(Current::Current1A5), 110), None);
+
+        // Decrease must be obeyed within tSinkAdj of the change
+        assert_eq!(
+            tracker.update(CcState::Rp(Current::Current1A5), 120),
+            Some(CurrentChange {
+                previous: Some(Current::Current3A0),
+                current: Some(Current::Current1A5),
+                adjust_by_ms: Some(170),
+            })
+        );
+    }
+
+    #[test]
+    fn test_current_tracker_wrap() {
+        let mut tracker = CurrentTracker::new();
+        tracker.update(CcState::Rp(Current::Current1A5), u32::MAX - 4);
+        assert_eq!(
+            tracker.update(CcState::Rp(Current::Current1A5), 5),
+            Some(CurrentChange {
+                previous: None,
+                current: Some(Current::Current1A5),
+                adjust_by_ms: None,
+            })
+        );
+        tracker.update(CcState::Open, 10);
+        assert_eq!(
+            tracker.update(CcState::Open, 20),
+            Some(CurrentChange {
+                previous: Some(Current::Current1A5),
+                current: None,
+                adjust_by_ms: Some(70),
+            })
+        );
+    }
+}
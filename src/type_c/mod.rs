@@ -1,3 +1,6 @@
+pub mod cc;
+pub mod state_machine;
+
 /// Type-C current
 #[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
@@ -29,7 +32,7 @@ impl Current {
 }
 
 /// The current state of a Type-C port.
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
 #[cfg_attr(feature = "defmt", derive(defmt::Format))]
 pub enum ConnectionState {
     /// The port is connected to an USB Type-C Digital Audio (TCDA) accessory.
@@ -0,0 +1,957 @@
+//! Type-C connection state machine as defined in 4.5.2 of the Type-C spec.
+//!
+//! The state machine does not perform any IO. It is driven by the detected state of the CC pins and VBUS and outputs
+//! the CC terminations to apply, whether to supply VBUS and VCONN, and the resulting connection and plug orientation.
+//! It is suitable for ports that detect the CC state with discrete comparators rather than a TCPC.
+//!
+//! At most one timer runs at a time. Starting a timer replaces the running one and changing the CC terminations stops
+//! it. After any change of the CC terminations the caller must report the newly detected CC state through
+//! [`Input::CcChanged`].
+
+use super::{ConnectionState, Current};
+use crate::constants::{
+    T_CC_DEBOUNCE_MS, T_DRP_MS, T_DRP_TRY_MS, T_ERROR_RECOVERY_MS, T_PD_DEBOUNCE_MS, T_TRY_CC_DEBOUNCE_MS,
+};
+use crate::{PlugOrientation, PowerRole};
+
+/// Port power role capability
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Mode {
+    /// Sink only
+    #[default]
+    Sink,
+    /// Source only
+    Source,
+    /// Dual role power, toggling between sink and source while unattached
+    Drp,
+}
+
+/// Preferred power role of a DRP
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum TryRole {
+    /// No preference
+    #[default]
+    None,
+    /// Prefer the source role using Try.SRC
+    Source,
+    /// Prefer the sink role using Try.SNK
+    Sink,
+}
+
+/// State machine configuration
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Config {
+    /// Port power role capability
+    pub mode: Mode,
+    /// Preferred power role, only used in DRP mode
+    pub try_role: TryRole,
+    /// Current advertised through Rp when acting as a source
+    pub rp_current: Current,
+    /// True if audio and debug accessories are supported
+    pub accessories: bool,
+}
+
+/// Detected state of a CC pin
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum CcState {
+    /// Nothing attached, `vOPEN`
+    #[default]
+    Open,
+    /// Ra, a powered cable or audio adapter, detected while presenting Rp
+    Ra,
+    /// Rd, a sink, detected while presenting Rp
+    Rd,
+    /// Rp advertising the given current, detected while presenting Rd
+    Rp(Current),
+}
+
+/// Detected state of both CC pins
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Cc {
+    /// CC1 state
+    pub cc1: CcState,
+    /// CC2 state
+    pub cc2: CcState,
+}
+
+impl Cc {
+    /// Create a new CC state
+    pub const fn new(cc1: CcState, cc2: CcState) -> Self {
+        Self { cc1, cc2 }
+    }
+
+    /// Returns the state of the CC pin used for communication with the given orientation
+    pub fn get(&self, orientation: PlugOrientation) -> CcState {
+        match orientation {
+            PlugOrientation::CC1 => self.cc1,
+            PlugOrientation::CC2 => self.cc2,
+        }
+    }
+
+    /// Returns true if both pins are open
+    pub fn is_open(&self) -> bool {
+        self.cc1 == CcState::Open && self.cc2 == CcState::Open
+    }
+
+    /// Returns the orientation and whether VCONN is required if exactly one pin has Rd
+    pub fn rd(&self) -> Option<(PlugOrientation, bool)> {
+        match (self.cc1, self.cc2) {
+            (CcState::Rd, CcState::Open | CcState::Ra) => Some((PlugOrientation::CC1, self.cc2 == CcState::Ra)),
+            (CcState::Open | CcState::Ra, CcState::Rd) => Some((PlugOrientation::CC2, self.cc1 == CcState::Ra)),
+            _ => None,
+        }
+    }
+
+    /// Returns the orientation and advertised current if exactly one pin has Rp
+    pub fn rp(&self) -> Option<(PlugOrientation, Current)> {
+        match (self.cc1, self.cc2) {
+            (CcState::Rp(current), CcState::Open) => Some((PlugOrientation::CC1, current)),
+            (CcState::Open, CcState::Rp(current)) => Some((PlugOrientation::CC2, current)),
+            _ => None,
+        }
+    }
+
+    /// Returns true if either pin has Rd
+    pub fn has_rd(&self) -> bool {
+        self.cc1 == CcState::Rd || self.cc2 == CcState::Rd
+    }
+
+    /// Returns true if either pin has Rp
+    pub fn has_rp(&self) -> bool {
+        matches!(self.cc1, CcState::Rp(_)) || matches!(self.cc2, CcState::Rp(_))
+    }
+
+    /// Returns true if both pins have Ra, an audio adapter accessory
+    pub fn is_audio_accessory(&self) -> bool {
+        self.cc1 == CcState::Ra && self.cc2 == CcState::Ra
+    }
+
+    /// Returns true if both pins have Rd, a debug accessory seen by a source
+    pub fn is_debug_accessory_src(&self) -> bool {
+        self.cc1 == CcState::Rd && self.cc2 == CcState::Rd
+    }
+
+    /// Returns the lower advertised current if both pins have Rp, a debug accessory seen by a sink
+    pub fn debug_accessory_snk(&self) -> Option<Current> {
+        match (self.cc1, self.cc2) {
+            (CcState::Rp(cc1), CcState::Rp(cc2)) => Some(if cc1.to_ma(false) <= cc2.to_ma(false) { cc1 } else { cc2 }),
+            _ => None,
+        }
+    }
+}
+
+/// CC termination applied to both CC pins
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Termination {
+    /// No termination
+    Open,
+    /// Rd
+    Rd,
+    /// Rp advertising the given current
+    Rp(Current),
+}
+
+/// Timers used by the Type-C state machine
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Timer {
+    /// Half of the DRP toggle period, `tDRP`, with a 50% duty cycle
+    DrpToggle,
+    /// Attach debounce, `tCCDebounce`
+    CcDebounce,
+    /// Detach debounce, `tPDDebounce`
+    PdDebounce,
+    /// Try.SRC and TryWait.SRC attach debounce, `tTryCCDebounce`
+    TryCcDebounce,
+    /// Time spent in Try.SRC, Try.SNK and TryWait.SRC before checking the partner, `tDRPTry`
+    DrpTry,
+    /// Time spent with terminations removed, `tErrorRecovery`
+    ErrorRecovery,
+}
+
+impl Timer {
+    /// Returns the timeout for this timer in milliseconds
+    pub const fn duration_ms(self) -> u16 {
+        match self {
+            Timer::DrpToggle => T_DRP_MS.nominal.0 / 2,
+            Timer::CcDebounce => T_CC_DEBOUNCE_MS.nominal.0,
+            Timer::PdDebounce => T_PD_DEBOUNCE_MS.nominal.0,
+            Timer::TryCcDebounce => T_TRY_CC_DEBOUNCE_MS.nominal.0,
+            Timer::DrpTry => T_DRP_TRY_MS.nominal.0,
+            Timer::ErrorRecovery => T_ERROR_RECOVERY_MS.0,
+        }
+    }
+}
+
+/// Type-C connection states
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum State {
+    /// `Disabled`, waiting for [`Input::Start`]
+    #[default]
+    Disabled,
+    /// `ErrorRecovery`
+    ErrorRecovery,
+    /// `Unattached.SNK`
+    UnattachedSnk,
+    /// `AttachWait.SNK`
+    AttachWaitSnk,
+    /// `Attached.SNK`
+    AttachedSnk(PlugOrientation),
+    /// `Unattached.SRC`
+    UnattachedSrc,
+    /// `AttachWait.SRC`
+    AttachWaitSrc,
+    /// `Attached.SRC`
+    AttachedSrc(PlugOrientation),
+    /// `Try.SRC`
+    TrySrc,
+    /// `TryWait.SNK`
+    TryWaitSnk,
+    /// `Try.SNK`
+    TrySnk,
+    /// `TryWait.SRC`
+    TryWaitSrc,
+    /// `AudioAccessory`
+    AudioAccessory,
+    /// `UnorientedDebugAccessory.SRC`
+    DebugAccessorySrc,
+    /// `DebugAccessory.SNK`
+    DebugAccessorySnk,
+}
+
+/// Inputs to the Type-C state machine
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Input {
+    /// Start the state machine
+    Start,
+    /// The detected CC state has changed
+    CcChanged(Cc),
+    /// VBUS is present (true) or at vSafe0V (false)
+    Vbus(bool),
+    /// Timer expired
+    TimerExpired(Timer),
+    /// Remove the terminations and restart detection
+    ErrorRecovery,
+}
+
+/// Resulting connection after entering an attached or accessory state
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Attached {
+    /// Connection type
+    pub connection: ConnectionState,
+    /// Power role
+    pub power_role: PowerRole,
+    /// Plug orientation, accessories are always reported as [`PlugOrientation::CC1`]
+    pub orientation: PlugOrientation,
+    /// Current advertised through Rp, by the port partner when sinking or by this port when sourcing
+    pub current: Current,
+    /// True if VBUS must be supplied
+    pub vbus: bool,
+    /// True if VCONN must be supplied on the CC pin not used for communication
+    pub vconn: bool,
+}
+
+/// Outputs from the Type-C state machine
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Output {
+    /// Start the timer
+    StartTimer(Timer),
+    /// Apply the termination to both CC pins, stop supplying VBUS and VCONN and start the timer if any
+    SetTermination {
+        /// Termination to apply
+        termination: Termination,
+        /// Timer to start
+        timer: Option<Timer>,
+    },
+    /// An attached or accessory state has been entered, the termination is left unchanged
+    Attached(Attached),
+    /// The current advertised by the source has changed while attached as a sink
+    CurrentChanged(Current),
+}
+
+/// Attempted transition that is not allowed by the state machine
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct InvalidTransition {
+    /// The current state of the state machine
+    pub state: State,
+    /// The input that was attempted
+    pub input: Input,
+}
+
+// Doctest tries to compile the mermaid code as rust so just disable it
+#[cfg_attr(not(doctest), aquamarine::aquamarine)]
+#[cfg_attr(not(doctest), doc = "include_mmd!(\"docs/type_c/state_machine.mmd\")")]
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct StateMachine {
+    config: Config,
+    state: State,
+    /// Last reported CC state
+    cc: Cc,
+    /// Last reported VBUS state
+    vbus: bool,
+    /// True once the CC state has been stable for the debounce time, or `tDRPTry` has passed in Try.SNK
+    debounced: bool,
+    /// Current advertised by the source while attached as a sink
+    current: Current,
+}
+
+impl StateMachine {
+    /// Create a new state machine in the Disabled state
+    pub const fn new(config: Config) -> Self {
+        Self {
+            config,
+            state: State::Disabled,
+            cc: Cc::new(CcState::Open, CcState::Open),
+            vbus: false,
+            debounced: false,
+            current: Current::UsbDefault,
+        }
+    }
+
+    /// Returns the current state
+    pub fn state(&self) -> State {
+        self.state
+    }
+
+    /// Returns the configuration
+    pub fn config(&self) -> Config {
+        self.config
+    }
+
+    fn set_termination(
+        &mut self,
+        state: State,
+        termination: Termination,
+        timer: Option<Timer>,
+    ) -> (State, Option<Output>) {
+        // The caller reports the CC state seen with the new termination
+        self.cc = Cc::default();
+        self.debounced = false;
+        (state, Some(Output::SetTermination { termination, timer }))
+    }
+
+    fn rp(&self) -> Termination {
+        Termination::Rp(self.config.rp_current)
+    }
+
+    fn unattached_snk(&mut self) -> (State, Option<Output>) {
+        let timer = (self.config.mode == Mode::Drp).then_some(Timer::DrpToggle);
+        self.set_termination(State::UnattachedSnk, Termination::Rd, timer)
+    }
+
+    fn unattached_src(&mut self) -> (State, Option<Output>) {
+        let timer = (self.config.mode == Mode::Drp).then_some(Timer::DrpToggle);
+        let rp = self.rp();
+        self.set_termination(State::UnattachedSrc, rp, timer)
+    }
+
+    /// Returns to the unattached state for the configured mode, DRPs always start as a sink
+    fn unattached(&mut self) -> (State, Option<Output>) {
+        match self.config.mode {
+            Mode::Source => self.unattached_src(),
+            Mode::Sink | Mode::Drp => self.unattached_snk(),
+        }
+    }
+
+    fn try_role(&self) -> TryRole {
+        if self.config.mode == Mode::Drp {
+            self.config.try_role
+        } else {
+            TryRole::None
+        }
+    }
+
+    fn attached(&self, state: State, attached: Attached) -> (State, Option<Output>) {
+        (state, Some(Output::Attached(attached)))
+    }
+
+    fn attached_snk(&mut self, orientation: PlugOrientation, current: Current) -> (State, Option<Output>) {
+        self.current = current;
+        self.attached(
+            State::AttachedSnk(orientation),
+            Attached {
+                connection: ConnectionState::Attached,
+                power_role: PowerRole::Sink,
+                orientation,
+                current,
+                vbus: false,
+                vconn: false,
+            },
+        )
+    }
+
+    fn attached_src(&mut self, orientation: PlugOrientation, vconn: bool) -> (State, Option<Output>) {
+        self.attached(
+            State::AttachedSrc(orientation),
+            Attached {
+                connection: ConnectionState::Attached,
+                power_role: PowerRole::Source,
+                orientation,
+                current: self.config.rp_current,
+                vbus: true,
+                vconn,
+            },
+        )
+    }
+
+    /// Leave AttachWait.SNK or TryWait.SNK once the CC state is debounced and VBUS is present
+    fn attach_snk(&mut self) -> (State, Option<Output>) {
+        if let Some(current) = self.cc.debug_accessory_snk() {
+            if self.state == State::AttachWaitSnk && self.config.accessories {
+                return self.attached(
+                    State::DebugAccessorySnk,
+                    Attached {
+                        connection: ConnectionState::DebugAccessory,
+                        power_role: PowerRole::Sink,
+                        orientation: PlugOrientation::CC1,
+                        current,
+                        vbus: false,
+                        vconn: false,
+                    },
+                );
+            }
+        } else if let Some((orientation, current)) = self.cc.rp() {
+            if self.state == State::AttachWaitSnk && self.try_role() == TryRole::Source {
+                let rp = self.rp();
+                return self.set_termination(State::TrySrc, rp, Some(Timer::DrpTry));
+            }
+            return self.attached_snk(orientation, current);
+        }
+
+        (self.state, None)
+    }
+
+    /// Leave AttachWait.SRC once the CC state is debounced and VBUS is at vSafe0V
+    fn attach_src(&mut self) -> (State, Option<Output>) {
+        let accessory = |connection, vbus| Attached {
+            connection,
+            power_role: PowerRole::Source,
+            orientation: PlugOrientation::CC1,
+            current: self.config.rp_current,
+            vbus,
+            vconn: false,
+        };
+
+        if self.config.accessories && self.cc.is_audio_accessory() {
+            self.attached(State::AudioAccessory, accessory(ConnectionState::AudioAccessory, false))
+        } else if self.config.accessories && self.cc.is_debug_accessory_src() {
+            self.attached(
+                State::DebugAccessorySrc,
+                accessory(ConnectionState::DebugAccessory, true),
+            )
+        } else if let Some((orientation, vconn)) = self.cc.rd() {
+            if self.try_role() == TryRole::Sink {
+                self.set_termination(State::TrySnk, Termination::Rd, Some(Timer::DrpTry))
+            } else {
+                self.attached_src(orientation, vconn)
+            }
+        } else {
+            (self.state, None)
+        }
+    }
+
+    /// Try.SRC or TryWait.SRC did not find a sink
+    fn try_src_failed(&mut self) -> (State, Option<Output>) {
+        if self.state == State::TrySrc {
+            self.set_termination(State::TryWaitSnk, Termination::Rd, None)
+        } else {
+            self.unattached_snk()
+        }
+    }
+
+    /// Leave Try.SNK once `tDRPTry` has passed
+    fn try_snk(&mut self) -> (State, Option<Output>) {
+        match self.cc.rp() {
+            Some((orientation, current)) if self.vbus => self.attached_snk(orientation, current),
+            _ if !self.cc.has_rp() => {
+                let rp = self.rp();
+                self.set_termination(State::TryWaitSrc, rp, Some(Timer::DrpTry))
+            }
+            _ => (self.state, None),
+        }
+    }
+
+    /// Transition the state machine based on the input and return the resulting action if any.
+    pub fn consume(&mut self, input: Input) -> Result<Option<Output>, InvalidTransition> {
+        use State::*;
+
+        match input {
+            Input::CcChanged(cc) => self.cc = cc,
+            Input::Vbus(present) => self.vbus = present,
+            _ => {}
+        }
+
+        let (next_state, output) = match (self.state, input) {
+            (Disabled, Input::Start) | (ErrorRecovery, Input::TimerExpired(Timer::ErrorRecovery)) => self.unattached(),
+            (Disabled, Input::ErrorRecovery) => {
+                return Err(InvalidTransition {
+                    state: self.state,
+                    input,
+                })
+            }
+            (_, Input::ErrorRecovery) => {
+                self.set_termination(ErrorRecovery, Termination::Open, Some(Timer::ErrorRecovery))
+            }
+
+            // Unattached states
+            (UnattachedSnk, Input::CcChanged(cc)) if cc.has_rp() => {
+                (AttachWaitSnk, Some(Output::StartTimer(Timer::CcDebounce)))
+            }
+            (UnattachedSrc, Input::CcChanged(cc))
+                if cc.has_rd() || (self.config.accessories && cc.is_audio_accessory()) =>
+            {
+                (AttachWaitSrc, Some(Output::StartTimer(Timer::CcDebounce)))
+            }
+            (UnattachedSnk, Input::TimerExpired(Timer::DrpToggle)) => self.unattached_src(),
+            (UnattachedSrc, Input::TimerExpired(Timer::DrpToggle)) => self.unattached_snk(),
+
+            // Sink attach
+            (AttachWaitSnk | TryWaitSnk, Input::CcChanged(cc)) => {
+                self.debounced = false;
+                let timer = if cc.has_rp() {
+                    Timer::CcDebounce
+                } else {
+                    Timer::PdDebounce
+                };
+                (self.state, Some(Output::StartTimer(timer)))
+            }
+            (AttachWaitSnk | TryWaitSnk, Input::TimerExpired(Timer::CcDebounce)) => {
+                self.debounced = true;
+                if self.vbus {
+                    self.attach_snk()
+                } else {
+                    (self.state, None)
+                }
+            }
+            (AttachWaitSnk | TryWaitSnk, Input::Vbus(true)) if self.debounced => self.attach_snk(),
+            (AttachWaitSnk | TryWaitSnk, Input::TimerExpired(Timer::PdDebounce)) => self.unattached_snk(),
+
+            // Sink detach
+            (AttachedSnk(_) | DebugAccessorySnk, Input::Vbus(false)) => self.unattached_snk(),
+            (AttachedSnk(orientation), Input::CcChanged(cc)) => match cc.get(orientation) {
+                CcState::Rp(current) if current != self.current => {
+                    self.current = current;
+                    (self.state, Some(Output::CurrentChanged(current)))
+                }
+                _ => (self.state, None),
+            },
+
+            // Source attach
+            (AttachWaitSrc, Input::CcChanged(cc)) => {
+                if cc.has_rd() || (self.config.accessories && cc.is_audio_accessory()) {
+                    self.debounced = false;
+                    (AttachWaitSrc, Some(Output::StartTimer(Timer::CcDebounce)))
+                } else {
+                    self.unattached()
+                }
+            }
+            (AttachWaitSrc, Input::TimerExpired(Timer::CcDebounce)) => {
+                self.debounced = true;
+                if self.vbus {
+                    (AttachWaitSrc, None)
+                } else {
+                    self.attach_src()
+                }
+            }
+            (AttachWaitSrc, Input::Vbus(false)) if self.debounced => self.attach_src(),
+
+            // Source detach
+            (AttachedSrc(orientation), Input::CcChanged(cc)) if cc.get(orientation) != CcState::Rd => {
+                if self.try_role() == TryRole::Source {
+                    self.set_termination(TryWaitSnk, Termination::Rd, None)
+                } else {
+                    self.unattached()
+                }
+            }
+            (AudioAccessory, Input::CcChanged(cc)) if cc.is_open() => self.unattached(),
+            (DebugAccessorySrc, Input::CcChanged(cc)) if !cc.is_debug_accessory_src() => self.unattached(),
+
+            // Try.SRC and TryWait.SRC
+            (TrySrc | TryWaitSrc, Input::CcChanged(cc)) if cc.rd().is_some() => {
+                (self.state, Some(Output::StartTimer(Timer::TryCcDebounce)))
+            }
+            (TrySrc | TryWaitSrc, Input::TimerExpired(Timer::TryCcDebounce)) => match self.cc.rd() {
+                Some((orientation, vconn)) => self.attached_src(orientation, vconn),
+                None => self.try_src_failed(),
+            },
+            (TrySrc | TryWaitSrc, Input::TimerExpired(Timer::DrpTry)) => self.try_src_failed(),
+
+            // Try.SNK
+            (TrySnk, Input::TimerExpired(Timer::DrpTry)) => {
+                self.debounced = true;
+                self.try_snk()
+            }
+            (TrySnk, Input::CcChanged(_) | Input::Vbus(_)) if self.debounced => self.try_snk(),
+
+            // CC and VBUS changes that do not cause a transition
+            (_, Input::CcChanged(_) | Input::Vbus(_)) => (self.state, None),
+
+            // Invalid transition
+            _ => {
+                return Err(InvalidTransition {
+                    state: self.state,
+                    input,
+                })
+            }
+        };
+
+        self.state = next_state;
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const RP: CcState = CcState::Rp(Current::Current3A0);
+    const OPEN: Cc = Cc::new(CcState::Open, CcState::Open);
+
+    fn config(mode: Mode) -> Config {
+        Config {
+            mode,
+            try_role: TryRole::None,
+            rp_current: Current::Current1A5,
+            accessories: true,
+        }
+    }
+
+    fn set_termination(termination: Termination, timer: Option<Timer>) -> Result<Option<Output>, InvalidTransition> {
+        Ok(Some(Output::SetTermination { termination, timer }))
+    }
+
+    fn sink(orientation: PlugOrientation, current: Current) -> Result<Option<Output>, InvalidTransition> {
+        Ok(Some(Output::Attached(Attached {
+            connection: ConnectionState::Attached,
+            power_role: PowerRole::Sink,
+            orientation,
+            current,
+            vbus: false,
+            vconn: false,
+        })))
+    }
+
+    fn source(orientation: PlugOrientation, vconn: bool) -> Result<Option<Output>, InvalidTransition> {
+        Ok(Some(Output::Attached(Attached {
+            connection: ConnectionState::Attached,
+            power_role: PowerRole::Source,
+            orientation,
+            current: Current::Current1A5,
+            vbus: true,
+            vconn,
+        })))
+    }
+
+    /// Drive the state machine through AttachWait.SRC with the given CC state
+    fn attach_wait_src(sm: &mut StateMachine, cc: Cc) -> Result<Option<Output>, InvalidTransition> {
+        sm.consume(Input::CcChanged(cc))?;
+        sm.consume(Input::TimerExpired(Timer::CcDebounce))
+    }
+
+    #[test]
+    fn test_sink() {
+        let mut sm = StateMachine::new(config(Mode::Sink));
+        assert_eq!(sm.consume(Input::Start), set_termination(Termination::Rd, None));
+
+        let cc = Cc::new(CcState::Open, RP);
+        assert_eq!(
+            sm.consume(Input::CcChanged(cc)),
+            Ok(Some(Output::StartTimer(Timer::CcDebounce)))
+        );
+        assert_eq!(sm.consume(Input::TimerExpired(Timer::CcDebounce)), Ok(None));
+        assert_eq!(
+            sm.consume(Input::Vbus(true)),
+            sink(PlugOrientation::CC2, Current::Current3A0)
+        );
+        assert_eq!(sm.state(), State::AttachedSnk(PlugOrientation::CC2));
+
+        let cc = Cc::new(CcState::Open, CcState::Rp(Current::Current1A5));
+        assert_eq!(
+            sm.consume(Input::CcChanged(cc)),
+            Ok(Some(Output::CurrentChanged(Current::Current1A5)))
+        );
+
+        assert_eq!(sm.consume(Input::Vbus(false)), set_termination(Termination::Rd, None));
+        assert_eq!(sm.state(), State::UnattachedSnk);
+    }
+
+    #[test]
+    fn test_sink_debounce() {
+        let mut sm = StateMachine::new(config(Mode::Sink));
+        sm.consume(Input::Start).ok();
+        sm.consume(Input::Vbus(true)).ok();
+        sm.consume(Input::CcChanged(Cc::new(RP, CcState::Open))).ok();
+
+        // Bounce before tCCDebounce restarts the debounce
+        assert_eq!(
+            sm.consume(Input::CcChanged(OPEN)),
+            Ok(Some(Output::StartTimer(Timer::PdDebounce)))
+        );
+        assert_eq!(
+            sm.consume(Input::CcChanged(Cc::new(RP, CcState::Open))),
+            Ok(Some(Output::StartTimer(Timer::CcDebounce)))
+        );
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::CcDebounce)),
+            sink(PlugOrientation::CC1, Current::Current3A0)
+        );
+
+        // Removed before attach
+        let mut sm = StateMachine::new(config(Mode::Sink));
+        sm.consume(Input::Start).ok();
+        sm.consume(Input::CcChanged(Cc::new(RP, CcState::Open))).ok();
+        sm.consume(Input::CcChanged(OPEN)).ok();
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::PdDebounce)),
+            set_termination(Termination::Rd, None)
+        );
+    }
+
+    #[test]
+    fn test_source() {
+        let mut sm = StateMachine::new(config(Mode::Source));
+        assert_eq!(
+            sm.consume(Input::Start),
+            set_termination(Termination::Rp(Current::Current1A5), None)
+        );
+
+        // Powered cable on CC1
+        let cc = Cc::new(CcState::Ra, CcState::Rd);
+        assert_eq!(
+            sm.consume(Input::CcChanged(cc)),
+            Ok(Some(Output::StartTimer(Timer::CcDebounce)))
+        );
+        assert_eq!(sm.state(), State::AttachWaitSrc);
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::CcDebounce)),
+            source(PlugOrientation::CC2, true)
+        );
+
+        // Ra remains after the sink is removed
+        assert_eq!(
+            sm.consume(Input::CcChanged(Cc::new(CcState::Ra, CcState::Open))),
+            set_termination(Termination::Rp(Current::Current1A5), None)
+        );
+        assert_eq!(sm.state(), State::UnattachedSrc);
+    }
+
+    #[test]
+    fn test_source_waits_for_vsafe0v() {
+        let mut sm = StateMachine::new(config(Mode::Source));
+        sm.consume(Input::Start).ok();
+        sm.consume(Input::Vbus(true)).ok();
+        assert_eq!(attach_wait_src(&mut sm, Cc::new(CcState::Rd, CcState::Open)), Ok(None));
+        assert_eq!(sm.consume(Input::Vbus(false)), source(PlugOrientation::CC1, false));
+    }
+
+    #[test]
+    fn test_drp_toggle() {
+        let mut sm = StateMachine::new(config(Mode::Drp));
+        assert_eq!(
+            sm.consume(Input::Start),
+            set_termination(Termination::Rd, Some(Timer::DrpToggle))
+        );
+        assert_eq!(sm.consume(Input::CcChanged(OPEN)), Ok(None));
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::DrpToggle)),
+            set_termination(Termination::Rp(Current::Current1A5), Some(Timer::DrpToggle))
+        );
+        assert_eq!(
+            attach_wait_src(&mut sm, Cc::new(CcState::Rd, CcState::Open)),
+            source(PlugOrientation::CC1, false)
+        );
+
+        // Detach returns to Unattached.SNK
+        assert_eq!(
+            sm.consume(Input::CcChanged(OPEN)),
+            set_termination(Termination::Rd, Some(Timer::DrpToggle))
+        );
+    }
+
+    #[test]
+    fn test_try_src() {
+        let mut sm = StateMachine::new(Config {
+            try_role: TryRole::Source,
+            ..config(Mode::Drp)
+        });
+        sm.consume(Input::Start).ok();
+        sm.consume(Input::Vbus(true)).ok();
+        sm.consume(Input::CcChanged(Cc::new(RP, CcState::Open))).ok();
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::CcDebounce)),
+            set_termination(Termination::Rp(Current::Current1A5), Some(Timer::DrpTry))
+        );
+        assert_eq!(sm.state(), State::TrySrc);
+
+        // The partner also switched to a sink
+        assert_eq!(
+            sm.consume(Input::CcChanged(Cc::new(CcState::Rd, CcState::Open))),
+            Ok(Some(Output::StartTimer(Timer::TryCcDebounce)))
+        );
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::TryCcDebounce)),
+            source(PlugOrientation::CC1, false)
+        );
+    }
+
+    #[test]
+    fn test_try_src_fails() {
+        let mut sm = StateMachine::new(Config {
+            try_role: TryRole::Source,
+            ..config(Mode::Drp)
+        });
+        sm.consume(Input::Start).ok();
+        sm.consume(Input::Vbus(true)).ok();
+        sm.consume(Input::CcChanged(Cc::new(RP, CcState::Open))).ok();
+        sm.consume(Input::TimerExpired(Timer::CcDebounce)).ok();
+
+        // Source only partner
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::DrpTry)),
+            set_termination(Termination::Rd, None)
+        );
+        assert_eq!(sm.state(), State::TryWaitSnk);
+        assert_eq!(
+            sm.consume(Input::CcChanged(Cc::new(RP, CcState::Open))),
+            Ok(Some(Output::StartTimer(Timer::CcDebounce)))
+        );
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::CcDebounce)),
+            sink(PlugOrientation::CC1, Current::Current3A0)
+        );
+    }
+
+    #[test]
+    fn test_try_snk() {
+        let mut sm = StateMachine::new(Config {
+            try_role: TryRole::Sink,
+            ..config(Mode::Drp)
+        });
+        sm.consume(Input::Start).ok();
+        sm.consume(Input::TimerExpired(Timer::DrpToggle)).ok();
+        assert_eq!(
+            attach_wait_src(&mut sm, Cc::new(CcState::Open, CcState::Rd)),
+            set_termination(Termination::Rd, Some(Timer::DrpTry))
+        );
+        assert_eq!(sm.state(), State::TrySnk);
+
+        // The partner switched to a source but VBUS is not present yet
+        sm.consume(Input::CcChanged(Cc::new(CcState::Open, RP))).ok();
+        assert_eq!(sm.consume(Input::TimerExpired(Timer::DrpTry)), Ok(None));
+        assert_eq!(
+            sm.consume(Input::Vbus(true)),
+            sink(PlugOrientation::CC2, Current::Current3A0)
+        );
+    }
+
+    #[test]
+    fn test_try_snk_fails() {
+        let mut sm = StateMachine::new(Config {
+            try_role: TryRole::Sink,
+            ..config(Mode::Drp)
+        });
+        sm.consume(Input::Start).ok();
+        sm.consume(Input::TimerExpired(Timer::DrpToggle)).ok();
+        attach_wait_src(&mut sm, Cc::new(CcState::Rd, CcState::Open)).ok();
+
+        // Sink only partner
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::DrpTry)),
+            set_termination(Termination::Rp(Current::Current1A5), Some(Timer::DrpTry))
+        );
+        assert_eq!(sm.state(), State::TryWaitSrc);
+        sm.consume(Input::CcChanged(Cc::new(CcState::Rd, CcState::Open))).ok();
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::TryCcDebounce)),
+            source(PlugOrientation::CC1, false)
+        );
+    }
+
+    #[test]
+    fn test_accessories() {
+        let mut sm = StateMachine::new(config(Mode::Source));
+        sm.consume(Input::Start).ok();
+        assert_eq!(
+            attach_wait_src(&mut sm, Cc::new(CcState::Ra, CcState::Ra)),
+            Ok(Some(Output::Attached(Attached {
+                connection: ConnectionState::AudioAccessory,
+                power_role: PowerRole::Source,
+                orientation: PlugOrientation::CC1,
+                current: Current::Current1A5,
+                vbus: false,
+                vconn: false,
+            })))
+        );
+        assert_eq!(
+            sm.consume(Input::CcChanged(Cc::new(CcState::Ra, CcState::Open))),
+            Ok(None)
+        );
+        assert_eq!(
+            sm.consume(Input::CcChanged(OPEN)),
+            set_termination(Termination::Rp(Current::Current1A5), None)
+        );
+
+        let mut sm = StateMachine::new(config(Mode::Sink));
+        sm.consume(Input::Start).ok();
+        sm.consume(Input::Vbus(true)).ok();
+        sm.consume(Input::CcChanged(Cc::new(RP, CcState::Rp(Current::UsbDefault))))
+            .ok();
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::CcDebounce)),
+            Ok(Some(Output::Attached(Attached {
+                connection: ConnectionState::DebugAccessory,
+                power_role: PowerRole::Sink,
+                orientation: PlugOrientation::CC1,
+                current: Current::UsbDefault,
+                vbus: false,
+                vconn: false,
+            })))
+        );
+        assert_eq!(sm.state(), State::DebugAccessorySnk);
+
+        // Accessories not supported
+        let mut sm = StateMachine::new(Config {
+            accessories: false,
+            ..config(Mode::Source)
+        });
+        sm.consume(Input::Start).ok();
+        assert_eq!(
+            sm.consume(Input::CcChanged(Cc::new(CcState::Ra, CcState::Ra))),
+            Ok(None)
+        );
+        assert_eq!(sm.state(), State::UnattachedSrc);
+    }
+
+    #[test]
+    fn test_error_recovery() {
+        let mut sm = StateMachine::new(config(Mode::Sink));
+        assert_eq!(
+            sm.consume(Input::ErrorRecovery),
+            Err(InvalidTransition {
+                state: State::Disabled,
+                input: Input::ErrorRecovery,
+            })
+        );
+        sm.consume(Input::Start).ok();
+        assert_eq!(
+            sm.consume(Input::ErrorRecovery),
+            set_termination(Termination::Open, Some(Timer::ErrorRecovery))
+        );
+        assert_eq!(
+            sm.consume(Input::TimerExpired(Timer::ErrorRecovery)),
+            set_termination(Termination::Rd, None)
+        );
+    }
+}